@@ -1,4 +1,9 @@
 //! Crate for adding linear acceleration to a Speed
+//!
+//! Accelerations are timed with a [`Clock`](interfaces::Clock), the
+//! [`SystemClock`](interfaces::SystemClock) unless created `with_clock`, so
+//! they also run where the system has no [`Instant`](std::time::Instant),
+//! e.g. on `wasm32-unknown-unknown`.
 
 use std::ops::Mul;
 
@@ -1,53 +1,68 @@
-use std::{
-    ops::Mul,
-    time::{Duration, Instant},
-};
+use std::{ops::Mul, time::Duration};
 
 use directions::Stop;
+use interfaces::Clock;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use interfaces::SystemClock;
 use speed::Speed;
 
 use crate::Accelerator;
 
 /// Apply linear acceleration using [`Decelerate`]
 #[derive(Debug, Copy, Clone)]
-pub struct LinearAcceleration {
+pub struct LinearAcceleration<C> {
     /// The [`Duration`] it takes to accelerate to full [`Speed`]
     duration: Duration,
-    /// Last acceleration start
-    last: Option<Instant>,
+    /// Last acceleration start, in the time of the clock
+    last: Option<Duration>,
+    /// [`Clock`] the acceleration is timed with
+    clock: C,
 }
 
-impl LinearAcceleration {
-    /// Create a new [`LinearAcceleration`]
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl LinearAcceleration<SystemClock> {
+    /// Create a new [`LinearAcceleration`] timed with the [`SystemClock`]
     ///
     /// The [`Duration`] is the amount of time before the speed reaches it's
     /// full value
     pub fn new(duration: Duration) -> Self {
+        Self::with_clock(duration, SystemClock::new())
+    }
+}
+
+impl<C: Clock> LinearAcceleration<C> {
+    /// Create a new [`LinearAcceleration`] timed with a [`Clock`]
+    ///
+    /// The [`Duration`] is the amount of time before the speed reaches it's
+    /// full value
+    pub fn with_clock(duration: Duration, clock: C) -> Self {
         Self {
             duration,
             last: None,
+            clock,
         }
     }
 
-    /// Reset the [`LinearAcceleration`]
-    pub fn reset(&mut self) -> Option<Instant> {
+    /// Reset the [`LinearAcceleration`], returning when the last acceleration
+    /// started in the time of its [`Clock`]
+    pub fn reset(&mut self) -> Option<Duration> {
         self.last.take()
     }
 }
 
-impl<S> Accelerator<S> for LinearAcceleration
+impl<S, C> Accelerator<S> for LinearAcceleration<C>
 where
     S: Mul<Speed, Output = S> + Stop + Sized,
+    C: Clock,
 {
     fn apply(&mut self, value: S) -> S {
         let multi = if value.is_stop() {
             self.last = None;
             Speed::MAX
         } else {
-            if self.last.is_none() {
-                self.last = Some(Instant::now());
-            };
-            let since_last = self.last.unwrap().elapsed().as_millis() as f64;
+            let now = self.clock.now();
+            let start = *self.last.get_or_insert(now);
+            let since_last = now.saturating_sub(start).as_millis() as f64;
             let multiplier = since_last / self.duration.as_millis() as f64;
             Speed::new_clamp(multiplier)
         };
@@ -55,3 +70,49 @@ where
         value * multi
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, time::Duration};
+
+    use directions::VehicleDirection;
+    use interfaces::Clock;
+    use speed::Speed;
+
+    use super::LinearAcceleration;
+    use crate::Accelerate;
+
+    /// [`Clock`] moved on by hand
+    #[derive(Debug, Default)]
+    struct ManualClock(Cell<Duration>);
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Duration {
+            self.0.get()
+        }
+    }
+
+    /// Test that the speed grows linearly with the time of the clock and
+    /// starts over after stopping
+    #[test]
+    fn accelerate_with_clock() {
+        let clock = ManualClock::default();
+        let mut acceleration = LinearAcceleration::with_clock(Duration::from_secs(1), &clock);
+        let (standing, full) = (
+            VehicleDirection::forward(Speed::MIN),
+            VehicleDirection::forward(Speed::MAX),
+        );
+
+        assert_eq!(full.accelerate(&mut acceleration), standing);
+        clock.0.set(Duration::from_millis(500));
+        assert_eq!(
+            full.accelerate(&mut acceleration),
+            VehicleDirection::forward(Speed::HALF)
+        );
+        clock.0.set(Duration::from_secs(2));
+        assert_eq!(full.accelerate(&mut acceleration), full);
+
+        VehicleDirection::STOP.accelerate(&mut acceleration);
+        assert_eq!(full.accelerate(&mut acceleration), standing);
+    }
+}
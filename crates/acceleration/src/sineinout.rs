@@ -1,35 +1,48 @@
-use std::{
-    ops::Mul,
-    time::{Duration, Instant},
-};
+use std::{ops::Mul, time::Duration};
 
 use directions::Stop;
+use interfaces::Clock;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use interfaces::SystemClock;
 use speed::Speed;
 
 use crate::Accelerator;
 
 /// Apply sine-in-out acceleration
 #[derive(Debug, Copy, Clone)]
-pub struct SineInOutAcceleration {
+pub struct SineInOutAcceleration<C> {
     /// The [`Duration`] it takes to accelerate from zero to full [`Speed`]
     duration: Duration,
-    /// Timestamp when the acceleration started
-    last: Option<Instant>,
+    /// Timestamp when the acceleration started, in the time of the clock
+    last: Option<Duration>,
+    /// [`Clock`] the acceleration is timed with
+    clock: C,
 }
 
-impl SineInOutAcceleration {
-    /// Create a new [`SineInOutAcceleration`]
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl SineInOutAcceleration<SystemClock> {
+    /// Create a new [`SineInOutAcceleration`] timed with the [`SystemClock`]
     ///
     /// The [`Duration`] is the amount of time before the speed reaches full value.
     pub fn new(duration: Duration) -> Self {
+        Self::with_clock(duration, SystemClock::new())
+    }
+}
+
+impl<C: Clock> SineInOutAcceleration<C> {
+    /// Create a new [`SineInOutAcceleration`] timed with a [`Clock`]
+    ///
+    /// The [`Duration`] is the amount of time before the speed reaches full value.
+    pub fn with_clock(duration: Duration, clock: C) -> Self {
         Self {
             duration,
             last: None,
+            clock,
         }
     }
 
     /// Reset the [`SineInOutAcceleration`], removing any notion of start time.
-    pub fn reset(&mut self) -> Option<Instant> {
+    pub fn reset(&mut self) -> Option<Duration> {
         self.last.take()
     }
 
@@ -42,9 +55,10 @@ impl SineInOutAcceleration {
     }
 }
 
-impl<S> Accelerator<S> for SineInOutAcceleration
+impl<S, C> Accelerator<S> for SineInOutAcceleration<C>
 where
     S: Mul<Speed, Output = S> + Stop + Sized,
+    C: Clock,
 {
     fn apply(&mut self, value: S) -> S {
         // If the value is at stop, reset the timer and return max (no acceleration needed)
@@ -54,12 +68,11 @@ where
         }
 
         // If this is the first time applying acceleration after not being stopped, record the start time
-        if self.last.is_none() {
-            self.last = Some(Instant::now());
-        }
+        let now = self.clock.now();
+        let start = *self.last.get_or_insert(now);
 
         // Calculate how far we are into the duration
-        let elapsed = self.last.unwrap().elapsed();
+        let elapsed = now.saturating_sub(start);
         let fraction = elapsed.as_secs_f64() / self.duration.as_secs_f64();
 
         // Clamp fraction into [0, 1], because the easing function is defined in that range
@@ -1,6 +1,5 @@
-// TODO: Use SeedRng so we can use fuzzing for testing
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
 
 /// kmeans clustering
 ///
@@ -11,9 +10,21 @@ use rand::thread_rng;
 /// where each element is the index from 0..k showing which group the element
 /// belongs to -> This can be used to calculate the average for each group
 pub fn kmeans(values: &[f64], k: usize, max_iters: usize) -> Vec<usize> {
-    let mut rng = thread_rng();
+    kmeans_with_rng(values, k, max_iters, &mut thread_rng())
+}
 
-    let mut centroids: Vec<f64> = values.choose_multiple(&mut rng, k).cloned().collect();
+/// [kmeans] clustering, choosing the initial centroids with a given [`Rng`]
+///
+/// A seeded [`Rng`] always finds the same clusters for the same values.
+///
+/// # Panics
+///
+/// Panics if `k` is zero while there are values to cluster.
+pub fn kmeans_with_rng<R>(values: &[f64], k: usize, max_iters: usize, rng: &mut R) -> Vec<usize>
+where
+    R: Rng + ?Sized,
+{
+    let mut centroids: Vec<f64> = values.choose_multiple(rng, k).cloned().collect();
     let mut assignments = vec![0; values.len()];
 
     for _ in 0..max_iters {
@@ -68,3 +79,47 @@ pub fn average_cluster_sizes(values: &[f64], assignments: &[usize], k: usize) ->
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{average_cluster_sizes, kmeans_with_rng};
+
+    proptest! {
+        /// Test that every value is assigned to one of the clusters, even
+        /// NaN and the infinities, and that the same seed always finds the
+        /// same clusters
+        #[test]
+        fn assigns_every_value(
+            values in vec(prop::num::f64::ANY, 0..50),
+            k in 1..5usize,
+            seed in any::<u64>(),
+        ) {
+            let assignments = kmeans_with_rng(&values, k, 20, &mut StdRng::seed_from_u64(seed));
+            prop_assert_eq!(assignments.len(), values.len());
+            prop_assert!(assignments.iter().all(|cluster| *cluster < k));
+            prop_assert_eq!(
+                &assignments,
+                &kmeans_with_rng(&values, k, 20, &mut StdRng::seed_from_u64(seed))
+            );
+            prop_assert_eq!(average_cluster_sizes(&values, &assignments, k).len(), k);
+        }
+
+        /// Test that the average of every cluster of sensor values lies
+        /// within the values
+        #[test]
+        fn averages_within_values(values in vec(any::<u8>(), 1..100), seed in any::<u64>()) {
+            let values: Vec<f64> = values.into_iter().map(f64::from).collect();
+            let assignments = kmeans_with_rng(&values, 2, 100, &mut StdRng::seed_from_u64(seed));
+            let averages = average_cluster_sizes(&values, &assignments, 2);
+
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            for cluster in (0..2).filter(|cluster| assignments.contains(cluster)) {
+                prop_assert!(averages[cluster] >= min - 1e-9 && averages[cluster] <= max + 1e-9);
+            }
+        }
+    }
+}
@@ -7,9 +7,19 @@
 //
 // should use kmeans clustering (https://docs.rs/kmeans/latest/kmeans/)
 
+use std::fmt::Display;
+
+// Only enables seeding the random number generator in the browser
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use getrandom as _;
+
 mod kmeans;
 use kmeans::{average_cluster_sizes, kmeans};
 
+/// Smallest difference between the line and the floor of a plausible
+/// [`SensorCalibration`]
+pub const MIN_CONTRAST: u8 = 10;
+
 /// Log sensor values to calibrate a sensor
 #[derive(Debug, Default)]
 pub struct SingleSensorCalibration {
@@ -32,27 +42,85 @@ impl SingleSensorCalibration {
         let assignments = kmeans(self.data.as_slice(), 2, 100);
         let averages = average_cluster_sizes(self.data.as_slice(), assignments.as_slice(), 2);
 
+        // Skip empty clusters, e.g. when every value was the same, so they
+        // don't pass for a floor of 0
+        let averages: Vec<f64> = (0..2)
+            .filter(|cluster| assignments.contains(cluster))
+            .map(|cluster| averages[cluster])
+            .collect();
+
+        // A logged NaN makes its cluster average NaN, which `total_cmp` orders
+        // instead of panicking like `partial_cmp().unwrap()`
         let min = averages
             .iter()
             .copied()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
+            .min_by(f64::total_cmp)
+            .unwrap_or_default();
         let max = averages
             .iter()
             .copied()
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
-
-        // TODO: remove this
-        dbg!(min);
-        dbg!(max);
+            .max_by(f64::total_cmp)
+            .unwrap_or_default();
 
         SensorCalibration::new(max as u8, min as u8)
     }
 }
 
+/// Log values of the left and right sensor to calibrate both
+#[derive(Debug, Default)]
+pub struct DualSensorCalibration {
+    left: SingleSensorCalibration,
+    right: SingleSensorCalibration,
+}
+
+impl DualSensorCalibration {
+    /// Log a value of the left and right sensor, read at the same time
+    pub fn log(&mut self, left: f64, right: f64) {
+        self.left.log(left);
+        self.right.log(right);
+    }
+
+    /// Generate a [`SensorCalibration`] of the left and right sensor
+    ///
+    /// Fails if a sensor barely tells the line from the floor, see
+    /// [`SensorCalibration::validate`].
+    pub fn calibrate(self) -> Result<(SensorCalibration, SensorCalibration), CalibrationError> {
+        let left = self.left.calibrate();
+        let right = self.right.calibrate();
+        left.validate().map_err(CalibrationError::Left)?;
+        right.validate().map_err(CalibrationError::Right)?;
+        Ok((left, right))
+    }
+}
+
+/// A [`SensorCalibration`] that can't tell the line from the floor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalibrationError {
+    /// Implausible calibration of the left sensor
+    Left(SensorCalibration),
+    /// Implausible calibration of the right sensor
+    Right(SensorCalibration),
+}
+
+impl Display for CalibrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (sensor, calibration) = match self {
+            Self::Left(calibration) => ("left", calibration),
+            Self::Right(calibration) => ("right", calibration),
+        };
+        write!(
+            f,
+            "the {sensor} sensor can't tell the line ({}) from the floor ({})",
+            calibration.line, calibration.floor
+        )
+    }
+}
+
+impl std::error::Error for CalibrationError {}
+
 /// The end result of calibrating a sensor
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SensorCalibration {
     /// The sensor value of the line
     pub line: u8,
@@ -70,4 +138,54 @@ impl SensorCalibration {
     pub fn average(&self) -> f64 {
         (self.line as f64 + self.floor as f64) / 2.0
     }
+
+    /// Check that the line is at least [`MIN_CONTRAST`] above the floor,
+    /// otherwise the sensor likely never saw the line while calibrating
+    pub fn validate(&self) -> Result<(), SensorCalibration> {
+        if self.line.saturating_sub(self.floor) < MIN_CONTRAST {
+            return Err(*self);
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use crate::{DualSensorCalibration, SingleSensorCalibration};
+
+    /// Test that calibrating without any values fails instead of passing
+    /// for a line and floor of 0
+    #[test]
+    fn calibrate_nothing() {
+        assert!(DualSensorCalibration::default().calibrate().is_err());
+    }
+
+    proptest! {
+        /// Test that calibrating never panics, whatever was logged
+        #[test]
+        fn calibrate_any_values(values in vec(prop::num::f64::ANY, 0..100)) {
+            let mut calibration = DualSensorCalibration::default();
+            for pair in values.chunks(2) {
+                calibration.log(pair[0], *pair.last().unwrap());
+            }
+            let _ = calibration.calibrate();
+        }
+
+        /// Test that the floor of sensor values is never above the line,
+        /// and both lie within the values
+        #[test]
+        fn calibrate_sensor_values(values in vec(any::<u8>(), 1..100)) {
+            let mut sensor = SingleSensorCalibration::default();
+            for value in &values {
+                sensor.log(*value as f64);
+            }
+            let calibration = sensor.calibrate();
+
+            let (min, max) = (values.iter().min(), values.iter().max());
+            prop_assert!(calibration.floor <= calibration.line);
+            prop_assert!(Some(&calibration.floor) >= min && Some(&calibration.line) <= max);
+        }
+    }
 }
@@ -0,0 +1,96 @@
+//! Sensor channels selected for charting on the command line
+//!
+//! A channel is given either by the name of a known sensor, such as `left`, by
+//! the number of an ADC input, such as `2`, or by naming an ADC input, such as
+//! `battery=2`.
+
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Context, Error};
+use consts::{Sensors, ADC_CHANNELS};
+use interfaces::ToSensorChannel;
+
+/// A named sensor channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Channel {
+    /// Name of the channel shown on the chart and used in logs
+    pub name: String,
+    /// The ADC input the channel is read from
+    channel: u8,
+}
+
+impl Channel {
+    /// Create a [`Channel`] for a known sensor
+    fn sensor(name: &str, sensor: Sensors) -> Self {
+        Self {
+            name: name.to_string(),
+            channel: sensor.to_channel(),
+        }
+    }
+}
+
+impl ToSensorChannel for &Channel {
+    fn to_channel(&self) -> u8 {
+        self.channel
+    }
+}
+
+impl FromStr for Channel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, channel) = match s.split_once('=') {
+            Some((name, channel)) => (name.trim(), channel.trim()),
+            None => match s.trim() {
+                "left" => return Ok(Self::sensor("left", Sensors::Left)),
+                "right" => return Ok(Self::sensor("right", Sensors::Right)),
+                channel => ("", channel),
+            },
+        };
+
+        let channel: u8 = channel
+            .parse()
+            .with_context(|| format!("unknown channel `{s}`, expected a sensor or ADC input"))?;
+        ensure!(
+            channel < ADC_CHANNELS,
+            "ADC input {channel} does not exist, expected 0 to {}",
+            ADC_CHANNELS - 1
+        );
+
+        let name = match name {
+            "" => format!("ch{channel}"),
+            name if name == "elapsed_us" || name.contains([',', '"']) => {
+                bail!("invalid channel name `{name}`")
+            }
+            name => name.to_string(),
+        };
+        Ok(Self { name, channel })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use interfaces::ToSensorChannel;
+
+    use super::Channel;
+
+    /// Test parsing sensors, ADC inputs and named ADC inputs
+    #[test]
+    fn parse_channels() {
+        let right: Channel = "right".parse().unwrap();
+        assert_eq!((right.name.as_str(), (&right).to_channel()), ("right", 1));
+
+        let input: Channel = "3".parse().unwrap();
+        assert_eq!((input.name.as_str(), (&input).to_channel()), ("ch3", 3));
+
+        let battery: Channel = "battery=2".parse().unwrap();
+        assert_eq!(
+            (battery.name.as_str(), (&battery).to_channel()),
+            ("battery", 2)
+        );
+
+        assert!("4".parse::<Channel>().is_err());
+        assert!("imu".parse::<Channel>().is_err());
+        assert!("elapsed_us=0".parse::<Channel>().is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Recent charted points and the part of them shown on the chart
+
+use std::collections::VecDeque;
+
+/// How many points are kept for scrolling back
+pub const CAPACITY: usize = 65536;
+
+/// How many points are shown on the chart by default
+pub const DEFAULT_WINDOW: usize = 256;
+
+/// The least points shown on the chart
+pub const MIN_WINDOW: usize = 16;
+
+/// The most recent points with a window that can be resized and scrolled
+#[derive(Debug)]
+pub struct History<T> {
+    /// The kept points, oldest first
+    samples: VecDeque<T>,
+    /// How many points are shown
+    window: usize,
+    /// How many points the window is scrolled back from the newest point
+    offset: usize,
+}
+
+impl<T> History<T> {
+    /// Create an empty [`History`]
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(CAPACITY),
+            window: DEFAULT_WINDOW,
+            offset: 0,
+        }
+    }
+
+    /// Add a point, dropping the oldest one once full
+    pub fn push(&mut self, sample: T) {
+        if self.samples.len() == CAPACITY {
+            self.samples.pop_front();
+        };
+        self.samples.push_back(sample);
+    }
+
+    /// The points shown on the chart, oldest first
+    pub fn visible(&self) -> impl Iterator<Item = &T> {
+        let end = self.samples.len() - self.offset;
+        let start = end.saturating_sub(self.window);
+        self.samples.range(start..end)
+    }
+
+    /// How many points are shown
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// How many points the window is scrolled back from the newest point
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Scroll the window back in time by a number of points, or forward
+    /// when negative
+    pub fn scroll(&mut self, by: isize) {
+        let max = self.samples.len().saturating_sub(self.window);
+        self.offset = self.offset.saturating_add_signed(by).min(max);
+    }
+
+    /// Scroll back to the newest point
+    pub fn follow(&mut self) {
+        self.offset = 0;
+    }
+
+    /// Show twice as many points
+    pub fn zoom_out(&mut self) {
+        self.window = (self.window * 2).min(CAPACITY);
+        self.scroll(0);
+    }
+
+    /// Show half as many points
+    pub fn zoom_in(&mut self) {
+        self.window = (self.window / 2).max(MIN_WINDOW);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::samples::Sample;
+
+    use super::{History, DEFAULT_WINDOW};
+
+    /// A [`History`] holding samples whose values count up from zero
+    fn counting(len: usize) -> History<Sample> {
+        let mut history = History::new();
+        for i in 0..len {
+            history.push(Sample {
+                elapsed_us: i as u64,
+                values: vec![i as u8],
+            });
+        }
+        history
+    }
+
+    /// The elapsed times of the visible samples
+    fn visible(history: &History<Sample>) -> Vec<u64> {
+        history.visible().map(|sample| sample.elapsed_us).collect()
+    }
+
+    /// Test that the window shows the newest samples and scrolls within the history
+    #[test]
+    fn scroll_window() {
+        let mut history = counting(1000);
+        assert_eq!(visible(&history).len(), DEFAULT_WINDOW);
+        assert_eq!(visible(&history).last(), Some(&999));
+
+        history.scroll(100);
+        assert_eq!(visible(&history).last(), Some(&899));
+
+        // Scrolling stops at the oldest sample
+        history.scroll(10_000);
+        assert_eq!(visible(&history).first(), Some(&0));
+
+        history.scroll(-10_000);
+        assert_eq!(history.offset(), 0);
+    }
+
+    /// Test that zooming changes the amount of visible samples
+    #[test]
+    fn zoom_window() {
+        let mut history = counting(1000);
+        history.zoom_in();
+        assert_eq!(visible(&history).len(), DEFAULT_WINDOW / 2);
+
+        // Zooming out while scrolled keeps the window inside the history
+        history.scroll(10_000);
+        history.zoom_out();
+        history.zoom_out();
+        history.zoom_out();
+        assert_eq!(history.window(), DEFAULT_WINDOW * 4);
+        assert_eq!(visible(&history).first(), Some(&0));
+        assert_eq!(visible(&history).len(), 1000);
+    }
+}
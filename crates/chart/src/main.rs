@@ -1,133 +1,632 @@
 //! Create terminal chart from sensor values
+//!
+//! Each channel is charted in its own pane, next to a panel with the statistics
+//! of the charted window. The panes show the values over time, or a histogram
+//! of the charted window. The charted channels are selected using `--channels`,
+//! and default to the left and right sensors. The sensor values are charted
+//! live, and can be logged to a file using `--log`. A logged session can later
+//! be charted again using `--replay`. With `--remote`, the sensors of a logbot
+//! running the `server` are charted from its telemetry stream instead. With `--pid`, the terms of the line
+//! following controller are charted next to the sensor values.
+//!
+//! While charting, `space` pauses and resumes the stream, the arrow keys scroll
+//! through the history while paused, `[`/`]` shrink and grow the window, `+`/`-`
+//! change the polling interval, `h` switches between the values and the
+//! histogram and `esc` exits.
 
-use std::{collections::VecDeque, io::Stdout, time::Duration};
+use std::{
+    io::Stdout,
+    path::PathBuf,
+    sync::mpsc::{Receiver, TryRecvError},
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
+use anyhow::{bail, ensure, Result};
+use clap::Parser;
 use components::SensorController;
-use consts::Sensors;
 use defaults::TryDefault;
 use interfaces::SensorRead;
+use line::FollowLineStep;
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
     prelude::CrosstermBackend,
-    style::Style,
+    style::{Color, Style, Stylize},
     symbols,
-    widgets::{Axis, Block, Chart, Dataset},
-    Terminal,
+    text::Line,
+    widgets::{Axis, Bar, BarChart, BarGroup, Block, Chart, Dataset, GraphType, Paragraph},
+    Frame, Terminal,
 };
 
-/// How many values to show on the chart
-const HISTORY_SIZE: usize = 256;
+mod channel;
+mod history;
+mod pid;
+mod remote;
+mod samples;
+mod stats;
+
+use channel::Channel;
+use history::History;
+use pid::Pid;
+use samples::{Sample, SampleWriter};
+use stats::{Stats, BINS, BIN_WIDTH};
+use tuning::Preset;
 
-/// How often the sensors are polled for values
+/// How often the sensors are polled for values by default
 const INTERVAL: Duration = Duration::from_millis(1);
 
-/// Produce a live [`Chart`] of sensor events to the terminal
+/// The shortest polling interval
+const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The longest polling interval
+const MAX_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Colors of the charted channels, repeated when there are more channels
+const COLORS: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Cyan,
+    Color::Blue,
+    Color::LightMagenta,
+];
+
+/// Width of the statistics panel
+const STATS_WIDTH: u16 = 24;
+
+/// Description of the controls
+const HELP: &str = "space pause | ←/→ scroll | [/] window | +/- poll rate | h histogram | esc exit";
+
+/// Chart the values of the sensors
+#[derive(Parser)]
+struct Args {
+    /// Comma separated channels to chart: `left`, `right`, an ADC input such
+    /// as `2`, or a named ADC input such as `battery=2`
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values = ["left", "right"],
+        conflicts_with = "replay"
+    )]
+    channels: Vec<Channel>,
+    /// Log timestamped samples to this file, as CSV (`.csv`) or JSON lines (`.jsonl`)
+    #[arg(long, conflicts_with = "replay")]
+    log: Option<PathBuf>,
+    /// Chart the samples of a file written by `--log` instead of the sensors
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    /// Chart the sensors of a logbot running the `server` at this URL
+    #[arg(long, conflicts_with_all = ["replay", "channels"])]
+    remote: Option<String>,
+    /// Run the line following controller on the `left` channel and chart its
+    /// error, derivative and control output
+    #[arg(long)]
+    pid: bool,
+    /// Sensor calibration the controller follows the line with
+    #[arg(long, default_value = "calibration.toml", requires = "pid")]
+    calibration: PathBuf,
+    /// Tuning preset (slow, demo or race) of the controller
+    #[arg(long, default_value_t = Preset::Race, requires = "pid")]
+    preset: Preset,
+    /// TOML file overriding values of the tuning preset
+    #[arg(long, requires = "pid")]
+    tuning: Option<PathBuf>,
+}
+
+/// A charted [`Sample`] with the step of the controller, when it runs
+#[derive(Debug)]
+struct Point {
+    /// The sample
+    sample: Sample,
+    /// The step of the controller
+    pid: Option<FollowLineStep>,
+}
+
+/// What the sensor panes show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    /// The values over time
+    Values,
+    /// A histogram of the values, binned by [`BIN_WIDTH`]
+    Histogram,
+}
+
+impl View {
+    /// Switch to the other [`View`]
+    fn toggle(self) -> Self {
+        match self {
+            Self::Values => Self::Histogram,
+            Self::Histogram => Self::Values,
+        }
+    }
+}
+
+/// Where the charted [`Sample`]s come from
+#[derive(Debug)]
+enum Source<'a> {
+    /// Read from the sensors, logging the samples when a [`SampleWriter`] is given
+    Live {
+        /// The sensors
+        sensors: SensorController,
+        /// The charted channels
+        channels: Vec<Channel>,
+        /// [`Instant`] the samples are timed from
+        start: Instant,
+        /// Writer logging the samples
+        writer: Option<SampleWriter>,
+    },
+    /// Samples streamed by a logbot `server`, logging the samples when a
+    /// [`SampleWriter`] is given
+    Remote {
+        /// Receiver of the streamed samples
+        samples: Receiver<Result<Sample>>,
+        /// Writer logging the samples
+        writer: Option<SampleWriter>,
+    },
+    /// Recorded samples replayed with their original timing
+    Replay {
+        /// The recorded samples
+        samples: &'a [Sample],
+        /// Index of the next sample to replay
+        next: usize,
+    },
+}
+
+impl Source<'_> {
+    /// The new samples due at a point of the chart's [`Clock`]
+    fn poll(&mut self, elapsed: Duration) -> Result<Vec<Sample>> {
+        match self {
+            Self::Live {
+                sensors,
+                channels,
+                start,
+                writer,
+            } => {
+                // Live samples are timed in real time, including pauses
+                let sample = Sample {
+                    elapsed_us: start.elapsed().as_micros() as u64,
+                    values: channels
+                        .iter()
+                        .map(|channel| sensors.read(channel))
+                        .collect::<Result<_, _>>()?,
+                };
+                if let Some(writer) = writer {
+                    writer.write(&sample)?;
+                };
+                Ok(vec![sample])
+            }
+            Self::Remote { samples, writer } => {
+                let mut due = Vec::new();
+                loop {
+                    match samples.try_recv() {
+                        Ok(sample) => due.push(sample?),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => bail!("the telemetry stream ended"),
+                    };
+                }
+                if let Some(writer) = writer {
+                    for sample in &due {
+                        writer.write(sample)?;
+                    }
+                };
+                Ok(due)
+            }
+            Self::Replay { samples, next } => {
+                let due = samples[*next..].partition_point(|s| s.elapsed() <= elapsed);
+                *next += due;
+                Ok(samples[*next - due..*next].to_vec())
+            }
+        }
+    }
+}
+
+impl Source<'_> {
+    /// The [`SampleWriter`] logging the samples
+    fn writer(&mut self) -> Option<&mut SampleWriter> {
+        match self {
+            Self::Live { writer, .. } | Self::Remote { writer, .. } => writer.as_mut(),
+            Self::Replay { .. } => None,
+        }
+    }
+}
+
+/// Time spent charting, excluding pauses
+#[derive(Debug)]
+struct Clock {
+    /// [`Instant`] charting started
+    start: Instant,
+    /// Total time spent in completed pauses
+    paused: Duration,
+    /// [`Instant`] the current pause started
+    paused_since: Option<Instant>,
+}
+
+impl Clock {
+    /// Start a new [`Clock`]
+    fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            paused: Duration::ZERO,
+            paused_since: None,
+        }
+    }
+
+    /// Time spent charting, excluding pauses
+    fn elapsed(&self) -> Duration {
+        let end = self.paused_since.unwrap_or_else(Instant::now);
+        end.duration_since(self.start).saturating_sub(self.paused)
+    }
+
+    /// Whether the clock is paused
+    fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    /// Pause a running clock, or resume a paused one
+    fn toggle(&mut self) {
+        match self.paused_since.take() {
+            Some(since) => self.paused += since.elapsed(),
+            None => self.paused_since = Some(Instant::now()),
+        };
+    }
+}
+
+/// Wait at most `timeout` for the next key press
+fn next_key(timeout: Duration) -> Result<Option<KeyCode>> {
+    if event::poll(timeout)? {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                return Ok(Some(key.code));
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Produce a [`Chart`] of sensor events to the terminal until Esc is pressed
 fn chart(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-    sensors: &mut SensorController,
+    source: &mut Source,
+    channels: &[String],
+    mut pid: Option<Pid>,
 ) -> Result<()> {
-    // Store values from the left sensor
-    let mut left_history = VecDeque::with_capacity(HISTORY_SIZE);
-    // Store values from the right sensor
-    let mut right_history = VecDeque::with_capacity(HISTORY_SIZE);
+    let mut history = History::new();
+    let gains = pid.as_ref().map(Pid::gains);
+    let mut clock = Clock::start();
+    let mut interval = INTERVAL;
+    let mut view = View::Values;
 
     loop {
-        // Read new values from the sensors
-        let left = sensors.read(Sensors::Left)?;
-        let right = sensors.read(Sensors::Right)?;
-
-        // Ensure that history stays below history length
-        // after inserting new value
-        if left_history.len() == right_history.capacity() {
-            left_history.pop_front();
+        if !clock.is_paused() {
+            for sample in source.poll(clock.elapsed())? {
+                let pid = pid.as_mut().map(|pid| pid.step(&sample));
+                history.push(Point { sample, pid });
+            }
         };
-        left_history.push_back(left);
 
-        // Do the same for the right history
-        if right_history.len() == right_history.capacity() {
-            right_history.pop_front();
+        // Draw new graph to terminal
+        let status = status(&history, &clock, interval);
+        terminal
+            .draw(|frame| render(frame, &history, channels, gains.as_deref(), view, &status))?;
+
+        // Wait for the next poll while handling keys
+        let scroll = (history.window() / 8) as isize;
+        match next_key(interval)? {
+            Some(KeyCode::Esc) => return Ok(()),
+            Some(KeyCode::Char(' ')) => {
+                clock.toggle();
+                history.follow();
+            }
+            Some(KeyCode::Left) if clock.is_paused() => history.scroll(scroll),
+            Some(KeyCode::Right) if clock.is_paused() => history.scroll(-scroll),
+            Some(KeyCode::Char('[')) => history.zoom_in(),
+            Some(KeyCode::Char(']')) => history.zoom_out(),
+            Some(KeyCode::Char('+')) => interval = (interval / 2).max(MIN_INTERVAL),
+            Some(KeyCode::Char('-')) => interval = (interval * 2).min(MAX_INTERVAL),
+            Some(KeyCode::Char('h')) => view = view.toggle(),
+            _ => {}
         };
-        right_history.push_back(right);
+    }
+}
 
-        // Check if the user wants to exit using Esc
-        if event::poll(Duration::ZERO)? {
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Esc {
-                    return Ok(());
-                }
+/// Describe the state of the chart
+fn status(history: &History<Point>, clock: &Clock, interval: Duration) -> String {
+    let state = match (clock.is_paused(), history.offset()) {
+        (false, _) => "running".to_string(),
+        (true, 0) => "paused".to_string(),
+        (true, offset) => format!("paused, {offset} samples back"),
+    };
+    format!(
+        "{state} | window {} samples | interval {}ms",
+        history.window(),
+        interval.as_millis()
+    )
+}
+
+/// Render a pane per channel and the statistics of a [`History`] into a
+/// [`Frame`], with panes of the controller when its gains are given
+fn render(
+    frame: &mut Frame,
+    history: &History<Point>,
+    channels: &[String],
+    gains: Option<&str>,
+    view: View,
+    status: &str,
+) {
+    let [main_area, help_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+    let [charts_area, stats_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(STATS_WIDTH)]).areas(main_area);
+
+    // The controller is only charted over time
+    let pid_panes = match (gains, view) {
+        (Some(_), View::Values) => 2,
+        _ => 0,
+    };
+    let areas =
+        Layout::vertical(vec![Constraint::Fill(1); channels.len() + pid_panes]).split(charts_area);
+
+    for (i, (name, area)) in channels.iter().zip(areas.iter()).enumerate() {
+        let color = COLORS[i % COLORS.len()];
+
+        // Only the first pane shows the status
+        let block = match i {
+            0 => Block::bordered().title(status),
+            _ => Block::bordered(),
+        };
+
+        match view {
+            View::Values => {
+                let data = series(history, |point| point.sample.values[i] as f64);
+                let chart = sensor_chart(name, color, &data, history.window());
+                frame.render_widget(chart.block(block), *area);
             }
-        }
+            View::Histogram => {
+                let bins = stats::histogram(history.visible().map(|point| point.sample.values[i]));
+                let chart = sensor_histogram(name, color, &bins, block.inner(*area).width);
+                frame.render_widget(chart.block(block), *area);
+            }
+        };
+    }
 
-        // Draw new graph to terminal
-        terminal.draw(|frame| {
-            // Create Datasets from history
-            let left_data: Vec<(f64, f64)> = left_history
-                .iter()
-                .enumerate()
-                .map(|(i, v)| (i as f64, v.clone() as f64))
-                .collect();
-            let left_dataset = Dataset::default()
-                .name("Left Sensor")
-                .marker(symbols::Marker::Block)
-                .style(Style::default().fg(ratatui::style::Color::Red))
-                .data(&left_data);
-            let right_data: Vec<(f64, f64)> = right_history
-                .iter()
-                .enumerate()
-                .map(|(i, v)| (i as f64, v.clone() as f64))
-                .collect();
-            let right_dataset = Dataset::default()
-                .name("Right Sensor")
-                .marker(symbols::Marker::Block)
-                .style(Style::default().fg(ratatui::style::Color::Green))
-                .data(&right_data);
-
-            // Labels for sensor values on the y-axis
-            let labels = [
-                "0", "16", "32", "48", "64", "80", "96", "112", "128", "144", "160", "176", "192",
-                "208", "224", "250", "256",
-            ];
-
-            // Generate chart for the datasets
-            let chart = Chart::new(vec![left_dataset, right_dataset])
-                .block(Block::bordered())
-                .x_axis(
-                    Axis::default()
-                        .title("Time")
-                        .style(Style::default().fg(ratatui::style::Color::Magenta))
-                        .bounds([0.0, HISTORY_SIZE as f64]),
-                )
-                .y_axis(
-                    Axis::default()
-                        .title("Average")
-                        .style(Style::default().fg(ratatui::style::Color::Magenta))
-                        .bounds([0.0, 256.0])
-                        .labels(labels),
-                );
-
-            // Render chart to terminal
-            frame.render_widget(chart, frame.area());
-        })?;
-
-        // Sleep for the given interval
-        std::thread::sleep(INTERVAL);
+    if let (Some(gains), [error_area, control_area]) = (gains, &areas[channels.len()..]) {
+        // Samples charted before the controller ran have no step
+        let term = |f: fn(&FollowLineStep) -> f64| {
+            series(history, |point| point.pid.as_ref().map_or(0.0, f))
+        };
+        let error = term(|step| step.error);
+        let derivative = term(|step| step.derivative);
+        let control = term(|step| step.control);
+        let left = term(|step| step.direction.left.signed());
+        let right = term(|step| step.direction.right.signed());
+
+        let error_chart = signal_chart(
+            vec![
+                signal("error", Color::Yellow, &error),
+                signal("derivative", Color::Cyan, &derivative),
+            ],
+            bound(&[&error, &derivative]),
+            history.window(),
+        );
+        let control_chart = signal_chart(
+            vec![
+                signal("control", Color::White, &control),
+                signal("left motor", Color::Red, &left),
+                signal("right motor", Color::Green, &right),
+            ],
+            bound(&[&control, &left, &right]),
+            history.window(),
+        );
+        frame.render_widget(
+            error_chart.block(Block::bordered().title(format!("Controller {gains}"))),
+            *error_area,
+        );
+        frame.render_widget(control_chart.block(Block::bordered()), *control_area);
+    };
+
+    frame.render_widget(stats(history, channels), stats_area);
+    frame.render_widget(Paragraph::new(HELP), help_area);
+}
+
+/// Points of a value of the visible [`Point`]s
+fn series(history: &History<Point>, value: impl Fn(&Point) -> f64) -> Vec<(f64, f64)> {
+    history
+        .visible()
+        .enumerate()
+        .map(|(i, point)| (i as f64, value(point)))
+        .collect()
+}
+
+/// Create a [`Dataset`] of a signal of the controller
+fn signal<'a>(name: &'a str, color: Color, data: &'a [(f64, f64)]) -> Dataset<'a> {
+    Dataset::default()
+        .name(name)
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(data)
+}
+
+/// The largest magnitude of some signals, at least 1.0 to keep the scale
+/// when the signals settle at zero
+fn bound(signals: &[&[(f64, f64)]]) -> f64 {
+    signals
+        .iter()
+        .flat_map(|signal| signal.iter())
+        .map(|(_, value)| value.abs())
+        .fold(1.0, f64::max)
+}
+
+/// Create a [`Chart`] of signals of the controller within `-bound..=bound`
+fn signal_chart(datasets: Vec<Dataset>, bound: f64, window: usize) -> Chart {
+    let labels = [-bound, 0.0, bound].map(|label| format!("{label:.1}"));
+
+    Chart::new(datasets)
+        .x_axis(
+            Axis::default()
+                .title("Time")
+                .style(Style::default().fg(Color::Magenta))
+                .bounds([0.0, window as f64]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Magenta))
+                .bounds([-bound, bound])
+                .labels(labels),
+        )
+}
+
+/// Create the [`Chart`] of a single channel's values
+fn sensor_chart<'a>(
+    name: &'a str,
+    color: Color,
+    data: &'a [(f64, f64)],
+    window: usize,
+) -> Chart<'a> {
+    let dataset = Dataset::default()
+        .name(name)
+        .marker(symbols::Marker::Block)
+        .style(Style::default().fg(color))
+        .data(data);
+
+    // Labels for sensor values on the y-axis
+    let labels = ["0", "64", "128", "192", "256"];
+
+    // Generate chart for the dataset
+    Chart::new(vec![dataset])
+        .x_axis(
+            Axis::default()
+                .title("Time")
+                .style(Style::default().fg(Color::Magenta))
+                .bounds([0.0, window as f64]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("Value")
+                .style(Style::default().fg(Color::Magenta))
+                .bounds([0.0, 256.0])
+                .labels(labels),
+        )
+}
+
+/// Create a [`BarChart`] of a single channel's histogram, sized to fill `width`
+fn sensor_histogram<'a>(name: &'a str, color: Color, bins: &[u64], width: u16) -> BarChart<'a> {
+    // Label every fourth bin with the lowest value it covers
+    let bars: Vec<Bar> = bins
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let bar = Bar::default().value(count).text_value(String::new());
+            match i % 4 {
+                0 => bar.label(Line::from((i * BIN_WIDTH).to_string())),
+                _ => bar,
+            }
+        })
+        .collect();
+
+    BarChart::default()
+        .data(BarGroup::default().label(Line::from(name)).bars(&bars))
+        .bar_width((width / BINS as u16).saturating_sub(1).max(1))
+        .bar_gap(1)
+        .bar_style(Style::default().fg(color))
+}
+
+/// Create the statistics panel of the visible samples
+fn stats(history: &History<Point>, channels: &[String]) -> Paragraph<'static> {
+    let mut lines = Vec::new();
+    for (i, name) in channels.iter().enumerate() {
+        let stats = Stats::new(history.visible().map(|point| point.sample.values[i]));
+        lines.push(Line::from(name.clone()).bold());
+        match stats {
+            Some(stats) => lines.extend([
+                Line::from(format!("  min     {:>6}", stats.min)),
+                Line::from(format!("  max     {:>6}", stats.max)),
+                Line::from(format!("  mean    {:>6.1}", stats.mean)),
+                Line::from(format!("  stddev  {:>6.1}", stats.stddev)),
+            ]),
+            None => lines.push(Line::from("  no samples")),
+        };
+        lines.push(Line::default());
     }
+
+    let rate = match stats::sample_rate(history.visible().map(|point| &point.sample)) {
+        Some(rate) => format!("{rate:.0} samples/s"),
+        None => "-".to_string(),
+    };
+    lines.extend([
+        Line::from("Sample rate").bold(),
+        Line::from(format!("  {rate}")),
+    ]);
+
+    Paragraph::new(lines).block(Block::bordered().title("Statistics"))
 }
 
 /// Entrypoint for the `chart` binary
 fn main() -> Result<()> {
-    // Setup hardware
-    let mut controller = SensorController::try_default()?;
+    let args = Args::parse();
+
+    // Read the recording before setting up the terminal, to fail early
+    let recording = args.replay.map(samples::load).transpose()?;
+    let (mut source, channels) = match &recording {
+        Some(recording) => (
+            Source::Replay {
+                samples: &recording.samples,
+                next: 0,
+            },
+            recording.channels.clone(),
+        ),
+        None => {
+            let (names, samples) = match &args.remote {
+                Some(url) => {
+                    let (names, samples) = remote::connect(url)?;
+                    (names, Some(samples))
+                }
+                None => (args.channels.iter().map(|c| c.name.clone()).collect(), None),
+            };
+            for (i, name) in names.iter().enumerate() {
+                ensure!(!names[..i].contains(name), "channel `{name}` given twice");
+            }
+            let writer = args
+                .log
+                .map(|path| SampleWriter::create(path, names.clone()))
+                .transpose()?;
+            let source = match samples {
+                Some(samples) => Source::Remote { samples, writer },
+                None => Source::Live {
+                    // Setup hardware
+                    sensors: SensorController::try_default()?,
+                    channels: args.channels,
+                    start: Instant::now(),
+                    writer,
+                },
+            };
+            (source, names)
+        }
+    };
+
+    let pid = match args.pid {
+        true => Some(Pid::new(
+            &channels,
+            &args.calibration,
+            args.preset,
+            args.tuning.as_deref(),
+        )?),
+        false => None,
+    };
 
     // Setup terminal
     let mut terminal = ratatui::init();
 
-    // Produce a live chart of sensor events
-    let result = chart(&mut terminal, &mut controller);
+    // Produce a chart of sensor events
+    let result = chart(&mut terminal, &mut source, &channels, pid);
 
     // Restore terminal to original state
     ratatui::restore();
 
+    // Keep the samples logged before an error
+    if let Some(writer) = source.writer() {
+        writer.flush()?;
+    };
+
     result
 }
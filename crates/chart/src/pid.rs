@@ -0,0 +1,82 @@
+//! Running the line following controller on the charted samples
+//!
+//! The controller is run on the `left` channel, which logbot follows the line
+//! with, using the same calibration and tuning as the CLI. While logbot follows
+//! the line its controller sees the same sensor values, so the charted terms
+//! match those steering logbot. The derivative only matches when the chart
+//! samples at the rate logbot follows the line.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use calibration::SensorCalibration;
+use line::{FollowLineState, FollowLineStep};
+use tuning::{FollowTuning, Preset, Tuning};
+
+use crate::samples::Sample;
+
+/// Name of the channel logbot follows the line with
+pub const FOLLOWED_CHANNEL: &str = "left";
+
+/// The line following controller, stepped by the charted samples
+#[derive(Debug)]
+pub struct Pid {
+    /// The controller
+    state: FollowLineState,
+    /// Index of the followed channel in the samples
+    channel: usize,
+    /// The gains of the controller
+    gains: FollowTuning,
+}
+
+impl Pid {
+    /// Set up the controller for samples of some channels, using the saved
+    /// calibration and the tuning of a preset, overridden by a tuning file
+    pub fn new(
+        channels: &[String],
+        calibration: &Path,
+        preset: Preset,
+        tuning: Option<&Path>,
+    ) -> Result<Self> {
+        let channel = channels
+            .iter()
+            .position(|name| name == FOLLOWED_CHANNEL)
+            .with_context(|| {
+                format!("charting the controller needs the `{FOLLOWED_CHANNEL}` channel")
+            })?;
+
+        let value = std::fs::read_to_string(calibration)
+            .with_context(|| format!("failed to read `{}`", calibration.display()))?;
+        let calibration: SensorCalibration = toml::from_str(&value)
+            .with_context(|| format!("invalid calibration `{}`", calibration.display()))?;
+
+        let tuning = match tuning {
+            Some(path) => Tuning::from_file(preset, path)?,
+            None => preset.tuning(),
+        };
+
+        Ok(Self {
+            state: FollowLineState::new(tuning.follow_line_config(calibration)),
+            channel,
+            gains: tuning.follow,
+        })
+    }
+
+    /// Step the controller with the followed channel of a [`Sample`]
+    pub fn step(&mut self, sample: &Sample) -> FollowLineStep {
+        self.state.step_diagnostics(sample.values[self.channel])
+    }
+
+    /// Describe the gains of the controller
+    pub fn gains(&self) -> String {
+        let FollowTuning {
+            proportional,
+            derivative,
+            integral,
+        } = self.gains;
+        match integral {
+            Some(integral) => format!("P {proportional} D {derivative} I {integral}"),
+            None => format!("P {proportional} D {derivative}"),
+        }
+    }
+}
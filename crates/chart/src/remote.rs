@@ -0,0 +1,67 @@
+//! Receiving samples from the telemetry stream of a logbot `server`
+//!
+//! The server streams samples as JSON lines in the same [`Format`] as logged
+//! samples. The stream is read on its own thread, so the chart keeps drawing
+//! while waiting for samples.
+
+use std::{
+    io::{BufRead, BufReader, Lines},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use anyhow::{Context, Result};
+
+use crate::samples::{Format, Sample};
+
+/// Connect to the telemetry stream of a server at the given base URL
+///
+/// Returns the names of the streamed channels and a [`Receiver`] of the
+/// samples, timed from the first received sample.
+pub fn connect(url: &str) -> Result<(Vec<String>, Receiver<Result<Sample>>)> {
+    let url = format!("{}/v1/telemetry", url.trim_end_matches('/'));
+    let response = ureq::get(&url)
+        .call()
+        .with_context(|| format!("failed to reach `{url}`"))?;
+    let mut lines = BufReader::new(response.into_reader()).lines();
+
+    // The first sample names the channels
+    let first = lines
+        .next()
+        .context("the telemetry stream ended")?
+        .context("failed to read the telemetry stream")?;
+    let channels = Format::Jsonl
+        .channels(&first)
+        .context("invalid telemetry sample")?;
+    let start = Format::Jsonl.decode(&channels, &first)?.elapsed_us;
+
+    let (sender, receiver) = mpsc::channel();
+    let streamed = channels.clone();
+    thread::spawn(move || {
+        for sample in samples(lines, &streamed, start) {
+            let failed = sample.is_err();
+            // Stop once the chart exits or the stream fails
+            if sender.send(sample).is_err() || failed {
+                return;
+            };
+        }
+    });
+
+    Ok((channels, receiver))
+}
+
+/// Decode the streamed samples, timed from `start`
+fn samples<'a, R: BufRead + 'a>(
+    lines: Lines<R>,
+    channels: &'a [String],
+    start: u64,
+) -> impl Iterator<Item = Result<Sample>> + 'a {
+    lines.map(move |line| {
+        let line = line.context("failed to read the telemetry stream")?;
+        let mut sample = Format::Jsonl
+            .decode(channels, &line)
+            .context("invalid telemetry sample")?;
+        sample.elapsed_us = sample.elapsed_us.saturating_sub(start);
+        Ok(sample)
+    })
+}
@@ -0,0 +1,309 @@
+//! Logging sensor samples to a file and reading them back
+//!
+//! Samples are stored as CSV or as JSON lines, depending on the extension of
+//! the file. Both hold the microseconds since the start of the session and
+//! the value of every charted channel, named after the channel:
+//!
+//! ```text
+//! elapsed_us,left,right
+//! 1040,38,201
+//! ```
+//!
+//! ```json
+//! {"elapsed_us":1040,"left":38,"right":201}
+//! ```
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use serde_json::{Map, Value};
+
+/// Name of the timestamp column or key
+const ELAPSED: &str = "elapsed_us";
+
+/// Values of every charted channel at a point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    /// Microseconds since the start of the session
+    pub elapsed_us: u64,
+    /// Value of each channel, in the order of the channels
+    pub values: Vec<u8>,
+}
+
+impl Sample {
+    /// Time since the start of the session
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.elapsed_us)
+    }
+}
+
+/// Logged [`Sample`]s with the names of their channels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    /// Names of the channels
+    pub channels: Vec<String>,
+    /// The samples
+    pub samples: Vec<Sample>,
+}
+
+/// File format of logged [`Sample`]s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Comma separated values with a header, `.csv`
+    Csv,
+    /// One JSON object per line, `.jsonl`
+    Jsonl,
+}
+
+impl Format {
+    /// Select the [`Format`] using the extension of a path
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Ok(Self::Csv),
+            Some("jsonl") => Ok(Self::Jsonl),
+            _ => bail!(
+                "unknown sample format `{}`, expected a `.csv` or `.jsonl` file",
+                path.display()
+            ),
+        }
+    }
+
+    /// Encode a [`Sample`] of some channels as a single line
+    fn encode(self, channels: &[String], sample: &Sample) -> Result<String> {
+        match self {
+            Self::Csv => Ok(std::iter::once(sample.elapsed_us.to_string())
+                .chain(sample.values.iter().map(u8::to_string))
+                .collect::<Vec<_>>()
+                .join(",")),
+            Self::Jsonl => {
+                let mut object = Map::new();
+                object.insert(ELAPSED.to_string(), sample.elapsed_us.into());
+                for (channel, &value) in channels.iter().zip(&sample.values) {
+                    object.insert(channel.clone(), value.into());
+                }
+                Ok(serde_json::to_string(&object)?)
+            }
+        }
+    }
+
+    /// Decode a [`Sample`] of some channels from a single line
+    pub fn decode(self, channels: &[String], line: &str) -> Result<Sample> {
+        match self {
+            Self::Csv => {
+                let mut fields = line.split(',').map(str::trim);
+                let elapsed_us = fields
+                    .next()
+                    .ok_or_else(|| anyhow!("missing field `{ELAPSED}`"))?
+                    .parse()?;
+                let values = fields.map(str::parse).collect::<Result<Vec<u8>, _>>()?;
+                ensure!(
+                    values.len() == channels.len(),
+                    "expected {} fields",
+                    channels.len() + 1
+                );
+                Ok(Sample { elapsed_us, values })
+            }
+            Self::Jsonl => {
+                let object: Map<String, Value> = serde_json::from_str(line)?;
+                ensure!(
+                    object.len() == channels.len() + 1,
+                    "expected the keys `{ELAPSED}`, `{}`",
+                    channels.join("`, `")
+                );
+                let field = |name: &str| {
+                    object
+                        .get(name)
+                        .and_then(Value::as_u64)
+                        .ok_or_else(|| anyhow!("missing or invalid field `{name}`"))
+                };
+                let values = channels
+                    .iter()
+                    .map(|channel| Ok(u8::try_from(field(channel)?)?))
+                    .collect::<Result<_>>()?;
+                Ok(Sample {
+                    elapsed_us: field(ELAPSED)?,
+                    values,
+                })
+            }
+        }
+    }
+
+    /// Names of the channels given by the first line of a file
+    pub fn channels(self, line: &str) -> Result<Vec<String>> {
+        let names: Vec<String> = match self {
+            Self::Csv => line
+                .split(',')
+                .map(|name| name.trim().to_string())
+                .collect(),
+            Self::Jsonl => serde_json::from_str::<Map<String, Value>>(line)?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect(),
+        };
+        match names.split_first() {
+            Some((first, channels)) if first == ELAPSED && !channels.is_empty() => {
+                Ok(channels.to_vec())
+            }
+            _ => bail!("expected `{ELAPSED}` followed by the channels"),
+        }
+    }
+
+    /// Parse the [`Recording`] of a file's contents
+    pub fn parse(self, value: &str) -> Result<Recording> {
+        let mut lines = value
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .peekable();
+
+        let channels = match lines.peek() {
+            Some((_, line)) => self.channels(line).context("line 1: invalid header")?,
+            None => bail!("no samples"),
+        };
+        if self == Self::Csv {
+            lines.next();
+        };
+
+        let samples = lines
+            .map(|(index, line)| {
+                self.decode(&channels, line)
+                    .with_context(|| format!("line {}: invalid sample", index + 1))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Recording { channels, samples })
+    }
+}
+
+/// Writes [`Sample`]s to a file
+#[derive(Debug)]
+pub struct SampleWriter {
+    /// The buffered file
+    writer: BufWriter<File>,
+    /// Format of the file
+    format: Format,
+    /// Names of the channels
+    channels: Vec<String>,
+}
+
+impl SampleWriter {
+    /// Create a file to write samples of some channels to, in the [`Format`]
+    /// of its extension
+    pub fn create(path: impl AsRef<Path>, channels: Vec<String>) -> Result<Self> {
+        let path = path.as_ref();
+        let format = Format::from_path(path)?;
+        let file =
+            File::create(path).with_context(|| format!("failed to create `{}`", path.display()))?;
+
+        let mut writer = BufWriter::new(file);
+        if format == Format::Csv {
+            writeln!(writer, "{ELAPSED},{}", channels.join(","))?;
+        };
+        Ok(Self {
+            writer,
+            format,
+            channels,
+        })
+    }
+
+    /// Write a [`Sample`]
+    pub fn write(&mut self, sample: &Sample) -> Result<()> {
+        let line = self.format.encode(&self.channels, sample)?;
+        writeln!(self.writer, "{line}")?;
+        Ok(())
+    }
+
+    /// Write any buffered samples to the file
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Read the [`Recording`] of a file, in the [`Format`] of its extension
+pub fn load(path: impl AsRef<Path>) -> Result<Recording> {
+    let path = path.as_ref();
+    let format = Format::from_path(path)?;
+    let value = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+    format
+        .parse(&value)
+        .with_context(|| format!("invalid samples `{}`", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{load, Format, Recording, Sample, SampleWriter};
+
+    /// Test that samples survive a round trip through both formats
+    #[test]
+    fn round_trip() {
+        let recording = Recording {
+            channels: vec![
+                "right".to_string(),
+                "battery".to_string(),
+                "left".to_string(),
+            ],
+            samples: vec![
+                Sample {
+                    elapsed_us: 0,
+                    values: vec![200, 180, 40],
+                },
+                Sample {
+                    elapsed_us: 1040,
+                    values: vec![201, 179, 38],
+                },
+            ],
+        };
+
+        for extension in ["csv", "jsonl"] {
+            let path = std::env::temp_dir()
+                .join(format!("logbot-samples-{}.{extension}", std::process::id()));
+
+            let mut writer = SampleWriter::create(&path, recording.channels.clone()).unwrap();
+            for sample in &recording.samples {
+                writer.write(sample).unwrap();
+            }
+            writer.flush().unwrap();
+            drop(writer);
+
+            let loaded = load(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.unwrap(), recording);
+        }
+    }
+
+    /// Test that logs of the left and right sensors still load
+    #[test]
+    fn sensor_logs() {
+        let csv = Format::Csv.parse("elapsed_us,left,right\n1040,38,201\n");
+        let jsonl = Format::Jsonl.parse("{\"elapsed_us\":1040,\"left\":38,\"right\":201}\n");
+        for recording in [csv, jsonl] {
+            let recording = recording.unwrap();
+            assert_eq!(recording.channels, ["left", "right"]);
+            assert_eq!(recording.samples[0].values, [38, 201]);
+        }
+    }
+
+    /// Test that unknown formats and malformed lines are rejected
+    #[test]
+    fn invalid_samples() {
+        assert!(Format::from_path(Path::new("samples.txt")).is_err());
+        assert!(Format::Csv.parse("").is_err());
+        assert!(Format::Csv.parse("1,2,3\n").is_err());
+        assert!(Format::Csv.parse("elapsed_us\n1\n").is_err());
+        assert!(Format::Csv.parse("elapsed_us,left,right\n1,2\n").is_err());
+        assert!(Format::Csv
+            .parse("elapsed_us,left,right\n1,2,300\n")
+            .is_err());
+        assert!(Format::Jsonl.parse("{\"left\":1}\n").is_err());
+        assert!(Format::Jsonl
+            .parse("{\"elapsed_us\":1,\"left\":1}\n{\"elapsed_us\":2,\"right\":1}\n")
+            .is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! Statistics of the charted [`Sample`]s
+
+use std::time::Duration;
+
+use crate::samples::Sample;
+
+/// Statistics of the values of a single sensor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// The smallest value
+    pub min: u8,
+    /// The largest value
+    pub max: u8,
+    /// The average value
+    pub mean: f64,
+    /// The population standard deviation of the values
+    pub stddev: f64,
+}
+
+impl Stats {
+    /// Calculate the [`Stats`] of some values, [`None`] when there are none
+    pub fn new(values: impl IntoIterator<Item = u8>) -> Option<Self> {
+        let values: Vec<u8> = values.into_iter().collect();
+        let min = *values.iter().min()?;
+        let max = *values.iter().max()?;
+
+        let count = values.len() as f64;
+        let mean = values.iter().map(|&v| f64::from(v)).sum::<f64>() / count;
+        let variance = values
+            .iter()
+            .map(|&v| (f64::from(v) - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        Some(Self {
+            min,
+            max,
+            mean,
+            stddev: variance.sqrt(),
+        })
+    }
+}
+
+/// How many values of a sensor fall into each bin of a histogram
+pub const BIN_WIDTH: usize = 8;
+
+/// Number of bins in a histogram covering every sensor value
+pub const BINS: usize = 256 / BIN_WIDTH;
+
+/// Count how many values fall into each of the [`BINS`] of width [`BIN_WIDTH`]
+pub fn histogram(values: impl IntoIterator<Item = u8>) -> [u64; BINS] {
+    let mut bins = [0; BINS];
+    for value in values {
+        bins[value as usize / BIN_WIDTH] += 1;
+    }
+    bins
+}
+
+/// Samples per second between the first and the last of some [`Sample`]s,
+/// [`None`] when they don't span any time
+pub fn sample_rate<'a>(samples: impl IntoIterator<Item = &'a Sample>) -> Option<f64> {
+    let mut samples = samples.into_iter();
+    let first = samples.next()?;
+    let (count, last) = samples.fold((0, first), |(count, _), sample| (count + 1, sample));
+
+    let span = last.elapsed().saturating_sub(first.elapsed());
+    if span == Duration::ZERO {
+        return None;
+    };
+    Some(count as f64 / span.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::samples::Sample;
+
+    use super::{histogram, sample_rate, Stats, BINS};
+
+    /// Test the statistics of known values
+    #[test]
+    fn stats() {
+        assert_eq!(Stats::new([]), None);
+
+        let stats = Stats::new([2, 4, 4, 4, 5, 5, 7, 9]).unwrap();
+        assert_eq!(stats.min, 2);
+        assert_eq!(stats.max, 9);
+        assert_eq!(stats.mean, 5.0);
+        assert_eq!(stats.stddev, 2.0);
+    }
+
+    /// Test that values are counted in the bin covering them
+    #[test]
+    fn bins() {
+        let bins = histogram([0, 7, 8, 40, 40, 255]);
+        assert_eq!(bins[0], 2);
+        assert_eq!(bins[1], 1);
+        assert_eq!(bins[5], 2);
+        assert_eq!(bins[BINS - 1], 1);
+        assert_eq!(bins.iter().sum::<u64>(), 6);
+    }
+
+    /// Test that the sample rate is measured from the sample timestamps
+    #[test]
+    fn rate() {
+        let samples: Vec<Sample> = (0..=100)
+            .map(|i| Sample {
+                elapsed_us: 5_000_000 + i * 1000,
+                values: vec![0],
+            })
+            .collect();
+        let rate = sample_rate(&samples).unwrap();
+        assert!((rate - 1000.0).abs() < 1e-6);
+
+        assert_eq!(sample_rate(&samples[..1]), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! Selecting the motor implementation or the simulation at runtime
+
+use std::{fmt::Display, time::Duration};
+
+use anyhow::Result;
+use clap::ValueEnum;
+#[cfg(feature = "mock")]
+use components::mock::MockMotor;
+use components::sim::{SharedWorld, SimLift, SimMotor, SimSensors, SimSide};
+use components::{hardware_pwm, software_pwm, software_pwm::LiftMotor, SensorController};
+use defaults::{TryDefault, TryFromConfig};
+use directions::MotorDirection;
+use interfaces::{Drive, JogLift, Lift, SensorRead, ToSensorChannel};
+use rppal::{gpio, i2c, pwm};
+use speed::Speed;
+
+/// Motor implementations that can be selected with `--backend`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// [`hardware_pwm::DCMotor`] using the hardware PWM channels
+    #[default]
+    HardwarePwm,
+    /// [`software_pwm::DCMotor`] using software PWM on the GPIO pins
+    SoftwarePwm,
+    /// [`software_pwm::SignedMotor`] using a single signed PWM signal
+    Signed,
+    /// [`MockMotor`] that doesn't require any hardware, only the motors are mocked
+    #[cfg(feature = "mock")]
+    Mock,
+}
+
+impl Backend {
+    /// Time the motors need after being created, before they can be driven
+    pub fn activation_period(self) -> Duration {
+        match self {
+            #[cfg(feature = "mock")]
+            Self::Mock => Duration::ZERO,
+            _ => Duration::from_secs(5),
+        }
+    }
+}
+
+/// Motor of the selected [`Backend`]
+#[derive(Debug)]
+pub enum Motor<Side> {
+    /// [`Backend::HardwarePwm`]
+    HardwarePwm(hardware_pwm::DCMotor<Side>),
+    /// [`Backend::SoftwarePwm`]
+    SoftwarePwm(software_pwm::DCMotor<Side>),
+    /// [`Backend::Signed`]
+    Signed(software_pwm::SignedMotor<Side>),
+    /// [`Backend::Mock`]
+    #[cfg(feature = "mock")]
+    Mock(MockMotor<Side>),
+    /// Motor of the simulation started with `--sim`
+    Sim(SimMotor<Side>),
+}
+
+impl<Side> Motor<Side>
+where
+    hardware_pwm::DCMotor<Side>: TryFromConfig<Error = pwm::Error>,
+    software_pwm::DCMotor<Side>: TryFromConfig<Error = gpio::Error>,
+    software_pwm::SignedMotor<Side>: TryFromConfig<Error = gpio::Error>,
+{
+    /// Create the motor of a [`Backend`] from the hardware configuration
+    pub fn try_default(backend: Backend) -> Result<Self> {
+        Ok(match backend {
+            Backend::HardwarePwm => Self::HardwarePwm(hardware_pwm::DCMotor::try_default()?),
+            Backend::SoftwarePwm => Self::SoftwarePwm(software_pwm::DCMotor::try_default()?),
+            Backend::Signed => Self::Signed(software_pwm::SignedMotor::try_default()?),
+            #[cfg(feature = "mock")]
+            Backend::Mock => Self::Mock(MockMotor::new()),
+        })
+    }
+}
+
+impl<Side> Motor<Side> {
+    /// Create a motor driving the simulated logbot
+    pub fn sim(world: SharedWorld) -> Self {
+        Self::Sim(SimMotor::new(world))
+    }
+}
+
+impl<Side: SimSide> Drive for Motor<Side>
+where
+    hardware_pwm::DCMotor<Side>: Drive<Direction = MotorDirection, Error = pwm::Error>,
+    software_pwm::DCMotor<Side>: Drive<Direction = MotorDirection, Error = gpio::Error>,
+    software_pwm::SignedMotor<Side>: Drive<Direction = MotorDirection, Error = gpio::Error>,
+{
+    type Direction = MotorDirection;
+    type Error = MotorError;
+
+    fn drive(
+        &mut self,
+        direction: Self::Direction,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
+        match self {
+            Self::HardwarePwm(motor) => Ok(motor.drive(direction)?),
+            Self::SoftwarePwm(motor) => Ok(motor.drive(direction)?),
+            Self::Signed(motor) => Ok(motor.drive(direction)?),
+            #[cfg(feature = "mock")]
+            Self::Mock(motor) => Ok(motor.drive(direction).unwrap_or_else(|e| match e {})),
+            Self::Sim(motor) => Ok(motor.drive(direction).unwrap_or_else(|e| match e {})),
+        }
+    }
+
+    fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+        match self {
+            Self::HardwarePwm(motor) => Ok(motor.stop()?),
+            Self::SoftwarePwm(motor) => Ok(motor.stop()?),
+            Self::Signed(motor) => Ok(motor.stop()?),
+            #[cfg(feature = "mock")]
+            Self::Mock(motor) => Ok(motor.stop().unwrap_or_else(|e| match e {})),
+            Self::Sim(motor) => Ok(motor.stop().unwrap_or_else(|e| match e {})),
+        }
+    }
+}
+
+/// Errors of the [`Motor`] implementations
+#[derive(Debug)]
+pub enum MotorError {
+    /// Error of a motor using hardware PWM
+    Pwm(pwm::Error),
+    /// Error of a motor using software PWM
+    Gpio(gpio::Error),
+}
+
+impl From<pwm::Error> for MotorError {
+    fn from(value: pwm::Error) -> Self {
+        Self::Pwm(value)
+    }
+}
+
+impl From<gpio::Error> for MotorError {
+    fn from(value: gpio::Error) -> Self {
+        Self::Gpio(value)
+    }
+}
+
+impl Display for MotorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pwm(e) => e.fmt(f),
+            Self::Gpio(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for MotorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Pwm(e) => Some(e),
+            Self::Gpio(e) => Some(e),
+        }
+    }
+}
+
+/// Sensors of the hardware or the simulation
+#[derive(Debug)]
+pub enum SensorBackend {
+    /// The [`SensorController`] of the hardware
+    Hardware(SensorController),
+    /// Sensors of the simulation started with `--sim`
+    Sim(SimSensors),
+}
+
+impl SensorRead for SensorBackend {
+    type Output = u8;
+    type Error = i2c::Error;
+
+    fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
+        match self {
+            Self::Hardware(sensors) => sensors.read(sensor),
+            Self::Sim(sensors) => Ok(sensors.read(sensor).unwrap_or_else(|e| match e {})),
+        }
+    }
+}
+
+/// Lift of the hardware or the simulation
+#[derive(Debug)]
+pub enum LiftBackend {
+    /// The [`LiftMotor`] of the hardware
+    Hardware(Box<LiftMotor>),
+    /// Lift of the simulation started with `--sim`
+    Sim(SimLift),
+}
+
+impl Lift for LiftBackend {
+    type Error = gpio::Error;
+
+    fn up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        match self {
+            Self::Hardware(lift) => lift.up(speed),
+            Self::Sim(lift) => lift.up(speed).map_err(|e| match e {}),
+        }
+    }
+
+    fn down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        match self {
+            Self::Hardware(lift) => lift.down(speed),
+            Self::Sim(lift) => lift.down(speed).map_err(|e| match e {}),
+        }
+    }
+
+    fn is_up(&self) -> bool {
+        match self {
+            Self::Hardware(lift) => lift.is_up(),
+            Self::Sim(lift) => lift.is_up(),
+        }
+    }
+
+    fn is_down(&self) -> bool {
+        match self {
+            Self::Hardware(lift) => lift.is_down(),
+            Self::Sim(lift) => lift.is_down(),
+        }
+    }
+}
+
+impl JogLift for LiftBackend {
+    fn jog_up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        match self {
+            Self::Hardware(lift) => lift.jog_up(speed),
+            Self::Sim(lift) => lift.jog_up(speed).map_err(|e| match e {}),
+        }
+    }
+
+    fn jog_down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        match self {
+            Self::Hardware(lift) => lift.jog_down(speed),
+            Self::Sim(lift) => lift.jog_down(speed).map_err(|e| match e {}),
+        }
+    }
+
+    fn halt(&mut self) -> Result<(), Self::Error> {
+        match self {
+            Self::Hardware(lift) => lift.halt(),
+            Self::Sim(lift) => lift.halt().map_err(|e| match e {}),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+
+    use super::Backend;
+
+    /// Test that backends are selected using their kebab-case names
+    #[test]
+    fn backend_names() {
+        for (name, backend) in [
+            ("hardware-pwm", Backend::HardwarePwm),
+            ("software-pwm", Backend::SoftwarePwm),
+            ("signed", Backend::Signed),
+        ] {
+            assert_eq!(Backend::from_str(name, false).unwrap(), backend);
+        }
+        assert!(Backend::from_str("stepper", false).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+//! Storing a [`SensorCalibration`] between sessions
+//!
+//! The calibration is stored as TOML, e.g.
+//!
+//! ```toml
+//! line = 180
+//! floor = 40
+//! ```
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use calibration::SensorCalibration;
+
+/// File the calibration is saved to when no other file is given
+pub const DEFAULT_PATH: &str = "calibration.toml";
+
+/// Read a [`SensorCalibration`] from a file
+pub fn load(path: impl AsRef<Path>) -> Result<SensorCalibration> {
+    let path = path.as_ref();
+    let value = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+    toml::from_str(&value).with_context(|| format!("invalid calibration `{}`", path.display()))
+}
+
+/// Write a [`SensorCalibration`] to a file
+pub fn save(path: impl AsRef<Path>, calibration: &SensorCalibration) -> Result<()> {
+    let path = path.as_ref();
+    std::fs::write(path, toml::to_string(calibration)?)
+        .with_context(|| format!("failed to write `{}`", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use calibration::SensorCalibration;
+
+    use super::{load, save};
+
+    /// Test that a saved calibration is loaded unchanged
+    #[test]
+    fn round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("logbot-calibration-{}.toml", std::process::id()));
+        let calibration = SensorCalibration::new(180, 40);
+
+        save(&path, &calibration).unwrap();
+        let loaded = load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), calibration);
+    }
+}
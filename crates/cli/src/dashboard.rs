@@ -0,0 +1,223 @@
+//! Terminal dashboard showing the live state of logbot
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use components::sim::{Course, Pose, SharedWorld};
+use consts::Sensors;
+use interfaces::SensorRead;
+use logbot::snapshot::LiftPosition;
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Color, Style},
+    symbols::Marker,
+    text::Line,
+    widgets::{
+        canvas::{self, Canvas, Points},
+        Block, Gauge, Paragraph,
+    },
+    DefaultTerminal, Frame,
+};
+use speed::Speed;
+
+use crate::Logbot;
+
+/// Minimum time between two redraws of the [`Dashboard`]
+pub const REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What logbot is currently doing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Activity {
+    /// Driven by the user
+    Driving,
+    /// Calibrating the sensors
+    Calibrating,
+    /// Following the line
+    Following,
+    /// Replaying a recorded session
+    Replaying,
+    /// Stopped by the emergency stop, waiting to be re-armed
+    EmergencyStopped,
+}
+
+impl Activity {
+    /// Name of the activity shown on the dashboard
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Driving => "driving",
+            Self::Calibrating => "calibrating",
+            Self::Following => "following line",
+            Self::Replaying => "replaying session",
+            Self::EmergencyStopped => "emergency stopped, re-arm to drive",
+        }
+    }
+}
+
+/// Sensor readings shown on the [`Dashboard`]
+#[derive(Debug, Clone, Copy)]
+struct Readings {
+    /// Value of the left sensor
+    left: u8,
+    /// Value of the right sensor
+    right: u8,
+}
+
+/// Dashboard drawn to the terminal
+#[derive(Debug)]
+pub struct Dashboard {
+    /// The terminal the dashboard is drawn to
+    terminal: DefaultTerminal,
+    /// [`Instant`] of the last redraw
+    last_draw: Option<Instant>,
+    /// The driving [`Speed`]
+    pub speed: Speed,
+    /// What logbot is currently doing
+    pub activity: Activity,
+    /// Whether the session is being recorded
+    pub recording: bool,
+    /// Description of the controls
+    help: String,
+    /// The simulation, whose course is drawn when set
+    pub simulation: Option<SharedWorld>,
+}
+
+impl Dashboard {
+    /// Create a new [`Dashboard`] drawing to a terminal
+    pub fn new(terminal: DefaultTerminal, speed: Speed, recording: bool, help: String) -> Self {
+        Self {
+            terminal,
+            last_draw: None,
+            speed,
+            activity: Activity::Driving,
+            recording,
+            help,
+            simulation: None,
+        }
+    }
+
+    /// Redraw the [`Dashboard`] if the [`REFRESH_INTERVAL`] has passed
+    pub fn refresh(&mut self, logbot: &mut Logbot) -> Result<()> {
+        let due = self
+            .last_draw
+            .is_none_or(|last| last.elapsed() >= REFRESH_INTERVAL);
+        if due {
+            self.draw(logbot)?;
+        };
+        Ok(())
+    }
+
+    /// Redraw the [`Dashboard`] immediately
+    pub fn draw(&mut self, logbot: &mut Logbot) -> Result<()> {
+        let readings = Readings {
+            left: logbot.sensors.read(Sensors::Left)?,
+            right: logbot.sensors.read(Sensors::Right)?,
+        };
+
+        let status = [
+            format!("Activity:    {}", self.activity_text()),
+            format!("Speed:       {:.0}%", self.speed.value() * 100.0),
+            format!("Drive:       {}", logbot.vehicle.state()),
+            format!("Lift:        {}", LiftPosition::of(&logbot.lift)),
+            match logbot.calibration {
+                Some(calibration) => format!(
+                    "Calibration: line {}, floor {}",
+                    calibration.line, calibration.floor
+                ),
+                None => "Calibration: not calibrated".to_string(),
+            },
+        ];
+
+        let simulation = self
+            .simulation
+            .as_ref()
+            .map(|world| (world.pose(), world.course()));
+
+        self.terminal
+            .draw(|frame| render(frame, &status, readings, simulation, &self.help))?;
+        self.last_draw = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Text describing the current [`Activity`]
+    fn activity_text(&self) -> String {
+        match self.recording {
+            true => format!("{} (recording)", self.activity.name()),
+            false => self.activity.name().to_string(),
+        }
+    }
+}
+
+/// Render the dashboard into a [`Frame`], including the course when simulating
+fn render(
+    frame: &mut Frame,
+    status: &[String],
+    readings: Readings,
+    simulation: Option<(Pose, Course)>,
+    help: &str,
+) {
+    let [status_area, sensor_area, course_area, help_area] = Layout::vertical([
+        Constraint::Length(status.len() as u16 + 2),
+        Constraint::Length(4),
+        Constraint::Fill(simulation.is_some() as u16),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let lines: Vec<Line> = status.iter().map(|line| Line::raw(line.as_str())).collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("logbot")),
+        status_area,
+    );
+
+    let sensors = Block::bordered().title("Sensors");
+    let [left_area, right_area] = Layout::vertical([Constraint::Length(1), Constraint::Length(1)])
+        .areas(sensors.inner(sensor_area));
+    frame.render_widget(sensors, sensor_area);
+    frame.render_widget(sensor_gauge("Left", readings.left, Color::Red), left_area);
+    frame.render_widget(
+        sensor_gauge("Right", readings.right, Color::Green),
+        right_area,
+    );
+
+    if let Some((pose, simulated)) = simulation {
+        frame.render_widget(course(pose, simulated), course_area);
+    };
+
+    frame.render_widget(Paragraph::new(help), help_area);
+}
+
+/// Create a [`Canvas`] showing logbot on the simulated course
+fn course(pose: Pose, course: Course) -> impl ratatui::widgets::Widget {
+    Canvas::default()
+        .block(Block::bordered().title("Simulation"))
+        .marker(Marker::Braille)
+        .x_bounds([-0.2, 1.4])
+        .y_bounds([-0.2, 1.0])
+        .paint(move |ctx| {
+            for segment in course.segments().iter().chain(course.stop_lines()) {
+                for pair in segment.points(0.02).windows(2) {
+                    let [(x1, y1), (x2, y2)] = [pair[0], pair[1]];
+                    ctx.draw(&canvas::Line::new(x1, y1, x2, y2, Color::White));
+                }
+            }
+            ctx.layer();
+
+            // Draw logbot as a line from its center to its sensors
+            let [left, right] = pose.sensors();
+            for ((x, y), color) in [(left, Color::Red), (right, Color::Green)] {
+                ctx.draw(&canvas::Line::new(pose.x, pose.y, x, y, Color::Yellow));
+                ctx.draw(&Points {
+                    coords: &[(x, y)],
+                    color,
+                });
+            }
+        })
+}
+
+/// Create a [`Gauge`] showing the value of a sensor
+fn sensor_gauge(name: &str, value: u8, color: Color) -> Gauge<'static> {
+    Gauge::default()
+        .gauge_style(Style::default().fg(color))
+        .ratio(value as f64 / u8::MAX as f64)
+        .label(format!("{name}: {value}"))
+}
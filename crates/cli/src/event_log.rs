@@ -0,0 +1,238 @@
+//! Logging every event of a driving session
+//!
+//! The log records every input, every executed command with the resulting
+//! direction and every change of [`Activity`]. A summary is printed when the
+//! session ends, and the full log can be exported as JSON, e.g.
+//!
+//! ```json
+//! [
+//!   { "at_ms": 0, "duration_ms": 1200, "kind": "activity", "event": "driving" },
+//!   { "at_ms": 1200, "duration_ms": 0, "kind": "input", "event": "press forward" },
+//!   { "at_ms": 1200, "duration_ms": 800, "kind": "command", "event": "forward:0.1" }
+//! ]
+//! ```
+
+use std::{fmt::Display, fs::File, io::BufWriter, path::Path, time::Duration};
+
+use anyhow::{Context, Result};
+use directions::Stop;
+use event_list::{CompletedEvent, EventList};
+use serde::Serialize;
+
+use crate::{dashboard::Activity, input::InputEvent, session::SessionCommand};
+
+/// An event of a driving session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogEvent {
+    /// Input read from the keyboard or gamepad
+    Input(InputEvent),
+    /// A command executed on logbot, drive commands hold the resulting direction
+    Command(SessionCommand),
+    /// Logbot started an [`Activity`]
+    Activity(Activity),
+}
+
+impl LogEvent {
+    /// Kind of the event used in the JSON export
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Input(_) => "input",
+            Self::Command(_) => "command",
+            Self::Activity(_) => "activity",
+        }
+    }
+}
+
+impl Display for LogEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Input(event) => event.fmt(f),
+            Self::Command(command) => command.fmt(f),
+            Self::Activity(activity) => write!(f, "{}", activity.name()),
+        }
+    }
+}
+
+/// A [`LogEvent`] as written to the JSON export
+#[derive(Debug, Serialize)]
+struct ExportedEvent {
+    /// Milliseconds since the start of the session
+    at_ms: u128,
+    /// Milliseconds until the next event
+    duration_ms: u128,
+    /// Kind of the event
+    kind: &'static str,
+    /// Description of the event
+    event: String,
+}
+
+/// Log of all [`LogEvent`]s of a driving session
+#[derive(Debug, Default)]
+pub struct EventLog {
+    /// The logged events
+    events: EventList<LogEvent>,
+}
+
+impl EventLog {
+    /// Log an event that happened now
+    pub fn log(&mut self, event: LogEvent) {
+        self.events.push(event);
+    }
+
+    /// Complete the last event, ending the session
+    pub fn finish(&mut self) {
+        self.events.complete();
+    }
+
+    /// [`Summary`] of the completed events
+    pub fn summary(&self) -> Summary {
+        Summary::new(self.events.events())
+    }
+
+    /// Write the completed events to a JSON file
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("failed to create `{}`", path.display()))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &exported(self.events.events()))
+            .with_context(|| format!("failed to write `{}`", path.display()))
+    }
+}
+
+/// Convert [`CompletedEvent`]s into their exported form
+fn exported<'a>(events: impl Iterator<Item = &'a CompletedEvent<LogEvent>>) -> Vec<ExportedEvent> {
+    let mut at = Duration::ZERO;
+    events
+        .map(|event| {
+            let exported = ExportedEvent {
+                at_ms: at.as_millis(),
+                duration_ms: event.elapsed_time.as_millis(),
+                kind: event.data.kind(),
+                event: event.data.to_string(),
+            };
+            at += event.elapsed_time;
+            exported
+        })
+        .collect()
+}
+
+/// Summary of a driving session
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Summary {
+    /// Duration of the session
+    pub duration: Duration,
+    /// Number of inputs
+    pub inputs: usize,
+    /// Number of executed commands
+    pub commands: usize,
+    /// Number of [`Activity`] changes
+    pub activities: usize,
+    /// Time spent driving in a direction other than stop
+    pub moving: Duration,
+}
+
+impl Summary {
+    /// Summarize a session from its [`CompletedEvent`]s
+    fn new<'a>(events: impl Iterator<Item = &'a CompletedEvent<LogEvent>>) -> Self {
+        let mut summary = Self::default();
+        let mut moving = false;
+
+        for event in events {
+            match event.data {
+                LogEvent::Input(_) => summary.inputs += 1,
+                LogEvent::Command(command) => {
+                    summary.commands += 1;
+                    if let SessionCommand::Drive(direction) = command {
+                        moving = !direction.is_stop();
+                    };
+                }
+                LogEvent::Activity(activity) => {
+                    summary.activities += 1;
+                    // Autonomous activities move logbot on their own
+                    moving = activity != Activity::Driving && activity != Activity::Replaying;
+                }
+            };
+
+            summary.duration += event.elapsed_time;
+            if moving {
+                summary.moving += event.elapsed_time;
+            };
+        }
+        summary
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Session lasted {:.1}s", self.duration.as_secs_f64())?;
+        writeln!(f, "  moving:     {:.1}s", self.moving.as_secs_f64())?;
+        writeln!(f, "  inputs:     {}", self.inputs)?;
+        writeln!(f, "  commands:   {}", self.commands)?;
+        write!(f, "  activities: {}", self.activities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use directions::VehicleDirection;
+    use event_list::CompletedEvent;
+    use speed::Speed;
+
+    use super::{exported, LogEvent, Summary};
+    use crate::{dashboard::Activity, input::InputEvent, keys::Action, session::SessionCommand};
+
+    /// A short session of driving forward and following the line
+    fn session() -> Vec<CompletedEvent<LogEvent>> {
+        [
+            (LogEvent::Activity(Activity::Driving), 1000),
+            (LogEvent::Input(InputEvent::Press(Action::Forward)), 0),
+            (
+                LogEvent::Command(SessionCommand::Drive(VehicleDirection::forward(
+                    Speed::HALF,
+                ))),
+                500,
+            ),
+            (LogEvent::Input(InputEvent::Release(Action::Forward)), 0),
+            (
+                LogEvent::Command(SessionCommand::Drive(VehicleDirection::STOP)),
+                200,
+            ),
+            (LogEvent::Activity(Activity::Following), 300),
+            (LogEvent::Activity(Activity::Driving), 0),
+        ]
+        .map(|(data, millis)| CompletedEvent {
+            data,
+            elapsed_time: Duration::from_millis(millis),
+        })
+        .to_vec()
+    }
+
+    /// Test that the summary counts events and the time spent moving
+    #[test]
+    fn summary() {
+        let summary = Summary::new(session().iter());
+        assert_eq!(
+            summary,
+            Summary {
+                duration: Duration::from_millis(2000),
+                inputs: 2,
+                commands: 2,
+                activities: 3,
+                moving: Duration::from_millis(800),
+            }
+        );
+    }
+
+    /// Test that exported events are timed from the start of the session
+    #[test]
+    fn export_timing() {
+        let events = exported(session().iter());
+        assert_eq!(events[2].at_ms, 1000);
+        assert_eq!(events[2].kind, "command");
+        assert_eq!(events[2].event, "forward:0.5");
+        assert_eq!(events[5].at_ms, 1700);
+        assert_eq!(events[5].event, "following line");
+    }
+}
@@ -0,0 +1,94 @@
+//! Gamepad support using [`gilrs`]
+
+use anyhow::{anyhow, Result};
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use crate::{input::InputEvent, keys::Action};
+
+/// Stick values below this magnitude are treated as centered
+const DEADZONE: f64 = 0.1;
+
+/// Gamepad that maps its buttons to [`Action`]s
+#[derive(Debug)]
+pub struct Gamepad {
+    /// Event source for all connected gamepads
+    gilrs: Gilrs,
+    /// Last horizontal position of the left stick
+    x: f64,
+    /// Last vertical position of the left stick
+    y: f64,
+}
+
+impl Gamepad {
+    /// Start listening to connected gamepads
+    pub fn new() -> Result<Self> {
+        let gilrs = Gilrs::new().map_err(|e| anyhow!("failed to initialize gamepad: {e}"))?;
+        Ok(Self {
+            gilrs,
+            x: 0.0,
+            y: 0.0,
+        })
+    }
+
+    /// Return the next [`InputEvent`] if one is available, without waiting
+    pub fn poll(&mut self) -> Option<InputEvent> {
+        while let Some(event) = self.gilrs.next_event() {
+            let event = match event.event {
+                EventType::ButtonPressed(button, _) => button_action(button).map(InputEvent::Press),
+                EventType::ButtonReleased(button, _) => {
+                    button_action(button).map(InputEvent::Release)
+                }
+                EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+                    self.x = deadzone(value);
+                    Some(self.stick())
+                }
+                EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                    self.y = deadzone(value);
+                    Some(self.stick())
+                }
+                _ => None,
+            };
+
+            if event.is_some() {
+                return event;
+            };
+        }
+        None
+    }
+
+    /// The current position of the left stick
+    fn stick(&self) -> InputEvent {
+        InputEvent::Stick {
+            x: self.x,
+            y: self.y,
+        }
+    }
+}
+
+/// Map a gamepad [`Button`] to its [`Action`]
+///
+/// South calibrates, West follows the line, North finds the edge, East stops,
+/// Select re-arms, the D-pad moves the lift and Start exits the program
+fn button_action(button: Button) -> Option<Action> {
+    match button {
+        Button::South => Some(Action::Calibrate),
+        Button::West => Some(Action::Follow),
+        Button::North => Some(Action::FindEdge),
+        Button::East => Some(Action::Stop),
+        Button::DPadUp => Some(Action::LiftUp),
+        Button::DPadDown => Some(Action::LiftDown),
+        Button::Select => Some(Action::Rearm),
+        Button::Start => Some(Action::Exit),
+        _ => None,
+    }
+}
+
+/// Center stick values inside of the [`DEADZONE`]
+fn deadzone(value: f32) -> f64 {
+    let value = value as f64;
+    if value.abs() < DEADZONE {
+        0.0
+    } else {
+        value
+    }
+}
@@ -0,0 +1,143 @@
+//! Combined keyboard and gamepad input
+
+use std::{fmt::Display, time::Duration};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+
+#[cfg(feature = "gamepad")]
+use crate::gamepad::Gamepad;
+use crate::keys::{Action, Keybindings};
+
+/// Interval at which the gamepad is polled while waiting for keyboard events
+#[cfg(feature = "gamepad")]
+const GAMEPAD_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// An event read from the keyboard or a gamepad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    /// A key or gamepad button bound to an [`Action`] was pressed
+    Press(Action),
+    /// A key or gamepad button bound to an [`Action`] was released
+    Release(Action),
+    /// The analog stick of a gamepad moved, both axes range from -1.0 to 1.0
+    #[cfg(feature = "gamepad")]
+    Stick {
+        /// Horizontal axis, positive to the right
+        x: f64,
+        /// Vertical axis, positive forward
+        y: f64,
+    },
+}
+
+impl Display for InputEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Press(action) => write!(f, "press {action}"),
+            Self::Release(action) => write!(f, "release {action}"),
+            #[cfg(feature = "gamepad")]
+            Self::Stick { x, y } => write!(f, "stick {x:.2} {y:.2}"),
+        }
+    }
+}
+
+/// Reads [`InputEvent`]s from the keyboard and an optional gamepad
+#[derive(Debug)]
+pub struct Input {
+    /// Keybindings of the keyboard
+    keys: Keybindings,
+    /// Whether Shift was held during the last key event
+    shift: bool,
+    /// The gamepad, when enabled
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<Gamepad>,
+}
+
+impl Input {
+    /// Read input only from the keyboard
+    pub fn keyboard(keys: Keybindings) -> Self {
+        Self {
+            keys,
+            shift: false,
+            #[cfg(feature = "gamepad")]
+            gamepad: None,
+        }
+    }
+
+    /// Read input from the keyboard and a gamepad
+    #[cfg(feature = "gamepad")]
+    pub fn with_gamepad(keys: Keybindings) -> Result<Self> {
+        Ok(Self {
+            keys,
+            shift: false,
+            gamepad: Some(Gamepad::new()?),
+        })
+    }
+
+    /// Wait at most `timeout` for the next [`InputEvent`]
+    pub fn poll(&mut self, timeout: Duration) -> Result<Option<InputEvent>> {
+        #[cfg(feature = "gamepad")]
+        if self.gamepad.is_some() {
+            let start = std::time::Instant::now();
+            loop {
+                if let Some(event) = self.try_next()? {
+                    return Ok(Some(event));
+                };
+                let remaining = timeout.saturating_sub(start.elapsed());
+                if remaining.is_zero() {
+                    return Ok(None);
+                };
+                std::thread::sleep(remaining.min(GAMEPAD_POLL_INTERVAL));
+            }
+        };
+
+        if !event::poll(timeout)? {
+            return Ok(None);
+        };
+        Ok(self.keyboard_event(event::read()?))
+    }
+
+    /// Return the next [`InputEvent`] if one is available, without waiting
+    pub fn try_next(&mut self) -> Result<Option<InputEvent>> {
+        #[cfg(feature = "gamepad")]
+        if let Some(event) = self.gamepad.as_mut().and_then(Gamepad::poll) {
+            return Ok(Some(event));
+        };
+
+        if !event::poll(Duration::ZERO)? {
+            return Ok(None);
+        };
+        Ok(self.keyboard_event(event::read()?))
+    }
+
+    /// The [`Keybindings`] of the keyboard
+    pub fn keys(&self) -> &Keybindings {
+        &self.keys
+    }
+
+    /// Whether Shift was held during the last key event
+    pub fn shift(&self) -> bool {
+        self.shift
+    }
+
+    /// Convert a keyboard [`Event`] into an [`InputEvent`], ignoring unbound keys
+    ///
+    /// Shifted characters trigger the [`Action`] of their lowercase key.
+    fn keyboard_event(&mut self, event: Event) -> Option<InputEvent> {
+        match event {
+            Event::Key(key) => {
+                self.shift = key.modifiers.contains(KeyModifiers::SHIFT);
+                let action = self.keys.action(key.code).or_else(|| match key.code {
+                    KeyCode::Char(c) => self.keys.action(KeyCode::Char(c.to_ascii_lowercase())),
+                    _ => None,
+                })?;
+                match key.kind {
+                    KeyEventKind::Press => Some(InputEvent::Press(action)),
+                    KeyEventKind::Release => Some(InputEvent::Release(action)),
+                    KeyEventKind::Repeat => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! Configurable keybindings
+//!
+//! Keys are bound to an [`Action`] using a TOML file, in which every action
+//! lists its keys. Actions missing from the file keep their default keys:
+//!
+//! ```toml
+//! forward = ["up"]
+//! backward = ["down"]
+//! left = ["left"]
+//! right = ["right"]
+//! lift_up = ["pageup"]
+//! lift_down = ["pagedown"]
+//! ```
+//!
+//! Keys are either a single character or one of `space`, `esc`, `enter`,
+//! `tab`, `backspace`, `up`, `down`, `left`, `right`, `pageup` and `pagedown`.
+
+use std::{fmt::Display, path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Result};
+use crossterm::event::KeyCode;
+use serde::{de, Deserialize, Deserializer};
+
+/// Actions that keys can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Drive forward while held
+    Forward,
+    /// Drive backward while held
+    Backward,
+    /// Turn left while held
+    Left,
+    /// Turn right while held
+    Right,
+    /// Increase the driving speed
+    SpeedUp,
+    /// Decrease the driving speed
+    SpeedDown,
+    /// Move the lift up
+    LiftUp,
+    /// Move the lift down
+    LiftDown,
+    /// Calibrate the sensors
+    Calibrate,
+    /// Find the edge of the line, only used in remote control
+    FindEdge,
+    /// Follow the line
+    Follow,
+    /// Immediately stop all movement, until re-armed
+    Stop,
+    /// Re-arm after a [stop](Action::Stop), allowing movement again
+    Rearm,
+    /// Exit the program
+    Exit,
+}
+
+impl Display for Action {
+    /// Name of the action as used in the keybindings file
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Forward => "forward",
+            Self::Backward => "backward",
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::SpeedUp => "speed_up",
+            Self::SpeedDown => "speed_down",
+            Self::LiftUp => "lift_up",
+            Self::LiftDown => "lift_down",
+            Self::Calibrate => "calibrate",
+            Self::FindEdge => "find_edge",
+            Self::Follow => "follow",
+            Self::Stop => "stop",
+            Self::Rearm => "rearm",
+            Self::Exit => "exit",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A key of the keyboard that can be bound to an [`Action`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key(pub KeyCode);
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = match s {
+            "space" => KeyCode::Char(' '),
+            "esc" => KeyCode::Esc,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(anyhow!("unknown key `{s}`")),
+                }
+            }
+        };
+        Ok(Self(code))
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Keys bound to each [`Action`], see the [module](self) documentation for the format
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keybindings {
+    /// Keys of [`Action::Forward`]
+    pub forward: Vec<Key>,
+    /// Keys of [`Action::Backward`]
+    pub backward: Vec<Key>,
+    /// Keys of [`Action::Left`]
+    pub left: Vec<Key>,
+    /// Keys of [`Action::Right`]
+    pub right: Vec<Key>,
+    /// Keys of [`Action::SpeedUp`]
+    pub speed_up: Vec<Key>,
+    /// Keys of [`Action::SpeedDown`]
+    pub speed_down: Vec<Key>,
+    /// Keys of [`Action::LiftUp`]
+    pub lift_up: Vec<Key>,
+    /// Keys of [`Action::LiftDown`]
+    pub lift_down: Vec<Key>,
+    /// Keys of [`Action::Calibrate`]
+    pub calibrate: Vec<Key>,
+    /// Keys of [`Action::FindEdge`]
+    pub find_edge: Vec<Key>,
+    /// Keys of [`Action::Follow`]
+    pub follow: Vec<Key>,
+    /// Keys of [`Action::Stop`]
+    pub stop: Vec<Key>,
+    /// Keys of [`Action::Rearm`]
+    pub rearm: Vec<Key>,
+    /// Keys of [`Action::Exit`]
+    pub exit: Vec<Key>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let keys = |codes: &[KeyCode]| codes.iter().copied().map(Key).collect();
+        Self {
+            forward: keys(&[KeyCode::Char('w')]),
+            backward: keys(&[KeyCode::Char('s')]),
+            left: keys(&[KeyCode::Char('a')]),
+            right: keys(&[KeyCode::Char('d')]),
+            speed_up: keys(&[KeyCode::Char('+'), KeyCode::Char(']')]),
+            speed_down: keys(&[KeyCode::Char('-'), KeyCode::Char('[')]),
+            lift_up: keys(&[KeyCode::Up]),
+            lift_down: keys(&[KeyCode::Down]),
+            calibrate: keys(&[KeyCode::Char('c')]),
+            find_edge: keys(&[KeyCode::Char('f')]),
+            follow: keys(&[KeyCode::Char('e')]),
+            stop: keys(&[KeyCode::Char(' ')]),
+            rearm: keys(&[KeyCode::Char('r')]),
+            exit: keys(&[KeyCode::Esc]),
+        }
+    }
+}
+
+impl Keybindings {
+    /// Parse [`Keybindings`] from a TOML string, rejecting keys bound twice
+    pub fn from_toml(value: &str) -> Result<Self> {
+        let keys: Self = toml::from_str(value)?;
+
+        let bound: Vec<(Action, Key)> = keys
+            .bindings()
+            .into_iter()
+            .flat_map(|(action, keys)| keys.iter().map(move |key| (action, *key)))
+            .collect();
+        for (index, (action, key)) in bound.iter().enumerate() {
+            if let Some((other, _)) = bound[..index].iter().find(|(_, other)| other == key) {
+                bail!("key `{key}` is bound to both {other:?} and {action:?}");
+            };
+        }
+
+        Ok(keys)
+    }
+
+    /// Read [`Keybindings`] from a file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let value = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read `{}`", path.display()))?;
+        Self::from_toml(&value).with_context(|| format!("invalid keybindings `{}`", path.display()))
+    }
+
+    /// The [`Action`] bound to a [`KeyCode`]
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings()
+            .into_iter()
+            .find(|(_, keys)| keys.contains(&Key(code)))
+            .map(|(action, _)| action)
+    }
+
+    /// Short description of the controls
+    pub fn help(&self) -> String {
+        let keys = |keys: &[Key]| {
+            keys.iter()
+                .map(Key::to_string)
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        let drive = [&self.forward, &self.left, &self.backward, &self.right]
+            .map(|action| keys(action))
+            .join("/");
+
+        format!(
+            "{drive} drive | {}/{} speed | {} calibrate | {} follow | {}/{} lift | {} stop | {} re-arm | {} exit",
+            keys(&self.speed_up),
+            keys(&self.speed_down),
+            keys(&self.calibrate),
+            keys(&self.follow),
+            keys(&self.lift_up),
+            keys(&self.lift_down),
+            keys(&self.stop),
+            keys(&self.rearm),
+            keys(&self.exit),
+        )
+    }
+
+    /// Every [`Action`] with its keys
+    fn bindings(&self) -> [(Action, &[Key]); 14] {
+        [
+            (Action::Forward, &self.forward),
+            (Action::Backward, &self.backward),
+            (Action::Left, &self.left),
+            (Action::Right, &self.right),
+            (Action::SpeedUp, &self.speed_up),
+            (Action::SpeedDown, &self.speed_down),
+            (Action::LiftUp, &self.lift_up),
+            (Action::LiftDown, &self.lift_down),
+            (Action::Calibrate, &self.calibrate),
+            (Action::FindEdge, &self.find_edge),
+            (Action::Follow, &self.follow),
+            (Action::Stop, &self.stop),
+            (Action::Rearm, &self.rearm),
+            (Action::Exit, &self.exit),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::{Action, Key, Keybindings};
+
+    /// Test that keys survive a round trip through their names
+    #[test]
+    fn key_round_trip() {
+        for code in [
+            KeyCode::Char('w'),
+            KeyCode::Char(' '),
+            KeyCode::Esc,
+            KeyCode::PageUp,
+        ] {
+            assert_eq!(Key(code).to_string().parse::<Key>().unwrap(), Key(code));
+        }
+        assert!("ctrl".parse::<Key>().is_err());
+    }
+
+    /// Test that a file only overrides the actions it lists
+    #[test]
+    fn file_overrides_defaults() {
+        let keys = Keybindings::from_toml(
+            r#"
+            forward = ["up"]
+            lift_up = ["pageup"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(keys.action(KeyCode::Up), Some(Action::Forward));
+        assert_eq!(keys.action(KeyCode::PageUp), Some(Action::LiftUp));
+        assert_eq!(keys.action(KeyCode::Char('w')), None);
+        assert_eq!(keys.action(KeyCode::Char('c')), Some(Action::Calibrate));
+    }
+
+    /// Test that keys bound to multiple actions are rejected
+    #[test]
+    fn duplicate_keys_rejected() {
+        assert!(Keybindings::from_toml(r#"forward = ["c"]"#).is_err());
+        assert!(Keybindings::from_toml("").is_ok());
+    }
+}
@@ -1,125 +1,274 @@
 //! Command-line Interface for controlling logbot using the keyboard
+//!
+//! The live state of logbot is shown on a dashboard in the terminal.
+//!
+//! With the `gamepad` feature logbot can additionally be controlled using a
+//! gamepad, which is enabled with the `--gamepad` flag.
+//!
+//! Simple missions can be executed from a script using `cli run <script.toml>`
+//! and the box-moving demo is started using `cli demo`. A run recorded with
+//! `--black-box <dir>` is driven again using `cli redrive <dir>`, and
+//! `cli compare <dir> <tuning.toml>` compares how a changed tuning follows
+//! the line on its recorded sensor values.
+//!
+//! With `--remote <url>` the keyboard controls a logbot running the `server`
+//! instead of local hardware.
 
 use std::{
     io::stdout,
-    num::NonZero,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
 use crossterm::{
-    event::{
-        self, Event, KeyCode, KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
-        PushKeyboardEnhancementFlags,
-    },
+    event::{KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags},
     execute, terminal,
 };
 
-use components::{hardware_pwm::DCMotor, software_pwm::LiftMotor};
-//use components::software_pwm::DCMotor;
 use calibration::{SensorCalibration, SingleSensorCalibration};
-use components::{Left, Right, SensorController};
-use consts::Sensors;
+use components::{
+    sim::{self, SharedWorld, World},
+    software_pwm::LiftMotor,
+    Left, Right, SensorController,
+};
+use consts::{HardwareConfig, Sensors};
 use defaults::TryDefault;
-use directions::{MotorDirection, SpinDirection, VehicleDirection};
-use interfaces::{Drive, Lift, SensorRead, Spin};
-use line::{FollowLineConfig, FollowLineState};
-use oscillate::Oscillate;
+use demo::{
+    cancel::CancelToken,
+    config::{
+        DemoConfig, DEFAULT_BOXES, DEFAULT_FIND_EDGE_TIMEOUT_MS, DEFAULT_LEAVE_LINE_MS,
+        DEFAULT_SETTLE_MS,
+    },
+    mission::Step,
+    observer::DemoObserver,
+};
+use directions::{SpinDirection, VehicleDirection};
+use interfaces::{Drive, EmergencyStop, JogLift, Lift, SensorRead, Spin};
+use line::FollowLineState;
+use logbot::{calibrated::Uncalibrated, self_test::Check};
+use recorder::RecorderConfig;
 use speed::Speed;
+use tuning::{Preset, Tuning};
 use vehicle::Vehicle;
 
-const FORWARD: u8 = 0b0001;
-const BACKWARD: u8 = 0b0010;
-const LEFT: u8 = 0b0100;
-const RIGHT: u8 = 0b1000;
+mod backend;
+mod calibration_file;
+mod dashboard;
+mod event_log;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod input;
+mod keys;
+mod remote;
+mod script;
+mod session;
+mod steering;
+
+use backend::{Backend, LiftBackend, Motor, SensorBackend};
+use dashboard::{Activity, Dashboard, REFRESH_INTERVAL};
+use event_list::CompletedEvent;
+use event_log::{EventLog, LogEvent};
+use input::{Input, InputEvent};
+use keys::{Action, Keybindings};
+use remote::Remote;
+use session::{Recorder, SessionCommand};
+use steering::{movement, Steering};
 
 /// Control logbot using the keyboard
 #[derive(Parser)]
 struct Args {
-    /// [`Speed`] of logbot (from 0 to 100)
+    /// [`Speed`] of logbot (from 0 to 100), adjustable at runtime using `+`/`-` or `]`/`[`
     #[arg(short, long, value_parser = clap::value_parser!(u8).range(0..100), default_value_t = 10)]
     speed: u8,
+    /// Named robot profile of the hardware configuration
+    #[arg(long)]
+    profile: Option<String>,
+    /// Motor implementation to drive logbot with
+    #[arg(long, value_enum, default_value_t = Backend::default())]
+    backend: Backend,
+    /// Tuning preset (slow, demo or race), defaults to demo for the `demo`
+    /// subcommand and race otherwise
+    #[arg(long)]
+    preset: Option<Preset>,
+    /// TOML file overriding values of the tuning preset
+    #[arg(long)]
+    tuning: Option<PathBuf>,
+    /// Additionally control logbot using a gamepad
+    #[cfg(feature = "gamepad")]
+    #[arg(long)]
+    gamepad: bool,
+    /// Record the driving session to a file
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+    /// Replay a recorded driving session from a file
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    /// Load the sensor calibration from this file on startup, if it exists.
+    /// Calibrating saves to this file, or to `calibration.toml` when not given
+    #[arg(long)]
+    calibration: Option<PathBuf>,
+    /// TOML file remapping the keybindings
+    #[arg(long)]
+    keys: Option<PathBuf>,
+    /// Control a logbot running the `server` at this URL instead of local hardware
+    #[arg(long, conflicts_with_all = ["record", "replay"])]
+    remote: Option<String>,
+    /// Export every input, command and activity of the session as JSON to this
+    /// file on exit, a summary is always printed
+    #[arg(long, conflicts_with = "remote")]
+    events: Option<PathBuf>,
+    /// Drive a simulated logbot on a virtual line course instead of the hardware
+    #[arg(long, conflicts_with_all = ["backend", "remote"])]
+    sim: bool,
+    /// Record every drive, sensor read and lift motion of scripts and the demo
+    /// to rotating log files in this directory
+    #[arg(long, conflicts_with = "remote")]
+    black_box: Option<PathBuf>,
+    /// Run the self-test of the components, print the report and exit
+    #[arg(long, conflicts_with_all = ["remote", "record", "replay"])]
+    self_test: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Subcommands of the `cli`, the keyboard control is used without one
+#[derive(Subcommand)]
+enum Command {
+    /// Execute the steps of a TOML script
+    Run {
+        /// Path of the script
+        script: PathBuf,
+    },
+    /// Run the box-moving demo
+    Demo {
+        /// [`Speed`] when following the line (from 0 to 100)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        follow_speed: Option<u8>,
+        /// [`Speed`] when turning around on the line (from 0 to 100)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        turn_speed: Option<u8>,
+        /// [`Speed`] of the lift (from 0 to 100)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        lift_speed: Option<u8>,
+        /// Number of boxes to move, each in a lap
+        #[arg(long, default_value_t = DEFAULT_BOXES)]
+        boxes: u32,
+        /// Milliseconds to stand still after finding the edge or turning
+        #[arg(long, default_value_t = DEFAULT_SETTLE_MS)]
+        settle_ms: u64,
+        /// Milliseconds to spin off the line when turning around
+        #[arg(long, default_value_t = DEFAULT_LEAVE_LINE_MS)]
+        leave_line_ms: u64,
+        /// Milliseconds to look for the edge of the line before calibrating again
+        #[arg(long, default_value_t = DEFAULT_FIND_EDGE_TIMEOUT_MS)]
+        find_edge_timeout_ms: u64,
+    },
+    /// Drive logbot again with the commands recorded to a black box
+    Redrive {
+        /// Directory of the black box
+        directory: PathBuf,
+    },
+    /// Compare how the line follower of the tuning and of a changed tuning
+    /// steer on the sensor values recorded to a black box
+    Compare {
+        /// Directory of the black box
+        directory: PathBuf,
+        /// TOML file of the changed tuning
+        candidate: PathBuf,
+        /// Channel of the followed sensor
+        #[arg(long, default_value_t = 0)]
+        channel: u8,
+    },
 }
 
+impl Command {
+    /// The [`Preset`] used when none is given
+    fn default_preset(command: Option<&Self>) -> Preset {
+        match command {
+            Some(Self::Demo { .. }) => Preset::Demo,
+            _ => Preset::Race,
+        }
+    }
+}
+
+/// Vehicle driven by the motors of the selected [`Backend`]
+type BackendVehicle = Vehicle<Motor<Left>, Motor<Right>>;
+
+/// Logbot driven autonomously by the `run` and `demo` subcommands
+type AutonomousLogbot = logbot::Logbot<
+    BackendVehicle,
+    SensorBackend,
+    LiftBackend,
+    Uncalibrated,
+    Option<recorder::Recorder>,
+>;
+
 /// Logbot - bundle vehicle and sensors into a single struct
 #[derive(Debug)]
 struct Logbot {
-    vehicle: Vehicle<DCMotor<Left>, DCMotor<Right>>,
-    sensors: SensorController,
-    lift: LiftMotor,
+    vehicle: BackendVehicle,
+    sensors: SensorBackend,
+    lift: LiftBackend,
     calibration: Option<SensorCalibration>,
+    /// The jog the lift is currently moving with
+    jog: Option<SessionCommand>,
 }
 
-/// Turn a [`u8`] that represents state into a [`VehicleDirection`]
-fn u8_into_state(mut state: u8, speed: Speed) -> Option<VehicleDirection> {
-    // First remove contradicting states
-    if state & RIGHT != 0 && state & LEFT != 0 {
-        state &= !RIGHT & !LEFT;
-    };
-    if state & FORWARD != 0 && state & BACKWARD != 0 {
-        state &= !FORWARD & !BACKWARD;
-    };
+impl EmergencyStop for Logbot {
+    type Error = anyhow::Error;
 
-    // This is the ratio at which logbot turns when a horizontal and vertical
-    // state are selected
-    let turn_speed = speed / NonZero::<usize>::new(3).unwrap();
-
-    if state & (FORWARD | LEFT) == (FORWARD | LEFT) {
-        Some(VehicleDirection::new(
-            MotorDirection::Forward(turn_speed),
-            MotorDirection::Forward(speed),
-        ))
-    } else if state & (FORWARD | RIGHT) == (FORWARD | RIGHT) {
-        Some(VehicleDirection::new(
-            MotorDirection::Forward(speed),
-            MotorDirection::Forward(turn_speed),
-        ))
-    } else if state & (BACKWARD | LEFT) == (BACKWARD | LEFT) {
-        Some(VehicleDirection::new(
-            MotorDirection::Backward(turn_speed),
-            MotorDirection::Backward(speed),
-        ))
-    } else if state & (BACKWARD | RIGHT) == (BACKWARD | RIGHT) {
-        Some(VehicleDirection::new(
-            MotorDirection::Backward(speed),
-            MotorDirection::Backward(turn_speed),
-        ))
-    } else if state & FORWARD != 0 {
-        Some(VehicleDirection::forward(speed))
-    } else if state & BACKWARD != 0 {
-        Some(VehicleDirection::backward(speed))
-    } else if state & RIGHT != 0 {
-        Some(VehicleDirection::spin_right(speed))
-    } else if state & LEFT != 0 {
-        Some(VehicleDirection::spin_left(speed))
-    } else {
-        None
+    /// Stop the vehicle and halt the lift, the lift is halted even if stopping
+    /// the vehicle failed
+    fn emergency_stop(&mut self) -> Result<()> {
+        self.jog = None;
+        let vehicle = self.vehicle.stop();
+        let lift = self.lift.halt();
+
+        vehicle?;
+        lift?;
+        Ok(())
     }
 }
 
+/// Amount by which the speed keys change the driving [`Speed`]
+const SPEED_STEP: f64 = 0.05;
+
+/// Factor of the lift speed used for jogging while Shift is held
+const PRECISE_JOG: f64 = 0.5;
+
 /// Result of a [`check_key`] poll
 #[derive(Debug, Clone, Copy)]
 enum KeyPoll {
     Target,
-    Esc,
+    Exit,
+    EmergencyStop,
 }
 
-/// Helper method for checking if a target key was pressed
-fn check_key(target: char) -> Result<Option<KeyPoll>> {
-    if !event::poll(Duration::ZERO)? {
-        return Ok(None);
+/// Helper method for checking if the key of a target [`Action`] was pressed
+///
+/// The stop key takes a fast path, stopping logbot before anything else.
+fn check_key(
+    logbot: &mut Logbot,
+    input: &mut Input,
+    log: &mut EventLog,
+    target: Action,
+) -> Result<Option<KeyPoll>> {
+    let event = input.try_next()?;
+    if let Some(InputEvent::Press(Action::Stop)) = event {
+        logbot.emergency_stop()?;
     };
-    if let Event::Key(key) = event::read()? {
-        match key.code {
-            KeyCode::Esc => return Ok(Some(KeyPoll::Esc)),
-            KeyCode::Char(c) if c == target && key.kind == KeyEventKind::Press => {
-                return Ok(Some(KeyPoll::Target))
-            }
-            _ => {}
-        };
+    if let Some(event) = event {
+        log.log(LogEvent::Input(event));
     };
-    Ok(None)
+
+    match event {
+        Some(InputEvent::Press(Action::Stop)) => Ok(Some(KeyPoll::EmergencyStop)),
+        Some(InputEvent::Press(Action::Exit)) => Ok(Some(KeyPoll::Exit)),
+        Some(InputEvent::Press(action)) if action == target => Ok(Some(KeyPoll::Target)),
+        _ => Ok(None),
+    }
 }
 
 /// Calibrate sensors by oscillating over the line
@@ -129,25 +278,27 @@ fn check_key(target: char) -> Result<Option<KeyPoll>> {
 ///
 /// Returns Some(key) when a exit method was detected, and if the
 /// calibration ended successfully we return None
-fn calibrate(logbot: &mut Logbot) -> Result<Option<KeyPoll>> {
+fn calibrate(
+    logbot: &mut Logbot,
+    input: &mut Input,
+    log: &mut EventLog,
+    dashboard: &mut Dashboard,
+    tuning: &Tuning,
+) -> Result<Option<KeyPoll>> {
     // Records sensor values and produces calibrated sensor
-    let mut log = SingleSensorCalibration::default();
+    let mut samples = SingleSensorCalibration::default();
 
     // Configure and start oscillation
-    let mut oscillate = Oscillate::new(
-        Duration::from_secs(1),
-        SpinDirection::Left(Speed::HALF),
-        NonZero::<u32>::new(2).unwrap(),
-    )
-    .start(&mut logbot.vehicle)?;
+    let mut oscillate = tuning.calibrate.oscillate().start(&mut logbot.vehicle)?;
 
     // Actively wait for the first oscillation step
     while !oscillate.should_step() {
         // Check for incoming events
-        if let Some(key) = check_key('c')? {
+        if let Some(key) = check_key(logbot, input, log, Action::Calibrate)? {
             logbot.vehicle.stop()?;
             return Ok(Some(key));
         }
+        dashboard.refresh(logbot)?;
     }
     // Change directions for the first time
     // Now we want to start recording data
@@ -157,47 +308,50 @@ fn calibrate(logbot: &mut Logbot) -> Result<Option<KeyPoll>> {
     // while checking for cancelling events
     while !oscillate.should_step() {
         // Check for keypresses that could cancel the operation
-        if let Some(key) = check_key('c')? {
+        if let Some(key) = check_key(logbot, input, log, Action::Calibrate)? {
             logbot.vehicle.stop()?;
             return Ok(Some(key));
         };
+        dashboard.refresh(logbot)?;
 
         // Read and log values from sensor
         let left_value = logbot.sensors.read(Sensors::Left)?;
-        log.log(left_value as f64);
+        samples.log(left_value as f64);
     }
 
     // Move logbot back to its original position
-    // We want to spin back left for one second
+    // We want to spin back left for the duration of the first oscillation
     let start = Instant::now();
-    logbot.vehicle.spin(SpinDirection::Left(Speed::HALF))?;
+    logbot
+        .vehicle
+        .spin(SpinDirection::Left(tuning.calibrate.speed))?;
 
-    while start.elapsed() < Duration::from_secs(1) {
+    while start.elapsed() < tuning.calibrate.duration() {
         // Once again listen for cancelling event
-        if let Some(key) = check_key('c')? {
+        if let Some(key) = check_key(logbot, input, log, Action::Calibrate)? {
             logbot.vehicle.stop()?;
             return Ok(Some(key));
         };
+        dashboard.refresh(logbot)?;
     }
 
     logbot.vehicle.stop()?;
-    logbot.calibration = Some(log.calibrate());
+    logbot.calibration = Some(samples.calibrate());
     Ok(None)
 }
 
-/// Follow the line until 'e' or Esc is pressed
-fn follow_line(logbot: &mut Logbot) -> Result<KeyPoll> {
+/// Follow the line until the follow or exit key is pressed
+fn follow_line(
+    logbot: &mut Logbot,
+    input: &mut Input,
+    log: &mut EventLog,
+    dashboard: &mut Dashboard,
+    tuning: &Tuning,
+) -> Result<KeyPoll> {
     assert!(logbot.calibration.is_some());
 
     // Create config from calibration
-    let config = FollowLineConfig {
-        default_speed: Speed::HALF,
-        proportional: 0.6,
-        derivative: 0.3,
-        integral: None,
-        calibration: logbot.calibration.unwrap(),
-        reset_integral_on_target: true,
-    };
+    let config = tuning.follow_line_config(logbot.calibration.unwrap());
 
     // Set up state for following a line
     let mut follow_line = FollowLineState::new(config);
@@ -205,10 +359,11 @@ fn follow_line(logbot: &mut Logbot) -> Result<KeyPoll> {
     // Indefinitely follow the line
     loop {
         // Check for cancelling events
-        if let Some(key) = check_key('e')? {
+        if let Some(key) = check_key(logbot, input, log, Action::Follow)? {
             logbot.vehicle.stop()?;
             return Ok(key);
         };
+        dashboard.refresh(logbot)?;
 
         let sensor_value = logbot.sensors.read(Sensors::Left)?;
         let direction = follow_line.step(sensor_value);
@@ -216,146 +371,730 @@ fn follow_line(logbot: &mut Logbot) -> Result<KeyPoll> {
     }
 }
 
+/// Show a new [`Activity`] on the dashboard and log it
+fn start_activity(dashboard: &mut Dashboard, log: &mut EventLog, activity: Activity) {
+    dashboard.activity = activity;
+    log.log(LogEvent::Activity(activity));
+}
+
+/// Show and record an emergency stop, after logbot has been stopped
+fn emergency_stopped(
+    dashboard: &mut Dashboard,
+    log: &mut EventLog,
+    recorder: Option<&mut Recorder>,
+) {
+    // A replay stops at the same moment
+    if let Some(recorder) = recorder {
+        recorder.record(SessionCommand::Drive(VehicleDirection::STOP));
+        recorder.record(SessionCommand::LiftHalt);
+    };
+    start_activity(dashboard, log, Activity::EmergencyStopped);
+}
+
+/// Execute a [`SessionCommand`], recording it when a [`Recorder`] is given
+fn execute(
+    logbot: &mut Logbot,
+    command: SessionCommand,
+    lift_speed: Speed,
+    log: &mut EventLog,
+    recorder: Option<&mut Recorder>,
+) -> Result<()> {
+    // Record before executing, since moving the lift is blocking
+    log.log(LogEvent::Command(command));
+    if let Some(recorder) = recorder {
+        recorder.record(command);
+    };
+
+    match command {
+        SessionCommand::Drive(direction) => {
+            logbot.vehicle.drive(direction)?;
+        }
+        SessionCommand::LiftUp => logbot.lift.up(lift_speed)?,
+        SessionCommand::LiftDown => logbot.lift.down(lift_speed)?,
+        SessionCommand::JogUp(speed) => logbot.lift.jog_up(speed)?,
+        SessionCommand::JogDown(speed) => logbot.lift.jog_down(speed)?,
+        SessionCommand::LiftHalt => logbot.lift.halt()?,
+    };
+
+    logbot.jog = match command {
+        SessionCommand::Drive(_) => logbot.jog,
+        SessionCommand::JogUp(_) | SessionCommand::JogDown(_) => Some(command),
+        _ => None,
+    };
+    Ok(())
+}
+
+/// Halt the lift if it is jogging, since the jog is not repeated by autonomous activities
+fn halt_jog(
+    logbot: &mut Logbot,
+    lift_speed: Speed,
+    log: &mut EventLog,
+    recorder: Option<&mut Recorder>,
+) -> Result<()> {
+    match logbot.jog {
+        Some(_) => execute(logbot, SessionCommand::LiftHalt, lift_speed, log, recorder),
+        None => Ok(()),
+    }
+}
+
+/// Repeat the current jog, which stops the lift once it reaches the end of its range
+fn keep_jogging(logbot: &mut Logbot) -> Result<()> {
+    match logbot.jog {
+        Some(SessionCommand::JogUp(speed)) => logbot.lift.jog_up(speed)?,
+        Some(SessionCommand::JogDown(speed)) => logbot.lift.jog_down(speed)?,
+        _ => {}
+    };
+    Ok(())
+}
+
+/// Replay a recorded session until it ends or Esc is pressed
+fn replay(
+    logbot: &mut Logbot,
+    input: &mut Input,
+    log: &mut EventLog,
+    dashboard: &mut Dashboard,
+    session: &[CompletedEvent<SessionCommand>],
+    lift_speed: Speed,
+) -> Result<()> {
+    start_activity(dashboard, log, Activity::Replaying);
+
+    for event in session {
+        let start = Instant::now();
+        execute(logbot, event.data, lift_speed, log, None)?;
+
+        // Hold the command until the next one is due
+        while start.elapsed() < event.elapsed_time {
+            if let Some(KeyPoll::Exit | KeyPoll::EmergencyStop) =
+                check_key(logbot, input, log, Action::Exit)?
+            {
+                return logbot.emergency_stop();
+            };
+            keep_jogging(logbot)?;
+            dashboard.refresh(logbot)?;
+        }
+    }
+
+    logbot.vehicle.stop()?;
+    Ok(())
+}
+
 /// The main CLI of the program, terminal raw mode needs to be enabled
-fn cli(logbot: &mut Logbot, speed: Speed) -> Result<()> {
+fn cli(
+    logbot: &mut Logbot,
+    input: &mut Input,
+    log: &mut EventLog,
+    dashboard: &mut Dashboard,
+    mut recorder: Option<&mut Recorder>,
+    tuning: &Tuning,
+    calibration_path: &Path,
+) -> Result<()> {
     // Enforce that raw mode is enabled
     anyhow::ensure!(terminal::is_raw_mode_enabled()?);
 
+    // The dashboard starts out showing the speed given on the command line
+    let mut speed = dashboard.speed;
+    start_activity(dashboard, log, Activity::Driving);
+
     let mut state: u8 = 0b0000;
-    let lift_speed = Speed::HALF;
+    let mut steering = Steering::new(tuning.steering);
+    let lift_speed = tuning.lift_speed;
+    // Cleared by an emergency stop until explicitly re-armed
+    let mut armed = true;
 
-    // Read keyboard events
+    // Read keyboard and gamepad events, while keeping the dashboard up to date
     loop {
-        if let Event::Key(key) = event::read()? {
-            match key.kind {
-                KeyEventKind::Press => match key.code {
-                    // Add the modifier to the state
-                    KeyCode::Char(c) => {
-                        let modifier = match c {
-                            'w' => FORWARD,
-                            's' => BACKWARD,
-                            'a' => LEFT,
-                            'd' => RIGHT,
-                            // Calibration
-                            'c' => {
-                                match calibrate(logbot)? {
-                                    // Exit program
-                                    Some(KeyPoll::Esc) => break,
-                                    // Completed successfully or cancelled
-                                    _ => continue,
-                                };
-                            }
-                            // Follow line
-                            'e' => {
-                                if logbot.calibration.is_some() {
-                                    match follow_line(logbot)? {
-                                        KeyPoll::Esc => break,
-                                        KeyPoll::Target => continue,
-                                    };
-                                } else {
-                                    continue;
-                                }
-                            }
-                            _ => continue,
-                        };
-
-                        state |= modifier;
-                        match u8_into_state(state, speed) {
-                            Some(direction) => logbot.vehicle.drive(direction)?,
-                            None => logbot.vehicle.stop()?,
-                        };
-                    }
-                    // Exit the program
-                    KeyCode::Esc => {
-                        break;
-                    }
-                    // Moving the lift is a blocking operation, this means any
-                    // current movement could not be cancelled during the lift operation
-                    //
-                    // To prevent collisions we should only allow lift movement when
-                    // logbot is stationary
-                    KeyCode::Up if state == 0 => {
-                        logbot.lift.up(lift_speed)?;
-                        continue;
-                    }
-                    // Moving the lift is a blocking operation, this means any
-                    // current movement could not be cancelled during the lift operation
-                    //
-                    // To prevent collisions we should only allow lift movement when
-                    // logbot is stationary
-                    KeyCode::Down if state == 0 => {
-                        logbot.lift.down(lift_speed)?;
-                        continue;
-                    }
-                    _ => {}
-                },
-                KeyEventKind::Release => match key.code {
-                    // Remove the modifier from the state
-                    KeyCode::Char(c) => {
-                        let modifier = match c {
-                            'w' => !FORWARD,
-                            's' => !BACKWARD,
-                            'a' => !LEFT,
-                            'd' => !RIGHT,
-                            _ => continue,
-                        };
-
-                        state &= modifier;
-                        match u8_into_state(state, speed) {
-                            Some(direction) => logbot.vehicle.drive(direction)?,
-                            None => logbot.vehicle.stop()?,
-                        };
-                    }
-                    _ => {}
-                },
+        dashboard.refresh(logbot)?;
+        keep_jogging(logbot)?;
+        let Some(event) = input.poll(REFRESH_INTERVAL)? else {
+            // Held arc turns sharpen over time
+            if armed && steering.is_sharpening() {
+                let direction = steering.direction(state, speed);
+                execute(
+                    logbot,
+                    SessionCommand::Drive(direction),
+                    lift_speed,
+                    log,
+                    recorder.as_deref_mut(),
+                )?;
+            };
+            continue;
+        };
+
+        // Fast path, stop logbot before handling anything else
+        if let InputEvent::Press(Action::Stop) = event {
+            logbot.emergency_stop()?;
+        };
+        log.log(LogEvent::Input(event));
+
+        // Only re-arming and exiting are possible after an emergency stop
+        if !armed {
+            match event {
+                InputEvent::Press(Action::Rearm) => {
+                    armed = true;
+                    start_activity(dashboard, log, Activity::Driving);
+                }
+                InputEvent::Press(Action::Exit) => break,
                 _ => {}
             };
+            continue;
+        };
+
+        match event {
+            InputEvent::Press(action) => match action {
+                // Adjust the speed, applying it to the current movement
+                Action::SpeedUp | Action::SpeedDown => {
+                    speed = match action {
+                        Action::SpeedUp => speed.saturating_add_f64(SPEED_STEP),
+                        _ => speed.saturating_sub_f64(SPEED_STEP),
+                    };
+                    dashboard.speed = speed;
+
+                    if state != 0 {
+                        let direction = steering.direction(state, speed);
+                        execute(
+                            logbot,
+                            SessionCommand::Drive(direction),
+                            lift_speed,
+                            log,
+                            recorder.as_deref_mut(),
+                        )?;
+                    };
+                }
+                // Calibration
+                Action::Calibrate => {
+                    halt_jog(logbot, lift_speed, log, recorder.as_deref_mut())?;
+                    // Autonomous movement is not recorded, a replay waits instead
+                    if let Some(recorder) = recorder.as_deref_mut() {
+                        recorder.record(SessionCommand::Drive(VehicleDirection::STOP));
+                    };
+                    start_activity(dashboard, log, Activity::Calibrating);
+                    let result = calibrate(logbot, input, log, dashboard, tuning)?;
+                    start_activity(dashboard, log, Activity::Driving);
+                    match result {
+                        // Exit program
+                        Some(KeyPoll::Exit) => break,
+                        // Cancelled
+                        Some(KeyPoll::Target) => continue,
+                        Some(KeyPoll::EmergencyStop) => {
+                            state = 0b0000;
+                            armed = false;
+                            emergency_stopped(dashboard, log, recorder.as_deref_mut());
+                        }
+                        // Completed successfully, store it for the next session
+                        None => {
+                            if let Some(calibration) = logbot.calibration {
+                                calibration_file::save(calibration_path, &calibration)?;
+                            };
+                        }
+                    };
+                }
+                // Follow line
+                Action::Follow if logbot.calibration.is_some() => {
+                    halt_jog(logbot, lift_speed, log, recorder.as_deref_mut())?;
+                    // Autonomous movement is not recorded, a replay waits instead
+                    if let Some(recorder) = recorder.as_deref_mut() {
+                        recorder.record(SessionCommand::Drive(VehicleDirection::STOP));
+                    };
+                    start_activity(dashboard, log, Activity::Following);
+                    let result = follow_line(logbot, input, log, dashboard, tuning)?;
+                    start_activity(dashboard, log, Activity::Driving);
+                    match result {
+                        KeyPoll::Exit => break,
+                        KeyPoll::Target => continue,
+                        KeyPoll::EmergencyStop => {
+                            state = 0b0000;
+                            armed = false;
+                            emergency_stopped(dashboard, log, recorder.as_deref_mut());
+                        }
+                    };
+                }
+                // Logbot was already stopped, keys need to be pressed again
+                // to drive once re-armed
+                Action::Stop => {
+                    state = 0b0000;
+                    armed = false;
+                    emergency_stopped(dashboard, log, recorder.as_deref_mut());
+                }
+                // Exit the program
+                Action::Exit => {
+                    break;
+                }
+                // Jog the lift while the key is held, slower when Shift is held
+                Action::LiftUp | Action::LiftDown => {
+                    let speed = match input.shift() {
+                        true => Speed::new_clamp(lift_speed.value() * PRECISE_JOG),
+                        false => lift_speed,
+                    };
+                    let command = match action {
+                        Action::LiftUp => SessionCommand::JogUp(speed),
+                        _ => SessionCommand::JogDown(speed),
+                    };
+                    execute(logbot, command, lift_speed, log, recorder.as_deref_mut())?;
+                }
+                // Add the modifier to the state
+                action => {
+                    let Some(modifier) = movement(action) else {
+                        continue;
+                    };
+
+                    state |= modifier;
+                    let direction = steering.direction(state, speed);
+                    execute(
+                        logbot,
+                        SessionCommand::Drive(direction),
+                        lift_speed,
+                        log,
+                        recorder.as_deref_mut(),
+                    )?;
+                }
+            },
+            // Stop jogging the lift once its key is released
+            InputEvent::Release(Action::LiftUp | Action::LiftDown) => {
+                halt_jog(logbot, lift_speed, log, recorder.as_deref_mut())?;
+            }
+            InputEvent::Release(action) => {
+                // Remove the modifier from the state
+                let Some(modifier) = movement(action) else {
+                    continue;
+                };
+
+                state &= !modifier;
+                let direction = steering.direction(state, speed);
+                execute(
+                    logbot,
+                    SessionCommand::Drive(direction),
+                    lift_speed,
+                    log,
+                    recorder.as_deref_mut(),
+                )?;
+            }
+            // Drive proportionally to the stick position
+            #[cfg(feature = "gamepad")]
+            InputEvent::Stick { x, y } => {
+                let direction = VehicleDirection::from_axes(x, y, speed);
+                execute(
+                    logbot,
+                    SessionCommand::Drive(direction),
+                    lift_speed,
+                    log,
+                    recorder.as_deref_mut(),
+                )?;
+            }
         };
     }
 
-    // Stop the vehicle when user exists the program
+    // Stop the vehicle and the lift when user exists the program
     logbot.vehicle.stop()?;
+    logbot.lift.halt()?;
     Ok(())
 }
 
+/// Create the vehicle, sensors and lift of the hardware using the motors of a
+/// [`Backend`], or of the simulation when a [`World`] is given
+fn setup(
+    backend: Backend,
+    world: Option<&SharedWorld>,
+) -> Result<(BackendVehicle, SensorBackend, LiftBackend)> {
+    if let Some(world) = world {
+        return Ok((
+            Vehicle::new(Motor::sim(world.clone()), Motor::sim(world.clone())),
+            SensorBackend::Sim(sim::SimSensors::new(world.clone())),
+            LiftBackend::Sim(sim::SimLift::new(world.clone())),
+        ));
+    };
+
+    let right_motor = Motor::try_default(backend)?;
+    let left_motor = Motor::try_default(backend)?;
+    // Make sure to sleep through activation period
+    std::thread::sleep(backend.activation_period());
+
+    Ok((
+        Vehicle::new(left_motor, right_motor),
+        SensorBackend::Hardware(SensorController::try_default()?),
+        LiftBackend::Hardware(Box::new(LiftMotor::try_default()?)),
+    ))
+}
+
+/// Set up the hardware and run an autonomous mission
+///
+/// The vehicle is always stopped afterwards, even if the mission failed.
+fn autonomous(
+    backend: Backend,
+    world: Option<&SharedWorld>,
+    black_box: Option<&Path>,
+    mission: impl FnOnce(&mut AutonomousLogbot) -> Result<()>,
+) -> Result<()> {
+    let black_box = black_box
+        .map(|directory| recorder::Recorder::open(RecorderConfig::new(directory)))
+        .transpose()
+        .context("failed to open the black box")?;
+    let (vehicle, sensors, lift) = setup(backend, world)?;
+    let mut logbot = logbot::Logbot::builder()
+        .vehicle(vehicle)
+        .sensors(sensors)
+        .lift(lift)
+        .hooks(black_box)
+        .build();
+
+    let result = mission(&mut logbot);
+
+    // Always stop the vehicle.
+    logbot.stop()?;
+    if let Some(black_box) = logbot.hooks_mut() {
+        black_box.flush().context("failed to write the black box")?;
+        if let Some(e) = black_box.take_error() {
+            return Err(e).context("failed to write the black box");
+        };
+    };
+
+    result
+}
+
+/// Run the self-test of the components and print the report
+///
+/// Fails when any of the components is unhealthy.
+fn self_test(backend: Backend, world: Option<&SharedWorld>) -> Result<()> {
+    // The simulation has no battery monitor
+    let battery = match world {
+        Some(_) => None,
+        None => HardwareConfig::load()?.battery,
+    };
+    let (vehicle, sensors, lift) = setup(backend, world)?;
+    let mut logbot = logbot::Logbot::builder()
+        .vehicle(vehicle)
+        .sensors(sensors)
+        .lift(lift)
+        .build();
+
+    let report = logbot.self_test(battery);
+    println!("Motors:       {}", describe(&report.motors, |_| None));
+    println!(
+        "Left sensor:  {}",
+        describe(&report.left_sensor, |value| Some(value.to_string()))
+    );
+    println!(
+        "Right sensor: {}",
+        describe(&report.right_sensor, |value| Some(value.to_string()))
+    );
+    println!(
+        "Lift:         {}",
+        describe(&report.lift, |position| Some(position.to_string()))
+    );
+    if let Some(battery) = &report.battery {
+        println!(
+            "Battery:      {}",
+            describe(battery, |volts| Some(format!("{volts:.2}V")))
+        );
+    };
+
+    anyhow::ensure!(report.healthy, "the self-test failed");
+    Ok(())
+}
+
+/// Describe the outcome of a [`Check`] of the self-test, with its value if any
+fn describe<T>(check: &Check<T>, value: impl Fn(&T) -> Option<String>) -> String {
+    let value = check.value.as_ref().and_then(value);
+    match (check.passed, value, &check.error) {
+        (true, Some(value), _) => format!("ok ({value})"),
+        (true, None, _) => "ok".to_string(),
+        (false, _, Some(error)) => format!("failed, {error}"),
+        (false, _, None) => "failed".to_string(),
+    }
+}
+
+/// Execute a script without any user interaction
+fn run_script(
+    path: PathBuf,
+    tuning: &Tuning,
+    backend: Backend,
+    world: Option<&SharedWorld>,
+    black_box: Option<&Path>,
+) -> Result<()> {
+    // Read the script before setting up hardware, to fail early
+    let mission = script::load(path, tuning)?;
+    autonomous(backend, world, black_box, |logbot| {
+        script::run(logbot, &mission)
+    })
+}
+
+/// Run the box-moving [demo](demo::demo)
+fn run_demo(
+    config: &DemoConfig,
+    backend: Backend,
+    world: Option<&SharedWorld>,
+    black_box: Option<&Path>,
+) -> Result<()> {
+    autonomous(backend, world, black_box, |logbot| {
+        let report = demo::demo(logbot, config, &mut DemoProgress, &CancelToken::new());
+        for recovery in &report.recoveries {
+            println!("Recovered at {recovery}");
+        }
+        match report.failure {
+            Some(failure) => Err(anyhow::anyhow!("the demo failed at {failure}")),
+            None => Ok(()),
+        }
+    })
+}
+
+/// Drive logbot with the commands recorded to a black box
+fn run_redrive(
+    directory: &Path,
+    backend: Backend,
+    world: Option<&SharedWorld>,
+    black_box: Option<&Path>,
+) -> Result<()> {
+    // Read the recording before setting up hardware, to fail early
+    let records = recorder::read(directory).context("failed to read the black box")?;
+    autonomous(backend, world, black_box, |logbot| {
+        Ok(recorder::replay::redrive(logbot, &records)?)
+    })
+}
+
+/// Print how differently a changed tuning follows the line on the sensor
+/// values recorded to a black box
+fn run_compare(
+    directory: &Path,
+    channel: u8,
+    calibration: SensorCalibration,
+    baseline: &Tuning,
+    candidate: &Tuning,
+) -> Result<()> {
+    let records = recorder::read(directory).context("failed to read the black box")?;
+    let comparison = recorder::replay::compare(
+        &records,
+        channel,
+        baseline.follow_line_config(calibration),
+        candidate.follow_line_config(calibration),
+    );
+    println!("Steps: {}", comparison.steps());
+    println!(
+        "Largest control difference: {:.4}",
+        comparison.max_control_difference()
+    );
+    println!(
+        "Largest wheel speed difference: {:.4}",
+        comparison.max_speed_difference()
+    );
+    Ok(())
+}
+
+/// Prints the progress of the [demo](demo::demo)
+#[derive(Debug)]
+struct DemoProgress;
+
+impl DemoObserver for DemoProgress {
+    fn on_phase_start(&mut self, number: usize, step: &Step, elapsed: Duration) {
+        println!(
+            "[{:>6.1}s] Step {number}: {step} ({})",
+            elapsed.as_secs_f64(),
+            step.phase()
+        );
+    }
+
+    fn on_phase_end(&mut self, _number: usize, step: &Step, duration: Duration) {
+        println!(
+            "          {} took {:.1}s",
+            step.phase(),
+            duration.as_secs_f64()
+        );
+    }
+
+    fn on_lap(&mut self, lap: usize, duration: Duration) {
+        println!("Lap {lap}: {:.1}s", duration.as_secs_f64());
+    }
+}
+
+/// Control a logbot running the `server` using the keyboard
+fn remote_control(remote: &Remote, input: &mut Input, speed: Speed, tuning: &Tuning) -> Result<()> {
+    terminal::enable_raw_mode()?;
+    let mut stdout = stdout();
+    let flag = PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES);
+    execute!(stdout, flag)?;
+
+    // Restore the terminal even if we encounter an error
+    let result = remote::control(remote, input, speed, Steering::new(tuning.steering));
+
+    execute!(stdout, PopKeyboardEnhancementFlags)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
 /// Entrypoint for the `cli` binary
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    if let Some(profile) = args.profile {
+        consts::config::set_profile(profile);
+    };
+
     // Get the logbot speed from args
     let speed = Speed::new_clamp(args.speed as f64 / 100.0);
 
-    let right_motor: DCMotor<Right> = DCMotor::try_default()?;
-    let left_motor: DCMotor<Left> = DCMotor::try_default()?;
-    // Make sure to sleep through activation period
-    std::thread::sleep(Duration::from_secs(5));
+    let preset = args
+        .preset
+        .unwrap_or_else(|| Command::default_preset(args.command.as_ref()));
+    let mut tuning = match args.tuning {
+        Some(path) => Tuning::from_file(preset, path)?,
+        None => preset.tuning(),
+    };
+
+    let world = args.sim.then(World::shared);
+
+    if args.self_test {
+        return self_test(args.backend, world.as_ref());
+    };
+
+    match args.command {
+        Some(Command::Run { script }) => {
+            return run_script(
+                script,
+                &tuning,
+                args.backend,
+                world.as_ref(),
+                args.black_box.as_deref(),
+            )
+        }
+        Some(Command::Demo {
+            follow_speed,
+            turn_speed,
+            lift_speed,
+            boxes,
+            settle_ms,
+            leave_line_ms,
+            find_edge_timeout_ms,
+        }) => {
+            let percent = |value: u8| Speed::new_clamp(value as f64 / 100.0);
+            tuning.speed = follow_speed.map_or(tuning.speed, percent);
+            tuning.turn_speed = turn_speed.map_or(tuning.turn_speed, percent);
+            tuning.lift_speed = lift_speed.map_or(tuning.lift_speed, percent);
+            let config = DemoConfig {
+                tuning,
+                boxes,
+                settle_ms,
+                leave_line_ms,
+                find_edge_timeout_ms,
+            };
+            return run_demo(
+                &config,
+                args.backend,
+                world.as_ref(),
+                args.black_box.as_deref(),
+            );
+        }
+        Some(Command::Redrive { directory }) => {
+            return run_redrive(
+                &directory,
+                args.backend,
+                world.as_ref(),
+                args.black_box.as_deref(),
+            )
+        }
+        Some(Command::Compare {
+            directory,
+            candidate,
+            channel,
+        }) => {
+            let calibration = calibration_file::load(
+                args.calibration
+                    .as_deref()
+                    .unwrap_or(Path::new(calibration_file::DEFAULT_PATH)),
+            )?;
+            let candidate = Tuning::from_file(preset, candidate)?;
+            return run_compare(&directory, channel, calibration, &tuning, &candidate);
+        }
+        None => {}
+    };
+
+    let keys = match args.keys {
+        Some(path) => Keybindings::from_file(path)?,
+        None => Keybindings::default(),
+    };
+    #[cfg(feature = "gamepad")]
+    let mut input = match args.gamepad {
+        true => Input::with_gamepad(keys)?,
+        false => Input::keyboard(keys),
+    };
+    #[cfg(not(feature = "gamepad"))]
+    let mut input = Input::keyboard(keys);
+
+    if let Some(url) = args.remote {
+        return remote_control(&Remote::new(&url), &mut input, speed, &tuning);
+    };
+
+    // Read the session before setting up hardware, to fail early
+    let session = args.replay.map(session::load).transpose()?;
+    let mut recorder = args.record.map(Recorder::new);
 
-    let vehicle = Vehicle::new(left_motor, right_motor);
-    let sensors = SensorController::try_default()?;
+    // A missing calibration file is created after calibrating
+    let calibration = match &args.calibration {
+        Some(path) if path.exists() => Some(calibration_file::load(path)?),
+        _ => None,
+    };
+    let calibration_path = args
+        .calibration
+        .unwrap_or_else(|| PathBuf::from(calibration_file::DEFAULT_PATH));
 
-    let lift = LiftMotor::try_default()?;
+    let (vehicle, sensors, lift) = setup(args.backend, world.as_ref())?;
 
     let mut logbot = Logbot {
         vehicle,
         sensors,
         lift,
-        calibration: None,
+        calibration,
+        jog: None,
     };
 
+    // Enables raw mode and draws to the alternate screen
+    let mut dashboard = Dashboard::new(
+        ratatui::try_init()?,
+        speed,
+        recorder.is_some(),
+        input.keys().help(),
+    );
+    dashboard.simulation = world;
+
     let mut stdout = stdout();
-    terminal::enable_raw_mode()?;
     let flag = PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES);
     execute!(stdout, flag)?;
 
     // We run the main code in another function since we still need to disable
     // terminal raw mode even if we encounter an error
-    let result = cli(&mut logbot, speed);
+    let mut log = EventLog::default();
+    let result = match session {
+        Some(session) => replay(
+            &mut logbot,
+            &mut input,
+            &mut log,
+            &mut dashboard,
+            &session,
+            tuning.lift_speed,
+        ),
+        None => cli(
+            &mut logbot,
+            &mut input,
+            &mut log,
+            &mut dashboard,
+            recorder.as_mut(),
+            &tuning,
+            &calibration_path,
+        ),
+    };
 
     execute!(stdout, PopKeyboardEnhancementFlags)?;
-    terminal::disable_raw_mode()?;
+    ratatui::try_restore()?;
 
     // Always stop the vehicle.
     logbot.vehicle.stop()?;
 
+    // Save the recording and the event log even if the session ended with an error
+    if let Some(recorder) = recorder {
+        recorder.save()?;
+    };
+    log.finish();
+    println!("{}", log.summary());
+    if let Some(path) = args.events {
+        log.export(path)?;
+    };
+
     result
 }
@@ -0,0 +1,176 @@
+//! Remote control of logbot using the REST API of the `server`
+
+use std::{
+    io::{stdout, Write},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use directions::VehicleDirection;
+use serde::Deserialize;
+use speed::Speed;
+
+use crate::{
+    input::{Input, InputEvent},
+    keys::Action,
+    steering::{movement, Steering},
+    SPEED_STEP,
+};
+
+/// Interval at which input is polled
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Response returned by every endpoint of the `server`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Response {
+    /// HTTP status code describing the result
+    pub status: u16,
+    /// The cancelled command on success, otherwise the reason of the failure
+    pub reason: String,
+}
+
+/// Client of a logbot `server`
+#[derive(Debug)]
+pub struct Remote {
+    /// Agent used for all requests
+    agent: ureq::Agent,
+    /// Base URL of the server, without a trailing slash
+    url: String,
+}
+
+impl Remote {
+    /// Create a [`Remote`] for a server at the given base URL
+    pub fn new(url: &str) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Check that the server and its hardware are running
+    pub fn health(&self) -> Result<Response> {
+        let url = format!("{}/v1/health", self.url);
+        let response = self
+            .agent
+            .get(&url)
+            .call()
+            .with_context(|| format!("failed to reach `{url}`"))?;
+        Ok(response.into_json()?)
+    }
+
+    /// Send a command to an endpoint, e.g. `lift/up`
+    pub fn send(&self, endpoint: &str) -> Result<Response> {
+        let url = format!("{}/v1/{endpoint}", self.url);
+        let response = self
+            .agent
+            .post(&url)
+            .call()
+            .with_context(|| format!("failed to reach `{url}`"))?;
+        Ok(response.into_json()?)
+    }
+
+    /// Drive in a [`VehicleDirection`]
+    pub fn drive(&self, direction: VehicleDirection) -> Result<Response> {
+        let url = format!("{}/v1/drive", self.url);
+        let response = self
+            .agent
+            .post(&url)
+            .send_json(direction)
+            .with_context(|| format!("failed to reach `{url}`"))?;
+        Ok(response.into_json()?)
+    }
+}
+
+/// Print the [`Response`] to a command, terminal raw mode needs `\r\n` line endings
+fn report(command: &str, response: &Response) -> Result<()> {
+    let mut stdout = stdout();
+    write!(
+        stdout,
+        "{command}: {} {}\r\n",
+        response.status, response.reason
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Control a [`Remote`] logbot using the keyboard, terminal raw mode needs to be enabled
+///
+/// The lift is only moved while logbot is stationary.
+pub fn control(
+    remote: &Remote,
+    input: &mut Input,
+    mut speed: Speed,
+    mut steering: Steering,
+) -> Result<()> {
+    let mut state: u8 = 0b0000;
+    let mut direction = VehicleDirection::STOP;
+
+    report("health", &remote.health()?)?;
+
+    loop {
+        let next = match input.poll(POLL_INTERVAL)? {
+            // Held arc turns sharpen over time
+            None if steering.is_sharpening() => steering.direction(state, speed),
+            None => continue,
+            Some(event) => match event {
+                InputEvent::Press(Action::Exit) => break,
+                InputEvent::Press(Action::LiftUp) if direction == VehicleDirection::STOP => {
+                    report("lift up", &remote.send("lift/up")?)?;
+                    continue;
+                }
+                InputEvent::Press(Action::LiftDown) if direction == VehicleDirection::STOP => {
+                    report("lift down", &remote.send("lift/down")?)?;
+                    continue;
+                }
+                InputEvent::Press(
+                    action @ (Action::Calibrate | Action::FindEdge | Action::Follow | Action::Stop),
+                ) => {
+                    let endpoint = match action {
+                        Action::Calibrate => "calibrate",
+                        Action::FindEdge => "edge",
+                        Action::Follow => "follow",
+                        _ => "stop",
+                    };
+                    report(endpoint, &remote.send(endpoint)?)?;
+
+                    // The server is no longer driving in the last direction
+                    direction = VehicleDirection::STOP;
+                    continue;
+                }
+                // Adjust the speed, applying it to the current movement
+                InputEvent::Press(Action::SpeedUp) => {
+                    speed = speed.saturating_add_f64(SPEED_STEP);
+                    steering.direction(state, speed)
+                }
+                InputEvent::Press(Action::SpeedDown) => {
+                    speed = speed.saturating_sub_f64(SPEED_STEP);
+                    steering.direction(state, speed)
+                }
+                InputEvent::Press(action) => match movement(action) {
+                    Some(modifier) => {
+                        state |= modifier;
+                        steering.direction(state, speed)
+                    }
+                    None => continue,
+                },
+                InputEvent::Release(action) => match movement(action) {
+                    Some(modifier) => {
+                        state &= !modifier;
+                        steering.direction(state, speed)
+                    }
+                    None => continue,
+                },
+                #[cfg(feature = "gamepad")]
+                InputEvent::Stick { x, y } => VehicleDirection::from_axes(x, y, speed),
+            },
+        };
+
+        if next != direction {
+            direction = next;
+            report(&direction.to_string(), &remote.drive(direction)?)?;
+        };
+    }
+
+    report("stop", &remote.send("stop")?)?;
+    Ok(())
+}
@@ -0,0 +1,181 @@
+//! Scripted missions executed by `cli run`
+//!
+//! A script is a TOML file containing a list of steps, which are executed in
+//! order:
+//!
+//! ```toml
+//! [[step]]
+//! action = "calibrate"
+//!
+//! [[step]]
+//! action = "find_edge"
+//!
+//! [[step]]
+//! action = "follow"      # follow the line until a stop line
+//!
+//! [[step]]
+//! action = "lift_up"
+//! speed = 0.8            # the lift speed of the tuning if not given
+//!
+//! [[step]]
+//! action = "turn"        # spin off the line and back onto it
+//! direction = "spin_left:0.3"
+//! leave_line_ms = 800
+//!
+//! [[step]]
+//! action = "drive"
+//! direction = "backward:0.2"
+//! duration_ms = 1500
+//!
+//! [[step]]
+//! action = "spin"
+//! direction = "spin_right:0.2"
+//! angle = 180.0
+//! ```
+//!
+//! The steps are those of a [`Mission`], spinning by an angle is timed using
+//! the `spin_rate` of the [`Tuning`]. The demo itself is written as a script
+//! in `missions/demo.toml`.
+
+use std::{ops::ControlFlow, path::Path};
+
+use anyhow::{Context, Result};
+use demo::{
+    cancel::CancelToken,
+    mission::{self, Mission, Step},
+    DemoError,
+};
+use directions::{SpinDirection, VehicleDirection};
+use interfaces::{Lift, SensorRead, Spin};
+use serde::Deserialize;
+use tuning::Tuning;
+
+/// The steps of a script file, see the [module](self) documentation for the format
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Script {
+    /// The steps in order of execution
+    #[serde(rename = "step", default)]
+    steps: Vec<Step>,
+}
+
+/// Parse and validate the [`Mission`] of a script
+pub fn parse(value: &str, tuning: &Tuning) -> Result<Mission> {
+    let script: Script = toml::from_str(value)?;
+    Ok(Mission::new(script.steps, *tuning)?)
+}
+
+/// Read the [`Mission`] of a script file
+pub fn load(path: impl AsRef<Path>, tuning: &Tuning) -> Result<Mission> {
+    let path = path.as_ref();
+    let value = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+    parse(&value, tuning).with_context(|| format!("invalid script `{}`", path.display()))
+}
+
+/// Execute a [`Mission`], printing each step before it starts
+pub fn run<L>(logbot: &mut L, mission: &Mission) -> Result<()>
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+    DemoError<L>: std::error::Error + Send + Sync + 'static,
+{
+    let steps = mission.steps().len();
+    mission::run(logbot, mission, &CancelToken::new(), |number, step| {
+        println!("step {number}/{steps}: {step}");
+        ControlFlow::Continue(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use directions::{SpinDirection, VehicleDirection};
+    use speed::Speed;
+    use tuning::Tuning;
+
+    use demo::{
+        config::DemoConfig,
+        mission::{Mission, Step},
+    };
+
+    use super::parse;
+
+    /// Test parsing a script containing every kind of step
+    #[test]
+    fn parse_script() {
+        let mission = parse(
+            r#"
+            [[step]]
+            action = "calibrate"
+
+            [[step]]
+            action = "find_edge"
+
+            [[step]]
+            action = "follow"
+
+            [[step]]
+            action = "lift_up"
+
+            [[step]]
+            action = "drive"
+            direction = "forward:0.5"
+            duration_ms = 1500
+
+            [[step]]
+            action = "spin"
+            direction = "spin_left:0.5"
+            angle = 90.0
+            "#,
+            &Tuning::SLOW,
+        )
+        .unwrap();
+
+        assert_eq!(
+            mission.steps(),
+            [
+                Step::Calibrate,
+                Step::FindEdge,
+                Step::Follow,
+                Step::LiftUp { speed: None },
+                Step::Drive {
+                    direction: VehicleDirection::forward(Speed::HALF),
+                    duration_ms: 1500,
+                },
+                Step::Spin {
+                    direction: SpinDirection::Left(Speed::HALF),
+                    angle: 90.0,
+                },
+            ]
+        );
+    }
+
+    /// Test that the script of the demo is the same as the built-in demo
+    #[test]
+    fn parse_demo() {
+        let mission = parse(include_str!("../missions/demo.toml"), &Tuning::DEMO).unwrap();
+        assert_eq!(mission, Mission::demo(&DemoConfig::default()));
+    }
+
+    /// Test that scripts which cannot be executed are rejected
+    #[test]
+    fn parse_invalid() {
+        let parse = |value| parse(value, &Tuning::SLOW);
+
+        // Unknown action
+        assert!(parse("[[step]]\naction = \"jump\"").is_err());
+        // Invalid direction
+        assert!(
+            parse("[[step]]\naction = \"drive\"\ndirection = \"up\"\nduration_ms = 1").is_err()
+        );
+        // Following before calibrating
+        assert!(parse("[[step]]\naction = \"follow\"").is_err());
+        // Spinning without speed never completes
+        assert!(
+            parse("[[step]]\naction = \"spin\"\ndirection = \"spin_left:0\"\nangle = 90.0")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Recording of driving sessions
+//!
+//! A session file contains one command per line, prefixed by the number of
+//! milliseconds until the next command, e.g. `1200 forward:0.5` or
+//! `300 jog_up:0.3`. Empty lines and lines starting with `#` are ignored.
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use directions::VehicleDirection;
+use event_list::{CompletedEvent, EventList};
+use speed::Speed;
+
+/// A command issued during a driving session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionCommand {
+    /// Drive the vehicle in a [`VehicleDirection`]
+    Drive(VehicleDirection),
+    /// Move the lift all the way up
+    LiftUp,
+    /// Move the lift all the way down
+    LiftDown,
+    /// Start moving the lift up until it is halted
+    JogUp(Speed),
+    /// Start moving the lift down until it is halted
+    JogDown(Speed),
+    /// Stop moving the lift
+    LiftHalt,
+}
+
+impl Display for SessionCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Drive(direction) => direction.fmt(f),
+            Self::LiftUp => write!(f, "lift_up"),
+            Self::LiftDown => write!(f, "lift_down"),
+            Self::JogUp(speed) => write!(f, "jog_up:{}", speed.value()),
+            Self::JogDown(speed) => write!(f, "jog_down:{}", speed.value()),
+            Self::LiftHalt => write!(f, "lift_halt"),
+        }
+    }
+}
+
+impl FromStr for SessionCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let jog_speed = |speed: &str| -> Result<Speed> {
+            let value: f64 = speed.parse()?;
+            Speed::try_from(value).map_err(|value| anyhow!("speed {value} is out of range"))
+        };
+
+        match s.trim() {
+            "lift_up" => Ok(Self::LiftUp),
+            "lift_down" => Ok(Self::LiftDown),
+            "lift_halt" => Ok(Self::LiftHalt),
+            s => match s.split_once(':') {
+                Some(("jog_up", speed)) => Ok(Self::JogUp(jog_speed(speed)?)),
+                Some(("jog_down", speed)) => Ok(Self::JogDown(jog_speed(speed)?)),
+                _ => Ok(Self::Drive(s.parse()?)),
+            },
+        }
+    }
+}
+
+/// Records [`SessionCommand`]s with their timing and writes them to a file
+#[derive(Debug)]
+pub struct Recorder {
+    /// Path of the session file
+    path: PathBuf,
+    /// The recorded commands
+    events: EventList<SessionCommand>,
+}
+
+impl Recorder {
+    /// Create a [`Recorder`] that is saved to a given path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            events: EventList::default(),
+        }
+    }
+
+    /// Record a [`SessionCommand`] issued now
+    pub fn record(&mut self, command: SessionCommand) {
+        self.events.push(command);
+    }
+
+    /// Complete the last command and write the session to its file
+    pub fn save(mut self) -> Result<()> {
+        self.events.complete();
+
+        let file = File::create(&self.path)
+            .with_context(|| format!("failed to create `{}`", self.path.display()))?;
+        let mut writer = BufWriter::new(file);
+        for event in self.events.events() {
+            writeln!(writer, "{} {}", event.elapsed_time.as_millis(), event.data)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Parse a session, see the [module](self) documentation for the format
+pub fn parse(value: &str) -> Result<Vec<CompletedEvent<SessionCommand>>> {
+    value
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let (millis, command) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| anyhow!("line {number}: expected `<milliseconds> <command>`"))?;
+            let millis: u64 = millis
+                .parse()
+                .with_context(|| format!("line {number}: invalid duration `{millis}`"))?;
+            let command = command
+                .parse()
+                .with_context(|| format!("line {number}: invalid command"))?;
+
+            Ok(CompletedEvent {
+                data: command,
+                elapsed_time: Duration::from_millis(millis),
+            })
+        })
+        .collect()
+}
+
+/// Read a session from a file
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<CompletedEvent<SessionCommand>>> {
+    let path = path.as_ref();
+    let value = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+    parse(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use directions::VehicleDirection;
+    use speed::Speed;
+
+    use super::{parse, SessionCommand};
+
+    /// Test that every command survives a round trip through its text form
+    #[test]
+    fn command_round_trip() {
+        for command in [
+            SessionCommand::Drive(VehicleDirection::forward(Speed::HALF)),
+            SessionCommand::Drive(VehicleDirection::STOP),
+            SessionCommand::LiftUp,
+            SessionCommand::LiftDown,
+            SessionCommand::JogUp(Speed::HALF),
+            SessionCommand::JogDown(Speed::MAX),
+            SessionCommand::LiftHalt,
+        ] {
+            assert_eq!(
+                command.to_string().parse::<SessionCommand>().unwrap(),
+                command
+            );
+        }
+    }
+
+    /// Test parsing a session with comments and empty lines
+    #[test]
+    fn parse_session() {
+        let session = parse("# start\n1200 forward:0.5\n\n300 lift_up\n").unwrap();
+        assert_eq!(session.len(), 2);
+        assert_eq!(
+            session[0].data,
+            SessionCommand::Drive(VehicleDirection::forward(Speed::HALF))
+        );
+        assert_eq!(session[0].elapsed_time, Duration::from_millis(1200));
+        assert_eq!(session[1].data, SessionCommand::LiftUp);
+    }
+
+    /// Test that malformed lines are rejected
+    #[test]
+    fn parse_invalid() {
+        assert!(parse("forward:0.5").is_err());
+        assert!(parse("abc forward:0.5").is_err());
+        assert!(parse("100 sideways").is_err());
+        assert!(parse("100 jog_up:2").is_err());
+    }
+}
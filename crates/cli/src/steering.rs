@@ -0,0 +1,110 @@
+//! Turning the held movement keys into a [`VehicleDirection`]
+
+use std::time::Instant;
+
+use directions::VehicleDirection;
+use speed::Speed;
+use tuning::SteeringTuning;
+
+use crate::keys::Action;
+
+const FORWARD: u8 = 0b0001;
+const BACKWARD: u8 = 0b0010;
+const LEFT: u8 = 0b0100;
+const RIGHT: u8 = 0b1000;
+
+/// The state bit of a movement [`Action`]
+pub fn movement(action: Action) -> Option<u8> {
+    match action {
+        Action::Forward => Some(FORWARD),
+        Action::Backward => Some(BACKWARD),
+        Action::Left => Some(LEFT),
+        Action::Right => Some(RIGHT),
+        _ => None,
+    }
+}
+
+/// Steers arc turns, which sharpen the longer a turn key is held
+#[derive(Debug, Clone, Copy)]
+pub struct Steering {
+    /// Sharpness of the turns
+    tuning: SteeringTuning,
+    /// [`Instant`] the current arc turn started
+    turning_since: Option<Instant>,
+}
+
+impl Steering {
+    /// Create a new [`Steering`]
+    pub fn new(tuning: SteeringTuning) -> Self {
+        Self {
+            tuning,
+            turning_since: None,
+        }
+    }
+
+    /// Whether an arc turn is held, meaning the direction changes over time
+    pub fn is_sharpening(&self) -> bool {
+        self.turning_since.is_some() && self.tuning.sharpening != 0.0
+    }
+
+    /// Turn a [`u8`] that represents the held movement keys into a [`VehicleDirection`]
+    pub fn direction(&mut self, state: u8, speed: Speed) -> VehicleDirection {
+        self.direction_at(state, speed, Instant::now())
+    }
+
+    /// [`Steering::direction`] at a given [`Instant`]
+    fn direction_at(&mut self, state: u8, speed: Speed, now: Instant) -> VehicleDirection {
+        // Contradicting states cancel each other out
+        let axis = |positive: u8, negative: u8| {
+            (state & positive != 0) as u8 as f64 - (state & negative != 0) as u8 as f64
+        };
+
+        let y = axis(FORWARD, BACKWARD);
+        let mut x = axis(RIGHT, LEFT);
+
+        // Turn in an arc when moving, mirroring the steering when reversing
+        if y != 0.0 && x != 0.0 {
+            let since = *self.turning_since.get_or_insert(now);
+            x *= self.tuning.sharpness_after(now - since) * y;
+        } else {
+            self.turning_since = None;
+        };
+
+        VehicleDirection::from_axes(x, y, speed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use directions::{MotorDirection, VehicleDirection};
+    use speed::Speed;
+    use tuning::SteeringTuning;
+
+    use super::{Steering, FORWARD, LEFT};
+
+    /// Test that an arc turn sharpens until pivoting on the inner wheel
+    #[test]
+    fn arc_sharpens() {
+        let mut steering = Steering::new(SteeringTuning {
+            sharpness: 0.5,
+            sharpening: 0.5,
+        });
+        let start = Instant::now();
+
+        // Inner wheel turns at a third of the speed of the outer wheel
+        let direction = steering.direction_at(FORWARD | LEFT, Speed::MAX, start);
+        assert_eq!(direction.right, MotorDirection::Forward(Speed::MAX));
+        assert!(steering.is_sharpening());
+
+        let direction =
+            steering.direction_at(FORWARD | LEFT, Speed::MAX, start + Duration::from_secs(1));
+        assert_eq!(direction.left, MotorDirection::Stop);
+
+        // Releasing the turn resets the sharpness
+        let direction = steering.direction_at(FORWARD, Speed::MAX, start + Duration::from_secs(2));
+        assert_eq!(direction, VehicleDirection::forward(Speed::MAX));
+        assert!(!steering.is_sharpening());
+    }
+}
@@ -1,15 +1,23 @@
 //! Provide abstractions for hardware
 //!
 //! Provides abstractions for individual hardware components
-//! Uses the [`rppal`] library for interfacing with hardware.
+//! The components are written against the traits of the [`hal`] crate, and
+//! with the `rppal` feature (on by default) use the `rppal` library of the
+//! Raspberry Pi unless created from pins of another board.
 //! Often only the current state is saved in addition to the
 //! required data for interfacing with them.
 
+#[cfg(feature = "mock")]
+pub mod mock;
 mod motors;
 mod sensor;
+#[cfg(feature = "sim")]
+pub mod sim;
+mod ultrasonic;
 
 pub use motors::hardware_pwm;
 pub use motors::software_pwm;
 pub use motors::{Left, PwmConfig, Right};
 
 pub use sensor::SensorController;
+pub use ultrasonic::Ultrasonic;
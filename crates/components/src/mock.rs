@@ -0,0 +1,172 @@
+//! Mock components that don't require any hardware
+//!
+//! The mock components only keep track of their state, which allows running
+//! the project on machines without GPIO or I2C.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+
+use directions::MotorDirection;
+use interfaces::{Drive, JogLift, Lift, RangeFinder, SensorRead, ToSensorChannel};
+use speed::Speed;
+
+/// Motor that only records the [`MotorDirection`] it drives in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MockMotor<Side> {
+    /// State of the Motor
+    state: Option<MotorDirection>,
+    /// Zero-sized phantom data that stores the side of the Motor
+    _phantom: PhantomData<Side>,
+}
+
+impl<Side> MockMotor<Side> {
+    /// Create a new stopped [`MockMotor`]
+    pub fn new() -> Self {
+        Self {
+            state: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The current [`MotorDirection`], or [None] if the motor is stopped
+    pub fn state(&self) -> Option<MotorDirection> {
+        self.state
+    }
+}
+
+impl<Side> Default for MockMotor<Side> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Side> Drive for MockMotor<Side> {
+    type Direction = MotorDirection;
+    type Error = Infallible;
+
+    fn drive(
+        &mut self,
+        direction: Self::Direction,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
+        match direction {
+            Self::Direction::Stop => self.stop(),
+            direction => Ok(self.state.replace(direction)),
+        }
+    }
+
+    fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+        Ok(self.state.take())
+    }
+}
+
+/// Sensor controller returning preset values for every channel
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockSensorController {
+    /// Values of channels that were [set](Self::set)
+    values: HashMap<u8, u8>,
+    /// Value of all other channels
+    fallback: u8,
+}
+
+impl MockSensorController {
+    /// Create a new [`MockSensorController`] reading `fallback` on every channel
+    pub fn new(fallback: u8) -> Self {
+        Self {
+            values: HashMap::new(),
+            fallback,
+        }
+    }
+
+    /// Set the value read from a given sensor
+    pub fn set(&mut self, sensor: impl ToSensorChannel, value: u8) {
+        self.values.insert(sensor.to_channel(), value);
+    }
+}
+
+impl SensorRead for MockSensorController {
+    type Output = u8;
+    type Error = Infallible;
+
+    fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
+        let channel = sensor.to_channel();
+        Ok(self.values.get(&channel).copied().unwrap_or(self.fallback))
+    }
+}
+
+/// Range finder measuring a preset distance
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MockRangeFinder {
+    /// Distance measured in meters
+    distance: Option<f64>,
+}
+
+impl MockRangeFinder {
+    /// Create a new [`MockRangeFinder`] measuring `distance`
+    pub fn new(distance: Option<f64>) -> Self {
+        Self { distance }
+    }
+
+    /// Set the distance measured, [None] if nothing is in range
+    pub fn set(&mut self, distance: Option<f64>) {
+        self.distance = distance;
+    }
+}
+
+impl RangeFinder for MockRangeFinder {
+    type Error = Infallible;
+
+    fn distance(&mut self) -> Result<Option<f64>, Self::Error> {
+        Ok(self.distance)
+    }
+}
+
+/// Lift that instantly moves into its up or down position
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MockLift {
+    /// Whether the lift is in the up position
+    up: bool,
+}
+
+impl MockLift {
+    /// Create a new [`MockLift`] in the down position
+    pub fn new() -> Self {
+        Self { up: false }
+    }
+}
+
+impl Lift for MockLift {
+    type Error = Infallible;
+
+    fn up(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        self.up = true;
+        Ok(())
+    }
+
+    fn down(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        self.up = false;
+        Ok(())
+    }
+
+    fn is_up(&self) -> bool {
+        self.up
+    }
+
+    fn is_down(&self) -> bool {
+        !self.up
+    }
+}
+
+impl JogLift for MockLift {
+    fn jog_up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.up(speed)
+    }
+
+    fn jog_down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.down(speed)
+    }
+
+    fn halt(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -1,18 +1,22 @@
-//! DCMotor with a Hardware [`Pwm`] Implementation
+//! DCMotor with a Hardware [`PwmChannel`] Implementation
 
 use std::marker::PhantomData;
 
 use directions::MotorDirection;
+use hal::PwmChannel;
 use interfaces::Drive;
-use rppal::pwm::{self, Pwm};
+#[cfg(feature = "rppal")]
+use rppal::pwm::Pwm;
 
 use crate::{Left, PwmConfig, Right};
 
-/// DC Motor that uses Hardware [`Pwm`]
+/// DC Motor that uses a Hardware [`PwmChannel`], a [`Pwm`] channel of the
+/// Raspberry Pi by default
+#[cfg(feature = "rppal")]
 #[derive(Debug)]
-pub struct DCMotor<Side> {
-    /// The underlying [`Pwm`] that the Motor uses
-    pwm: Pwm,
+pub struct DCMotor<Side, P = Pwm> {
+    /// The underlying [`PwmChannel`] that the Motor uses
+    pwm: P,
     /// The [`Pwm`] Configuration for the specific [`HardwareDCMotor`]
     config: PwmConfig,
     /// State of the Motor
@@ -21,11 +25,27 @@ pub struct DCMotor<Side> {
     _phantom: PhantomData<Side>,
 }
 
-impl<Side> DCMotor<Side> {
+/// DC Motor that uses a Hardware [`PwmChannel`]
+///
+/// Without the `rppal` feature its channel has no default type.
+#[cfg(not(feature = "rppal"))]
+#[derive(Debug)]
+pub struct DCMotor<Side, P> {
+    /// The underlying [`PwmChannel`] that the Motor uses
+    pwm: P,
+    /// The PWM Configuration for the specific [`HardwareDCMotor`]
+    config: PwmConfig,
+    /// State of the Motor
+    state: Option<MotorDirection>,
+    /// Zero-sized phantom data that stores the side of the Motor
+    _phantom: PhantomData<Side>,
+}
+
+impl<Side, P: PwmChannel> DCMotor<Side, P> {
     /// Create a new [`DCMotor`] using a [`PwmConfig`]
     /// This activates the motor and the caller should wait a few seconds before
     /// using the motor (activation period)
-    pub fn new(pwm: Pwm, config: PwmConfig) -> pwm::Result<Self> {
+    pub fn new(mut pwm: P, config: PwmConfig) -> Result<Self, P::Error> {
         // Set period
         pwm.set_period(config.period)?;
         pwm.enable()?;
@@ -41,9 +61,9 @@ impl<Side> DCMotor<Side> {
     }
 }
 
-impl Drive for DCMotor<Left> {
+impl<P: PwmChannel> Drive for DCMotor<Left, P> {
     type Direction = MotorDirection;
-    type Error = pwm::Error;
+    type Error = P::Error;
 
     fn drive(
         &mut self,
@@ -60,6 +80,7 @@ impl Drive for DCMotor<Left> {
                     + self.config.pulse_width_range.mul_f64(speed.value());
                 self.pwm.set_pulse_width(pulse_width)?;
             }
+            Self::Direction::Stop => return self.stop(),
         };
 
         Ok(self.state.replace(direction))
@@ -71,9 +92,9 @@ impl Drive for DCMotor<Left> {
     }
 }
 
-impl Drive for DCMotor<Right> {
+impl<P: PwmChannel> Drive for DCMotor<Right, P> {
     type Direction = MotorDirection;
-    type Error = pwm::Error;
+    type Error = P::Error;
 
     fn drive(
         &mut self,
@@ -90,6 +111,7 @@ impl Drive for DCMotor<Right> {
                     - self.config.pulse_width_range.mul_f64(speed.value());
                 self.pwm.set_pulse_width(pulse_width)?;
             }
+            Self::Direction::Stop => return self.stop(),
         };
 
         Ok(self.state.replace(direction))
@@ -3,16 +3,21 @@
 use std::{marker::PhantomData, time::Duration};
 
 use directions::MotorDirection;
+use hal::OutputPin;
 use interfaces::Drive;
-use rppal::gpio::{self, OutputPin};
+#[cfg(feature = "rppal")]
+use rppal::gpio;
 
 use crate::{Left, PwmConfig, Right};
 
 /// Brushless DC Motor that Locked Anti-phase PWM for controls
+///
+/// Controlled by an [`OutputPin`], a GPIO pin of the Raspberry Pi by default.
+#[cfg(feature = "rppal")]
 #[derive(Debug)]
-pub struct DCMotor<Side> {
+pub struct DCMotor<Side, P = gpio::OutputPin> {
     /// [`OutputPin`] that controls [`Speed`] and [`MotorDirection`]
-    power: OutputPin,
+    power: P,
     /// Configuration of the pwm
     pwm_config: PwmConfig,
     /// State of the Motor
@@ -21,9 +26,25 @@ pub struct DCMotor<Side> {
     _phantom: PhantomData<Side>,
 }
 
-impl<Side> DCMotor<Side> {
+/// Brushless DC Motor that Locked Anti-phase PWM for controls
+///
+/// Without the `rppal` feature its [`OutputPin`] has no default type.
+#[cfg(not(feature = "rppal"))]
+#[derive(Debug)]
+pub struct DCMotor<Side, P> {
+    /// [`OutputPin`] that controls [`Speed`] and [`MotorDirection`]
+    power: P,
+    /// Configuration of the pwm
+    pwm_config: PwmConfig,
+    /// State of the Motor
+    state: Option<MotorDirection>,
+    /// Zero-sized phantom data that stores the side of the Motor
+    _phantom: PhantomData<Side>,
+}
+
+impl<Side, P: OutputPin> DCMotor<Side, P> {
     /// Create a new [`DCMotor`] using a [`PwmConfig`]
-    pub fn new(mut power: OutputPin, pwm_config: PwmConfig) -> gpio::Result<Self> {
+    pub fn new(mut power: P, pwm_config: PwmConfig) -> Result<Self, P::Error> {
         // Start the motor
         power.set_pwm(pwm_config.period, pwm_config.stop_pulse_width)?;
         std::thread::sleep(Duration::from_secs(5));
@@ -36,9 +57,9 @@ impl<Side> DCMotor<Side> {
     }
 }
 
-impl Drive for DCMotor<Left> {
+impl<P: OutputPin> Drive for DCMotor<Left, P> {
     type Direction = MotorDirection;
-    type Error = gpio::Error;
+    type Error = P::Error;
 
     fn drive(
         &mut self,
@@ -55,6 +76,7 @@ impl Drive for DCMotor<Left> {
                     + self.pwm_config.pulse_width_range.mul_f64(speed.value());
                 self.power.set_pwm(self.pwm_config.period, pulse_width)?;
             }
+            Self::Direction::Stop => return self.stop(),
         };
         Ok(self.state.replace(direction))
     }
@@ -66,9 +88,9 @@ impl Drive for DCMotor<Left> {
     }
 }
 
-impl Drive for DCMotor<Right> {
+impl<P: OutputPin> Drive for DCMotor<Right, P> {
     type Direction = MotorDirection;
-    type Error = gpio::Error;
+    type Error = P::Error;
 
     fn drive(
         &mut self,
@@ -85,6 +107,7 @@ impl Drive for DCMotor<Right> {
                     - self.pwm_config.pulse_width_range.mul_f64(speed.value());
                 self.power.set_pwm(self.pwm_config.period, pulse_width)?;
             }
+            Self::Direction::Stop => return self.stop(),
         };
         Ok(self.state.replace(direction))
     }
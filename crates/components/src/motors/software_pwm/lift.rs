@@ -1,35 +1,51 @@
 use std::time::Duration;
 
-use interfaces::Lift;
-use rppal::gpio::{self, InputPin, OutputPin};
+use hal::{InputPin, OutputPin};
+use interfaces::{JogLift, Lift};
+#[cfg(feature = "rppal")]
+use rppal::gpio;
 use speed::Speed;
 
 /// Represents a [`LiftMotor`] that lifts objects
 ///
-/// Reads its position from two [`InputPin`]s
+/// Reads its position from two [`InputPin`]s. Its pins are GPIO pins of the
+/// Raspberry Pi by default.
+#[cfg(feature = "rppal")]
 #[derive(Debug)]
-pub struct LiftMotor {
+pub struct LiftMotor<O = gpio::OutputPin, I = gpio::InputPin> {
     /// [`OutputPin`] that moves the Lift Motor
-    power: OutputPin,
+    power: O,
     /// Direction [`OutputPin`] that sets the direction
-    direction: OutputPin,
+    direction: O,
     /// Frequency of the Software PWM for the power pin
     frequency: f64,
     /// [`InputPin`] that checks whether Lift is in up position
-    up: InputPin,
+    up: I,
     /// [`InputPin`] that checks whether Lift is in down position
-    down: InputPin,
+    down: I,
 }
 
-impl LiftMotor {
+/// Represents a [`LiftMotor`] that lifts objects
+///
+/// Without the `rppal` feature its pins have no default type.
+#[cfg(not(feature = "rppal"))]
+#[derive(Debug)]
+pub struct LiftMotor<O, I> {
+    /// [`OutputPin`] that moves the Lift Motor
+    power: O,
+    /// Direction [`OutputPin`] that sets the direction
+    direction: O,
+    /// Frequency of the Software PWM for the power pin
+    frequency: f64,
+    /// [`InputPin`] that checks whether Lift is in up position
+    up: I,
+    /// [`InputPin`] that checks whether Lift is in down position
+    down: I,
+}
+
+impl<O, I> LiftMotor<O, I> {
     /// Create a new [`LiftMotor`]
-    pub fn new(
-        power: OutputPin,
-        direction: OutputPin,
-        frequency: f64,
-        up: InputPin,
-        down: InputPin,
-    ) -> Self {
+    pub fn new(power: O, direction: O, frequency: f64, up: I, down: I) -> Self {
         Self {
             power,
             direction,
@@ -40,15 +56,15 @@ impl LiftMotor {
     }
 }
 
-impl Lift for LiftMotor {
-    type Error = gpio::Error;
+impl<O: OutputPin, I: InputPin> Lift for LiftMotor<O, I> {
+    type Error = O::Error;
 
     /// Move the [`LiftMotor`] to its up position
     ///
     /// This is a blocking operation
     fn up(&mut self, speed: Speed) -> Result<(), Self::Error> {
         // Set the direction
-        self.direction.set_low();
+        self.direction.set_low()?;
 
         if !self.is_up() {
             self.power
@@ -69,7 +85,7 @@ impl Lift for LiftMotor {
     /// This is a blocking operation
     fn down(&mut self, speed: Speed) -> Result<(), Self::Error> {
         // Set the direction
-        self.direction.set_high();
+        self.direction.set_high()?;
 
         if !self.is_down() {
             self.power
@@ -93,3 +109,31 @@ impl Lift for LiftMotor {
         self.down.is_low()
     }
 }
+
+impl<O: OutputPin, I: InputPin> JogLift for LiftMotor<O, I> {
+    /// Move the [`LiftMotor`] up until it is halted or reaches its up position
+    fn jog_up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        // Set the direction
+        self.direction.set_low()?;
+
+        if self.is_up() {
+            return self.halt();
+        };
+        self.power.set_pwm_frequency(self.frequency, speed.value())
+    }
+
+    /// Move the [`LiftMotor`] down until it is halted or reaches its down position
+    fn jog_down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        // Set the direction
+        self.direction.set_high()?;
+
+        if self.is_down() {
+            return self.halt();
+        };
+        self.power.set_pwm_frequency(self.frequency, speed.value())
+    }
+
+    fn halt(&mut self) -> Result<(), Self::Error> {
+        self.power.clear_pwm()
+    }
+}
@@ -3,8 +3,10 @@
 use std::marker::PhantomData;
 
 use directions::MotorDirection;
+use hal::OutputPin;
 use interfaces::Drive;
-use rppal::gpio::{self, OutputPin};
+#[cfg(feature = "rppal")]
+use rppal::gpio;
 
 use crate::{Left, Right};
 
@@ -12,25 +14,47 @@ use crate::{Left, Right};
 ///
 /// A motor component can be mounted either on the [`Left`] or [`Right`] side.
 /// The power pin of the [`SignedMotor`] is controlled using software PWM.
+/// Its pins are [`OutputPin`]s, GPIO pins of the Raspberry Pi by default.
+#[cfg(feature = "rppal")]
 #[derive(Debug)]
-pub struct SignedMotor<Side> {
+pub struct SignedMotor<Side, P = gpio::OutputPin> {
     /// [`OutputPin`] for controlling the [`Speed`] of the [`SignedMotor`].
     /// This is controlled using software PWM.
-    power: OutputPin,
+    power: P,
     /// The operating frequency of the power pin PWM. 4096.0 is a good default.
     frequency: f64,
     /// [`OutputPin`] for controlling the [`MotorDirection`]
     /// The output state will be different depending on the 'Side' of the motor
-    direction: OutputPin,
+    direction: P,
     /// Stores the current state of the motor
     state: Option<MotorDirection>,
     /// Zero-sized phantom data that stores the side of the Motor
     _phantom: PhantomData<Side>,
 }
 
-impl<Side> SignedMotor<Side> {
+/// Motor Component
+///
+/// Without the `rppal` feature its [`OutputPin`]s have no default type.
+#[cfg(not(feature = "rppal"))]
+#[derive(Debug)]
+pub struct SignedMotor<Side, P> {
+    /// [`OutputPin`] for controlling the [`Speed`] of the [`SignedMotor`].
+    /// This is controlled using software PWM.
+    power: P,
+    /// The operating frequency of the power pin PWM. 4096.0 is a good default.
+    frequency: f64,
+    /// [`OutputPin`] for controlling the [`MotorDirection`]
+    /// The output state will be different depending on the 'Side' of the motor
+    direction: P,
+    /// Stores the current state of the motor
+    state: Option<MotorDirection>,
+    /// Zero-sized phantom data that stores the side of the Motor
+    _phantom: PhantomData<Side>,
+}
+
+impl<Side, P> SignedMotor<Side, P> {
     /// Create a new [`SignedMotor`] instance
-    pub fn new(power: OutputPin, frequency: f64, direction: OutputPin) -> Self {
+    pub fn new(power: P, frequency: f64, direction: P) -> Self {
         Self {
             power,
             frequency,
@@ -41,56 +65,151 @@ impl<Side> SignedMotor<Side> {
     }
 }
 
-impl Drive for SignedMotor<Right> {
+impl<P: OutputPin> Drive for SignedMotor<Right, P> {
     type Direction = MotorDirection;
-    type Error = gpio::Error;
+    type Error = P::Error;
 
-    fn drive(&mut self, direction: Self::Direction) -> gpio::Result<Option<Self::Direction>> {
+    fn drive(
+        &mut self,
+        direction: Self::Direction,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
         match direction {
             Self::Direction::Forward(speed) => {
-                self.direction.set_high();
+                self.direction.set_high()?;
                 self.power
                     .set_pwm_frequency(self.frequency, speed.value())?;
             }
             Self::Direction::Backward(speed) => {
-                self.direction.set_low();
+                self.direction.set_low()?;
                 self.power
                     .set_pwm_frequency(self.frequency, speed.value())?;
             }
+            Self::Direction::Stop => return self.stop(),
         };
         Ok(self.state.replace(direction))
     }
 
-    fn stop(&mut self) -> gpio::Result<Option<Self::Direction>> {
-        self.power.set_low();
+    fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+        self.power.set_low()?;
         self.power.clear_pwm()?;
         Ok(self.state.take())
     }
 }
 
-impl Drive for SignedMotor<Left> {
+impl<P: OutputPin> Drive for SignedMotor<Left, P> {
     type Direction = MotorDirection;
-    type Error = gpio::Error;
+    type Error = P::Error;
 
-    fn drive(&mut self, direction: Self::Direction) -> gpio::Result<Option<Self::Direction>> {
+    fn drive(
+        &mut self,
+        direction: Self::Direction,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
         match direction {
             Self::Direction::Forward(speed) => {
-                self.direction.set_low();
+                self.direction.set_low()?;
                 self.power
                     .set_pwm_frequency(self.frequency, speed.value())?;
             }
             Self::Direction::Backward(speed) => {
-                self.direction.set_high();
+                self.direction.set_high()?;
                 self.power
                     .set_pwm_frequency(self.frequency, speed.value())?;
             }
+            Self::Direction::Stop => return self.stop(),
         };
         Ok(self.state.replace(direction))
     }
 
-    fn stop(&mut self) -> gpio::Result<Option<Self::Direction>> {
-        self.power.set_low();
+    fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+        self.power.set_low()?;
         self.power.clear_pwm()?;
         Ok(self.state.take())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, convert::Infallible, rc::Rc, time::Duration};
+
+    use directions::MotorDirection;
+    use hal::OutputPin;
+    use interfaces::Drive;
+    use speed::Speed;
+
+    use super::SignedMotor;
+    use crate::{Left, Right};
+
+    /// Pin of another board, writing every change to a shared log
+    #[derive(Debug)]
+    struct Pin(&'static str, Rc<RefCell<Vec<String>>>);
+
+    impl OutputPin for Pin {
+        type Error = Infallible;
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.1.borrow_mut().push(format!("{} high", self.0));
+            Ok(())
+        }
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.1.borrow_mut().push(format!("{} low", self.0));
+            Ok(())
+        }
+
+        fn set_pwm(
+            &mut self,
+            _period: Duration,
+            _pulse_width: Duration,
+        ) -> Result<(), Self::Error> {
+            unreachable!("a signed motor uses a frequency")
+        }
+
+        fn set_pwm_frequency(
+            &mut self,
+            frequency: f64,
+            duty_cycle: f64,
+        ) -> Result<(), Self::Error> {
+            self.1
+                .borrow_mut()
+                .push(format!("{} pwm {frequency} {duty_cycle}", self.0));
+            Ok(())
+        }
+
+        fn clear_pwm(&mut self) -> Result<(), Self::Error> {
+            self.1.borrow_mut().push(format!("{} clear", self.0));
+            Ok(())
+        }
+    }
+
+    /// Test that the motors drive the pins of any board
+    #[test]
+    fn drive_pins() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut left = SignedMotor::<Left, _>::new(
+            Pin("power", log.clone()),
+            100.0,
+            Pin("direction", log.clone()),
+        );
+        let mut right = SignedMotor::<Right, _>::new(
+            Pin("power", log.clone()),
+            100.0,
+            Pin("direction", log.clone()),
+        );
+
+        left.drive(MotorDirection::Forward(Speed::HALF)).unwrap();
+        right.drive(MotorDirection::Forward(Speed::HALF)).unwrap();
+        left.stop().unwrap();
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                "direction low",
+                "power pwm 100 0.5",
+                "direction high",
+                "power pwm 100 0.5",
+                "power low",
+                "power clear",
+            ]
+        );
+    }
+}
@@ -1,27 +1,40 @@
+use hal::I2cBus;
 use interfaces::{SensorRead, ToSensorChannel};
-use rppal::i2c::{self, I2c};
+#[cfg(feature = "rppal")]
+use rppal::i2c::I2c;
 
 /// Sensor Controller that allows fetching state from multiple sensors
 ///
 /// [`SensorController`] is actually a Analog Digital Converter (ADC) and a
 /// Digital Analog Converter (DAC) in one. The hardware component represented
-/// is the Adafruit PCF8591 Quad 8-bit ADC/DAC. We use a [`I2c`] bus for communication.
+/// is the Adafruit PCF8591 Quad 8-bit ADC/DAC. We use an [`I2cBus`] for
+/// communication, the [`I2c`] bus of the Raspberry Pi by default.
 /// However we use it strictly for interfacing with a sensor array.
+#[cfg(feature = "rppal")]
 #[derive(Debug)]
-pub struct SensorController {
-    i2c: I2c,
+pub struct SensorController<B = I2c> {
+    i2c: B,
 }
 
-impl SensorController {
-    /// Create a new [`SensorController`] from a [`I2c`] bus
-    pub fn new(i2c: I2c) -> Self {
+/// Sensor Controller that allows fetching state from multiple sensors
+///
+/// Without the `rppal` feature its [`I2cBus`] has no default type.
+#[cfg(not(feature = "rppal"))]
+#[derive(Debug)]
+pub struct SensorController<B> {
+    i2c: B,
+}
+
+impl<B> SensorController<B> {
+    /// Create a new [`SensorController`] from an [`I2cBus`]
+    pub fn new(i2c: B) -> Self {
         Self { i2c }
     }
 }
 
-impl SensorRead for SensorController {
+impl<B: I2cBus> SensorRead for SensorController<B> {
     type Output = u8;
-    type Error = i2c::Error;
+    type Error = B::Error;
 
     /// Read a value from a sensor
     fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
@@ -0,0 +1,236 @@
+//! Simulated components driving in a [`sim`] world
+//!
+//! The simulated motors, sensors and lift share a [`World`], which tracks the
+//! position of logbot using differential drive kinematics. By default the
+//! course is a rectangular loop of tape with a stop line on its bottom and
+//! top side, see the [`sim`] crate for other courses.
+
+use std::{convert::Infallible, marker::PhantomData};
+
+use directions::MotorDirection;
+use interfaces::{Drive, JogLift, Lift, RangeFinder, SensorRead, ToSensorChannel};
+pub use sim::{Clock, Course, Obstacle, Pose, Segment, SharedWorld, Wheel, World};
+use speed::Speed;
+
+use crate::{Left, Right};
+
+/// Side of a [`SimMotor`]
+pub trait SimSide {
+    /// The wheel driven by a motor on this side
+    const WHEEL: Wheel;
+}
+
+impl SimSide for Left {
+    const WHEEL: Wheel = Wheel::Left;
+}
+
+impl SimSide for Right {
+    const WHEEL: Wheel = Wheel::Right;
+}
+
+/// Motor driving a wheel of the simulated logbot
+#[derive(Debug)]
+pub struct SimMotor<Side> {
+    /// The simulation
+    world: SharedWorld,
+    /// State of the Motor
+    state: Option<MotorDirection>,
+    /// Zero-sized phantom data that stores the side of the Motor
+    _phantom: PhantomData<Side>,
+}
+
+impl<Side> SimMotor<Side> {
+    /// Create a new stopped [`SimMotor`]
+    pub fn new(world: SharedWorld) -> Self {
+        Self {
+            world,
+            state: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Side: SimSide> Drive for SimMotor<Side> {
+    type Direction = MotorDirection;
+    type Error = Infallible;
+
+    fn drive(
+        &mut self,
+        direction: Self::Direction,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
+        self.world.lock().set_wheel(Side::WHEEL, direction);
+        match direction {
+            Self::Direction::Stop => Ok(self.state.take()),
+            direction => Ok(self.state.replace(direction)),
+        }
+    }
+
+    fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+        self.drive(MotorDirection::Stop)
+    }
+}
+
+/// Sensors reading the simulated course
+#[derive(Debug)]
+pub struct SimSensors {
+    /// The simulation
+    world: SharedWorld,
+}
+
+impl SimSensors {
+    /// Create new [`SimSensors`]
+    pub fn new(world: SharedWorld) -> Self {
+        Self { world }
+    }
+}
+
+impl SensorRead for SimSensors {
+    type Output = u8;
+    type Error = Infallible;
+
+    /// Channel 0 is the left sensor, any other channel the right sensor
+    fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
+        let sensor = match sensor.to_channel() {
+            0 => 0,
+            _ => 1,
+        };
+        Ok(self.world.lock().sensor(sensor))
+    }
+}
+
+/// Range finder measuring the distance to the [`Obstacle`]s ahead of the
+/// simulated logbot
+#[derive(Debug)]
+pub struct SimRangeFinder {
+    /// The simulation
+    world: SharedWorld,
+}
+
+impl SimRangeFinder {
+    /// Create a new [`SimRangeFinder`]
+    pub fn new(world: SharedWorld) -> Self {
+        Self { world }
+    }
+}
+
+impl RangeFinder for SimRangeFinder {
+    type Error = Infallible;
+
+    fn distance(&mut self) -> Result<Option<f64>, Self::Error> {
+        Ok(self.world.lock().range())
+    }
+}
+
+/// Lift of the simulated logbot, full moves are instant while jogs take time
+#[derive(Debug)]
+pub struct SimLift {
+    /// The simulation
+    world: SharedWorld,
+}
+
+impl SimLift {
+    /// Create a new [`SimLift`]
+    pub fn new(world: SharedWorld) -> Self {
+        Self { world }
+    }
+}
+
+impl Lift for SimLift {
+    type Error = Infallible;
+
+    fn up(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        self.world.lock().set_lift(true);
+        Ok(())
+    }
+
+    fn down(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        self.world.lock().set_lift(false);
+        Ok(())
+    }
+
+    fn is_up(&self) -> bool {
+        self.world.lock().lift() >= 1.0
+    }
+
+    fn is_down(&self) -> bool {
+        self.world.lock().lift() <= 0.0
+    }
+}
+
+impl JogLift for SimLift {
+    fn jog_up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.world.lock().jog_lift(speed.value());
+        Ok(())
+    }
+
+    fn jog_down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.world.lock().jog_lift(-speed.value());
+        Ok(())
+    }
+
+    fn halt(&mut self) -> Result<(), Self::Error> {
+        self.world.lock().jog_lift(0.0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use consts::Sensors;
+    use directions::MotorDirection;
+    use interfaces::{Drive, JogLift, Lift, SensorRead};
+    use speed::Speed;
+
+    use sim::{FLOOR_VALUE, LINE_VALUE};
+
+    use super::{SimLift, SimMotor, SimSensors, World};
+    use crate::{Left, Right};
+
+    /// Test that the sensors start on the edge of the line and driving moves logbot
+    #[test]
+    fn drive_along_line() {
+        let world = World::shared();
+        let mut sensors = SimSensors::new(world.clone());
+        let mut left: SimMotor<Left> = SimMotor::new(world.clone());
+        let mut right: SimMotor<Right> = SimMotor::new(world.clone());
+
+        let edge = sensors.read(Sensors::Left).unwrap();
+        assert!(FLOOR_VALUE < edge && edge < LINE_VALUE);
+        assert_eq!(sensors.read(Sensors::Right).unwrap(), FLOOR_VALUE);
+
+        let start = world.pose();
+        left.drive(MotorDirection::Forward(Speed::MAX)).unwrap();
+        right.drive(MotorDirection::Forward(Speed::MAX)).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        left.stop().unwrap();
+        right.stop().unwrap();
+
+        let end = world.pose();
+        // The motors start a moment apart, which barely turns logbot
+        assert!(end.x - start.x > 0.01);
+        assert!((end.y - start.y).abs() < 0.001);
+        assert!((end.heading - start.heading).abs() < 0.01);
+    }
+
+    /// Test that a jogged lift moves between its positions and stops when halted
+    #[test]
+    fn jog_lift() {
+        let mut lift = SimLift::new(World::shared());
+        assert!(lift.is_down());
+
+        lift.jog_up(Speed::MAX).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        lift.halt().unwrap();
+        assert!(!lift.is_down() && !lift.is_up());
+
+        // A halted lift stays in place
+        let position = lift.world.lock().lift();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(lift.world.lock().lift(), position);
+
+        lift.up(Speed::MAX).unwrap();
+        assert!(lift.is_up());
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::{Duration, Instant};
+
+use hal::{InputPin, OutputPin};
+use interfaces::RangeFinder;
+#[cfg(feature = "rppal")]
+use rppal::gpio;
+
+/// Speed of sound in meters per second at room temperature
+const SPEED_OF_SOUND: f64 = 343.0;
+
+/// Length of the pulse on the trigger pin starting a measurement
+const TRIGGER_PULSE: Duration = Duration::from_micros(10);
+
+/// Ultrasonic distance sensor measuring the distance to obstacles ahead
+///
+/// The hardware component represented is the HC-SR04. A pulse on the
+/// trigger [`OutputPin`] sends a burst of ultrasound, after which the echo
+/// [`InputPin`] is high for as long as the sound took to travel to the
+/// obstacle and back. Its pins are GPIO pins of the Raspberry Pi by default.
+/// The echo pin of the HC-SR04 outputs 5V, so it has to be connected through
+/// a voltage divider.
+#[cfg(feature = "rppal")]
+#[derive(Debug)]
+pub struct Ultrasonic<O = gpio::OutputPin, I = gpio::InputPin> {
+    /// [`OutputPin`] starting a measurement
+    trigger: O,
+    /// [`InputPin`] that is high while the echo travels
+    echo: I,
+    /// Farthest distance in meters that is measured
+    max_range: f64,
+}
+
+/// Ultrasonic distance sensor measuring the distance to obstacles ahead
+///
+/// Without the `rppal` feature its pins have no default type.
+#[cfg(not(feature = "rppal"))]
+#[derive(Debug)]
+pub struct Ultrasonic<O, I> {
+    /// [`OutputPin`] starting a measurement
+    trigger: O,
+    /// [`InputPin`] that is high while the echo travels
+    echo: I,
+    /// Farthest distance in meters that is measured
+    max_range: f64,
+}
+
+impl<O, I> Ultrasonic<O, I> {
+    /// Create a new [`Ultrasonic`] sensor, measuring distances up to
+    /// `max_range` meters
+    pub fn new(trigger: O, echo: I, max_range: f64) -> Self {
+        Self {
+            trigger,
+            echo,
+            max_range,
+        }
+    }
+
+    /// Time the echo of the farthest distance measured takes, plus the time
+    /// the sensor needs to start sending
+    fn timeout(&self) -> Duration {
+        Duration::from_secs_f64(2.0 * self.max_range / SPEED_OF_SOUND) + Duration::from_millis(1)
+    }
+}
+
+impl<O: OutputPin, I: InputPin> RangeFinder for Ultrasonic<O, I> {
+    type Error = O::Error;
+
+    /// Measure the distance to the nearest obstacle
+    ///
+    /// This is a blocking operation, taking up to the time the echo of the
+    /// maximum range takes.
+    fn distance(&mut self) -> Result<Option<f64>, Self::Error> {
+        self.trigger.set_high()?;
+        std::thread::sleep(TRIGGER_PULSE);
+        self.trigger.set_low()?;
+
+        // Wait for the echo to start and to end
+        let timeout = self.timeout();
+        let sent = Instant::now();
+        while self.echo.is_low() {
+            if sent.elapsed() >= timeout {
+                return Ok(None);
+            };
+        }
+        let start = Instant::now();
+        while !self.echo.is_low() {
+            if start.elapsed() >= timeout {
+                return Ok(None);
+            };
+        }
+
+        let distance = start.elapsed().as_secs_f64() * SPEED_OF_SOUND / 2.0;
+        Ok((distance <= self.max_range).then_some(distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        time::{Duration, Instant},
+    };
+
+    use hal::{InputPin, OutputPin};
+    use interfaces::RangeFinder;
+
+    use super::Ultrasonic;
+
+    /// Trigger pin that is never connected
+    #[derive(Debug)]
+    struct Trigger;
+
+    impl OutputPin for Trigger {
+        type Error = Infallible;
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_pwm(&mut self, _: Duration, _: Duration) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_pwm_frequency(&mut self, _: f64, _: f64) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn clear_pwm(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    /// Echo pin that is high for a duration from when it is created
+    #[derive(Debug)]
+    struct Echo(Instant, Duration);
+
+    impl InputPin for Echo {
+        fn is_low(&self) -> bool {
+            self.0.elapsed() >= self.1
+        }
+    }
+
+    /// Test that the echo is timed and nothing is measured without an echo
+    #[test]
+    fn measure_echo() {
+        // The echo of an obstacle 0.5m ahead
+        let echo = Echo(Instant::now(), Duration::from_secs_f64(1.0 / 343.0));
+        let mut sensor = Ultrasonic::new(Trigger, echo, 2.0);
+        let distance = sensor.distance().unwrap().unwrap();
+        assert!((0.3..0.6).contains(&distance), "{distance}");
+
+        // An echo that never starts
+        let mut sensor = Ultrasonic::new(Trigger, Echo(Instant::now(), Duration::ZERO), 2.0);
+        assert_eq!(sensor.distance().unwrap(), None);
+    }
+}
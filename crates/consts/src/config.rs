@@ -0,0 +1,555 @@
+//! Hardware configuration loadable from a TOML file
+//!
+//! Every value defaults to the constants defined in this crate, which means a
+//! configuration file only has to contain the values that differ.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::Path,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use interfaces::ToSensorChannel;
+
+use crate::{pins, pwm, FREQUENCY, I2C_SENSOR_ADDRESS};
+
+/// Environment variable that overrides the path of the configuration file
+pub const CONFIG_PATH_ENV: &str = "LOGBOT_CONFIG";
+
+/// Path of the configuration file when [`CONFIG_PATH_ENV`] is not set
+pub const DEFAULT_CONFIG_PATH: &str = "logbot.toml";
+
+/// Environment variable that selects a named robot profile
+pub const PROFILE_ENV: &str = "LOGBOT_PROFILE";
+
+/// Name of the table that contains the named robot profiles
+///
+/// Each profile is a table inside of it, e.g. `[robot.alpha]`, containing
+/// values that are layered over the rest of the configuration file
+pub const PROFILES_KEY: &str = "robot";
+
+/// Profile selected using [`set_profile`]
+static PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Select the named robot profile used by [`HardwareConfig::load`]
+///
+/// This takes precedence over the [`PROFILE_ENV`] environment variable
+pub fn set_profile(name: impl Into<String>) {
+    *PROFILE.lock().unwrap_or_else(PoisonError::into_inner) = Some(name.into());
+}
+
+/// The named robot profile of the current process
+///
+/// Either selected using [`set_profile`] or the [`PROFILE_ENV`] environment variable
+pub fn profile() -> Option<String> {
+    PROFILE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .or_else(|| std::env::var(PROFILE_ENV).ok())
+}
+
+/// Prefix of environment variables that override configuration values
+///
+/// The name of the variable is the prefix followed by the uppercase path of
+/// the value, joined by underscores. For example `LOGBOT_LEFT_MOTOR_POWER_PIN=13`
+/// overrides `power_pin` in the `left_motor` table.
+pub const ENV_PREFIX: &str = "LOGBOT";
+
+/// Complete hardware configuration of logbot
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HardwareConfig {
+    /// Address of the I2C bus used for sensors
+    pub i2c_sensor_address: u16,
+    /// Configuration of the left motor
+    pub left_motor: MotorConfig,
+    /// Configuration of the right motor
+    pub right_motor: MotorConfig,
+    /// Configuration of the lift
+    pub lift: LiftConfig,
+    /// Configuration of the battery monitor, none when the battery is not
+    /// wired to the ADC
+    pub battery: Option<BatteryConfig>,
+    /// Configuration of the range finder seeing obstacles ahead, none when
+    /// no range finder is mounted
+    pub range_finder: Option<RangeFinderConfig>,
+}
+
+/// Configuration of a single drive motor
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MotorConfig {
+    /// Power pin of the motor
+    pub power_pin: u8,
+    /// Direction pin of the motor, only used by signed motors
+    pub direction_pin: u8,
+    /// Hardware PWM channel of the motor
+    pub pwm_channel: u8,
+    /// Software PWM frequency of signed motors
+    pub frequency: f64,
+    /// Pulse widths when driving the motor using hardware PWM
+    pub hardware_pwm: PulseConfig,
+    /// Pulse widths when driving the motor using software PWM
+    pub software_pwm: PulseConfig,
+}
+
+/// Pulse width configuration of a PWM controlled motor, all values are in microseconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PulseConfig {
+    /// Duration of a PWM period
+    pub period_us: u64,
+    /// The pulse width for the stop signal
+    pub stop_pulse_width_us: u64,
+    /// The range of the pulse width in one direction
+    pub pulse_width_range_us: u64,
+}
+
+/// Configuration of the lift motor
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LiftConfig {
+    /// Power pin of the lift motor
+    pub power_pin: u8,
+    /// Direction pin of the lift motor
+    pub direction_pin: u8,
+    /// Input pin that is low when the lift is up
+    pub up_pin: u8,
+    /// Input pin that is low when the lift is down
+    pub down_pin: u8,
+    /// Software PWM frequency of the lift motor
+    pub frequency: f64,
+}
+
+/// Configuration of the battery monitor, reading the battery voltage
+/// through a voltage divider on an ADC input
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatteryConfig {
+    /// ADC input the voltage divider is connected to
+    pub channel: u8,
+    /// Battery volts per step of the 8-bit reading
+    pub volts_per_step: f64,
+}
+
+impl BatteryConfig {
+    /// Battery voltage of a reading of the ADC input
+    pub fn volts(&self, reading: u8) -> f64 {
+        reading as f64 * self.volts_per_step
+    }
+}
+
+impl ToSensorChannel for BatteryConfig {
+    fn to_channel(&self) -> u8 {
+        self.channel
+    }
+}
+
+/// Configuration of an ultrasonic range finder, e.g. the HC-SR04
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RangeFinderConfig {
+    /// Output pin starting a measurement
+    pub trigger_pin: u8,
+    /// Input pin that is high while the echo travels
+    pub echo_pin: u8,
+    /// Farthest distance in meters that is measured
+    pub max_range_m: f64,
+}
+
+impl PulseConfig {
+    /// Create a new [`PulseConfig`] with the default period and range
+    const fn with_stop_pulse_width(stop_pulse_width_us: u64) -> Self {
+        Self {
+            period_us: 20_000,
+            stop_pulse_width_us,
+            pulse_width_range_us: 500,
+        }
+    }
+
+    /// Duration of a PWM period
+    pub fn period(&self) -> Duration {
+        Duration::from_micros(self.period_us)
+    }
+
+    /// The pulse width for the stop signal
+    pub fn stop_pulse_width(&self) -> Duration {
+        Duration::from_micros(self.stop_pulse_width_us)
+    }
+
+    /// The range of the pulse width in one direction
+    pub fn pulse_width_range(&self) -> Duration {
+        Duration::from_micros(self.pulse_width_range_us)
+    }
+}
+
+impl MotorConfig {
+    /// Default configuration of the left motor
+    pub const LEFT: Self = Self {
+        power_pin: pins::LEFT_MOTOR_POWER,
+        direction_pin: pins::LEFT_MOTOR_DIRECTION,
+        pwm_channel: pwm::LEFT_MOTOR_CHANNEL,
+        frequency: FREQUENCY,
+        hardware_pwm: PulseConfig::with_stop_pulse_width(1480),
+        software_pwm: PulseConfig::with_stop_pulse_width(1500),
+    };
+
+    /// Default configuration of the right motor
+    pub const RIGHT: Self = Self {
+        power_pin: pins::RIGHT_MOTOR_POWER,
+        direction_pin: pins::RIGHT_MOTOR_DIRECTION,
+        pwm_channel: pwm::RIGHT_MOTOR_CHANNEL,
+        frequency: FREQUENCY,
+        hardware_pwm: PulseConfig::with_stop_pulse_width(1465),
+        software_pwm: PulseConfig::with_stop_pulse_width(1468),
+    };
+}
+
+impl Default for LiftConfig {
+    fn default() -> Self {
+        Self {
+            power_pin: pins::LIFT_MOTOR_POWER,
+            direction_pin: pins::LIFT_MOTOR_DIRECTION,
+            up_pin: pins::LIFT_UP,
+            down_pin: pins::LIFT_DOWN,
+            frequency: FREQUENCY,
+        }
+    }
+}
+
+impl Default for HardwareConfig {
+    fn default() -> Self {
+        Self {
+            i2c_sensor_address: I2C_SENSOR_ADDRESS,
+            left_motor: MotorConfig::LEFT,
+            right_motor: MotorConfig::RIGHT,
+            lift: LiftConfig::default(),
+            battery: None,
+            range_finder: None,
+        }
+    }
+}
+
+impl HardwareConfig {
+    /// Parse a [`HardwareConfig`] from a TOML string
+    ///
+    /// The values are layered over the [default](Self::default) configuration,
+    /// so any missing value falls back to its default. Named robot profiles
+    /// are ignored, see [`from_toml_profile`](Self::from_toml_profile).
+    pub fn from_toml(value: &str) -> Result<Self, ConfigError> {
+        Self::from_toml_profile(value, None)
+    }
+
+    /// Parse a [`HardwareConfig`] from a TOML string using a named robot profile
+    ///
+    /// The values of the profile are layered over the rest of the file,
+    /// which in turn is layered over the [default](Self::default) configuration.
+    pub fn from_toml_profile(value: &str, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let mut overlay: toml::Table = toml::from_str(value).map_err(ConfigError::Toml)?;
+        let profiles = overlay.remove(PROFILES_KEY);
+
+        let mut merged = toml::Value::try_from(Self::default())
+            .expect("Default HardwareConfig is always serializable");
+        merge(&mut merged, toml::Value::Table(overlay));
+
+        if let Some(name) = profile {
+            let profile = profiles
+                .as_ref()
+                .and_then(|profiles| profiles.get(name))
+                .ok_or_else(|| ConfigError::UnknownProfile(name.to_string()))?;
+            merge(&mut merged, profile.clone());
+        };
+
+        merged.try_into().map_err(ConfigError::Toml)
+    }
+
+    /// Read a [`HardwareConfig`] from a TOML file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_file_profile(path, None)
+    }
+
+    /// Read a [`HardwareConfig`] from a TOML file using a named robot profile
+    pub fn from_file_profile(
+        path: impl AsRef<Path>,
+        profile: Option<&str>,
+    ) -> Result<Self, ConfigError> {
+        let value = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_toml_profile(&value, profile)
+    }
+
+    /// Create a [`HardwareConfig`] from the [default](Self::default)
+    /// configuration and the environment variables of the current process
+    ///
+    /// See [`ENV_PREFIX`] for the naming of the variables
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::default().with_env()
+    }
+
+    /// Override values of the [`HardwareConfig`] using the environment
+    /// variables of the current process
+    ///
+    /// See [`ENV_PREFIX`] for the naming of the variables
+    pub fn with_env(self) -> Result<Self, ConfigError> {
+        self.with_vars(std::env::vars().collect())
+    }
+
+    /// Override values of the [`HardwareConfig`] using a map of variables
+    fn with_vars(self, vars: HashMap<String, String>) -> Result<Self, ConfigError> {
+        let mut value = toml::Value::try_from(self).expect("HardwareConfig is always serializable");
+        apply_vars(&mut value, ENV_PREFIX, &vars)?;
+        value.try_into().map_err(ConfigError::Toml)
+    }
+
+    /// Load the [`HardwareConfig`] used by the current process
+    ///
+    /// The file is read from the path in [`CONFIG_PATH_ENV`], or from
+    /// [`DEFAULT_CONFIG_PATH`] when the variable is not set. If the default
+    /// file does not exist the default configuration is used. The selected
+    /// [profile] is applied from the file, followed by environment variables,
+    /// see [`ENV_PREFIX`].
+    pub fn load() -> Result<Self, ConfigError> {
+        let profile = profile();
+        let profile = profile.as_deref();
+
+        let config = match std::env::var_os(CONFIG_PATH_ENV) {
+            Some(path) => Self::from_file_profile(path, profile),
+            None => match Self::from_file_profile(DEFAULT_CONFIG_PATH, profile) {
+                Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                    match profile {
+                        // A profile can't exist without a file
+                        Some(name) => Err(ConfigError::UnknownProfile(name.to_string())),
+                        None => Ok(Self::default()),
+                    }
+                }
+                result => result,
+            },
+        }?;
+
+        config.with_env()
+    }
+}
+
+/// Replace every value in a [`toml::Value`] which has a matching variable
+///
+/// The name of a value is its path appended to the prefix, see [`ENV_PREFIX`]
+fn apply_vars(
+    value: &mut toml::Value,
+    name: &str,
+    vars: &HashMap<String, String>,
+) -> Result<(), ConfigError> {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                apply_vars(value, &format!("{name}_{}", key.to_uppercase()), vars)?;
+            }
+        }
+        value => {
+            if let Some(raw) = vars.get(name) {
+                // Parse the variable as a TOML value, which allows e.g. hex integers
+                let mut table: toml::Table = toml::from_str(&format!("value = {raw}"))
+                    .map_err(|_| ConfigError::Env(name.to_string()))?;
+                *value = table
+                    .remove("value")
+                    .ok_or_else(|| ConfigError::Env(name.to_string()))?;
+            }
+        }
+    };
+    Ok(())
+}
+
+/// Recursively merge an overlay into a base [`toml::Value`]
+///
+/// Tables are merged key by key, any other value in the overlay replaces the base
+fn merge(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Errors that occur while loading a [`HardwareConfig`]
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file could not be read
+    Io(std::io::Error),
+    /// The configuration file is not valid
+    Toml(toml::de::Error),
+    /// The environment variable with the given name is not a valid value
+    Env(String),
+    /// The selected robot profile does not exist
+    UnknownProfile(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Toml(e) => e.fmt(f),
+            Self::Env(name) => write!(f, "invalid value in environment variable `{name}`"),
+            Self::UnknownProfile(name) => write!(f, "unknown robot profile `{name}`"),
+        }
+    }
+}
+
+impl core::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Toml(e) => Some(e),
+            Self::Env(_) | Self::UnknownProfile(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{HardwareConfig, MotorConfig};
+
+    /// Test that an empty file results in the default configuration
+    #[test]
+    fn empty_is_default() {
+        assert_eq!(
+            HardwareConfig::from_toml("").unwrap(),
+            HardwareConfig::default()
+        );
+    }
+
+    /// Test that values in the file override the defaults
+    #[test]
+    fn file_overrides_defaults() {
+        let config = HardwareConfig::from_toml(
+            r#"
+            i2c_sensor_address = 0x49
+
+            [lift]
+            up_pin = 4
+
+            [right_motor.hardware_pwm]
+            stop_pulse_width_us = 1470
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.i2c_sensor_address, 0x49);
+        assert_eq!(config.lift.up_pin, 4);
+        assert_eq!(config.left_motor, MotorConfig::LEFT);
+        assert_eq!(config.right_motor.hardware_pwm.stop_pulse_width_us, 1470);
+        assert_eq!(config.right_motor.power_pin, MotorConfig::RIGHT.power_pin);
+    }
+
+    /// Test that the default configuration survives a round-trip through TOML
+    #[test]
+    fn toml_round_trip() {
+        let config = HardwareConfig::default();
+        let value = toml::to_string(&config).unwrap();
+        assert_eq!(HardwareConfig::from_toml(&value).unwrap(), config);
+    }
+
+    /// Test that the battery monitor is only configured when given
+    #[test]
+    fn battery_optional() {
+        assert_eq!(HardwareConfig::default().battery, None);
+
+        let config = HardwareConfig::from_toml(
+            r#"
+            [battery]
+            channel = 2
+            volts_per_step = 0.05
+            "#,
+        )
+        .unwrap();
+        let battery = config.battery.unwrap();
+        assert_eq!(battery.channel, 2);
+        assert_eq!(battery.volts(160), 8.0);
+    }
+
+    /// Test that the range finder is only configured when given
+    #[test]
+    fn range_finder_optional() {
+        assert_eq!(HardwareConfig::default().range_finder, None);
+
+        let config = HardwareConfig::from_toml(
+            r#"
+            [range_finder]
+            trigger_pin = 17
+            echo_pin = 4
+            max_range_m = 2.0
+            "#,
+        )
+        .unwrap();
+        let range_finder = config.range_finder.unwrap();
+        assert_eq!(range_finder.trigger_pin, 17);
+        assert_eq!(range_finder.echo_pin, 4);
+    }
+
+    /// Test that unknown keys are rejected instead of silently ignored
+    #[test]
+    fn unknown_keys_rejected() {
+        assert!(HardwareConfig::from_toml("i2c_adress = 0x49").is_err());
+    }
+
+    /// Test that variables override the matching values
+    #[test]
+    fn vars_override_values() {
+        let vars = HashMap::from([
+            ("LOGBOT_LEFT_MOTOR_POWER_PIN".to_string(), "18".to_string()),
+            ("LOGBOT_I2C_SENSOR_ADDRESS".to_string(), "0x49".to_string()),
+            ("LOGBOT_UNRELATED".to_string(), "value".to_string()),
+        ]);
+        let config = HardwareConfig::default().with_vars(vars).unwrap();
+
+        assert_eq!(config.left_motor.power_pin, 18);
+        assert_eq!(config.i2c_sensor_address, 0x49);
+        assert_eq!(config.right_motor, MotorConfig::RIGHT);
+    }
+
+    /// Test that variables with an invalid value are rejected
+    #[test]
+    fn vars_invalid_value() {
+        let vars = HashMap::from([("LOGBOT_LIFT_UP_PIN".to_string(), "up".to_string())]);
+        assert!(HardwareConfig::default().with_vars(vars).is_err());
+    }
+
+    /// Test that a named profile is layered over the rest of the file
+    #[test]
+    fn profile_overrides_file() {
+        let value = r#"
+            i2c_sensor_address = 0x49
+
+            [robot.alpha.left_motor.hardware_pwm]
+            stop_pulse_width_us = 1490
+
+            [robot.beta]
+            i2c_sensor_address = 0x4a
+        "#;
+
+        let base = HardwareConfig::from_toml(value).unwrap();
+        assert_eq!(base.i2c_sensor_address, 0x49);
+        assert_eq!(base.left_motor, MotorConfig::LEFT);
+
+        let alpha = HardwareConfig::from_toml_profile(value, Some("alpha")).unwrap();
+        assert_eq!(alpha.i2c_sensor_address, 0x49);
+        assert_eq!(alpha.left_motor.hardware_pwm.stop_pulse_width_us, 1490);
+
+        let beta = HardwareConfig::from_toml_profile(value, Some("beta")).unwrap();
+        assert_eq!(beta.i2c_sensor_address, 0x4a);
+
+        assert!(HardwareConfig::from_toml_profile(value, Some("gamma")).is_err());
+    }
+}
@@ -3,9 +3,15 @@
 
 use interfaces::ToSensorChannel;
 
+pub mod config;
+pub use config::HardwareConfig;
+
 /// Address of the I2C bus used for sensors
 pub const I2C_SENSOR_ADDRESS: u16 = 0x48;
 
+/// Number of inputs on the ADC the sensors are read from
+pub const ADC_CHANNELS: u8 = 4;
+
 /// Default PWM frequency recommended for a SignedMotor
 pub const FREQUENCY: f64 = 4096.0;
 
@@ -0,0 +1,198 @@
+//! Validation of batches of commands
+//!
+//! A batch of [`Command`]s is executed in order, each waiting for the job of
+//! the previous one to finish. Before any of them is sent to the
+//! [`HardwareThread`](crate::hardware::HardwareThread), the whole batch is
+//! checked against the same requirements the hardware thread enforces, so a
+//! batch is either rejected as a whole or starts executing, e.g. following
+//! the line is rejected unless the edge is found earlier in the batch.
+
+use std::fmt::Display;
+
+use crate::hardware::Command;
+
+/// Check that every [`Command`] of a batch can be executed after the ones
+/// before it, where `calibrated` tells whether the followed sensor is
+/// calibrated before the batch starts
+///
+/// Whether logbot is on the edge of the line is not known outside of the
+/// hardware thread, so following the line always requires finding the edge
+/// earlier in the batch. Counting stop lines requires calibrating both
+/// sensors earlier in the batch.
+pub fn validate(commands: &[Command], calibrated: bool) -> Result<(), BatchError> {
+    if commands.is_empty() {
+        return Err(BatchError::Empty);
+    };
+
+    let mut calibrated = calibrated;
+    let mut both_calibrated = false;
+    let mut on_line = false;
+    for (number, command) in (1..).zip(commands) {
+        let required = |required: Command| BatchError::Required {
+            command: number,
+            required: required.as_str(),
+        };
+        match command {
+            Command::Calibrate => {
+                (calibrated, both_calibrated) = (true, true);
+                on_line = false;
+            }
+            Command::RestoreCalibration(_) => calibrated = true,
+            Command::FindEdge if !calibrated => {
+                return Err(required(Command::Calibrate));
+            }
+            Command::FindEdge => on_line = true,
+            Command::FollowLine => return Err(BatchError::Unlimited { command: number }),
+            Command::FollowFor(limit) if limit.is_unlimited() => {
+                return Err(BatchError::Unlimited { command: number });
+            }
+            Command::FollowFor(_) if !on_line => {
+                return Err(required(Command::FindEdge));
+            }
+            Command::FollowFor(limit) if limit.stop_lines.is_some() && !both_calibrated => {
+                return Err(required(Command::Calibrate));
+            }
+            Command::Drive(_) | Command::Demo | Command::Mission(_) => on_line = false,
+            // Commands of a batch only start once the previous ones finished
+            Command::Pause | Command::Resume => {
+                return Err(BatchError::Unbatchable {
+                    command: number,
+                    name: command.as_str(),
+                });
+            }
+            Command::FollowFor(_)
+            | Command::LiftUp(_)
+            | Command::LiftDown(_)
+            | Command::Stop
+            | Command::Diagnose(_) => {}
+        };
+    }
+    Ok(())
+}
+
+/// Reasons a batch of [`Command`]s is rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchError {
+    /// The batch has no commands
+    Empty,
+    /// A command requires another command earlier in the batch
+    Required {
+        /// Number of the command, counting from 1
+        command: usize,
+        /// Name of the required command
+        required: &'static str,
+    },
+    /// A command only applies to a running command, so it can't be batched
+    Unbatchable {
+        /// Number of the command, counting from 1
+        command: usize,
+        /// Name of the command
+        name: &'static str,
+    },
+    /// A command follows the line without any limit, so it never completes
+    Unlimited {
+        /// Number of the command, counting from 1
+        command: usize,
+    },
+}
+
+impl Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => f.write_str("the batch has no commands"),
+            Self::Required { command, required } => {
+                write!(f, "command {command}: requires {required}")
+            }
+            Self::Unbatchable { command, name } => {
+                write!(f, "command {command}: {name} can't be batched")
+            }
+            Self::Unlimited { command } => {
+                write!(f, "command {command}: follows the line without any limit")
+            }
+        }
+    }
+}
+
+impl core::error::Error for BatchError {}
+
+#[cfg(test)]
+mod tests {
+    use demo::follow::FollowLimit;
+
+    use super::{validate, BatchError};
+    use crate::hardware::Command;
+
+    /// Following for a second
+    const FOLLOW: Command = Command::FollowFor(FollowLimit {
+        duration_ms: Some(1000),
+        distance_m: None,
+        stop_lines: None,
+    });
+
+    /// Test that commands are only accepted after the commands they require
+    #[test]
+    fn require_earlier_commands() {
+        assert_eq!(
+            validate(&[FOLLOW], true),
+            Err(BatchError::Required {
+                command: 1,
+                required: "FindEdge"
+            })
+        );
+        assert_eq!(
+            validate(&[Command::FindEdge, FOLLOW], false),
+            Err(BatchError::Required {
+                command: 1,
+                required: "Calibrate"
+            })
+        );
+        assert_eq!(
+            validate(&[Command::Calibrate, Command::FindEdge, FOLLOW], false),
+            Ok(())
+        );
+        assert_eq!(validate(&[Command::FindEdge, FOLLOW], true), Ok(()));
+    }
+
+    /// Test that driving away from the line requires finding the edge again
+    #[test]
+    fn leave_line() {
+        let drive = Command::Drive("forward:0.5".parse().unwrap());
+        assert_eq!(
+            validate(&[Command::FindEdge, drive, FOLLOW], true),
+            Err(BatchError::Required {
+                command: 3,
+                required: "FindEdge"
+            })
+        );
+    }
+
+    /// Test that empty batches, batches pausing and batches that never
+    /// complete are rejected
+    #[test]
+    fn reject_batches() {
+        assert_eq!(validate(&[], true), Err(BatchError::Empty));
+        assert_eq!(
+            validate(&[Command::Pause], true),
+            Err(BatchError::Unbatchable {
+                command: 1,
+                name: "Pause"
+            })
+        );
+        assert_eq!(
+            validate(&[Command::FindEdge, Command::FollowLine], true),
+            Err(BatchError::Unlimited { command: 2 })
+        );
+
+        let stop_line = Command::FollowFor(FollowLimit {
+            stop_lines: Some(1),
+            ..FollowLimit::default()
+        });
+        assert_eq!(
+            validate(&[Command::FindEdge, stop_line], true),
+            Err(BatchError::Required {
+                command: 2,
+                required: "Calibrate"
+            })
+        );
+    }
+}
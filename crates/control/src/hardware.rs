@@ -0,0 +1,1391 @@
+//! Actor thread for handling hardware operations
+
+use std::{
+    cell::RefCell,
+    fmt::{Debug, Display},
+    ops::ControlFlow,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use acceleration::{Accelerate, LinearAcceleration};
+
+use calibration::{SensorCalibration, SingleSensorCalibration};
+use consts::{config::BatteryConfig, Sensors};
+use demo::{
+    avoid::{self, Arbitration, Avoidance},
+    cancel::CancelToken,
+    config::DemoConfig,
+    follow::{is_stop_line, FollowLimit, FollowProgress},
+    mission::{self, Mission},
+};
+use directions::{SpinDirection, VehicleDirection};
+use interfaces::{Drive, Lift, RangeFinder, SensorRead, Spin, ToSensorChannel};
+use line::FollowLineState;
+use logbot::{
+    error::LogbotError,
+    self_test::{self, SelfTest},
+};
+use odometry::DeadReckoning;
+use speed::Speed;
+use tokio::{
+    runtime::Handle,
+    sync::{
+        broadcast,
+        mpsc::{self, error::TryRecvError},
+        oneshot, watch,
+    },
+    task::JoinHandle,
+};
+use tuning::Tuning;
+
+use crate::{
+    history::History,
+    jobs::{JobId, JobState, Jobs},
+    power::{self, Power},
+    telemetry::{MissionStep, Sampler, Status, TelemetrySample, INTERVAL},
+    watchdog,
+};
+
+/// The [`Result`] of a [`Request`]
+///
+/// The Ok() variant means the command was executed successfully.
+/// Containing the previous Command it cancelled.
+///
+/// The Err() variant means the command failed. The reason is contained
+/// within the Err.
+pub type CommandResult = std::result::Result<Command, CommandDenied>;
+
+/// [`Request`] execution of a [`Command`] on the [`HardwareThread`], with the
+/// [`JobId`] of long-running commands
+pub type Request = (Command, Option<JobId>, Responder);
+
+/// Sender of the [`CommandResult`] of a [`Request`], which records the
+/// requested [`Command`] in the [`History`]
+#[derive(Debug)]
+pub struct Responder {
+    /// The requested command
+    command: Command,
+    /// Sender of the result
+    sender: oneshot::Sender<CommandResult>,
+    /// History the command is recorded in
+    history: Arc<History>,
+    /// Sender of the [`SelfTest`] of a [`Command::Diagnose`]
+    report: Option<oneshot::Sender<SelfTest>>,
+}
+
+impl Responder {
+    /// Create a [`Responder`] recording a [`Command`] in a [`History`]
+    pub fn new(
+        command: Command,
+        sender: oneshot::Sender<CommandResult>,
+        history: Arc<History>,
+    ) -> Self {
+        Self {
+            command,
+            sender,
+            history,
+            report: None,
+        }
+    }
+
+    /// Send the [`SelfTest`] of a [`Command::Diagnose`] before accepting it
+    pub fn report(mut self, diagnostics: SelfTest) {
+        if let Some(report) = self.report.take() {
+            let _ = report.send(diagnostics);
+        };
+        self.send(Ok(Command::Stop));
+    }
+
+    /// Send the [`CommandResult`], which is dropped if the request was abandoned
+    pub fn send(self, result: CommandResult) {
+        match &result {
+            Ok(_) => self.history.accept(&self.command),
+            Err(denied) => self.history.deny(&self.command, denied),
+        };
+        let _ = self.sender.send(result);
+    }
+}
+
+/// The [`CommandResult`] of a [`Request`], with the [`JobId`] assigned to an
+/// accepted long-running command
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    /// Result of the command
+    pub result: CommandResult,
+    /// Job of the command, if it is long-running and was accepted
+    pub job: Option<JobId>,
+}
+
+/// Range finder seeing the obstacles avoided while following the line
+pub struct Obstacles(Box<dyn FnMut() -> Result<Option<f64>, String> + Send>);
+
+impl Obstacles {
+    /// Create [`Obstacles`] seen by a [`RangeFinder`]
+    pub fn new<R>(mut range_finder: R) -> Self
+    where
+        R: RangeFinder + Send + 'static,
+        R::Error: Debug,
+    {
+        Self(Box::new(move || {
+            range_finder.distance().map_err(|e| format!("{e:?}"))
+        }))
+    }
+
+    /// Distance to the obstacle ahead in meters, [None] if nothing is in range
+    fn distance(&mut self) -> Result<Option<f64>, String> {
+        (self.0)()
+    }
+}
+
+impl Debug for Obstacles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Obstacles").finish_non_exhaustive()
+    }
+}
+
+/// The [`LogbotError`] returned when the [`HardwareThread`] exits on failure
+pub type HardwareError<L> =
+    LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, <L as Lift>::Error>;
+
+/// [`Command`]s that control hardware
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Drive in a direction until the next command
+    Drive(VehicleDirection),
+    /// Follow the line until stopped, requires finding the edge first
+    FollowLine,
+    /// Follow the line until a [`FollowLimit`] is reached
+    FollowFor(FollowLimit),
+    /// Calibrate both sensors by oscillating over the line
+    Calibrate,
+    /// Find the edge of the line, requires a calibration
+    FindEdge,
+    /// Move the lift up, at the lift speed of the [`Tuning`] unless a
+    /// [`Speed`] is given
+    LiftUp(Option<Speed>),
+    /// Move the lift down, at the lift speed of the [`Tuning`] unless a
+    /// [`Speed`] is given
+    LiftDown(Option<Speed>),
+    /// Stop whatever logbot is doing
+    Stop,
+    /// Run the full demo
+    Demo,
+    /// Restore the calibration of the followed sensor
+    RestoreCalibration(SensorCalibration),
+    /// Run a [`Mission`]
+    Mission(Mission),
+    /// Pause the running command, keeping its progress
+    Pause,
+    /// Resume the paused command
+    Resume,
+    /// Run the self-test of the components, reading the battery with a
+    /// [`BatteryConfig`]
+    Diagnose(Option<BatteryConfig>),
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Command {
+    /// Convert the [`Command`] to a string slice
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stop => "Stop",
+            Self::Drive(_) => "Drive",
+            Self::LiftUp(_) => "LiftUp",
+            Self::LiftDown(_) => "LiftDown",
+            Self::Calibrate => "Calibrate",
+            Self::FindEdge => "FindEdge",
+            Self::FollowLine => "FollowLine",
+            Self::FollowFor(_) => "FollowFor",
+            Self::Demo => "Demo",
+            Self::RestoreCalibration(_) => "RestoreCalibration",
+            Self::Mission(_) => "Mission",
+            Self::Pause => "Pause",
+            Self::Resume => "Resume",
+            Self::Diagnose(_) => "Diagnose",
+        }
+    }
+
+    /// Whether the [`Command`] keeps running after it is responded to, and
+    /// is therefore tracked as a job
+    pub fn is_job(&self) -> bool {
+        matches!(
+            self,
+            Self::FollowLine
+                | Self::FollowFor(_)
+                | Self::Calibrate
+                | Self::FindEdge
+                | Self::LiftUp(_)
+                | Self::LiftDown(_)
+                | Self::Demo
+                | Self::Mission(_)
+        )
+    }
+
+    /// Whether the [`Command`] moves logbot
+    pub fn moves(&self) -> bool {
+        match self {
+            Self::Drive(direction) => *direction != VehicleDirection::STOP,
+            command => command.is_job(),
+        }
+    }
+}
+
+/// Reasons for a [`Command`] being denied
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandDenied {
+    /// Logbot is busy executing the contained command
+    Busy(Command),
+    /// The contained command has to be executed first
+    Required(Command),
+    /// Nothing is running that could be paused
+    Idle,
+    /// The battery is critical, logbot may not move
+    Battery,
+}
+
+impl Display for CommandDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Busy(command) => write!(f, "busy with {command}"),
+            Self::Required(command) => write!(f, "requires {command}"),
+            Self::Idle => f.write_str("nothing to pause"),
+            Self::Battery => f.write_str("battery is critical"),
+        }
+    }
+}
+
+/// Thread for handling hardware operations
+#[derive(Debug)]
+pub struct HardwareThread<L>
+where
+    L: Send,
+
+    L: Drive<Direction = VehicleDirection>,
+    <L as Drive>::Error: Debug + Send,
+
+    L: Spin<SpinDirection = SpinDirection>,
+
+    L: SensorRead<Output = u8>,
+    <L as SensorRead>::Error: Debug + Send,
+
+    L: Lift,
+    <L as Lift>::Error: Debug + Send,
+{
+    channel: mpsc::Sender<Request>,
+    stop: mpsc::Sender<Request>,
+    handle: JoinHandle<Result<(), HardwareError<L>>>,
+    jobs: Arc<Jobs>,
+    history: Arc<History>,
+    heartbeat: watch::Sender<Instant>,
+    cancel: CancelToken,
+}
+
+impl<L> HardwareThread<L>
+where
+    L: Send + 'static,
+
+    L: Drive<Direction = VehicleDirection>,
+    <L as Drive>::Error: Debug + Send,
+
+    L: Spin<SpinDirection = SpinDirection>,
+
+    L: SensorRead<Output = u8>,
+    <L as SensorRead>::Error: Debug + Send,
+
+    L: Lift,
+    <L as Lift>::Error: Debug + Send,
+{
+    /// Spawn a new [`HardwareThread`] operating with the latest [`Tuning`] of
+    /// `tuning`, broadcasting sensor samples to the receivers of `telemetry` and
+    /// publishing its [`Status`] to the receivers of `status`
+    ///
+    /// The calibration of the followed sensor is kept in `calibration`, the
+    /// obstacles seen by `obstacles` are avoided while following the line and
+    /// long-running commands are tracked in `jobs`. Every command is recorded
+    /// in the [`History`] of the thread. While driving manually, logbot is
+    /// stopped unless the [heartbeat](Self::heartbeat) is refreshed within
+    /// the `heartbeat` interval. With a battery monitored by `power`, the
+    /// speed is derated as the voltage sags and logbot is stopped once the
+    /// battery is critical.
+    #[allow(clippy::too_many_arguments)]
+    pub fn spawn(
+        logbot: L,
+        tuning: watch::Receiver<Tuning>,
+        telemetry: broadcast::Sender<TelemetrySample>,
+        status: watch::Sender<Status>,
+        calibration: watch::Sender<Option<SensorCalibration>>,
+        obstacles: Option<Obstacles>,
+        power: Option<Power>,
+        jobs: Arc<Jobs>,
+        heartbeat: Duration,
+    ) -> Self {
+        let (wx, rx) = mpsc::channel(10);
+        let (stop, stop_rx) = mpsc::channel(10);
+        let cancel = CancelToken::new();
+        let requests = Requests {
+            stop: stop_rx,
+            other: rx,
+            cancel: cancel.clone(),
+        };
+        let history = Arc::new(History::default());
+        let beats = watch::Sender::new(Instant::now());
+        tokio::spawn(watchdog::watch_heartbeat(
+            heartbeat,
+            beats.subscribe(),
+            status.subscribe(),
+            stop.clone(),
+            Arc::clone(&history),
+        ));
+        if power.is_some() {
+            tokio::spawn(power::watch_power(
+                status.subscribe(),
+                stop.clone(),
+                cancel.clone(),
+                Arc::clone(&history),
+            ));
+        };
+
+        let sampler = Sampler::new(telemetry, status, power);
+        let thread_jobs = Arc::clone(&jobs);
+        let thread_history = Arc::clone(&history);
+        let handle = tokio::task::spawn_blocking(move || {
+            let result = handle_commands(
+                logbot,
+                requests,
+                tuning,
+                sampler,
+                calibration,
+                obstacles,
+                &thread_jobs,
+                &thread_history,
+            );
+            if let Err(e) = &result {
+                thread_jobs.fail_running(&format!("{e:?}"));
+                thread_history.finish();
+            };
+            result
+        });
+        Self {
+            channel: wx,
+            stop,
+            handle,
+            jobs,
+            history,
+            heartbeat: beats,
+            cancel,
+        }
+    }
+
+    /// Send a [`Command`] to the [`HardwareThread`]
+    ///
+    /// A [`Command::Stop`] skips the queue of other commands, so it is
+    /// executed within one iteration of any running command, and cancels the
+    /// running step of the demo or a mission.
+    ///
+    /// Returns [None](`Option::None`) when the [`HardwareThread`] is no longer running.
+    pub async fn send(&self, command: Command) -> Option<Response> {
+        let job = command.is_job().then(|| self.jobs.start(&command));
+        let (sender, rx) = oneshot::channel();
+        let responder = Responder::new(command.clone(), sender, Arc::clone(&self.history));
+        let stop = command == Command::Stop;
+        let channel = match stop {
+            true => &self.stop,
+            false => &self.channel,
+        };
+        // Both calls are successful when the thread is active
+        let result = match channel.send((command, job, responder)).await {
+            Ok(()) => {
+                // Cancel only once the stop is queued, so the cancelled step
+                // finds it and answers it
+                if stop {
+                    self.cancel.cancel();
+                };
+                rx.await.ok()
+            }
+            Err(_) => None,
+        };
+
+        // Jobs of denied commands never start
+        if let (Some(job), false) = (job, matches!(result, Some(Ok(_)))) {
+            self.jobs.remove(job);
+        };
+        Some(Response {
+            job: job.filter(|_| matches!(result, Some(Ok(_)))),
+            result: result?,
+        })
+    }
+
+    /// Run the self-test of the components on the [`HardwareThread`], reading
+    /// the battery voltage with a [`BatteryConfig`]
+    ///
+    /// The self-test is denied while logbot is busy. Returns
+    /// [None](`Option::None`) when the [`HardwareThread`] is no longer running.
+    pub async fn diagnose(
+        &self,
+        battery: Option<BatteryConfig>,
+    ) -> Option<Result<SelfTest, CommandDenied>> {
+        let command = Command::Diagnose(battery);
+        let (sender, rx) = oneshot::channel();
+        let (report, report_rx) = oneshot::channel();
+        let mut responder = Responder::new(command.clone(), sender, Arc::clone(&self.history));
+        responder.report = Some(report);
+
+        self.channel.send((command, None, responder)).await.ok()?;
+        match rx.await.ok()? {
+            Ok(_) => report_rx.await.ok().map(Ok),
+            Err(denied) => Some(Err(denied)),
+        }
+    }
+
+    /// Refresh the heartbeat of manual driving
+    pub fn heartbeat(&self) {
+        self.heartbeat.send_replace(Instant::now());
+    }
+
+    /// The [`History`] of the commands sent to the [`HardwareThread`]
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Whether the [`HardwareThread`] is finished
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+/// Receiver of [`Request`]s, where a [`Command::Stop`] skips the queue of
+/// other requests
+#[derive(Debug)]
+struct Requests {
+    /// Requests to stop
+    stop: mpsc::Receiver<Request>,
+    /// Any other requests
+    other: mpsc::Receiver<Request>,
+    /// Cancelled when a stop is sent, before it is received
+    cancel: CancelToken,
+}
+
+impl Requests {
+    /// Receive a [`Request`] without waiting, requests to stop first
+    fn try_recv(&mut self) -> Result<Request, TryRecvError> {
+        self.stop.try_recv().or_else(|_| self.other.try_recv())
+    }
+
+    /// Wait for the next [`Request`], requests to stop first
+    ///
+    /// Returns [None](`Option::None`) once every sender is dropped.
+    async fn recv(&mut self) -> Option<Request> {
+        tokio::select! {
+            biased;
+            Some(request) = self.stop.recv() => Some(request),
+            request = self.other.recv() => request,
+        }
+    }
+}
+
+/// Wait for the next [`Request`], sampling the sensors while waiting
+///
+/// Returns [None](`Option::None`) once every sender is dropped.
+fn next_request<L>(
+    logbot: &mut L,
+    channel: &mut Requests,
+    sampler: &mut Sampler,
+) -> Result<Option<Request>, <L as SensorRead>::Error>
+where
+    L: SensorRead<Output = u8> + Lift,
+{
+    let runtime = Handle::current();
+    loop {
+        sampler.sample(logbot)?;
+        if let Ok(request) = runtime.block_on(tokio::time::timeout(INTERVAL, channel.recv())) {
+            return Ok(request);
+        };
+    }
+}
+
+/// Stop logbot while a [`Command`] is paused, until it is resumed or stopped
+///
+/// The [`Command::Pause`] is responded to once logbot stands still. While
+/// paused, the sensors keep being sampled, a [`Command::Resume`] or a
+/// [`Command::Stop`] ends the pause and any other command is denied. Returns
+/// how long the command was paused, or [None](`Option::None`) if it was
+/// stopped.
+fn pause<L>(
+    logbot: &mut L,
+    channel: &mut Requests,
+    sampler: &mut Sampler,
+    command: &Command,
+    response: Responder,
+) -> Result<Option<Duration>, HardwareError<L>>
+where
+    L: Drive<Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    let start = Instant::now();
+    logbot.stop().map_err(LogbotError::Vehicle)?;
+    sampler.report(logbot, Command::Pause, Some(VehicleDirection::STOP));
+    response.send(Ok(command.clone()));
+
+    while let Some((request, _, response)) =
+        next_request(logbot, channel, sampler).map_err(LogbotError::Sensor)?
+    {
+        match request {
+            Command::Resume => {
+                sampler.report(logbot, command.clone(), None);
+                response.send(Ok(Command::Pause));
+                return Ok(Some(start.elapsed()));
+            }
+            Command::Stop => {
+                sampler.report(logbot, Command::Stop, Some(VehicleDirection::STOP));
+                response.send(Ok(command.clone()));
+                return Ok(None);
+            }
+            _ => {
+                response.send(Err(CommandDenied::Busy(command.clone())));
+            }
+        };
+    }
+    Ok(None)
+}
+
+/// Process hardware requests syncronously
+#[allow(clippy::too_many_arguments)]
+fn handle_commands<L>(
+    mut logbot: L,
+    mut channel: Requests,
+    active_tuning: watch::Receiver<Tuning>,
+    mut sampler: Sampler,
+    left_calibration: watch::Sender<Option<SensorCalibration>>,
+    mut obstacles: Option<Obstacles>,
+    jobs: &Jobs,
+    history: &History,
+) -> Result<(), HardwareError<L>>
+where
+    L: Drive<Direction = VehicleDirection>,
+    <L as Drive>::Error: Debug,
+    L: Spin<SpinDirection = SpinDirection>,
+    L: SensorRead<Output = u8>,
+    <L as SensorRead>::Error: Debug,
+    L: Lift,
+{
+    // Store the current calibration status, the left calibration is shared
+    // with the API
+    let mut right_calibration: Option<SensorCalibration> = None;
+
+    // Store the state whether logbot is currently on the line or not
+    let mut on_line = false;
+
+    'outer: while let Some((command, job, response)) =
+        next_request(&mut logbot, &mut channel, &mut sampler).map_err(LogbotError::Sensor)?
+    {
+        // A critical battery only lets logbot stand still
+        if sampler.is_critical() && command.moves() {
+            response.send(Err(CommandDenied::Battery));
+            continue 'outer;
+        };
+
+        // Changes of the tuning and the speed limit apply from the next
+        // command on
+        let tuning = power::derate(*active_tuning.borrow(), sampler.speed_limit());
+
+        match command {
+            Command::Demo => {
+                // Run the full demo, stopping it within the running step
+                response.send(Ok(Command::Stop));
+                let demo = Mission::demo(&DemoConfig::new(tuning));
+                let state = run_mission(
+                    &mut logbot,
+                    &mut channel,
+                    &mut sampler,
+                    Command::Demo,
+                    &demo,
+                )?;
+                jobs.finish(job, state);
+                history.finish();
+                on_line = false;
+                continue 'outer;
+            }
+            Command::Mission(mission) => {
+                // The tuning of the mission was taken when it was created,
+                // so it is derated like the tuning of any other command
+                let Ok(derated) = power::derate_mission(&mission, sampler.speed_limit()) else {
+                    response.send(Err(CommandDenied::Battery));
+                    continue 'outer;
+                };
+                response.send(Ok(Command::Stop));
+                let command = Command::Mission(mission);
+                let state =
+                    run_mission(&mut logbot, &mut channel, &mut sampler, command, &derated)?;
+                jobs.finish(job, state);
+                history.finish();
+                on_line = false;
+                continue 'outer;
+            }
+            command @ (Command::FollowLine | Command::FollowFor(_)) => {
+                if !on_line {
+                    response.send(Err(CommandDenied::Required(Command::FindEdge)));
+                    continue 'outer;
+                };
+
+                let limit = match command {
+                    Command::FollowFor(limit) => limit,
+                    _ => FollowLimit::default(),
+                };
+
+                // Check that we have calibrated, so we can follow the line
+                let calibration = match *left_calibration.borrow() {
+                    Some(calibration) => calibration,
+                    None => {
+                        // Fail, since no calibration data is available
+                        response.send(Err(CommandDenied::Required(Command::Calibrate)));
+                        continue 'outer;
+                    }
+                };
+
+                // Stop lines are only seen with both sensors calibrated
+                let stop_lines = match (limit.stop_lines, right_calibration) {
+                    (None, _) => None,
+                    (Some(_), Some(right)) => Some((calibration, right)),
+                    (Some(_), None) => {
+                        response.send(Err(CommandDenied::Required(Command::Calibrate)));
+                        continue 'outer;
+                    }
+                };
+
+                // Line following can proceed
+                response.send(Ok(Command::Stop));
+
+                let mut acceleration = LinearAcceleration::new(tuning.acceleration());
+
+                // Create the config for following the line
+                let config = tuning.follow_line_config(calibration);
+
+                // Create state for line following from config
+                let mut state = FollowLineState::new(config);
+
+                // Track the progress towards the limit
+                let mut progress = FollowProgress::new(
+                    limit,
+                    DeadReckoning::new(tuning.drive_rate, tuning.spin_rate),
+                );
+
+                // Arbitrate with the obstacles ahead, if they are seen
+                let mut avoidance = Avoidance::new(tuning.avoid);
+
+                // Lets start following the line while listening to new commands
+                loop {
+                    // We want to handle each command differently
+                    if let Ok((request, _, response)) = channel.try_recv() {
+                        match request {
+                            Command::Stop => {
+                                // Stop the vehicle and break out the following loop
+                                logbot.stop().map_err(LogbotError::Vehicle)?;
+                                sampler.report(
+                                    &logbot,
+                                    Command::Stop,
+                                    Some(VehicleDirection::STOP),
+                                );
+                                response.send(Ok(command));
+                                jobs.finish(job, JobState::Cancelled);
+                                continue 'outer;
+                            }
+                            Command::Pause => {
+                                let Some(paused) = pause(
+                                    &mut logbot,
+                                    &mut channel,
+                                    &mut sampler,
+                                    &command,
+                                    response,
+                                )?
+                                else {
+                                    jobs.finish(job, JobState::Cancelled);
+                                    continue 'outer;
+                                };
+                                // Accelerate again from standing still
+                                acceleration = LinearAcceleration::new(tuning.acceleration());
+                                progress.resume(paused);
+                            }
+                            _ => {
+                                response.send(Err(CommandDenied::Busy(command.clone())));
+                            }
+                        };
+                    };
+
+                    sampler.sample(&mut logbot).map_err(LogbotError::Sensor)?;
+
+                    // Move following state forward
+                    let sensor_value = logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?;
+                    let direction = state.step(sensor_value);
+                    let direction = direction.accelerate(&mut acceleration);
+
+                    let arbitration = match obstacles.as_mut().map(Obstacles::distance) {
+                        None => Arbitration::Follow(Speed::MAX),
+                        Some(Ok(distance)) => avoidance.arbitrate(distance),
+                        Some(Err(e)) => {
+                            // Not knowing what is ahead is as bad as an obstacle
+                            tracing::warn!("Failed to measure the distance to obstacles: {e}");
+                            Arbitration::Stop
+                        }
+                    };
+                    let direction = match arbitration {
+                        Arbitration::Follow(scale) => direction * scale,
+                        Arbitration::Stop => {
+                            // Accelerate again once the way is free
+                            acceleration = LinearAcceleration::new(tuning.acceleration());
+                            VehicleDirection::STOP
+                        }
+                        Arbitration::Detour => {
+                            tracing::info!("Driving around an obstacle blocking the line");
+                            sampler.report(&logbot, command.clone(), None);
+
+                            // Only stops sent from now on cancel the detour
+                            let cancel = channel.cancel.clone();
+                            cancel.reset();
+                            let result = avoid::detour(&mut logbot, &calibration, &tuning, &cancel);
+                            let ended = match result {
+                                Ok(()) => None,
+                                Err(LogbotError::Cancelled) => {
+                                    // Answer the stop that cancelled the detour
+                                    if let Ok((_, _, response)) = channel.stop.try_recv() {
+                                        response.send(Ok(command.clone()));
+                                    };
+                                    Some(JobState::Cancelled)
+                                }
+                                Err(LogbotError::TimedOut) => {
+                                    history.finish();
+                                    Some(JobState::Failed {
+                                        error: "lost the line driving around an obstacle"
+                                            .to_string(),
+                                    })
+                                }
+                                Err(e) => return Err(e),
+                            };
+                            if let Some(ended) = ended {
+                                on_line = false;
+                                sampler.report(
+                                    &logbot,
+                                    Command::Stop,
+                                    Some(VehicleDirection::STOP),
+                                );
+                                jobs.finish(job, ended);
+                                continue 'outer;
+                            };
+
+                            // Follow the line found behind the obstacle from standing still
+                            state = FollowLineState::new(config);
+                            acceleration = LinearAcceleration::new(tuning.acceleration());
+                            avoidance.reset();
+                            continue;
+                        }
+                    };
+
+                    let on_stop_line = match &stop_lines {
+                        Some(calibration) => {
+                            let right = logbot.read(Sensors::Right).map_err(LogbotError::Sensor)?;
+                            is_stop_line(calibration, sensor_value, right)
+                        }
+                        None => false,
+                    };
+                    if progress.update(direction, on_stop_line) {
+                        break;
+                    };
+
+                    logbot.drive(direction).map_err(LogbotError::Vehicle)?;
+                    sampler.report(&logbot, command.clone(), Some(direction));
+                }
+
+                // The limit is reached
+                logbot.stop().map_err(LogbotError::Vehicle)?;
+                sampler.report(&logbot, Command::Stop, Some(VehicleDirection::STOP));
+                jobs.finish(job, JobState::Succeeded);
+                history.finish();
+            }
+            Command::Calibrate => {
+                on_line = false;
+
+                // Respond with successful oscillation
+                response.send(Ok(Command::Stop));
+                sampler.report(&logbot, Command::Calibrate, None);
+
+                // Oscillation configuration
+                let oscillate = tuning.calibrate.oscillate();
+
+                // Calibrate sensors by oscillating and evaulating sensor readings
+                // Calibrate both sensors by logging values
+                let mut left_sensor = SingleSensorCalibration::default();
+                let mut right_sensor = SingleSensorCalibration::default();
+
+                // Oscillate the vehicle, multiplying the time on each direction change
+                let mut oscillate = oscillate.start(&mut logbot).map_err(LogbotError::Vehicle)?;
+
+                // Wait until we first change direction, since we want to record
+                // one contiguous line with the sensors
+
+                // Do active waiting since we don't want to block incoming stop messages
+                while !oscillate.should_step() {
+                    sampler.sample(&mut logbot).map_err(LogbotError::Sensor)?;
+
+                    // Check for incoming messages
+                    if let Ok((command, _, response)) = channel.try_recv() {
+                        match command {
+                            Command::Stop => {
+                                // Stop the vehicle and stop oscillation
+                                logbot.stop().map_err(LogbotError::Vehicle)?;
+                                sampler.report(
+                                    &logbot,
+                                    Command::Stop,
+                                    Some(VehicleDirection::STOP),
+                                );
+                                response.send(Ok(Command::Calibrate));
+                                jobs.finish(job, JobState::Cancelled);
+                                continue 'outer;
+                            }
+                            Command::Pause => {
+                                match pause(
+                                    &mut logbot,
+                                    &mut channel,
+                                    &mut sampler,
+                                    &Command::Calibrate,
+                                    response,
+                                )? {
+                                    Some(paused) => oscillate
+                                        .resume(&mut logbot, paused)
+                                        .map_err(LogbotError::Vehicle)?,
+                                    None => {
+                                        jobs.finish(job, JobState::Cancelled);
+                                        continue 'outer;
+                                    }
+                                };
+                            }
+                            _ => {
+                                response.send(Err(CommandDenied::Busy(Command::Calibrate)));
+                            }
+                        }
+                    }
+                }
+
+                oscillate.step(&mut logbot).map_err(LogbotError::Vehicle)?;
+
+                // Read sensor values continuously until we're supposed to oscillate again
+                while !oscillate.should_step() {
+                    sampler.sample(&mut logbot).map_err(LogbotError::Sensor)?;
+
+                    // Check for incoming messages
+                    if let Ok((command, _, response)) = channel.try_recv() {
+                        match command {
+                            Command::Stop => {
+                                // Stop the vehicle and stop oscillation
+                                logbot.stop().map_err(LogbotError::Vehicle)?;
+                                sampler.report(
+                                    &logbot,
+                                    Command::Stop,
+                                    Some(VehicleDirection::STOP),
+                                );
+                                response.send(Ok(Command::Calibrate));
+                                jobs.finish(job, JobState::Cancelled);
+                                continue 'outer;
+                            }
+                            Command::Pause => {
+                                match pause(
+                                    &mut logbot,
+                                    &mut channel,
+                                    &mut sampler,
+                                    &Command::Calibrate,
+                                    response,
+                                )? {
+                                    Some(paused) => oscillate
+                                        .resume(&mut logbot, paused)
+                                        .map_err(LogbotError::Vehicle)?,
+                                    None => {
+                                        jobs.finish(job, JobState::Cancelled);
+                                        continue 'outer;
+                                    }
+                                };
+                            }
+                            _ => {
+                                response.send(Err(CommandDenied::Busy(Command::Calibrate)));
+                            }
+                        }
+                    }
+
+                    // Read values from sensors
+                    let left_value = logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?;
+                    let right_value = logbot.read(Sensors::Right).map_err(LogbotError::Sensor)?;
+
+                    left_sensor.log(left_value as f64);
+                    right_sensor.log(right_value as f64);
+                }
+
+                // Stop the vehicle once the oscillation is done
+                logbot.stop().map_err(LogbotError::Vehicle)?;
+                sampler.report(&logbot, Command::Stop, Some(VehicleDirection::STOP));
+
+                // Evaluate sensor readings to get calibrated sensors
+                left_calibration.send_replace(Some(left_sensor.calibrate()));
+                right_calibration = Some(right_sensor.calibrate());
+                jobs.finish(job, JobState::Succeeded);
+                history.finish();
+            }
+            Command::FindEdge => {
+                let calibration = match *left_calibration.borrow() {
+                    Some(calibration) => calibration,
+                    None => {
+                        response.send(Err(CommandDenied::Required(Command::Calibrate)));
+                        continue 'outer;
+                    }
+                };
+
+                response.send(Ok(Command::Stop));
+                sampler.report(&logbot, Command::FindEdge, None);
+
+                // Oscillation configuration
+                let mut oscillate = tuning
+                    .find_edge
+                    .oscillate()
+                    .start(&mut logbot)
+                    .map_err(LogbotError::Vehicle)?;
+
+                'edge: loop {
+                    while !oscillate.should_step() {
+                        sampler.sample(&mut logbot).map_err(LogbotError::Sensor)?;
+
+                        // Check for incoming messages
+                        if let Ok((command, _, response)) = channel.try_recv() {
+                            match command {
+                                Command::Stop => {
+                                    // Stop finding edge
+                                    logbot.stop().map_err(LogbotError::Vehicle)?;
+                                    sampler.report(
+                                        &logbot,
+                                        Command::Stop,
+                                        Some(VehicleDirection::STOP),
+                                    );
+                                    on_line = false;
+                                    response.send(Ok(Command::FindEdge));
+                                    jobs.finish(job, JobState::Cancelled);
+                                    continue 'outer;
+                                }
+                                Command::Pause => {
+                                    match pause(
+                                        &mut logbot,
+                                        &mut channel,
+                                        &mut sampler,
+                                        &Command::FindEdge,
+                                        response,
+                                    )? {
+                                        Some(paused) => oscillate
+                                            .resume(&mut logbot, paused)
+                                            .map_err(LogbotError::Vehicle)?,
+                                        None => {
+                                            on_line = false;
+                                            jobs.finish(job, JobState::Cancelled);
+                                            continue 'outer;
+                                        }
+                                    };
+                                }
+                                _ => {
+                                    response.send(Err(CommandDenied::Busy(Command::FindEdge)));
+                                }
+                            };
+                        };
+                        // Check if we have found the edge
+                        let value =
+                            logbot.read(Sensors::Right).map_err(LogbotError::Sensor)? as f64;
+                        if (value - calibration.line as f64).abs() < 2.0 {
+                            break 'edge;
+                        };
+                    }
+                    // We should change directions
+                    oscillate.step(&mut logbot).map_err(LogbotError::Vehicle)?;
+                }
+                logbot.stop().map_err(LogbotError::Vehicle)?;
+                sampler.report(&logbot, Command::Stop, Some(VehicleDirection::STOP));
+                jobs.finish(job, JobState::Succeeded);
+                history.finish();
+                on_line = true;
+            }
+            Command::LiftUp(speed) => {
+                // Vehicle should be stopped, since lift is a blocking operating
+                // It should be stopped anyway, but this makes sure it is
+                let _ = logbot.stop();
+
+                response.send(Ok(Command::LiftUp(speed)));
+                sampler.report(
+                    &logbot,
+                    Command::LiftUp(speed),
+                    Some(VehicleDirection::STOP),
+                );
+                logbot
+                    .up(speed.unwrap_or(tuning.lift_speed))
+                    .map_err(LogbotError::Lift)?;
+                sampler.report(&logbot, Command::Stop, Some(VehicleDirection::STOP));
+                jobs.finish(job, JobState::Succeeded);
+                history.finish();
+            }
+            Command::LiftDown(speed) => {
+                // Vehicle should be stopped, since lift is a blocking operating
+                // It should be stopped anyway, but this makes sure it is
+                let _ = logbot.stop();
+
+                response.send(Ok(Command::LiftDown(speed)));
+                sampler.report(
+                    &logbot,
+                    Command::LiftDown(speed),
+                    Some(VehicleDirection::STOP),
+                );
+                logbot
+                    .down(speed.unwrap_or(tuning.lift_speed))
+                    .map_err(LogbotError::Lift)?;
+                sampler.report(&logbot, Command::Stop, Some(VehicleDirection::STOP));
+                jobs.finish(job, JobState::Succeeded);
+                history.finish();
+            }
+            Command::Drive(direction) => {
+                // Manual driving moves logbot off the line
+                on_line = false;
+
+                let direction = direction * sampler.speed_limit();
+                logbot.drive(direction).map_err(LogbotError::Vehicle)?;
+                sampler.report(&logbot, Command::Drive(direction), Some(direction));
+                response.send(Ok(Command::Stop));
+            }
+            Command::RestoreCalibration(calibration) => {
+                left_calibration.send_replace(Some(calibration));
+                response.send(Ok(Command::Stop));
+            }
+            Command::Stop => {
+                logbot.stop().map_err(LogbotError::Vehicle)?;
+                sampler.report(&logbot, Command::Stop, Some(VehicleDirection::STOP));
+                // The logbot is already currently not doing anything
+                // We can simply return with a success value
+                response.send(Ok(Command::Stop));
+            }
+            Command::Pause => {
+                response.send(Err(CommandDenied::Idle));
+            }
+            Command::Resume => {
+                response.send(Err(CommandDenied::Required(Command::Pause)));
+            }
+            Command::Diagnose(battery) => {
+                response.report(self_test::run(&mut logbot, battery));
+            }
+        };
+    }
+    Ok(())
+}
+
+/// Logbot [sampling](Sampler::sample) the sensors and the battery before
+/// every sensor read, so the battery is monitored within the steps of a
+/// mission
+struct Sampled<'a, L> {
+    /// The sampled logbot
+    logbot: &'a mut L,
+    /// Sampler shared with the reports of the mission
+    sampler: &'a RefCell<&'a mut Sampler>,
+}
+
+impl<L: Drive> Drive for Sampled<'_, L> {
+    type Direction = L::Direction;
+    type Error = L::Error;
+
+    fn drive(
+        &mut self,
+        direction: Self::Direction,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
+        self.logbot.drive(direction)
+    }
+
+    fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+        self.logbot.stop()
+    }
+}
+
+impl<L: Spin> Spin for Sampled<'_, L> {
+    type SpinDirection = L::SpinDirection;
+
+    fn spin(
+        &mut self,
+        direction: Self::SpinDirection,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
+        self.logbot.spin(direction)
+    }
+}
+
+impl<L> SensorRead for Sampled<'_, L>
+where
+    L: SensorRead<Output = u8> + Lift,
+{
+    type Output = u8;
+    type Error = <L as SensorRead>::Error;
+
+    fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
+        self.sampler.borrow_mut().sample(self.logbot)?;
+        self.logbot.read(sensor)
+    }
+}
+
+impl<L: Lift> Lift for Sampled<'_, L> {
+    type Error = L::Error;
+
+    fn up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.logbot.up(speed)
+    }
+
+    fn down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.logbot.down(speed)
+    }
+
+    fn is_up(&self) -> bool {
+        self.logbot.is_up()
+    }
+
+    fn is_down(&self) -> bool {
+        self.logbot.is_down()
+    }
+}
+
+/// Execute a [`Mission`] started by a [`Command`], reporting each step
+///
+/// A [`Command::Stop`] cancels the running step and ends the mission. Other
+/// requests are answered between steps, where they are denied. The battery is
+/// read throughout, so once it turns critical
+/// [`watch_power`](power::watch_power) cancels the running step. Returns the
+/// [`JobState`] of the finished mission.
+fn run_mission<L>(
+    logbot: &mut L,
+    channel: &mut Requests,
+    sampler: &mut Sampler,
+    command: Command,
+    mission: &Mission,
+) -> Result<JobState, HardwareError<L>>
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    // Only stops sent from now on cancel the mission
+    let cancel = channel.cancel.clone();
+    cancel.reset();
+    sampler.report(logbot, command.clone(), None);
+
+    let steps = mission.steps().len();
+    let mut state = JobState::Succeeded;
+    let mut stopped = None;
+    let shared = RefCell::new(&mut *sampler);
+    let mut sampled = Sampled {
+        logbot: &mut *logbot,
+        sampler: &shared,
+    };
+    let result = mission::run(&mut sampled, mission, &cancel, |number, step| {
+        shared.borrow().progress(Some(MissionStep {
+            number,
+            steps,
+            phase: step.phase(),
+            description: step.to_string(),
+        }));
+
+        while let Ok((request, _, response)) = channel.try_recv() {
+            match request {
+                Command::Stop => {
+                    // Answered once the mission is no longer reported
+                    stopped = Some(response);
+                    state = JobState::Cancelled;
+                    return ControlFlow::Break(());
+                }
+                _ => {
+                    response.send(Err(CommandDenied::Busy(command.clone())));
+                }
+            };
+        }
+        ControlFlow::Continue(())
+    });
+    let cancelled = match result {
+        Err(LogbotError::Cancelled) => true,
+        result => result.map(|()| false)?,
+    };
+
+    sampler.progress(None);
+    sampler.report(logbot, Command::Stop, Some(VehicleDirection::STOP));
+    if let Some(response) = stopped {
+        response.send(Ok(command.clone()));
+    };
+    if cancelled {
+        // Answer the stop that cancelled the running step
+        if let Ok((_, _, response)) = channel.stop.try_recv() {
+            response.send(Ok(command));
+        };
+        state = JobState::Cancelled;
+    };
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use calibration::SensorCalibration;
+    use components::sim::{Obstacle, SharedWorld, SimRangeFinder, World};
+    use defaults::SimLogbot;
+    use directions::VehicleDirection;
+    use tokio::sync::{broadcast, watch};
+    use tuning::Tuning;
+
+    use super::{Command, CommandDenied, HardwareThread, Obstacles, Speed};
+    use crate::{
+        jobs::{JobState, Jobs},
+        telemetry::{LiftPosition, Status},
+    };
+
+    /// Spawn a [`HardwareThread`] driving a simulated logbot
+    fn spawn() -> (HardwareThread<SimLogbot>, watch::Sender<Status>) {
+        spawn_in(World::shared(), None)
+    }
+
+    /// Spawn a [`HardwareThread`] driving a simulated logbot in a world,
+    /// avoiding [`Obstacles`]
+    fn spawn_in(
+        world: SharedWorld,
+        obstacles: Option<Obstacles>,
+    ) -> (HardwareThread<SimLogbot>, watch::Sender<Status>) {
+        let logbot = defaults::sim(world);
+        let status = watch::Sender::new(Status::idle(&logbot));
+        let thread = HardwareThread::spawn(
+            logbot,
+            watch::Sender::new(Tuning::SLOW).subscribe(),
+            broadcast::channel(1).0,
+            status.clone(),
+            watch::Sender::new(None),
+            obstacles,
+            None,
+            Arc::new(Jobs::default()),
+            Duration::from_secs(1),
+        );
+        (thread, status)
+    }
+
+    /// Test that commands are denied until the commands they require ran
+    #[tokio::test(flavor = "multi_thread")]
+    async fn deny_commands() {
+        let (thread, _status) = spawn();
+
+        let response = thread.send(Command::FollowLine).await.unwrap();
+        assert_eq!(
+            response.result,
+            Err(CommandDenied::Required(Command::FindEdge))
+        );
+        assert_eq!(response.job, None);
+
+        let response = thread.send(Command::FindEdge).await.unwrap();
+        assert_eq!(
+            response.result,
+            Err(CommandDenied::Required(Command::Calibrate))
+        );
+
+        let response = thread.send(Command::Pause).await.unwrap();
+        assert_eq!(response.result, Err(CommandDenied::Idle));
+    }
+
+    /// Test that driving is published in the status and ended by a stop
+    #[tokio::test(flavor = "multi_thread")]
+    async fn drive_and_stop() {
+        let (thread, status) = spawn();
+        let direction: VehicleDirection = "forward:0.5".parse().unwrap();
+
+        let response = thread.send(Command::Drive(direction)).await.unwrap();
+        assert_eq!(response.result, Ok(Command::Stop));
+        assert_eq!(status.borrow().direction, Some(direction));
+
+        thread.send(Command::Stop).await.unwrap();
+        assert_eq!(status.borrow().direction, Some(VehicleDirection::STOP));
+    }
+
+    /// Test that the lift moves its full travel at a given speed
+    #[tokio::test(flavor = "multi_thread")]
+    async fn lift_at_speed() {
+        let (thread, status) = spawn();
+        let mut status = status.subscribe();
+
+        let response = thread
+            .send(Command::LiftUp(Some(Speed::MAX)))
+            .await
+            .unwrap();
+        assert_eq!(response.result, Ok(Command::LiftUp(Some(Speed::MAX))));
+        assert!(response.job.is_some());
+
+        status
+            .wait_for(|status| status.lift == LiftPosition::Up)
+            .await
+            .unwrap();
+    }
+
+    /// Test that a stop cancels the running step of the demo
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancel_demo() {
+        let (thread, status) = spawn();
+        let mut status = status.subscribe();
+
+        let response = thread.send(Command::Demo).await.unwrap();
+        assert_eq!(response.result, Ok(Command::Stop));
+        status
+            .wait_for(|status| status.mission.is_some())
+            .await
+            .unwrap();
+
+        // Calibrating takes far longer than the timeout
+        let response = tokio::time::timeout(Duration::from_secs(2), thread.send(Command::Stop))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.result, Ok(Command::Demo));
+        assert_eq!(status.borrow().mission, None);
+    }
+
+    /// Test that the self-test passes on the simulated components
+    #[tokio::test(flavor = "multi_thread")]
+    async fn diagnose() {
+        let (thread, _status) = spawn();
+
+        let diagnostics = thread.diagnose(None).await.unwrap().unwrap();
+        assert!(diagnostics.healthy);
+        assert_eq!(diagnostics.battery, None);
+    }
+
+    /// Test that logbot stands still in front of an obstacle on the line
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stop_for_obstacle() {
+        let world = World::shared();
+        let start = world.pose();
+        world.lock().place_obstacle(Obstacle {
+            x: start.x + 0.2,
+            y: 0.0,
+            radius: 0.05,
+        });
+        let obstacles = Obstacles::new(SimRangeFinder::new(world.clone()));
+        let (thread, status) = spawn_in(world, Some(obstacles));
+
+        let calibration = SensorCalibration::new(200, 40);
+        let response = thread
+            .send(Command::RestoreCalibration(calibration))
+            .await
+            .unwrap();
+        assert_eq!(response.result, Ok(Command::Stop));
+        let response = thread.send(Command::FindEdge).await.unwrap();
+        let state = thread.jobs.wait(response.job.unwrap()).await;
+        assert_eq!(state, Some(JobState::Succeeded));
+
+        let response = thread.send(Command::FollowLine).await.unwrap();
+        assert_eq!(response.result, Ok(Command::Stop));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(status.borrow().command, "FollowLine");
+        assert_eq!(status.borrow().direction, Some(VehicleDirection::STOP));
+
+        thread.send(Command::Stop).await.unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+//! Timeline of the commands sent to the hardware
+//!
+//! Every [`Command`] is recorded when the [`HardwareThread`] responds to it.
+//! Accepted commands are kept in an [`EventList`], where each of them lasts
+//! until the next accepted command or until it finishes on its own. Commands
+//! that take effect at once without moving logbot, and denied commands
+//! together with the reason they were denied, are kept next to it. Only the
+//! last [`CAPACITY`] sequences of accepted commands and the last [`CAPACITY`]
+//! other commands are kept.
+//!
+//! [`HardwareThread`]: crate::hardware::HardwareThread
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use event_list::EventList;
+use serde::Serialize;
+
+use crate::hardware::{Command, CommandDenied};
+
+/// How many sequences of accepted commands and other commands are kept
+pub const CAPACITY: usize = 256;
+
+/// Outcome of a recorded [`Command`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Outcome {
+    /// The command was accepted
+    Accepted {
+        /// How long the command ran in milliseconds, none while it is running
+        duration_ms: Option<u64>,
+    },
+    /// The command was denied
+    Denied {
+        /// Why the command was denied
+        reason: String,
+    },
+}
+
+/// A recorded [`Command`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    /// Time of the response in milliseconds since the Unix epoch
+    pub time_ms: u64,
+    /// The recorded command
+    pub command: &'static str,
+    /// Outcome of the command
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+/// An accepted [`Command`] in the [`EventList`]
+#[derive(Debug, Clone, Copy)]
+struct Accepted {
+    /// Time of the response in milliseconds since the Unix epoch
+    time_ms: u64,
+    /// The accepted command
+    command: &'static str,
+}
+
+/// The recorded commands
+#[derive(Debug, Default)]
+struct Timeline {
+    /// Commands that ran for a duration
+    accepted: EventList<Accepted>,
+    /// Commands without a duration, oldest first
+    other: VecDeque<Entry>,
+}
+
+impl Timeline {
+    /// Record a command without a duration
+    fn push_other(&mut self, entry: Entry) {
+        self.other.push_back(entry);
+        if self.other.len() > CAPACITY {
+            self.other.pop_front();
+        };
+    }
+}
+
+/// History of the last commands sent to the hardware
+#[derive(Debug, Default)]
+pub struct History {
+    /// The recorded commands
+    timeline: Mutex<Timeline>,
+}
+
+impl History {
+    /// Access the timeline, even if another thread panicked while accessing it
+    fn lock(&self) -> std::sync::MutexGuard<'_, Timeline> {
+        self.timeline.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record an accepted [`Command`], ending the previous one
+    pub fn accept(&self, command: &Command) {
+        let mut timeline = self.lock();
+        let time_ms = now_ms();
+        match command {
+            // Restoring the calibration does not interrupt driving
+            Command::RestoreCalibration(_) => timeline.push_other(Entry {
+                time_ms,
+                command: command.as_str(),
+                outcome: Outcome::Accepted {
+                    duration_ms: Some(0),
+                },
+            }),
+            _ => {
+                timeline.accepted.push(Accepted {
+                    time_ms,
+                    command: command.as_str(),
+                });
+                // Logbot is standing still right after stopping
+                if *command == Command::Stop {
+                    timeline.accepted.complete();
+                };
+                timeline.accepted.truncate_front(CAPACITY);
+            }
+        };
+    }
+
+    /// Record a denied [`Command`]
+    pub fn deny(&self, command: &Command, reason: &CommandDenied) {
+        self.lock().push_other(Entry {
+            time_ms: now_ms(),
+            command: command.as_str(),
+            outcome: Outcome::Denied {
+                reason: reason.to_string(),
+            },
+        });
+    }
+
+    /// End the running command, which finished on its own
+    pub fn finish(&self) {
+        let mut timeline = self.lock();
+        timeline.accepted.complete();
+        timeline.accepted.truncate_front(CAPACITY);
+    }
+
+    /// The recorded commands responded to between two times in milliseconds
+    /// since the Unix epoch, oldest first
+    pub fn entries(&self, from: Option<u64>, to: Option<u64>) -> Vec<Entry> {
+        let timeline = self.lock();
+        let completed = timeline.accepted.events().map(|event| Entry {
+            time_ms: event.data.time_ms,
+            command: event.data.command,
+            outcome: Outcome::Accepted {
+                duration_ms: Some(event.elapsed_time.as_millis() as u64),
+            },
+        });
+        let running = timeline
+            .accepted
+            .active_event()
+            .as_ref()
+            .map(|event| Entry {
+                time_ms: event.data.time_ms,
+                command: event.data.command,
+                outcome: Outcome::Accepted { duration_ms: None },
+            });
+
+        let mut entries: Vec<Entry> = completed
+            .chain(running)
+            .chain(timeline.other.iter().cloned())
+            .filter(|entry| {
+                from.is_none_or(|from| entry.time_ms >= from)
+                    && to.is_none_or(|to| entry.time_ms <= to)
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.time_ms);
+        entries
+    }
+}
+
+/// The current time in milliseconds since the Unix epoch
+fn now_ms() -> u64 {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO);
+    elapsed.as_millis() as u64
+}
@@ -0,0 +1,136 @@
+//! Registry of long-running jobs
+//!
+//! Commands that keep the hardware busy, such as calibrating or following the
+//! line, are responded to as soon as they start. Each of them is assigned a
+//! [`JobId`], which can be polled for its [`JobState`] or
+//! [waited](Jobs::wait) for until the job finishes. Only the last
+//! [`CAPACITY`] jobs are kept.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::hardware::Command;
+
+/// How many jobs are kept before the oldest ones are forgotten
+pub const CAPACITY: usize = 64;
+
+/// Identifier of a job, counting up from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JobId(u64);
+
+/// State of a job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobState {
+    /// The job is still running
+    Running,
+    /// The job finished
+    Succeeded,
+    /// The job was stopped before it finished
+    Cancelled,
+    /// The hardware failed while running the job
+    Failed {
+        /// Description of the failure
+        error: String,
+    },
+}
+
+/// A job started by a [`Command`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Job {
+    /// Identifier of the job
+    pub id: JobId,
+    /// The command that started the job
+    pub command: &'static str,
+    /// State of the job
+    #[serde(flatten)]
+    pub state: JobState,
+}
+
+/// Registry of the last [`CAPACITY`] jobs
+#[derive(Debug, Default)]
+pub struct Jobs {
+    /// The jobs by their identifier
+    jobs: Mutex<BTreeMap<JobId, Job>>,
+    /// Notified whenever jobs finish
+    finished: Notify,
+}
+
+impl Jobs {
+    /// Access the jobs, even if another thread panicked while accessing them
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<JobId, Job>> {
+        self.jobs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Register a running job started by a [`Command`]
+    pub fn start(&self, command: &Command) -> JobId {
+        let mut jobs = self.lock();
+        let id = JobId(jobs.last_key_value().map_or(1, |(JobId(last), _)| last + 1));
+        jobs.insert(
+            id,
+            Job {
+                id,
+                command: command.as_str(),
+                state: JobState::Running,
+            },
+        );
+        while jobs.len() > CAPACITY {
+            jobs.pop_first();
+        }
+        id
+    }
+
+    /// Finish a running job, doing nothing without a job
+    pub fn finish(&self, id: Option<JobId>, state: JobState) {
+        let mut jobs = self.lock();
+        if let Some(job) = id.and_then(|id| jobs.get_mut(&id)) {
+            if job.state == JobState::Running {
+                job.state = state;
+            };
+        };
+        self.finished.notify_waiters();
+    }
+
+    /// Fail every running job
+    pub fn fail_running(&self, error: &str) {
+        for job in self.lock().values_mut() {
+            if job.state == JobState::Running {
+                job.state = JobState::Failed {
+                    error: error.to_string(),
+                };
+            };
+        }
+        self.finished.notify_waiters();
+    }
+
+    /// Forget a job, which never started
+    pub fn remove(&self, id: JobId) {
+        self.lock().remove(&id);
+    }
+
+    /// The [`Job`] with an identifier, if it is still known
+    pub fn get(&self, id: JobId) -> Option<Job> {
+        self.lock().get(&id).cloned()
+    }
+
+    /// Wait until the job with an identifier finishes, returning the
+    /// [`JobState`] it finished in
+    ///
+    /// Returns [None](`Option::None`) once the job is no longer known.
+    pub async fn wait(&self, id: JobId) -> Option<JobState> {
+        loop {
+            // Registered before checking, so no finish is missed
+            let finished = self.finished.notified();
+            match self.get(id)?.state {
+                JobState::Running => finished.await,
+                state => return Some(state),
+            };
+        }
+    }
+}
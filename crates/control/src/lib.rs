@@ -0,0 +1,25 @@
+//! Actor controlling the logbot hardware
+//!
+//! The [`HardwareThread`] owns logbot and executes the [`Command`]s sent to
+//! it one at a time, so every frontend, be it the REST API, the CLI or a
+//! test, drives logbot through the same loop. Alongside, it tracks
+//! long-running commands as [`jobs`], records a [`history`] of the commands,
+//! publishes [`telemetry`] and runs the
+//! [self-test](logbot::self_test) of the components.
+//! Ordered [`batch`]es of commands are validated as a whole before they are
+//! executed, and the [`scheduler`] queues missions across restarts. The
+//! [`power`] of the battery derates the speed and stops logbot once critical.
+
+// https://github.com/rust-lang/rust/issues/95513
+#![allow(unused_crate_dependencies)]
+
+pub mod batch;
+pub mod hardware;
+pub mod history;
+pub mod jobs;
+pub mod power;
+pub mod scheduler;
+pub mod telemetry;
+pub mod watchdog;
+
+pub use hardware::{Command, CommandDenied, CommandResult, HardwareThread, Obstacles};
@@ -0,0 +1,395 @@
+//! Battery management and low-power behavior
+//!
+//! With a [`BatteryConfig`] the [`HardwareThread`] reads the battery voltage
+//! every [`INTERVAL`] into a [`Power`] monitor, which keeps the readings of
+//! the last [`PowerConfig::window`]. From them it estimates how long the
+//! battery lasts until it is critical, and derates the speed of logbot while
+//! the voltage sags below [`PowerConfig::derate_volts`]. The
+//! [`PowerStatus`] is published as part of the [`Status`], e.g.
+//!
+//! ```json
+//! {"volts":7.2,"level":"Derated","speed_limit":0.875,"runtime_s":840}
+//! ```
+//!
+//! Once the voltage drops below [`PowerConfig::critical_volts`], the level
+//! latches at [`PowerLevel::Critical`] until the battery is charged or
+//! swapped: [`watch_power`] stops whatever logbot is doing and commands that
+//! move logbot are denied with [`CommandDenied::Battery`].
+//!
+//! [`HardwareThread`]: crate::hardware::HardwareThread
+//! [`CommandDenied::Battery`]: crate::hardware::CommandDenied::Battery
+
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use consts::config::BatteryConfig;
+use demo::{
+    cancel::CancelToken,
+    mission::{Mission, MissionError},
+};
+use interfaces::SensorRead;
+use serde::Serialize;
+use speed::Speed;
+use tokio::sync::{mpsc, oneshot, watch};
+use tuning::Tuning;
+
+use crate::{
+    hardware::{Command, Request, Responder},
+    history::History,
+    telemetry::Status,
+};
+
+/// Time between two readings of the battery voltage
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Readings averaged into the current voltage, smoothing out the sag of
+/// short bursts of load
+const SMOOTHING: Duration = Duration::from_secs(5);
+
+/// Thresholds of the battery voltage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerConfig {
+    /// Voltage of a fully charged battery
+    pub full_volts: f64,
+    /// Voltage below which the speed is derated
+    pub derate_volts: f64,
+    /// Voltage below which logbot is stopped
+    pub critical_volts: f64,
+    /// Share of the speed left right above the critical voltage
+    pub min_speed: Speed,
+    /// Time the readings are kept to estimate the remaining runtime
+    pub window: Duration,
+}
+
+impl Default for PowerConfig {
+    /// Thresholds of a 2S lithium polymer battery
+    fn default() -> Self {
+        Self {
+            full_volts: 8.4,
+            derate_volts: 7.4,
+            critical_volts: 6.6,
+            min_speed: Speed::HALF,
+            window: Duration::from_secs(120),
+        }
+    }
+}
+
+/// How much charge the battery has left
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PowerLevel {
+    /// Logbot runs at full speed
+    Normal,
+    /// The voltage sags, logbot runs at a derated speed
+    Derated,
+    /// The battery is nearly empty, logbot is stopped
+    Critical,
+}
+
+/// The state of the battery published to the clients
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PowerStatus {
+    /// The smoothed battery voltage
+    pub volts: f64,
+    /// How much charge is left
+    pub level: PowerLevel,
+    /// Share of the speed logbot is allowed to drive at
+    pub speed_limit: Speed,
+    /// Estimated seconds until the battery is critical, none until the
+    /// voltage is seen dropping
+    pub runtime_s: Option<u64>,
+}
+
+/// Monitor of the battery voltage over time
+#[derive(Debug, Clone)]
+pub struct Power {
+    /// Where the battery voltage is read from
+    battery: BatteryConfig,
+    /// Thresholds of the voltage
+    config: PowerConfig,
+    /// Voltages read within the window, oldest first
+    readings: VecDeque<(Instant, f64)>,
+    /// Whether the battery was critical since it was last charged
+    critical: bool,
+}
+
+impl Power {
+    /// Create a [`Power`] monitor of the battery read with a [`BatteryConfig`]
+    pub fn new(battery: BatteryConfig, config: PowerConfig) -> Self {
+        Self {
+            battery,
+            config,
+            readings: VecDeque::new(),
+            critical: false,
+        }
+    }
+
+    /// Read the battery voltage if the last reading is at least [`INTERVAL`]
+    /// old, returning the updated [`PowerStatus`]
+    pub fn sample<L>(&mut self, logbot: &mut L) -> Result<Option<PowerStatus>, L::Error>
+    where
+        L: SensorRead<Output = u8>,
+    {
+        let now = Instant::now();
+        let due = self
+            .readings
+            .back()
+            .is_none_or(|&(last, _)| now.duration_since(last) >= INTERVAL);
+        if !due {
+            return Ok(None);
+        };
+
+        let volts = self.battery.volts(logbot.read(self.battery)?);
+        self.record(now, volts);
+        Ok(self.status())
+    }
+
+    /// Record a voltage read at an [`Instant`], forgetting readings older
+    /// than the window
+    pub fn record(&mut self, at: Instant, volts: f64) {
+        self.readings.push_back((at, volts));
+        while self
+            .readings
+            .front()
+            .is_some_and(|&(read, _)| at.saturating_duration_since(read) > self.config.window)
+        {
+            self.readings.pop_front();
+        }
+
+        // A charged or swapped battery recovers from being critical
+        match self.volts() {
+            Some(volts) if volts < self.config.critical_volts => self.critical = true,
+            Some(volts) if volts >= self.config.derate_volts => self.critical = false,
+            _ => {}
+        };
+    }
+
+    /// The battery voltage averaged over the latest readings, none before
+    /// the first reading
+    pub fn volts(&self) -> Option<f64> {
+        let &(last, _) = self.readings.back()?;
+        let (sum, count) = self
+            .readings
+            .iter()
+            .rev()
+            .take_while(|&&(read, _)| last.duration_since(read) <= SMOOTHING)
+            .fold((0.0, 0), |(sum, count), &(_, volts)| {
+                (sum + volts, count + 1)
+            });
+        Some(sum / count as f64)
+    }
+
+    /// How much charge the battery has left
+    pub fn level(&self) -> Option<PowerLevel> {
+        let volts = self.volts()?;
+        Some(match volts {
+            _ if self.critical => PowerLevel::Critical,
+            volts if volts < self.config.derate_volts => PowerLevel::Derated,
+            _ => PowerLevel::Normal,
+        })
+    }
+
+    /// Share of the speed logbot is allowed to drive at, decreasing from full
+    /// speed at the derate voltage to the minimum speed at the critical
+    /// voltage and none once critical
+    pub fn speed_limit(&self) -> Speed {
+        let config = &self.config;
+        match (self.level(), self.volts()) {
+            (Some(PowerLevel::Critical), _) => Speed::MIN,
+            (Some(PowerLevel::Derated), Some(volts)) => {
+                let sag =
+                    (config.derate_volts - volts) / (config.derate_volts - config.critical_volts);
+                let min = config.min_speed.value();
+                Speed::new_clamp(1.0 - (1.0 - min) * sag)
+            }
+            _ => Speed::MAX,
+        }
+    }
+
+    /// Estimated time until the battery is critical, from the rate the
+    /// voltage dropped at within the window
+    ///
+    /// None until the readings span at least a tenth of the window and show
+    /// the voltage dropping.
+    pub fn runtime(&self) -> Option<Duration> {
+        let &(first, _) = self.readings.front()?;
+        let &(last, _) = self.readings.back()?;
+        if last.duration_since(first) < self.config.window / 10 {
+            return None;
+        };
+
+        // Least squares fit of the voltage over the seconds since the first reading
+        let points = self
+            .readings
+            .iter()
+            .map(|&(read, volts)| (read.duration_since(first).as_secs_f64(), volts));
+        let n = self.readings.len() as f64;
+        let (t, v, tt, tv) = points.fold((0.0, 0.0, 0.0, 0.0), |(t, v, tt, tv), (x, y)| {
+            (t + x, v + y, tt + x * x, tv + x * y)
+        });
+        let slope = (n * tv - t * v) / (n * tt - t * t);
+        if slope.is_nan() || slope >= 0.0 {
+            return None;
+        };
+
+        let margin = (self.volts()? - self.config.critical_volts).max(0.0);
+        Duration::try_from_secs_f64(margin / -slope).ok()
+    }
+
+    /// The [`PowerStatus`] published to the clients, none before the first
+    /// reading
+    pub fn status(&self) -> Option<PowerStatus> {
+        Some(PowerStatus {
+            volts: self.volts()?,
+            level: self.level()?,
+            speed_limit: self.speed_limit(),
+            runtime_s: self.runtime().map(|runtime| runtime.as_secs()),
+        })
+    }
+}
+
+/// Derate the driving speeds of a [`Tuning`] to a speed limit
+///
+/// The lift is not derated, since it has to carry its load regardless.
+pub fn derate(tuning: Tuning, limit: Speed) -> Tuning {
+    let mut tuning = tuning;
+    tuning.speed = tuning.speed * limit;
+    tuning.turn_speed = tuning.turn_speed * limit;
+    tuning.calibrate.speed = tuning.calibrate.speed * limit;
+    tuning.find_edge.speed = tuning.find_edge.speed * limit;
+    tuning
+}
+
+/// Derate a [`Mission`] to a speed limit, its [`Tuning`] like [`derate`] and
+/// the directions given to its steps alike
+///
+/// Fails like [`Mission::new`] once a step can no longer be executed, i.e.
+/// spinning by an angle when derated to a standstill.
+pub fn derate_mission(mission: &Mission, limit: Speed) -> Result<Mission, MissionError> {
+    let steps = mission
+        .steps()
+        .iter()
+        .map(|step| step.scaled(limit))
+        .collect();
+    Mission::new(steps, derate(*mission.tuning(), limit))
+}
+
+/// Whether the battery of a [`Status`] is critical
+fn is_critical(status: &Status) -> bool {
+    status
+        .power
+        .is_some_and(|power| power.level == PowerLevel::Critical)
+}
+
+/// Stop logbot whenever the battery turns critical while it isn't idle,
+/// cancelling the running step of the demo or a mission like
+/// [`HardwareThread::send`](crate::hardware::HardwareThread::send) does
+///
+/// Returns once the [`HardwareThread`](crate::hardware::HardwareThread) is
+/// no longer running.
+pub async fn watch_power(
+    mut status: watch::Receiver<Status>,
+    channel: mpsc::Sender<Request>,
+    cancel: CancelToken,
+    history: Arc<History>,
+) {
+    loop {
+        let (critical, idle) = {
+            let status = status.borrow_and_update();
+            (
+                is_critical(&status),
+                status.command == Command::Stop.as_str(),
+            )
+        };
+        if critical && !idle {
+            tracing::error!("Battery is critical, stopping");
+            let (sender, _) = oneshot::channel();
+            let responder = Responder::new(Command::Stop, sender, Arc::clone(&history));
+            if channel
+                .send((Command::Stop, None, responder))
+                .await
+                .is_err()
+            {
+                return;
+            };
+            cancel.cancel();
+        };
+        if status.changed().await.is_err() {
+            return;
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use consts::config::BatteryConfig;
+    use speed::Speed;
+
+    use super::{Power, PowerConfig, PowerLevel};
+
+    /// Create a [`Power`] monitor with the default thresholds
+    fn power() -> Power {
+        let battery = BatteryConfig {
+            channel: 2,
+            volts_per_step: 0.05,
+        };
+        Power::new(battery, PowerConfig::default())
+    }
+
+    /// Test that the speed is derated as the voltage sags and stays stopped
+    /// once critical until charged
+    #[test]
+    fn derate_and_latch() {
+        let mut power = power();
+        let start = Instant::now();
+        assert_eq!(power.status(), None);
+
+        power.record(start, 8.0);
+        assert_eq!(power.level(), Some(PowerLevel::Normal));
+        assert_eq!(power.speed_limit(), Speed::MAX);
+
+        // Halfway between the derate and the critical voltage
+        let mut power = self::power();
+        power.record(start, 7.0);
+        assert_eq!(power.level(), Some(PowerLevel::Derated));
+        assert!((power.speed_limit().value() - 0.75).abs() < 1e-9);
+
+        power.record(start + Duration::from_secs(10), 6.5);
+        assert_eq!(power.level(), Some(PowerLevel::Critical));
+        assert_eq!(power.speed_limit(), Speed::MIN);
+
+        // Recovering without load doesn't end being critical
+        power.record(start + Duration::from_secs(20), 7.0);
+        assert_eq!(power.level(), Some(PowerLevel::Critical));
+
+        power.record(start + Duration::from_secs(30), 8.4);
+        assert_eq!(power.level(), Some(PowerLevel::Normal));
+    }
+
+    /// Test that the runtime is estimated from the voltage dropping
+    #[test]
+    fn estimate_runtime() {
+        let mut power = power();
+        let start = Instant::now();
+
+        // Dropping 0.01V per second from 8.0V
+        for second in 0..60 {
+            power.record(
+                start + Duration::from_secs(second),
+                8.0 - 0.01 * second as f64,
+            );
+        }
+        // The smoothed voltage is 7.43V, 0.83V above the critical voltage
+        let runtime = power.runtime().unwrap().as_secs_f64();
+        assert!((runtime - 83.0).abs() < 1.0, "{runtime}");
+
+        // Only the readings within the window are kept
+        power.record(start + Duration::from_secs(600), 8.4);
+        assert_eq!(power.runtime(), None);
+        assert_eq!(power.volts(), Some(8.4));
+    }
+}
@@ -0,0 +1,348 @@
+//! Queue of missions surviving restarts
+//!
+//! The [`Scheduler`] keeps a [`Schedule`] of missions, executed one after
+//! another, together with the step the first of them reached. Every change
+//! is written to a JSON file before it is acknowledged, so a restart, e.g.
+//! after a power blip mid-shift, continues with the same missions:
+//!
+//! ```json
+//! {
+//!   "next_id": 3,
+//!   "held": false,
+//!   "missions": [
+//!     {"id": 1, "step": 4, "steps": [{"action": "calibrate"}, ...]},
+//!     {"id": 2, "step": null, "steps": [...]}
+//!   ]
+//! }
+//! ```
+//!
+//! A mission that was interrupted [resumes](Scheduled::resume) at the step
+//! it reached. The calibration is lost on a restart, so logbot calibrates
+//! again first if the mission calibrated before that step. When a mission
+//! fails or is stopped, the schedule is held until it is released, so logbot
+//! doesn't drive off on its own after an operator stopped it.
+
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use demo::mission::{Mission, MissionError, Step};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{futures::Notified, Notify};
+use tuning::Tuning;
+
+/// Identifier of a scheduled mission, counting up from 1
+pub type MissionId = u64;
+
+/// A mission waiting in the [`Schedule`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scheduled {
+    /// Identifier of the mission
+    pub id: MissionId,
+    /// Number of the step the mission reached, counting from 1, none until
+    /// it started
+    pub step: Option<usize>,
+    /// Steps of the mission
+    pub steps: Vec<Step>,
+}
+
+impl Scheduled {
+    /// The steps to execute to finish the mission, starting at the step it
+    /// reached
+    ///
+    /// If the mission calibrated before that step, the steps start by
+    /// calibrating again, and by finding the edge if the step follows the
+    /// line.
+    pub fn resume(&self) -> Resumed {
+        let start = self.step.unwrap_or(1).clamp(1, self.steps.len().max(1)) - 1;
+        let (done, remaining) = self.steps.split_at(start.min(self.steps.len()));
+
+        let mut steps = Vec::new();
+        if done.contains(&Step::Calibrate) {
+            steps.push(Step::Calibrate);
+            if matches!(
+                remaining.first(),
+                Some(Step::Follow | Step::FollowFor(_) | Step::Turn { .. })
+            ) {
+                steps.push(Step::FindEdge);
+            };
+        };
+        let added = steps.len();
+        steps.extend_from_slice(remaining);
+        Resumed {
+            id: self.id,
+            steps,
+            added,
+            start,
+        }
+    }
+}
+
+/// Steps resuming a [`Scheduled`] mission
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resumed {
+    /// Identifier of the mission
+    pub id: MissionId,
+    /// Steps to execute
+    pub steps: Vec<Step>,
+    /// Number of steps added in front of the remaining steps of the mission
+    added: usize,
+    /// Number of steps of the mission skipped since they were done
+    start: usize,
+}
+
+impl Resumed {
+    /// Number of the step of the scheduled mission, given the number of a
+    /// step of the resumed one, both counting from 1
+    ///
+    /// Steps added in front count as the first remaining step.
+    pub fn step(&self, number: usize) -> usize {
+        self.start + number.saturating_sub(self.added).max(1)
+    }
+
+    /// Validate the steps into a [`Mission`] with a [`Tuning`]
+    pub fn mission(&self, tuning: Tuning) -> Result<Mission, MissionError> {
+        Mission::new(self.steps.clone(), tuning)
+    }
+}
+
+/// Missions in the order they are executed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Identifier of the next scheduled mission
+    next_id: MissionId,
+    /// Whether no mission is started until the schedule is released
+    pub held: bool,
+    /// The missions, the first one is running or started next
+    pub missions: VecDeque<Scheduled>,
+}
+
+/// Queue of missions, persisted to a file if it has one
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    /// File the schedule is persisted to
+    path: Option<PathBuf>,
+    /// The missions
+    schedule: Mutex<Schedule>,
+    /// Notified whenever the schedule changes
+    changed: Notify,
+}
+
+impl Scheduler {
+    /// Create a [`Scheduler`] persisted to a file, continuing the schedule
+    /// in it if it exists
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, ScheduleError> {
+        let path = path.into();
+        let schedule = match std::fs::read_to_string(&path) {
+            Ok(value) => serde_json::from_str(&value).map_err(ScheduleError::Json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Schedule::default(),
+            Err(e) => return Err(ScheduleError::Io(e)),
+        };
+        Ok(Self {
+            path: Some(path),
+            schedule: Mutex::new(schedule),
+            changed: Notify::new(),
+        })
+    }
+
+    /// Access the schedule, even if another thread panicked while accessing it
+    fn lock(&self) -> MutexGuard<'_, Schedule> {
+        self.schedule.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Change the schedule, persisting it before anyone is notified
+    ///
+    /// The change is undone if it can't be persisted.
+    fn update<T>(&self, change: impl FnOnce(&mut Schedule) -> T) -> Result<T, ScheduleError> {
+        let mut schedule = self.lock();
+        let mut changed = schedule.clone();
+        let result = change(&mut changed);
+        if let Some(path) = &self.path {
+            persist(path, &changed)?;
+        };
+        *schedule = changed;
+        self.changed.notify_waiters();
+        Ok(result)
+    }
+
+    /// A copy of the [`Schedule`]
+    pub fn schedule(&self) -> Schedule {
+        self.lock().clone()
+    }
+
+    /// Future completing once the schedule changes, registered before
+    /// checking the schedule so no change is missed
+    pub fn changed(&self) -> Notified<'_> {
+        self.changed.notified()
+    }
+
+    /// Queue the steps of a mission after the scheduled ones
+    pub fn push(&self, steps: Vec<Step>) -> Result<MissionId, ScheduleError> {
+        self.update(|schedule| {
+            schedule.next_id = schedule.next_id.max(1);
+            let id = schedule.next_id;
+            schedule.next_id += 1;
+            schedule.missions.push_back(Scheduled {
+                id,
+                step: None,
+                steps,
+            });
+            id
+        })
+    }
+
+    /// Remove a mission, returning whether it was scheduled
+    ///
+    /// Removing the running mission doesn't stop it, only its result is
+    /// ignored.
+    pub fn remove(&self, id: MissionId) -> Result<bool, ScheduleError> {
+        self.update(|schedule| {
+            let before = schedule.missions.len();
+            schedule.missions.retain(|mission| mission.id != id);
+            schedule.missions.len() != before
+        })
+    }
+
+    /// Hold or release the schedule
+    pub fn hold(&self, held: bool) -> Result<(), ScheduleError> {
+        self.update(|schedule| schedule.held = held)
+    }
+
+    /// The mission to execute next, resumed where it was interrupted, none
+    /// while the schedule is held or empty
+    pub fn next(&self) -> Option<Resumed> {
+        let schedule = self.lock();
+        match schedule.held {
+            true => None,
+            false => schedule.missions.front().map(Scheduled::resume),
+        }
+    }
+
+    /// Record that a mission reached a step, counting from 1
+    pub fn progress(&self, id: MissionId, step: usize) -> Result<(), ScheduleError> {
+        if self.lock().missions.front().map(|m| (m.id, m.step)) == Some((id, Some(step))) {
+            return Ok(());
+        };
+        self.update(|schedule| {
+            if let Some(mission) = schedule.missions.iter_mut().find(|m| m.id == id) {
+                mission.step = Some(step);
+            };
+        })
+    }
+
+    /// Record that a mission ended, removing it if it succeeded and holding
+    /// the schedule otherwise
+    pub fn finish(&self, id: MissionId, succeeded: bool) -> Result<(), ScheduleError> {
+        self.update(|schedule| match succeeded {
+            true => schedule.missions.retain(|mission| mission.id != id),
+            false => schedule.held = true,
+        })
+    }
+}
+
+/// Write a [`Schedule`] to a file, replacing the previous one at once so a
+/// power loss never leaves half of it
+fn persist(path: &Path, schedule: &Schedule) -> Result<(), ScheduleError> {
+    let value = serde_json::to_string_pretty(schedule).map_err(ScheduleError::Json)?;
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, value).map_err(ScheduleError::Io)?;
+    std::fs::rename(&partial, path).map_err(ScheduleError::Io)
+}
+
+/// Errors that occur while persisting the [`Schedule`]
+#[derive(Debug)]
+pub enum ScheduleError {
+    /// The schedule file could not be read or written
+    Io(std::io::Error),
+    /// The schedule file is not valid
+    Json(serde_json::Error),
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Json(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for ScheduleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Json(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use demo::{follow::FollowLimit, mission::Step};
+
+    use super::{Scheduled, Scheduler};
+
+    /// Following until the next stop line
+    const FOLLOW: Step = Step::FollowFor(FollowLimit {
+        duration_ms: None,
+        distance_m: None,
+        stop_lines: Some(1),
+    });
+
+    /// Test that a restart continues the missions where they were
+    #[test]
+    fn resume_after_restart() {
+        let path =
+            std::env::temp_dir().join(format!("logbot-schedule-{}.json", std::process::id()));
+        let steps = vec![Step::Calibrate, Step::FindEdge, FOLLOW];
+
+        let scheduler = Scheduler::open(&path).unwrap();
+        let first = scheduler.push(steps.clone()).unwrap();
+        let second = scheduler.push(vec![Step::Calibrate]).unwrap();
+        scheduler.progress(first, 3).unwrap();
+        drop(scheduler);
+
+        let scheduler = Scheduler::open(&path).unwrap();
+        let resumed = scheduler.next().unwrap();
+        assert_eq!(resumed.id, first);
+        assert_eq!(resumed.steps, vec![Step::Calibrate, Step::FindEdge, FOLLOW]);
+        assert_eq!(resumed.step(1), 3);
+        assert_eq!(resumed.step(3), 3);
+
+        scheduler.finish(first, true).unwrap();
+        assert_eq!(scheduler.next().map(|resumed| resumed.id), Some(second));
+        assert!(scheduler.push(steps).unwrap() > second);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Test that a failed mission holds the schedule until it is released
+    #[test]
+    fn hold_after_failure() {
+        let scheduler = Scheduler::default();
+        let id = scheduler.push(vec![Step::Calibrate]).unwrap();
+
+        scheduler.finish(id, false).unwrap();
+        assert!(scheduler.next().is_none());
+        scheduler.hold(false).unwrap();
+        assert_eq!(scheduler.next().map(|resumed| resumed.id), Some(id));
+
+        assert!(scheduler.remove(id).unwrap());
+        assert!(scheduler.next().is_none());
+    }
+
+    /// Test that steps without a calibration before them resume unchanged
+    #[test]
+    fn resume_without_calibration() {
+        let mission = Scheduled {
+            id: 1,
+            step: Some(2),
+            steps: vec![Step::LiftUp { speed: None }, Step::Wait { duration_ms: 10 }],
+        };
+        let resumed = mission.resume();
+        assert_eq!(resumed.steps, vec![Step::Wait { duration_ms: 10 }]);
+        assert_eq!(resumed.step(1), 2);
+    }
+}
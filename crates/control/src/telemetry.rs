@@ -0,0 +1,231 @@
+//! Streaming sensor values to clients
+//!
+//! While any client listens, the [`HardwareThread`](crate::hardware::HardwareThread)
+//! samples both sensors at most every [`INTERVAL`], both while idle and while
+//! following a line, calibrating or finding the edge. The full demo runs
+//! without any sampling. Each sample is streamed as a line of JSON, e.g.
+//!
+//! ```json
+//! {"elapsed_us":1040,"left":38,"right":201}
+//! ```
+//!
+//! Clients of the server-sent events stream receive the same samples at a
+//! rate of their choice, up to [`MAX_RATE`].
+//!
+//! Alongside the samples the [`Status`] of logbot is published whenever it
+//! changes. WebSocket clients receive both combined into a [`Frame`], e.g.
+//!
+//! ```json
+//! {"sensors":{"elapsed_us":1040,"left":38,"right":201},"command":"Drive","direction":{"left":{"Forward":0.2},"right":{"Forward":0.2}},"lift":"Down"}
+//! ```
+//!
+//! Clients of the server-sent events status stream receive only the
+//! [`Status`], including the [`MissionStep`] of a running demo or mission
+//! and, with a battery monitor, the [`PowerStatus`].
+
+use std::time::{Duration, Instant};
+
+use consts::Sensors;
+use directions::VehicleDirection;
+use interfaces::{Lift, SensorRead};
+pub use logbot::snapshot::LiftPosition;
+use serde::Serialize;
+use speed::Speed;
+use tokio::sync::{broadcast, watch};
+
+use crate::{
+    hardware::Command,
+    power::{Power, PowerLevel, PowerStatus},
+};
+
+/// Shortest time between two samples
+pub const INTERVAL: Duration = Duration::from_millis(5);
+
+/// Highest rate in Hz clients can receive samples at, one every [`INTERVAL`]
+pub const MAX_RATE: u32 = 1000 / INTERVAL.as_millis() as u32;
+
+/// How many samples are buffered for each client before it lags behind
+pub const CAPACITY: usize = 1024;
+
+/// Values of both sensors at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TelemetrySample {
+    /// Microseconds since the hardware thread started
+    pub elapsed_us: u64,
+    /// Value of the left sensor
+    pub left: u8,
+    /// Value of the right sensor
+    pub right: u8,
+}
+
+/// Progress of a running mission
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissionStep {
+    /// Number of the running step, counting from 1
+    pub number: usize,
+    /// Number of steps in the mission
+    pub steps: usize,
+    /// Phase logbot is in during the running step, e.g. `following`
+    pub phase: &'static str,
+    /// Description of the running step
+    pub description: String,
+}
+
+/// What logbot is currently doing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Status {
+    /// The [`Command`] being executed, `Stop` while idle
+    pub command: &'static str,
+    /// The direction logbot is driven in, unknown while it oscillates or runs
+    /// the demo
+    pub direction: Option<VehicleDirection>,
+    /// Position of the lift
+    pub lift: LiftPosition,
+    /// The running step while executing a mission
+    pub mission: Option<MissionStep>,
+    /// The state of the battery, none without a battery monitor
+    pub power: Option<PowerStatus>,
+}
+
+impl Status {
+    /// The [`Status`] of an idle logbot
+    pub fn idle<L: Lift>(logbot: &L) -> Self {
+        Self {
+            command: Command::Stop.as_str(),
+            direction: Some(VehicleDirection::STOP),
+            lift: LiftPosition::of(logbot),
+            mission: None,
+            power: None,
+        }
+    }
+}
+
+/// The latest sample and [`Status`], sent to WebSocket clients
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Frame {
+    /// The latest sample, none until the first sample is taken
+    pub sensors: Option<TelemetrySample>,
+    /// What logbot is currently doing
+    #[serde(flatten)]
+    pub status: Status,
+}
+
+/// Samples the sensors and publishes the [`Status`] for the clients
+/// listening to the telemetry
+#[derive(Debug)]
+pub struct Sampler {
+    /// Sender broadcasting samples to the clients
+    sender: broadcast::Sender<TelemetrySample>,
+    /// Sender publishing the [`Status`] to the clients
+    status: watch::Sender<Status>,
+    /// [`Instant`] the samples are timed from
+    start: Instant,
+    /// [`Instant`] of the last sample
+    last: Option<Instant>,
+    /// Monitor of the battery voltage, if the battery can be read
+    power: Option<Power>,
+}
+
+impl Sampler {
+    /// Create a [`Sampler`] broadcasting samples to the receivers of `sender`
+    /// and publishing the [`Status`] to the receivers of `status`, including
+    /// the battery monitored by `power`
+    pub fn new(
+        sender: broadcast::Sender<TelemetrySample>,
+        status: watch::Sender<Status>,
+        power: Option<Power>,
+    ) -> Self {
+        Self {
+            sender,
+            status,
+            start: Instant::now(),
+            last: None,
+            power,
+        }
+    }
+
+    /// Share of the speed logbot is allowed to drive at, full speed without a
+    /// battery monitor
+    pub fn speed_limit(&self) -> Speed {
+        self.power
+            .as_ref()
+            .map_or(Speed::MAX, |power| power.speed_limit())
+    }
+
+    /// Whether the battery is critical
+    pub fn is_critical(&self) -> bool {
+        self.power
+            .as_ref()
+            .is_some_and(|power| power.level() == Some(PowerLevel::Critical))
+    }
+
+    /// Publish the [`Command`] being executed and the direction logbot is
+    /// driven in
+    pub fn report<L: Lift>(
+        &self,
+        logbot: &L,
+        command: Command,
+        direction: Option<VehicleDirection>,
+    ) {
+        let lift = LiftPosition::of(logbot);
+        self.status.send_if_modified(|status| {
+            let modified = (status.command, status.direction, status.lift)
+                != (command.as_str(), direction, lift);
+            (status.command, status.direction, status.lift) = (command.as_str(), direction, lift);
+            modified
+        });
+    }
+
+    /// Publish the running step of a mission
+    pub fn progress(&self, step: Option<MissionStep>) {
+        self.status.send_if_modified(|status| {
+            let modified = status.mission != step;
+            status.mission = step;
+            modified
+        });
+    }
+
+    /// Sample both sensors if any client is listening and the last sample is
+    /// at least [`INTERVAL`] old, publishing changes of the [`LiftPosition`]
+    ///
+    /// The battery is read regardless of the clients, whenever its last
+    /// reading is at least [`power::INTERVAL`](crate::power::INTERVAL) old.
+    pub fn sample<L>(&mut self, logbot: &mut L) -> Result<(), <L as SensorRead>::Error>
+    where
+        L: SensorRead<Output = u8> + Lift,
+    {
+        if let Some(power) = self.power.as_mut() {
+            if let Some(reading) = power.sample(logbot)? {
+                self.status.send_if_modified(|status| {
+                    let modified = status.power != Some(reading);
+                    status.power = Some(reading);
+                    modified
+                });
+            };
+        };
+
+        let due = self.last.is_none_or(|last| last.elapsed() >= INTERVAL);
+        if !due || self.sender.receiver_count() == 0 {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let sample = TelemetrySample {
+            elapsed_us: now.duration_since(self.start).as_micros() as u64,
+            left: logbot.read(Sensors::Left)?,
+            right: logbot.read(Sensors::Right)?,
+        };
+        self.last = Some(now);
+
+        let lift = LiftPosition::of(logbot);
+        self.status.send_if_modified(|status| {
+            let modified = status.lift != lift;
+            status.lift = lift;
+            modified
+        });
+
+        // Clients may disconnect at any time
+        let _ = self.sender.send(sample);
+        Ok(())
+    }
+}
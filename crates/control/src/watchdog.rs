@@ -0,0 +1,74 @@
+//! Heartbeat watchdog for manual driving
+//!
+//! While logbot is driven manually, clients have to refresh a heartbeat,
+//! either explicitly or by sending the direction again, within an interval.
+//! Once the heartbeat lapses the [`HardwareThread`] is sent a
+//! [`Command::Stop`], so a dropped connection can't leave logbot driving.
+//!
+//! [`HardwareThread`]: crate::hardware::HardwareThread
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use directions::VehicleDirection;
+use tokio::sync::{mpsc, oneshot, watch};
+
+use crate::{
+    hardware::{Command, Request, Responder},
+    history::History,
+    telemetry::Status,
+};
+
+/// Interval of the heartbeat when none is configured
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Whether logbot is being driven manually
+fn is_driving(status: &Status) -> bool {
+    status.command == Command::Drive(VehicleDirection::STOP).as_str()
+        && status.direction != Some(VehicleDirection::STOP)
+}
+
+/// Stop logbot whenever the heartbeat lapses while it is driven manually
+///
+/// Returns once the [`HardwareThread`](crate::hardware::HardwareThread) is
+/// no longer running.
+pub async fn watch_heartbeat(
+    interval: Duration,
+    mut heartbeat: watch::Receiver<Instant>,
+    mut status: watch::Receiver<Status>,
+    channel: mpsc::Sender<Request>,
+    history: Arc<History>,
+) {
+    loop {
+        if !is_driving(&status.borrow_and_update()) {
+            if status.changed().await.is_err() {
+                return;
+            };
+            continue;
+        };
+
+        let deadline = *heartbeat.borrow_and_update() + interval;
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline.into()) => {
+                tracing::warn!("Heartbeat lapsed for {:?}, stopping", interval);
+                let (sender, _) = oneshot::channel();
+                let responder = Responder::new(Command::Stop, sender, Arc::clone(&history));
+                if channel.send((Command::Stop, None, responder)).await.is_err() {
+                    return;
+                };
+                // Wait for the stop to be executed
+                if status.changed().await.is_err() {
+                    return;
+                };
+            }
+            changed = heartbeat.changed() => if changed.is_err() {
+                return;
+            },
+            changed = status.changed() => if changed.is_err() {
+                return;
+            },
+        };
+    }
+}
@@ -0,0 +1,237 @@
+//! Builder for assembling a [`Logbot`] from interchangeable components
+
+use std::marker::PhantomData;
+
+use components::hardware_pwm;
+use components::software_pwm::{self, LiftMotor};
+use components::{Left, Right, SensorController};
+use consts::HardwareConfig;
+use interfaces::Drive;
+use logbot::Logbot;
+use vehicle::Vehicle;
+
+use crate::{validate, DefaultError, TryFromConfig};
+
+/// A [`Logbot`] assembled by a [`LogbotAssembler`]
+pub type AssembledLogbot<LM, RM, S, L> = Logbot<Vehicle<LM, RM>, S, L>;
+
+/// Error returned by [`LogbotAssembler::assemble`]
+pub type AssembleError<LM, RM, S, L> =
+    DefaultError<<AssembledLogbot<LM, RM, S, L> as TryFromConfig>::Error>;
+
+/// Result of [`LogbotAssembler::assemble`]
+pub type AssembleResult<LM, RM, S, L> =
+    Result<AssembledLogbot<LM, RM, S, L>, AssembleError<LM, RM, S, L>>;
+
+/// [`LogbotAssembler`] of the stack with software PWM motors
+pub type SoftwarePwmAssembler = LogbotAssembler<
+    software_pwm::DCMotor<Left>,
+    software_pwm::DCMotor<Right>,
+    SensorController,
+    LiftMotor,
+>;
+
+/// [`LogbotAssembler`] of the stack with signed software PWM motors
+pub type SignedAssembler = LogbotAssembler<
+    software_pwm::SignedMotor<Left>,
+    software_pwm::SignedMotor<Right>,
+    SensorController,
+    LiftMotor,
+>;
+
+/// Marker for the component types selected in a [`LogbotAssembler`]
+type Components<LM, RM, S, L> = PhantomData<fn() -> (LM, RM, S, L)>;
+
+/// Builder for a [`Logbot`], choosing the type of every component
+///
+/// The components are selected using type parameters and are created once
+/// the [`Logbot`] is [assembled](Self::assemble). By default hardware PWM
+/// motors, the [`SensorController`] and the [`LiftMotor`] are used, and the
+/// common stacks with [software PWM](Self::software_pwm) and
+/// [signed](Self::signed) motors have presets of their own.
+///
+/// ```no_run
+/// use components::{software_pwm, Left};
+/// use defaults::LogbotAssembler;
+///
+/// let logbot = LogbotAssembler::new()
+///     .left_motor::<software_pwm::DCMotor<Left>>()
+///     .assemble()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct LogbotAssembler<LM, RM, S, L> {
+    /// The [`HardwareConfig`], [loaded](HardwareConfig::load) when [None]
+    config: Option<HardwareConfig>,
+    /// Selected component types
+    components: Components<LM, RM, S, L>,
+}
+
+impl
+    LogbotAssembler<
+        hardware_pwm::DCMotor<Left>,
+        hardware_pwm::DCMotor<Right>,
+        SensorController,
+        LiftMotor,
+    >
+{
+    /// Create a new [`LogbotAssembler`] using the default components
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            components: PhantomData,
+        }
+    }
+
+    /// Create a [`LogbotAssembler`] driving with software PWM instead of the
+    /// hardware PWM channels
+    pub fn software_pwm() -> SoftwarePwmAssembler {
+        Self::new()
+            .left_motor::<software_pwm::DCMotor<Left>>()
+            .right_motor::<software_pwm::DCMotor<Right>>()
+    }
+
+    /// Create a [`LogbotAssembler`] driving motors that take a direction pin
+    /// next to their software PWM pin
+    pub fn signed() -> SignedAssembler {
+        Self::new()
+            .left_motor::<software_pwm::SignedMotor<Left>>()
+            .right_motor::<software_pwm::SignedMotor<Right>>()
+    }
+}
+
+impl Default
+    for LogbotAssembler<
+        hardware_pwm::DCMotor<Left>,
+        hardware_pwm::DCMotor<Right>,
+        SensorController,
+        LiftMotor,
+    >
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<LM, RM, S, L> LogbotAssembler<LM, RM, S, L> {
+    /// Use a given [`HardwareConfig`] instead of [loading](HardwareConfig::load) it
+    pub fn config(self, config: HardwareConfig) -> Self {
+        Self {
+            config: Some(config),
+            ..self
+        }
+    }
+
+    /// Select the type of the left motor
+    pub fn left_motor<T>(self) -> LogbotAssembler<T, RM, S, L> {
+        self.select()
+    }
+
+    /// Select the type of the right motor
+    pub fn right_motor<T>(self) -> LogbotAssembler<LM, T, S, L> {
+        self.select()
+    }
+
+    /// Select the type of the sensors
+    pub fn sensors<T>(self) -> LogbotAssembler<LM, RM, T, L> {
+        self.select()
+    }
+
+    /// Select the type of the lift
+    pub fn lift<T>(self) -> LogbotAssembler<LM, RM, S, T> {
+        self.select()
+    }
+
+    /// Change the component types while keeping the [`HardwareConfig`]
+    fn select<LM2, RM2, S2, L2>(self) -> LogbotAssembler<LM2, RM2, S2, L2> {
+        LogbotAssembler {
+            config: self.config,
+            components: PhantomData,
+        }
+    }
+}
+
+impl<LM, RM, S, L> LogbotAssembler<LM, RM, S, L>
+where
+    LM: Drive + TryFromConfig,
+    RM: Drive + TryFromConfig,
+    S: TryFromConfig,
+    L: TryFromConfig,
+{
+    /// Create all components and assemble them into a [`Logbot`]
+    ///
+    /// The pin assignments are [validated](validate) before any component
+    /// is created.
+    pub fn assemble(self) -> AssembleResult<LM, RM, S, L> {
+        let config = match self.config {
+            Some(config) => config,
+            None => HardwareConfig::load()?,
+        };
+        validate::<AssembledLogbot<LM, RM, S, L>>(&config)?;
+        Logbot::try_from_config(&config).map_err(DefaultError::Hardware)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components::{software_pwm, Left, Right, SensorController};
+    use consts::HardwareConfig;
+    use logbot::no_lift::NoLift;
+
+    use super::{AssembledLogbot, LogbotAssembler};
+    use crate::{validate, DefaultError, PinConflict};
+
+    /// Test that conflicting pins are reported before any hardware is claimed
+    #[test]
+    fn conflicts_fail_before_creation() {
+        let mut config = HardwareConfig::default();
+        config.left_motor.direction_pin = config.lift.up_pin;
+
+        let result = LogbotAssembler::new()
+            .left_motor::<software_pwm::SignedMotor<Left>>()
+            .config(config)
+            .assemble();
+
+        match result {
+            Err(DefaultError::Pins(conflicts)) => assert_eq!(
+                conflicts.0,
+                vec![PinConflict::DuplicatePin(config.lift.up_pin)]
+            ),
+            other => panic!("expected pin conflicts, got {other:?}"),
+        }
+    }
+
+    /// Test that the signed preset claims the direction pins of its motors
+    #[test]
+    fn signed_preset_claims_direction_pins() {
+        let mut config = HardwareConfig::default();
+        config.right_motor.direction_pin = config.lift.up_pin;
+
+        let result = LogbotAssembler::signed().config(config).assemble();
+
+        match result {
+            Err(DefaultError::Pins(conflicts)) => assert_eq!(
+                conflicts.0,
+                vec![PinConflict::DuplicatePin(config.lift.up_pin)]
+            ),
+            other => panic!("expected pin conflicts, got {other:?}"),
+        }
+    }
+
+    /// Test that logbot without a lift doesn't claim the pins of the lift
+    #[test]
+    fn no_lift_claims_no_pins() {
+        let mut config = HardwareConfig::default();
+        config.left_motor.direction_pin = config.lift.up_pin;
+
+        let result = validate::<
+            AssembledLogbot<
+                software_pwm::SignedMotor<Left>,
+                software_pwm::SignedMotor<Right>,
+                SensorController,
+                NoLift,
+            >,
+        >(&config);
+        assert_eq!(result, Ok(()));
+    }
+}
@@ -0,0 +1,57 @@
+//! Errors that occur when creating default hardware components
+
+use std::fmt::Display;
+
+use consts::config::ConfigError;
+
+use crate::PinConflicts;
+
+/// Error of a [`TryDefault`](crate::TryDefault) implementation
+#[derive(Debug)]
+pub enum DefaultError<E> {
+    /// Loading the [`HardwareConfig`](consts::HardwareConfig) failed
+    Config(ConfigError),
+    /// The pin assignments of the [`HardwareConfig`](consts::HardwareConfig)
+    /// conflict
+    Pins(PinConflicts),
+    /// Setting up the hardware component failed
+    Hardware(E),
+}
+
+impl<E> From<ConfigError> for DefaultError<E> {
+    fn from(value: ConfigError) -> Self {
+        Self::Config(value)
+    }
+}
+
+impl<E> From<PinConflicts> for DefaultError<E> {
+    fn from(value: PinConflicts) -> Self {
+        Self::Pins(value)
+    }
+}
+
+impl<E> Display for DefaultError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(e) => e.fmt(f),
+            Self::Pins(e) => e.fmt(f),
+            Self::Hardware(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E> core::error::Error for DefaultError<E>
+where
+    E: core::error::Error,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Config(e) => e.source(),
+            Self::Pins(e) => e.source(),
+            Self::Hardware(e) => e.source(),
+        }
+    }
+}
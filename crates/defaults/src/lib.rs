@@ -1,19 +1,18 @@
 //! Fallible Default trait
 //!
-//! We also implement the trait for some hardware components using the [`consts`] crate
-
-use std::time::Duration;
+//! Hardware components implement [`TryFromConfig`] to be created from a
+//! [`HardwareConfig`]. Every such component also implements [`TryDefault`],
+//! which uses the [`HardwareConfig`] loaded for the current process. The
+//! pin assignments are [validated](validate) before any GPIO is claimed.
 
 use components::hardware_pwm;
 use components::software_pwm;
 use components::software_pwm::LiftMotor;
-use components::{Left, PwmConfig, Right, SensorController};
-use consts::pwm::{LEFT_MOTOR_CHANNEL, RIGHT_MOTOR_CHANNEL};
-use consts::{
-    pins::{self, LEFT_MOTOR_POWER, RIGHT_MOTOR_POWER},
-    FREQUENCY, I2C_SENSOR_ADDRESS,
-};
+use components::{Left, PwmConfig, Right, SensorController, Ultrasonic};
+use consts::config::{MotorConfig, PulseConfig};
+use consts::HardwareConfig;
 use interfaces::Drive;
+use logbot::{error::LogbotError, Logbot};
 use rppal::pwm::Channel;
 use rppal::pwm::{self, Pwm};
 use rppal::{
@@ -23,138 +22,300 @@ use rppal::{
 use vehicle::Vehicle;
 use vehicle::VehicleError;
 
+mod error;
+pub use error::DefaultError;
+
+mod validate;
+pub use validate::{validate, PinConflict, PinConflicts, Resource};
+
+#[cfg(feature = "mock")]
+mod mock;
+
+mod no_lift;
+
+mod assembler;
+pub use assembler::{
+    AssembleError, AssembleResult, AssembledLogbot, LogbotAssembler, SignedAssembler,
+    SoftwarePwmAssembler,
+};
+
+#[cfg(feature = "sim")]
+mod sim;
+#[cfg(feature = "sim")]
+pub use sim::{sim, SimLogbot};
+
 /// Trait for generating fallible [`Default`] implementations
 pub trait TryDefault: Sized {
     /// The [Error](`core::error::Error`)
     type Error;
 
-    /// Generate the default implementation using the [`consts`] crate
+    /// Generate the default implementation using the [`HardwareConfig`]
     fn try_default() -> Result<Self, Self::Error>;
 }
 
-impl TryDefault for software_pwm::SignedMotor<Left> {
-    type Error = gpio::Error;
+/// Trait for fallibly creating hardware from a [`HardwareConfig`]
+pub trait TryFromConfig: Sized {
+    /// The [Error](`core::error::Error`)
+    type Error;
+
+    /// Create the hardware using the values of a [`HardwareConfig`]
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error>;
+
+    /// List the hardware [resources](Resource) claimed when created from a
+    /// [`HardwareConfig`]
+    fn resources(config: &HardwareConfig) -> Vec<Resource>;
+}
+
+/// Anything that can be created from a [`HardwareConfig`] has a default,
+/// which uses the [loaded](HardwareConfig::load) configuration
+impl<T> TryDefault for T
+where
+    T: TryFromConfig,
+{
+    type Error = DefaultError<T::Error>;
 
     fn try_default() -> Result<Self, Self::Error> {
-        let power = Gpio::new()?.get(pins::LEFT_MOTOR_POWER)?.into_output_low();
-        let direction = Gpio::new()?
-            .get(pins::LEFT_MOTOR_DIRECTION)?
-            .into_output_low();
-        let motor = Self::new(power, FREQUENCY, direction);
-        Ok(motor)
+        let config = HardwareConfig::load()?;
+        validate::<T>(&config)?;
+        T::try_from_config(&config).map_err(DefaultError::Hardware)
     }
 }
 
-impl TryDefault for software_pwm::SignedMotor<Right> {
+/// Convert the pulse widths of a [`PulseConfig`] into a [`PwmConfig`]
+fn pwm_config(pulse: &PulseConfig) -> PwmConfig {
+    PwmConfig {
+        period: pulse.period(),
+        stop_pulse_width: pulse.stop_pulse_width(),
+        pulse_width_range: pulse.pulse_width_range(),
+    }
+}
+
+/// Create a [`software_pwm::SignedMotor`] from a [`MotorConfig`]
+fn signed_motor<Side>(config: &MotorConfig) -> gpio::Result<software_pwm::SignedMotor<Side>> {
+    let power = Gpio::new()?.get(config.power_pin)?.into_output_low();
+    let direction = Gpio::new()?.get(config.direction_pin)?.into_output_low();
+    Ok(software_pwm::SignedMotor::new(
+        power,
+        config.frequency,
+        direction,
+    ))
+}
+
+/// Create a [`software_pwm::DCMotor`] from a [`MotorConfig`]
+fn software_dc_motor<Side>(config: &MotorConfig) -> gpio::Result<software_pwm::DCMotor<Side>> {
+    let pin = Gpio::new()?.get(config.power_pin)?.into_output_low();
+    software_pwm::DCMotor::new(pin, pwm_config(&config.software_pwm))
+}
+
+/// Create a [`hardware_pwm::DCMotor`] from a [`MotorConfig`]
+fn hardware_dc_motor<Side>(config: &MotorConfig) -> pwm::Result<hardware_pwm::DCMotor<Side>> {
+    let channel = Channel::try_from(config.pwm_channel)?;
+    let pwm = Pwm::new(channel)?;
+    hardware_pwm::DCMotor::new(pwm, pwm_config(&config.hardware_pwm))
+}
+
+/// Resources claimed by a [`software_pwm::SignedMotor`]
+fn signed_motor_resources(config: &MotorConfig) -> Vec<Resource> {
+    vec![
+        Resource::Gpio(config.power_pin),
+        Resource::Gpio(config.direction_pin),
+    ]
+}
+
+/// Resources claimed by a [`hardware_pwm::DCMotor`]
+fn hardware_dc_motor_resources(config: &MotorConfig) -> Vec<Resource> {
+    vec![Resource::HardwarePwm {
+        channel: config.pwm_channel,
+        pin: config.power_pin,
+    }]
+}
+
+impl TryFromConfig for software_pwm::SignedMotor<Left> {
     type Error = gpio::Error;
 
-    fn try_default() -> Result<Self, Self::Error> {
-        let power = Gpio::new()?.get(pins::RIGHT_MOTOR_POWER)?.into_output_low();
-        let direction = Gpio::new()?
-            .get(pins::RIGHT_MOTOR_DIRECTION)?
-            .into_output_low();
-        let motor = Self::new(power, FREQUENCY, direction);
-        Ok(motor)
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        signed_motor(&config.left_motor)
+    }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        signed_motor_resources(&config.left_motor)
     }
 }
 
-impl TryDefault for software_pwm::DCMotor<Left> {
+impl TryFromConfig for software_pwm::SignedMotor<Right> {
     type Error = gpio::Error;
 
-    fn try_default() -> Result<Self, Self::Error> {
-        let config = PwmConfig {
-            period: Duration::from_millis(20),
-            stop_pulse_width: Duration::from_micros(1500),
-            pulse_width_range: Duration::from_micros(500),
-        };
-        let pin = Gpio::new()?.get(LEFT_MOTOR_POWER)?.into_output_low();
-        let motor = Self::new(pin, config)?;
-        Ok(motor)
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        signed_motor(&config.right_motor)
+    }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        signed_motor_resources(&config.right_motor)
     }
 }
 
-impl TryDefault for software_pwm::DCMotor<Right> {
+impl TryFromConfig for software_pwm::DCMotor<Left> {
     type Error = gpio::Error;
 
-    fn try_default() -> Result<Self, Self::Error> {
-        let config = PwmConfig {
-            period: Duration::from_millis(20),
-            stop_pulse_width: Duration::from_micros(1468),
-            pulse_width_range: Duration::from_micros(500),
-        };
-        let pin = Gpio::new()?.get(RIGHT_MOTOR_POWER)?.into_output_low();
-        let motor = Self::new(pin, config)?;
-        Ok(motor)
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        software_dc_motor(&config.left_motor)
+    }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        vec![Resource::Gpio(config.left_motor.power_pin)]
     }
 }
 
-impl TryDefault for SensorController {
+impl TryFromConfig for software_pwm::DCMotor<Right> {
+    type Error = gpio::Error;
+
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        software_dc_motor(&config.right_motor)
+    }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        vec![Resource::Gpio(config.right_motor.power_pin)]
+    }
+}
+
+impl TryFromConfig for hardware_pwm::DCMotor<Left> {
+    type Error = pwm::Error;
+
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        hardware_dc_motor(&config.left_motor)
+    }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        hardware_dc_motor_resources(&config.left_motor)
+    }
+}
+
+impl TryFromConfig for hardware_pwm::DCMotor<Right> {
+    type Error = pwm::Error;
+
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        hardware_dc_motor(&config.right_motor)
+    }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        hardware_dc_motor_resources(&config.right_motor)
+    }
+}
+
+impl TryFromConfig for SensorController {
     type Error = i2c::Error;
 
-    fn try_default() -> Result<Self, Self::Error> {
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
         let mut i2c = I2c::new()?;
-        i2c.set_slave_address(I2C_SENSOR_ADDRESS)?;
+        i2c.set_slave_address(config.i2c_sensor_address)?;
         Ok(Self::new(i2c))
     }
+
+    fn resources(_config: &HardwareConfig) -> Vec<Resource> {
+        Vec::new()
+    }
 }
 
-impl TryDefault for hardware_pwm::DCMotor<Left> {
-    type Error = pwm::Error;
+impl TryFromConfig for LiftMotor {
+    type Error = gpio::Error;
 
-    fn try_default() -> Result<Self, Self::Error> {
-        let config = PwmConfig {
-            period: Duration::from_millis(20),
-            stop_pulse_width: Duration::from_micros(1480),
-            pulse_width_range: Duration::from_micros(500),
-        };
-        let channel = Channel::try_from(LEFT_MOTOR_CHANNEL)?;
-        let pwm = Pwm::new(channel)?;
-        let motor = Self::new(pwm, config)?;
-        Ok(motor)
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        let lift = &config.lift;
+        let power = Gpio::new()?.get(lift.power_pin)?.into_output_low();
+        let direction = Gpio::new()?.get(lift.direction_pin)?.into_output_low();
+        let up = Gpio::new()?.get(lift.up_pin)?.into_input();
+        let down = Gpio::new()?.get(lift.down_pin)?.into_input();
+
+        Ok(Self::new(power, direction, lift.frequency, up, down))
+    }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        let lift = &config.lift;
+        [
+            lift.power_pin,
+            lift.direction_pin,
+            lift.up_pin,
+            lift.down_pin,
+        ]
+        .into_iter()
+        .map(Resource::Gpio)
+        .collect()
     }
 }
 
-impl TryDefault for hardware_pwm::DCMotor<Right> {
-    type Error = pwm::Error;
+/// The [`Ultrasonic`] range finder is only created when configured
+impl TryFromConfig for Option<Ultrasonic> {
+    type Error = gpio::Error;
 
-    fn try_default() -> Result<Self, Self::Error> {
-        let config = PwmConfig {
-            period: Duration::from_millis(20),
-            stop_pulse_width: Duration::from_micros(1465),
-            pulse_width_range: Duration::from_micros(500),
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        let Some(range_finder) = &config.range_finder else {
+            return Ok(None);
         };
-        let channel = Channel::try_from(RIGHT_MOTOR_CHANNEL)?;
-        let pwm = Pwm::new(channel)?;
-        let motor = Self::new(pwm, config)?;
-        Ok(motor)
+        let trigger = Gpio::new()?
+            .get(range_finder.trigger_pin)?
+            .into_output_low();
+        let echo = Gpio::new()?.get(range_finder.echo_pin)?.into_input();
+
+        Ok(Some(Ultrasonic::new(
+            trigger,
+            echo,
+            range_finder.max_range_m,
+        )))
+    }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        config
+            .range_finder
+            .iter()
+            .flat_map(|range_finder| [range_finder.trigger_pin, range_finder.echo_pin])
+            .map(Resource::Gpio)
+            .collect()
     }
 }
 
-impl<LM, RM> TryDefault for Vehicle<LM, RM>
+impl<LM, RM> TryFromConfig for Vehicle<LM, RM>
 where
-    LM: Drive + TryDefault,
-    RM: Drive + TryDefault,
+    LM: Drive + TryFromConfig,
+    RM: Drive + TryFromConfig,
 {
-    type Error = VehicleError<<LM as TryDefault>::Error, <RM as TryDefault>::Error>;
+    type Error = VehicleError<<LM as TryFromConfig>::Error, <RM as TryFromConfig>::Error>;
 
-    fn try_default() -> Result<Self, Self::Error> {
-        let left = LM::try_default().map_err(VehicleError::Left)?;
-        let right = RM::try_default().map_err(VehicleError::Right)?;
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        let left = LM::try_from_config(config).map_err(VehicleError::Left)?;
+        let right = RM::try_from_config(config).map_err(VehicleError::Right)?;
         Ok(Self::new(left, right))
     }
+
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        let mut resources = LM::resources(config);
+        resources.extend(RM::resources(config));
+        resources
+    }
 }
 
-impl TryDefault for LiftMotor {
-    type Error = gpio::Error;
+impl<V, S, L> TryFromConfig for Logbot<V, S, L>
+where
+    V: TryFromConfig,
+    S: TryFromConfig,
+    L: TryFromConfig,
+{
+    type Error = LogbotError<V::Error, S::Error, L::Error>;
 
-    fn try_default() -> Result<Self, Self::Error> {
-        let power = Gpio::new()?.get(pins::LIFT_MOTOR_POWER)?.into_output_low();
-        let direction = Gpio::new()?
-            .get(pins::LIFT_MOTOR_DIRECTION)?
-            .into_output_low();
-        let up = Gpio::new()?.get(pins::LIFT_UP)?.into_input();
-        let down = Gpio::new()?.get(pins::LIFT_DOWN)?.into_input();
+    fn try_from_config(config: &HardwareConfig) -> Result<Self, Self::Error> {
+        let vehicle = V::try_from_config(config).map_err(LogbotError::Vehicle)?;
+        let sensors = S::try_from_config(config).map_err(LogbotError::Sensor)?;
+        let lift = L::try_from_config(config).map_err(LogbotError::Lift)?;
+        Ok(Logbot::builder()
+            .vehicle(vehicle)
+            .sensors(sensors)
+            .lift(lift)
+            .build())
+    }
 
-        Ok(Self::new(power, direction, FREQUENCY, up, down))
+    fn resources(config: &HardwareConfig) -> Vec<Resource> {
+        let mut resources = V::resources(config);
+        resources.extend(S::resources(config));
+        resources.extend(L::resources(config));
+        resources
     }
 }
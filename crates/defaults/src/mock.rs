@@ -0,0 +1,59 @@
+//! [`TryFromConfig`] implementations for the [mock](components::mock) components
+//!
+//! Mock components ignore the [`HardwareConfig`] and don't claim any
+//! [resources](Resource).
+
+use std::convert::Infallible;
+
+use components::mock::{MockLift, MockMotor, MockRangeFinder, MockSensorController};
+use consts::HardwareConfig;
+
+use crate::{Resource, TryFromConfig};
+
+impl<Side> TryFromConfig for MockMotor<Side> {
+    type Error = Infallible;
+
+    fn try_from_config(_config: &HardwareConfig) -> Result<Self, Self::Error> {
+        Ok(Self::new())
+    }
+
+    fn resources(_config: &HardwareConfig) -> Vec<Resource> {
+        Vec::new()
+    }
+}
+
+impl TryFromConfig for MockSensorController {
+    type Error = Infallible;
+
+    fn try_from_config(_config: &HardwareConfig) -> Result<Self, Self::Error> {
+        Ok(Self::default())
+    }
+
+    fn resources(_config: &HardwareConfig) -> Vec<Resource> {
+        Vec::new()
+    }
+}
+
+impl TryFromConfig for MockLift {
+    type Error = Infallible;
+
+    fn try_from_config(_config: &HardwareConfig) -> Result<Self, Self::Error> {
+        Ok(Self::new())
+    }
+
+    fn resources(_config: &HardwareConfig) -> Vec<Resource> {
+        Vec::new()
+    }
+}
+
+impl TryFromConfig for MockRangeFinder {
+    type Error = Infallible;
+
+    fn try_from_config(_config: &HardwareConfig) -> Result<Self, Self::Error> {
+        Ok(Self::default())
+    }
+
+    fn resources(_config: &HardwareConfig) -> Vec<Resource> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,23 @@
+//! [`TryFromConfig`] implementation for [`NoLift`]
+//!
+//! Logbot without a lift ignores the [`HardwareConfig`] of the lift and
+//! doesn't claim its [resources](Resource).
+
+use std::convert::Infallible;
+
+use consts::HardwareConfig;
+use logbot::no_lift::NoLift;
+
+use crate::{Resource, TryFromConfig};
+
+impl TryFromConfig for NoLift {
+    type Error = Infallible;
+
+    fn try_from_config(_config: &HardwareConfig) -> Result<Self, Self::Error> {
+        Ok(Self)
+    }
+
+    fn resources(_config: &HardwareConfig) -> Vec<Resource> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,25 @@
+//! Preset of a [`Logbot`] built from the [simulated](components::sim) components
+
+use components::{
+    sim::{SharedWorld, SimLift, SimMotor, SimSensors},
+    Left, Right,
+};
+use logbot::Logbot;
+use vehicle::Vehicle;
+
+use crate::AssembledLogbot;
+
+/// A [`Logbot`] driving in a simulated [`World`](components::sim::World)
+pub type SimLogbot = AssembledLogbot<SimMotor<Left>, SimMotor<Right>, SimSensors, SimLift>;
+
+/// Build a [`SimLogbot`] driving in a shared simulation
+pub fn sim(world: SharedWorld) -> SimLogbot {
+    Logbot::builder()
+        .vehicle(Vehicle::new(
+            SimMotor::new(world.clone()),
+            SimMotor::new(world.clone()),
+        ))
+        .sensors(SimSensors::new(world.clone()))
+        .lift(SimLift::new(world))
+        .build()
+}
@@ -0,0 +1,173 @@
+//! Validation of the pin assignments of a [`HardwareConfig`]
+
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use consts::HardwareConfig;
+use rppal::pwm::Channel;
+
+use crate::TryFromConfig;
+
+/// GPIO pins which can output a hardware PWM signal
+const HARDWARE_PWM_PINS: [u8; 4] = [12, 13, 18, 19];
+
+/// A hardware resource claimed when creating a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// A GPIO pin, using its BCM number
+    Gpio(u8),
+    /// A hardware PWM channel outputting on a GPIO pin
+    HardwarePwm {
+        /// The PWM channel
+        channel: u8,
+        /// The GPIO pin the signal is output on
+        pin: u8,
+    },
+}
+
+/// A single problem found when validating pin assignments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinConflict {
+    /// The GPIO pin is claimed by more than one component
+    DuplicatePin(u8),
+    /// The hardware PWM channel is claimed by more than one component
+    DuplicateChannel(u8),
+    /// The hardware PWM channel does not exist
+    InvalidChannel(u8),
+    /// The GPIO pin cannot output a hardware PWM signal
+    InvalidPwmPin(u8),
+}
+
+impl Display for PinConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicatePin(pin) => write!(f, "GPIO pin {pin} is assigned more than once"),
+            Self::DuplicateChannel(channel) => {
+                write!(f, "PWM channel {channel} is assigned more than once")
+            }
+            Self::InvalidChannel(channel) => write!(f, "PWM channel {channel} does not exist"),
+            Self::InvalidPwmPin(pin) => write!(f, "GPIO pin {pin} does not support hardware PWM"),
+        }
+    }
+}
+
+/// All [conflicts](PinConflict) found in a [`HardwareConfig`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinConflicts(pub Vec<PinConflict>);
+
+impl Display for PinConflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid pin configuration: ")?;
+        for (i, conflict) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            conflict.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for PinConflicts {}
+
+/// Check the [resources](Resource) claimed by `T` for a given [`HardwareConfig`]
+///
+/// Every conflict is collected instead of stopping at the first one, so
+/// all problems of a configuration can be fixed at once.
+pub fn validate<T>(config: &HardwareConfig) -> Result<(), PinConflicts>
+where
+    T: TryFromConfig,
+{
+    check_resources(&T::resources(config))
+}
+
+/// Find all conflicts in a list of claimed [resources](Resource)
+fn check_resources(resources: &[Resource]) -> Result<(), PinConflicts> {
+    let mut pins = HashSet::new();
+    let mut channels = HashSet::new();
+    let mut conflicts = Vec::new();
+
+    let mut report = |conflict| {
+        if !conflicts.contains(&conflict) {
+            conflicts.push(conflict);
+        }
+    };
+
+    for resource in resources {
+        let pin = match *resource {
+            Resource::Gpio(pin) => pin,
+            Resource::HardwarePwm { channel, pin } => {
+                if Channel::try_from(channel).is_err() {
+                    report(PinConflict::InvalidChannel(channel));
+                }
+                if !channels.insert(channel) {
+                    report(PinConflict::DuplicateChannel(channel));
+                }
+                if !HARDWARE_PWM_PINS.contains(&pin) {
+                    report(PinConflict::InvalidPwmPin(pin));
+                }
+                pin
+            }
+        };
+
+        if !pins.insert(pin) {
+            report(PinConflict::DuplicatePin(pin));
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(PinConflicts(conflicts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_resources, PinConflict, PinConflicts, Resource};
+
+    /// Test that distinct resources pass validation
+    #[test]
+    fn distinct_resources_are_valid() {
+        let resources = [
+            Resource::Gpio(5),
+            Resource::Gpio(6),
+            Resource::HardwarePwm {
+                channel: 0,
+                pin: 12,
+            },
+            Resource::HardwarePwm {
+                channel: 1,
+                pin: 13,
+            },
+        ];
+        assert_eq!(check_resources(&resources), Ok(()));
+    }
+
+    /// Test that every conflict is reported, not only the first
+    #[test]
+    fn conflicts_are_aggregated() {
+        let resources = [
+            Resource::Gpio(5),
+            Resource::Gpio(5),
+            Resource::Gpio(5),
+            Resource::HardwarePwm {
+                channel: 7,
+                pin: 20,
+            },
+            Resource::HardwarePwm {
+                channel: 7,
+                pin: 12,
+            },
+        ];
+        assert_eq!(
+            check_resources(&resources),
+            Err(PinConflicts(vec![
+                PinConflict::DuplicatePin(5),
+                PinConflict::InvalidChannel(7),
+                PinConflict::InvalidPwmPin(20),
+                PinConflict::DuplicateChannel(7),
+            ]))
+        );
+    }
+}
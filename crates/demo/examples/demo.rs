@@ -5,7 +5,7 @@
 
 use components::{hardware_pwm::DCMotor, software_pwm::LiftMotor, Left, Right, SensorController};
 use defaults::TryDefault;
-use demo::demo;
+use demo::{cancel::CancelToken, config::DemoConfig, demo, observer::NoObserver};
 use logbot::Logbot;
 use vehicle::Vehicle;
 
@@ -13,13 +13,27 @@ use vehicle::Vehicle;
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let vehicle: Vehicle<DCMotor<Left>, DCMotor<Right>> = Vehicle::try_default()?;
 
-    let mut logbot = Logbot::new(
-        vehicle,
-        SensorController::try_default()?,
-        LiftMotor::try_default()?,
-    );
+    let mut logbot = Logbot::builder()
+        .vehicle(vehicle)
+        .sensors(SensorController::try_default()?)
+        .lift(LiftMotor::try_default()?)
+        .build();
 
-    demo(&mut logbot)?;
+    let report = demo(
+        &mut logbot,
+        &DemoConfig::default(),
+        &mut NoObserver,
+        &CancelToken::new(),
+    );
+    for (number, duration) in (1..).zip(&report.laps) {
+        println!("Lap {number}: {:.1}s", duration.as_secs_f64());
+    }
+    for recovery in &report.recoveries {
+        println!("Recovered at {recovery}");
+    }
 
-    Ok(())
+    match report.failure {
+        Some(failure) => Err(failure.into()),
+        None => Ok(()),
+    }
 }
@@ -0,0 +1,283 @@
+//! Avoiding obstacles seen by a [`RangeFinder`] while following the line
+//!
+//! An [`Avoidance`] arbitrates between the line follower and the distance to
+//! the obstacle ahead, using the [`AvoidTuning`] of the [`Tuning`]. Beyond the
+//! slow distance the line is followed as usual, closer to the obstacle logbot
+//! slows down and within the stop distance it stands still. An obstacle
+//! blocking logbot for longer than the detour time is driven around using
+//! [`detour`], which finds the line again behind it.
+//!
+//! Logbot follows the line with the line on its left, so obstacles are
+//! passed on the right without crossing the line.
+//!
+//! [`RangeFinder`]: interfaces::RangeFinder
+
+use std::time::{Duration, Instant};
+
+use calibration::SensorCalibration;
+use consts::Sensors;
+use directions::{SpeedControl, SpinDirection, VehicleDirection};
+use interfaces::{Drive, SensorRead, Spin};
+use logbot::error::LogbotError;
+use speed::Speed;
+use tuning::{AvoidTuning, Tuning};
+
+use crate::{cancel::CancelToken, turn_on_line};
+
+/// Share of the speed left when slowed down right before stopping
+const MIN_SCALE: f64 = 0.25;
+
+/// What to do about the obstacle ahead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arbitration {
+    /// Follow the line with the speed scaled by a factor
+    Follow(Speed),
+    /// Stand still in front of the obstacle
+    Stop,
+    /// Drive around the obstacle
+    Detour,
+}
+
+/// Arbitration between following the line and the obstacle ahead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Avoidance {
+    /// Distances and times of avoiding obstacles
+    tuning: AvoidTuning,
+    /// Since when an obstacle blocks logbot
+    blocked: Option<Instant>,
+}
+
+impl Avoidance {
+    /// Create a new [`Avoidance`] that isn't blocked
+    pub fn new(tuning: AvoidTuning) -> Self {
+        Self {
+            tuning,
+            blocked: None,
+        }
+    }
+
+    /// Decide what to do about an obstacle at a distance in meters, [None]
+    /// if nothing is in range
+    pub fn arbitrate(&mut self, distance: Option<f64>) -> Arbitration {
+        self.arbitrate_at(distance, Instant::now())
+    }
+
+    /// Forget about the obstacle, e.g. once it is driven around
+    pub fn reset(&mut self) {
+        self.blocked = None;
+    }
+
+    /// Decide what to do about an obstacle at an [`Instant`]
+    fn arbitrate_at(&mut self, distance: Option<f64>, now: Instant) -> Arbitration {
+        let tuning = &self.tuning;
+        let Some(distance) = distance.filter(|&distance| distance < tuning.slow_distance_m) else {
+            self.blocked = None;
+            return Arbitration::Follow(Speed::MAX);
+        };
+
+        if distance > tuning.stop_distance_m {
+            self.blocked = None;
+            let range = tuning.slow_distance_m - tuning.stop_distance_m;
+            let scale = (distance - tuning.stop_distance_m) / range;
+            return Arbitration::Follow(Speed::new_clamp(MIN_SCALE + (1.0 - MIN_SCALE) * scale));
+        };
+
+        let blocked = *self.blocked.get_or_insert(now);
+        match tuning.detour_after() {
+            Some(after) if now.saturating_duration_since(blocked) >= after => Arbitration::Detour,
+            _ => Arbitration::Stop,
+        }
+    }
+}
+
+/// Drive around the obstacle ahead and find the line behind it, until done
+/// or cancelled
+///
+/// Logbot turns right, drives away from the line, passes the obstacle and
+/// drives back until the left sensor sees the line, all timed using the
+/// `spin_rate` and `drive_rate` of the [`Tuning`]. It then crosses onto the
+/// line and turns right until the left sensor is back on it. Fails with
+/// [`LogbotError::TimedOut`] when the line isn't seen within the search
+/// distance.
+pub fn detour<L, LiftError>(
+    logbot: &mut L,
+    left_calibration: &SensorCalibration,
+    tuning: &Tuning,
+    cancel: &CancelToken,
+) -> Result<(), LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, LiftError>>
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+{
+    let avoid = &tuning.avoid;
+    let right = SpinDirection::Right(tuning.turn_speed);
+
+    // Leave the line and drive past the obstacle
+    spin_by(logbot, right, 90.0, tuning, cancel)?;
+    drive_by(logbot, avoid.detour_width_m, tuning, cancel)?;
+    spin_by(logbot, !right, 90.0, tuning, cancel)?;
+    drive_by(logbot, avoid.detour_length_m, tuning, cancel)?;
+    spin_by(logbot, !right, 90.0, tuning, cancel)?;
+
+    // Drive back until the line is seen
+    let Some(timeout) = tuning.drive_duration(avoid.search_distance_m, tuning.speed) else {
+        return Err(LogbotError::TimedOut);
+    };
+    logbot
+        .drive(VehicleDirection::forward(tuning.speed))
+        .map_err(LogbotError::Vehicle)?;
+    let start = Instant::now();
+    while logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?
+        < left_calibration.line.saturating_sub(3)
+    {
+        if start.elapsed() >= timeout {
+            logbot.stop().map_err(LogbotError::Vehicle)?;
+            return Err(LogbotError::TimedOut);
+        };
+        cancel.sleep(logbot, Duration::from_micros(300))?;
+    }
+
+    // Turn back onto the line with the wheels on it
+    drive_by(logbot, avoid.cross_distance_m, tuning, cancel)?;
+    turn_on_line(logbot, left_calibration, right, Duration::ZERO, cancel)
+}
+
+/// Spin in-place by an angle in degrees, not at all if the angle is never
+/// completed
+fn spin_by<L, SE, LE>(
+    logbot: &mut L,
+    direction: SpinDirection,
+    angle: f64,
+    tuning: &Tuning,
+    cancel: &CancelToken,
+) -> Result<(), LogbotError<<L as Drive>::Error, SE, LE>>
+where
+    L: Spin<SpinDirection = SpinDirection>,
+{
+    let Some(duration) = tuning.spin_duration(angle, direction.speed()) else {
+        return Ok(());
+    };
+    logbot.spin(direction).map_err(LogbotError::Vehicle)?;
+    cancel.sleep(logbot, duration)?;
+    logbot.stop().map_err(LogbotError::Vehicle)?;
+    Ok(())
+}
+
+/// Drive straight forward by a distance in meters, not at all if the
+/// distance is never reached
+fn drive_by<L, SE, LE>(
+    logbot: &mut L,
+    distance: f64,
+    tuning: &Tuning,
+    cancel: &CancelToken,
+) -> Result<(), LogbotError<<L as Drive>::Error, SE, LE>>
+where
+    L: Drive<Direction = VehicleDirection>,
+{
+    let Some(duration) = tuning.drive_duration(distance, tuning.speed) else {
+        return Ok(());
+    };
+    logbot
+        .drive(VehicleDirection::forward(tuning.speed))
+        .map_err(LogbotError::Vehicle)?;
+    cancel.sleep(logbot, duration)?;
+    logbot.stop().map_err(LogbotError::Vehicle)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use calibration::SensorCalibration;
+    use components::{
+        mock::{MockLift, MockMotor, MockSensorController},
+        Left, Right,
+    };
+    use logbot::{error::LogbotError, Logbot};
+    use speed::Speed;
+    use tuning::{AvoidTuning, Tuning};
+    use vehicle::Vehicle;
+
+    use super::{detour, Arbitration, Avoidance};
+    use crate::cancel::CancelToken;
+
+    /// Test that logbot slows down, stops and detours the closer the obstacle
+    #[test]
+    fn arbitrate_distances() {
+        let mut avoidance = Avoidance::new(AvoidTuning::DEFAULT);
+        let start = Instant::now();
+
+        assert_eq!(
+            avoidance.arbitrate_at(None, start),
+            Arbitration::Follow(Speed::MAX)
+        );
+        assert_eq!(
+            avoidance.arbitrate_at(Some(1.0), start),
+            Arbitration::Follow(Speed::MAX)
+        );
+        let Arbitration::Follow(slow) = avoidance.arbitrate_at(Some(0.2), start) else {
+            panic!("an obstacle beyond the stop distance is followed towards");
+        };
+        assert!(Speed::new_const(0.25) < slow && slow < Speed::HALF);
+
+        // Blocked until the detour time has passed
+        assert_eq!(avoidance.arbitrate_at(Some(0.1), start), Arbitration::Stop);
+        let later = start + Duration::from_millis(1999);
+        assert_eq!(avoidance.arbitrate_at(Some(0.1), later), Arbitration::Stop);
+        let later = start + Duration::from_secs(2);
+        assert_eq!(
+            avoidance.arbitrate_at(Some(0.1), later),
+            Arbitration::Detour
+        );
+
+        // An obstacle moving away restarts the wait
+        avoidance.arbitrate_at(None, later);
+        assert_eq!(avoidance.arbitrate_at(Some(0.1), later), Arbitration::Stop);
+
+        // Without a detour time logbot waits for the obstacle to go away
+        let mut avoidance = Avoidance::new(AvoidTuning {
+            detour_after_ms: None,
+            ..AvoidTuning::DEFAULT
+        });
+        avoidance.arbitrate_at(Some(0.0), start);
+        let later = start + Duration::from_secs(60);
+        assert_eq!(avoidance.arbitrate_at(Some(0.0), later), Arbitration::Stop);
+    }
+
+    /// Test that a detour finds the line behind the obstacle or times out
+    #[test]
+    fn detour_finds_line() {
+        let tuning = Tuning {
+            spin_rate: 90.0 / 0.001 / Tuning::RACE.turn_speed.value(),
+            drive_rate: 1000.0,
+            ..Tuning::RACE
+        };
+        let calibration = SensorCalibration::new(200, 40);
+        let cancel = CancelToken::new();
+
+        // The sensors always see the line
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(MockSensorController::new(200))
+            .lift(MockLift::new())
+            .build();
+        let result = detour::<_, ()>(&mut logbot, &calibration, &tuning, &cancel);
+        assert!(result.is_ok());
+
+        // The sensors never see the line
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(MockSensorController::new(40))
+            .lift(MockLift::new())
+            .build();
+        let result = detour::<_, ()>(&mut logbot, &calibration, &tuning, &cancel);
+        assert!(matches!(result, Err(LogbotError::TimedOut)));
+    }
+}
@@ -0,0 +1,401 @@
+//! Behaviors that missions are assembled from
+//!
+//! Each step of the [demo](crate::demo) is a [`Behavior`], run on logbot with
+//! a [`Context`] holding the [`Tuning`], the calibration of the sensors and the
+//! [`CancelToken`]. A tuple of behaviors runs each of them in order, so new
+//! missions are put together without writing another loop.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use components::{mock::{MockLift, MockMotor, MockSensorController}, Left, Right};
+//! use demo::{
+//!     behavior::{Behavior, Context, LiftDown, LiftUp, Wait},
+//!     cancel::CancelToken,
+//! };
+//! use interfaces::Lift;
+//! use logbot::Logbot;
+//! use tuning::Tuning;
+//! use vehicle::Vehicle;
+//!
+//! let mut logbot = Logbot::builder()
+//!     .vehicle(Vehicle::new(MockMotor::<Left>::new(), MockMotor::<Right>::new()))
+//!     .sensors(MockSensorController::new(0))
+//!     .lift(MockLift::new())
+//!     .build();
+//!
+//! let mut context = Context::new(Tuning::DEMO, CancelToken::new());
+//! let pick_up = (LiftUp(None), (Wait(Duration::from_millis(10)), LiftDown(None)));
+//! pick_up.run(&mut logbot, &mut context).unwrap();
+//! assert!(logbot.is_down());
+//! ```
+
+use std::time::{Duration, Instant};
+
+use consts::Sensors;
+use directions::{SpeedControl, SpinDirection, VehicleDirection};
+use interfaces::{Drive, Lift, SensorRead, Spin};
+use logbot::error::LogbotError;
+use speed::Speed;
+use tuning::Tuning;
+
+use crate::{
+    calibrate, cancel::CancelToken, find_edge, follow::FollowLimit, follow_for, follow_until_line,
+    turn_on_line, Calibration, DemoError,
+};
+
+/// Time between reads of the sensors while a [`Behavior`] waits
+const WAIT_READ: Duration = Duration::from_millis(10);
+
+/// State shared by the [`Behavior`]s of a mission
+#[derive(Debug, Clone)]
+pub struct Context {
+    /// The tuning the behaviors run with
+    pub tuning: Tuning,
+    /// Calibration of the left and right sensor, none until calibrated
+    pub calibration: Option<Calibration>,
+    /// Token cancelling the running behavior
+    pub cancel: CancelToken,
+}
+
+impl Context {
+    /// Create an uncalibrated [`Context`]
+    pub fn new(tuning: Tuning, cancel: CancelToken) -> Self {
+        Self {
+            tuning,
+            calibration: None,
+            cancel,
+        }
+    }
+
+    /// The calibration of the sensors, [calibrating](Calibrate) first if
+    /// there is none yet
+    fn calibrated<L>(&mut self, logbot: &mut L) -> Result<Calibration, DemoError<L>>
+    where
+        L: Spin<SpinDirection = SpinDirection>,
+        L: SensorRead<Output = u8>,
+        L: Lift,
+    {
+        if let Some(calibration) = self.calibration {
+            return Ok(calibration);
+        };
+        let calibration = calibrate(logbot, &self.tuning, &self.cancel)?;
+        self.calibration = Some(calibration);
+        Ok(calibration)
+    }
+
+    /// Wait for a duration, checking the [`CancelToken`] while waiting
+    ///
+    /// The sensors are read every [`WAIT_READ`] like in the control loops,
+    /// so hosts sampling on sensor reads, e.g. the battery monitor of the
+    /// hardware thread, keep sampling while logbot drives blindly.
+    fn wait<L>(&self, logbot: &mut L, duration: Duration) -> Result<(), DemoError<L>>
+    where
+        L: Drive + SensorRead + Lift,
+    {
+        let start = Instant::now();
+        while let Some(remaining) = duration.checked_sub(start.elapsed()) {
+            logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?;
+            self.cancel.sleep(logbot, remaining.min(WAIT_READ))?;
+        }
+        self.cancel.check(logbot)
+    }
+}
+
+/// Something logbot does, run with a [`Context`]
+///
+/// Behaviors that need the calibration of the sensors calibrate first when
+/// the [`Context`] has none.
+pub trait Behavior<L>
+where
+    L: Drive + SensorRead + Lift,
+{
+    /// Run the behavior until it is done, failed or cancelled
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>>;
+}
+
+/// Run both behaviors in order
+impl<L, A, B> Behavior<L> for (A, B)
+where
+    L: Drive + SensorRead + Lift,
+    A: Behavior<L>,
+    B: Behavior<L>,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        self.0.run(logbot, context)?;
+        self.1.run(logbot, context)
+    }
+}
+
+/// Calibrate the sensors by oscillating over the line, see
+/// [`calibrate`](crate::calibrate)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Calibrate;
+
+impl<L> Behavior<L> for Calibrate
+where
+    L: Spin<SpinDirection = SpinDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        context.calibration = None;
+        context.calibrated(logbot)?;
+        Ok(())
+    }
+}
+
+/// Spin in a direction until the right sensor finds the edge of the line,
+/// see [`find_edge`](crate::find_edge)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FindEdge {
+    /// Direction to spin in
+    pub direction: SpinDirection,
+    /// Time after which finding the edge fails with
+    /// [`LogbotError::TimedOut`], none to never give up
+    pub timeout: Option<Duration>,
+}
+
+impl FindEdge {
+    /// Find the edge spinning left at the speed of the [`Tuning`], without a
+    /// timeout
+    pub fn left(tuning: &Tuning) -> Self {
+        Self {
+            direction: SpinDirection::Left(tuning.find_edge.speed),
+            timeout: None,
+        }
+    }
+}
+
+impl<L> Behavior<L> for FindEdge
+where
+    L: Spin<SpinDirection = SpinDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        let (_, right) = context.calibrated(logbot)?;
+        find_edge(
+            logbot,
+            &right,
+            self.direction,
+            self.timeout,
+            &context.cancel,
+        )
+    }
+}
+
+/// Spin off the line until the left sensor finds it again, see
+/// [`turn_on_line`](crate::turn_on_line)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TurnOnLine {
+    /// Direction to spin in
+    pub direction: SpinDirection,
+    /// Time to spin off the line before looking for it again
+    pub leave_line: Duration,
+}
+
+impl<L> Behavior<L> for TurnOnLine
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        let (left, _) = context.calibrated(logbot)?;
+        turn_on_line(
+            logbot,
+            &left,
+            self.direction,
+            self.leave_line,
+            &context.cancel,
+        )
+    }
+}
+
+/// Follow the line until a stop line is detected, see
+/// [`follow_until_line`](crate::follow_until_line)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FollowUntilLine;
+
+impl<L> Behavior<L> for FollowUntilLine
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        let (left, right) = context.calibrated(logbot)?;
+        follow_until_line(
+            logbot,
+            &left,
+            &right,
+            context.tuning.follow_line_config(left),
+            context.tuning.acceleration(),
+            &context.cancel,
+        )
+    }
+}
+
+/// Follow the line until a [`FollowLimit`] is reached, see
+/// [`follow_for`](crate::follow_for)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FollowFor(pub FollowLimit);
+
+impl<L> Behavior<L> for FollowFor
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        let calibration = context.calibrated(logbot)?;
+        follow_for(
+            logbot,
+            &calibration,
+            &context.tuning,
+            self.0,
+            &context.cancel,
+        )
+    }
+}
+
+/// Move the lift up at a [`Speed`], or the lift speed of the [`Tuning`] if
+/// none is given
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LiftUp(pub Option<Speed>);
+
+impl<L> Behavior<L> for LiftUp
+where
+    L: Drive + SensorRead + Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        context.cancel.check(logbot)?;
+        logbot
+            .up(self.0.unwrap_or(context.tuning.lift_speed))
+            .map_err(LogbotError::Lift)
+    }
+}
+
+/// Move the lift down at a [`Speed`], or the lift speed of the [`Tuning`] if
+/// none is given
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LiftDown(pub Option<Speed>);
+
+impl<L> Behavior<L> for LiftDown
+where
+    L: Drive + SensorRead + Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        context.cancel.check(logbot)?;
+        logbot
+            .down(self.0.unwrap_or(context.tuning.lift_speed))
+            .map_err(LogbotError::Lift)
+    }
+}
+
+/// Stand still for a duration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Wait(pub Duration);
+
+impl<L> Behavior<L> for Wait
+where
+    L: Drive + SensorRead + Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        context.wait(logbot, self.0)
+    }
+}
+
+/// Drive in a direction for a duration, then stop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriveFor {
+    /// Direction to drive in
+    pub direction: VehicleDirection,
+    /// Time to drive for
+    pub duration: Duration,
+}
+
+impl<L> Behavior<L> for DriveFor
+where
+    L: Drive<Direction = VehicleDirection> + SensorRead + Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        logbot.drive(self.direction).map_err(LogbotError::Vehicle)?;
+        context.wait(logbot, self.duration)?;
+        logbot.stop().map_err(LogbotError::Vehicle)?;
+        Ok(())
+    }
+}
+
+/// Spin in-place by an angle in degrees, timed using the `spin_rate` of the
+/// [`Tuning`]
+///
+/// Spinning at a speed that never completes the angle doesn't spin at all.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpinBy {
+    /// Direction to spin in
+    pub direction: SpinDirection,
+    /// Angle to spin by in degrees
+    pub angle: f64,
+}
+
+impl<L> Behavior<L> for SpinBy
+where
+    L: Spin<SpinDirection = SpinDirection> + SensorRead + Lift,
+{
+    fn run(&self, logbot: &mut L, context: &mut Context) -> Result<(), DemoError<L>> {
+        let Some(duration) = context
+            .tuning
+            .spin_duration(self.angle, self.direction.speed())
+        else {
+            return Ok(());
+        };
+        logbot.spin(self.direction).map_err(LogbotError::Vehicle)?;
+        context.wait(logbot, duration)?;
+        logbot.stop().map_err(LogbotError::Vehicle)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use calibration::SensorCalibration;
+    use components::{
+        mock::{MockLift, MockMotor, MockSensorController},
+        Left, Right,
+    };
+    use interfaces::Lift;
+    use logbot::{error::LogbotError, Logbot};
+    use tuning::Tuning;
+    use vehicle::Vehicle;
+
+    use super::{Behavior, Context, FindEdge, LiftUp};
+    use crate::cancel::CancelToken;
+
+    /// Test that behaviors run in order and stop once cancelled
+    #[test]
+    fn run_in_order() {
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(MockSensorController::new(200))
+            .lift(MockLift::new())
+            .build();
+        let calibration = SensorCalibration::new(200, 40);
+        let mut context = Context::new(Tuning::DEMO, CancelToken::new());
+        context.calibration = Some((calibration, calibration));
+
+        let behavior = (FindEdge::left(&Tuning::DEMO), LiftUp(None));
+        assert!(behavior.run(&mut logbot, &mut context).is_ok());
+        assert!(logbot.is_up());
+
+        context.cancel.cancel();
+        assert!(matches!(
+            behavior.run(&mut logbot, &mut context),
+            Err(LogbotError::Cancelled)
+        ));
+    }
+}
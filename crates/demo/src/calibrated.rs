@@ -0,0 +1,122 @@
+//! Operations of a [`Calibrated`] [`Logbot`]
+//!
+//! Finding the edge of the line and following it need the calibration of
+//! the sensors, so [`LineFollowing`] is only implemented for a [`Logbot`]
+//! that was [calibrated](Logbot::calibrated).
+//!
+//! ```
+//! use calibration::SensorCalibration;
+//! use components::{mock::{MockLift, MockMotor, MockSensorController}, Left, Right};
+//! use demo::{calibrated::LineFollowing, cancel::CancelToken};
+//! use directions::SpinDirection;
+//! use logbot::Logbot;
+//! use tuning::Tuning;
+//! use vehicle::Vehicle;
+//!
+//! let logbot = Logbot::builder()
+//!     .vehicle(Vehicle::new(MockMotor::<Left>::new(), MockMotor::<Right>::new()))
+//!     .sensors(MockSensorController::new(200))
+//!     .lift(MockLift::new())
+//!     .build();
+//!
+//! let calibration = SensorCalibration::new(200, 40);
+//! let mut logbot = logbot.calibrated((calibration, calibration));
+//! logbot.find_edge(SpinDirection::Left(Tuning::DEMO.find_edge.speed), &CancelToken::new()).unwrap();
+//! ```
+//!
+//! Finding the edge before calibrating fails to compile:
+//!
+//! ```compile_fail
+//! use components::{mock::{MockLift, MockMotor, MockSensorController}, Left, Right};
+//! use demo::{calibrated::LineFollowing, cancel::CancelToken};
+//! use directions::SpinDirection;
+//! use logbot::Logbot;
+//! use tuning::Tuning;
+//! use vehicle::Vehicle;
+//!
+//! let mut logbot = Logbot::builder()
+//!     .vehicle(Vehicle::new(MockMotor::<Left>::new(), MockMotor::<Right>::new()))
+//!     .sensors(MockSensorController::new(200))
+//!     .lift(MockLift::new())
+//!     .build();
+//!
+//! logbot.find_edge(SpinDirection::Left(Tuning::DEMO.find_edge.speed), &CancelToken::new()).unwrap();
+//! ```
+
+use std::{convert::Infallible, time::Duration};
+
+use directions::{SpinDirection, VehicleDirection};
+use interfaces::{Drive, SensorRead, Spin};
+use logbot::{calibrated::Calibrated, error::LogbotError, Logbot};
+use tuning::Tuning;
+
+use crate::{cancel::CancelToken, find_edge, follow::FollowLimit, follow_for, turn_on_line};
+
+/// [`LogbotError`] of the [`LineFollowing`] operations, which don't move the
+/// lift
+pub type LineError<L> = LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, Infallible>;
+
+/// Operations that need the calibration of the sensors
+pub trait LineFollowing: Spin + SensorRead + Sized {
+    /// Spin until the followed sensor is on the edge of the line, see
+    /// [`find_edge`](crate::find_edge)
+    fn find_edge(
+        &mut self,
+        direction: SpinDirection,
+        cancel: &CancelToken,
+    ) -> Result<(), LineError<Self>>;
+
+    /// Spin from the line until it is found again after spinning off it for
+    /// `leave_line`, see [`turn_on_line`](crate::turn_on_line)
+    fn turn_on_line(
+        &mut self,
+        direction: SpinDirection,
+        leave_line: Duration,
+        cancel: &CancelToken,
+    ) -> Result<(), LineError<Self>>;
+
+    /// Follow the line until a [`FollowLimit`] is reached, see
+    /// [`follow_for`](crate::follow_for)
+    fn follow_for(
+        &mut self,
+        tuning: &Tuning,
+        limit: FollowLimit,
+        cancel: &CancelToken,
+    ) -> Result<(), LineError<Self>>;
+}
+
+impl<V, S, L, H> LineFollowing for Logbot<V, S, L, Calibrated, H>
+where
+    Self: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    Self: SensorRead<Output = u8>,
+{
+    fn find_edge(
+        &mut self,
+        direction: SpinDirection,
+        cancel: &CancelToken,
+    ) -> Result<(), LineError<Self>> {
+        let calibration = self.calibration();
+        find_edge(self, &calibration.right, direction, None, cancel)
+    }
+
+    fn turn_on_line(
+        &mut self,
+        direction: SpinDirection,
+        leave_line: Duration,
+        cancel: &CancelToken,
+    ) -> Result<(), LineError<Self>> {
+        let calibration = self.calibration();
+        turn_on_line(self, &calibration.left, direction, leave_line, cancel)
+    }
+
+    fn follow_for(
+        &mut self,
+        tuning: &Tuning,
+        limit: FollowLimit,
+        cancel: &CancelToken,
+    ) -> Result<(), LineError<Self>> {
+        let calibration = self.calibration();
+        let calibration = (calibration.left, calibration.right);
+        follow_for(self, &calibration, tuning, limit, cancel)
+    }
+}
@@ -0,0 +1,108 @@
+//! Cancelling the [demo](crate::demo) and its steps while they run
+//!
+//! Every loop of the steps checks a [`CancelToken`], so another thread can
+//! interrupt a running step instead of waiting for it to finish. A cancelled
+//! step stops the vehicle and fails with [`LogbotError::Cancelled`].
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use interfaces::Drive;
+use logbot::error::LogbotError;
+
+/// Time between checks of a [`CancelToken`] while waiting
+const POLL: Duration = Duration::from_micros(300);
+
+/// Token cancelling the steps it is given to, shared by cloning it
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a [`CancelToken`] that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every step given this token or a clone of it
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Undo a cancellation, so the token can be given to the next steps
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    /// Whether the token is cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Stop logbot and fail with [`LogbotError::Cancelled`] if the token is
+    /// cancelled
+    pub(crate) fn check<L, SE, LE>(
+        &self,
+        logbot: &mut L,
+    ) -> Result<(), LogbotError<<L as Drive>::Error, SE, LE>>
+    where
+        L: Drive,
+    {
+        if self.is_cancelled() {
+            logbot.stop().map_err(LogbotError::Vehicle)?;
+            return Err(LogbotError::Cancelled);
+        };
+        Ok(())
+    }
+
+    /// Wait for a duration, [checking](Self::check) the token while waiting
+    pub(crate) fn sleep<L, SE, LE>(
+        &self,
+        logbot: &mut L,
+        duration: Duration,
+    ) -> Result<(), LogbotError<<L as Drive>::Error, SE, LE>>
+    where
+        L: Drive,
+    {
+        let start = Instant::now();
+        while let Some(remaining) = duration.checked_sub(start.elapsed()) {
+            self.check(logbot)?;
+            std::thread::sleep(remaining.min(POLL));
+        }
+        self.check(logbot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use components::{mock::MockMotor, Left};
+    use logbot::error::LogbotError;
+
+    use super::CancelToken;
+
+    /// Test that a clone of a token cancels the wait of the original
+    #[test]
+    fn cancel_wait() {
+        let token = CancelToken::new();
+        let mut motor = MockMotor::<Left>::new();
+        assert_eq!(
+            token.sleep::<_, (), ()>(&mut motor, Duration::from_millis(1)),
+            Ok(())
+        );
+
+        token.clone().cancel();
+        assert_eq!(
+            token.sleep::<_, (), ()>(&mut motor, Duration::from_secs(60)),
+            Err(LogbotError::Cancelled)
+        );
+
+        token.reset();
+        assert!(!token.is_cancelled());
+    }
+}
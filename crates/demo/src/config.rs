@@ -0,0 +1,57 @@
+//! Settings of the box-moving [demo](crate::demo)
+//!
+//! The speeds, the line following controller, the oscillations and the lift
+//! speed come from the [`Tuning`], while the number of boxes and the pauses
+//! between the steps of the demo are set next to it, so the demo can be tuned
+//! per venue and floor.
+
+use tuning::Tuning;
+
+/// Default number of boxes moved by the demo
+pub const DEFAULT_BOXES: u32 = 1;
+
+/// Default milliseconds to stand still after finding the edge or turning
+pub const DEFAULT_SETTLE_MS: u64 = 200;
+
+/// Default milliseconds to spin off the line before looking for it again
+pub const DEFAULT_LEAVE_LINE_MS: u64 = 1000;
+
+/// Default milliseconds to look for the edge before calibrating again
+pub const DEFAULT_FIND_EDGE_TIMEOUT_MS: u64 = 10_000;
+
+/// Settings of the box-moving [demo](crate::demo)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DemoConfig {
+    /// Speeds, controller, oscillations and lift speed of the demo
+    pub tuning: Tuning,
+    /// Number of boxes to move, each in a lap of picking it up, turning and
+    /// dropping it off
+    pub boxes: u32,
+    /// Milliseconds to stand still after finding the edge or turning, so
+    /// logbot settles before the next step
+    pub settle_ms: u64,
+    /// Milliseconds to spin off the line when turning, before looking for
+    /// the line again
+    pub leave_line_ms: u64,
+    /// Milliseconds to look for the edge of the line before calibrating again
+    pub find_edge_timeout_ms: u64,
+}
+
+impl DemoConfig {
+    /// Create a [`DemoConfig`] with a [`Tuning`] and the default pauses
+    pub fn new(tuning: Tuning) -> Self {
+        Self {
+            tuning,
+            boxes: DEFAULT_BOXES,
+            settle_ms: DEFAULT_SETTLE_MS,
+            leave_line_ms: DEFAULT_LEAVE_LINE_MS,
+            find_edge_timeout_ms: DEFAULT_FIND_EDGE_TIMEOUT_MS,
+        }
+    }
+}
+
+impl Default for DemoConfig {
+    fn default() -> Self {
+        Self::new(Tuning::DEMO)
+    }
+}
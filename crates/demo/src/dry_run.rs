@@ -0,0 +1,253 @@
+//! Dry run of the [demo](crate::demo) on a simulated logbot
+//!
+//! [`dry_run`] executes the whole demo against the [simulated](components::sim)
+//! components instead of the real robot, recording every change of the direction
+//! logbot is commanded in, every move of the lift and the timing of every phase as a
+//! [`Trace`]. Changes to the choreography can be checked this way, e.g. in
+//! CI, before they are tried on the real robot. The simulation runs in real
+//! time, so a dry run takes as long as the demo would.
+//!
+//! ```no_run
+//! use demo::{config::DemoConfig, dry_run::dry_run};
+//!
+//! let trace = dry_run(&DemoConfig::default());
+//! assert!(trace.report.is_success());
+//! for phase in &trace.phases {
+//!     println!("{}: {:.1}s", phase.step.phase(), phase.duration.as_secs_f64());
+//! }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use components::{
+    sim::{SimLift, SimMotor, SimSensors, World},
+    Left, Right,
+};
+use directions::VehicleDirection;
+use logbot::{
+    calibrated::Uncalibrated,
+    hooks::{DriveHook, LiftHook, LiftMotion, SensorHook},
+    Logbot,
+};
+use vehicle::Vehicle;
+
+use crate::{
+    cancel::CancelToken, config::DemoConfig, mission::Step, observer::DemoObserver,
+    report::DemoReport, DemoError,
+};
+
+/// Simulated logbot recording what it was commanded to do
+pub type DryRunLogbot =
+    Logbot<Vehicle<SimMotor<Left>, SimMotor<Right>>, SimSensors, SimLift, Uncalibrated, Recorder>;
+
+/// Direction logbot was commanded in during a [dry run](dry_run)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Command {
+    /// Time since the dry run started
+    pub elapsed: Duration,
+    /// Direction of the vehicle, [`VehicleDirection::STOP`] when stopped
+    pub direction: VehicleDirection,
+}
+
+/// Move of the lift during a [dry run](dry_run)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiftMove {
+    /// Time since the dry run started
+    pub elapsed: Duration,
+    /// How the lift was moved
+    pub motion: LiftMotion,
+}
+
+/// Timing of a phase of the demo during a [dry run](dry_run)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Phase {
+    /// Number of the step, counting from 1
+    pub number: usize,
+    /// The step executed in this phase
+    pub step: Step,
+    /// Time since the dry run started when the phase started
+    pub start: Duration,
+    /// How long the phase took
+    pub duration: Duration,
+}
+
+/// Everything logbot was commanded to do during a [dry run](dry_run)
+#[derive(Debug)]
+pub struct Trace {
+    /// Changes of the commanded direction, in order
+    pub commands: Vec<Command>,
+    /// Moves of the lift, in order
+    pub lifts: Vec<LiftMove>,
+    /// Phases of the demo, in order
+    pub phases: Vec<Phase>,
+    /// The [`DemoReport`] of the simulated demo
+    pub report: DemoReport<DemoError<DryRunLogbot>>,
+}
+
+/// [Hook](logbot::hooks) recording the directions and lift moves of logbot
+#[derive(Debug)]
+pub struct Recorder {
+    /// When recording started
+    started: Instant,
+    /// Recorded directions
+    commands: Vec<Command>,
+    /// Recorded moves of the lift
+    lifts: Vec<LiftMove>,
+}
+
+impl Recorder {
+    /// Create a [`Recorder`] counting time from `started`
+    fn new(started: Instant) -> Self {
+        Self {
+            started,
+            commands: Vec::new(),
+            lifts: Vec::new(),
+        }
+    }
+
+    /// Record a direction, unless logbot is already commanded in it
+    fn command(&mut self, direction: VehicleDirection) {
+        // Control loops drive in the same direction over and over
+        if self
+            .commands
+            .last()
+            .is_some_and(|command| command.direction == direction)
+        {
+            return;
+        };
+        self.commands.push(Command {
+            elapsed: self.started.elapsed(),
+            direction,
+        });
+    }
+}
+
+impl DriveHook<VehicleDirection> for Recorder {
+    fn on_drive(&mut self, direction: VehicleDirection) -> VehicleDirection {
+        self.command(direction);
+        direction
+    }
+
+    fn on_stop(&mut self) {
+        self.command(VehicleDirection::STOP);
+    }
+}
+
+impl LiftHook for Recorder {
+    fn on_lift(&mut self, motion: LiftMotion) {
+        self.lifts.push(LiftMove {
+            elapsed: self.started.elapsed(),
+            motion,
+        });
+    }
+}
+
+impl SensorHook<u8> for Recorder {}
+
+/// [`DemoObserver`] recording the timing of every phase
+#[derive(Debug)]
+struct Phases {
+    /// When recording started
+    started: Instant,
+    /// Recorded phases
+    phases: Vec<Phase>,
+    /// Time since recording started when the running phase started
+    start: Duration,
+}
+
+impl DemoObserver for Phases {
+    fn on_phase_start(&mut self, _number: usize, _step: &Step, _elapsed: Duration) {
+        self.start = self.started.elapsed();
+    }
+
+    fn on_phase_end(&mut self, number: usize, step: &Step, duration: Duration) {
+        self.phases.push(Phase {
+            number,
+            step: *step,
+            start: self.start,
+            duration,
+        });
+    }
+}
+
+/// Run the [demo](crate::demo) with a [`DemoConfig`] on a simulated logbot
+///
+/// Logbot starts on the edge of the line of a new [`World`], and the
+/// returned [`Trace`] holds what it was commanded to do and how the demo
+/// ended.
+pub fn dry_run(config: &DemoConfig) -> Trace {
+    let world = World::shared();
+    let started = Instant::now();
+    let mut logbot = Logbot::builder()
+        .vehicle(Vehicle::new(
+            SimMotor::<Left>::new(world.clone()),
+            SimMotor::<Right>::new(world.clone()),
+        ))
+        .sensors(SimSensors::new(world.clone()))
+        .lift(SimLift::new(world))
+        .hooks(Recorder::new(started))
+        .build();
+    let mut observer = Phases {
+        started,
+        phases: Vec::new(),
+        start: Duration::ZERO,
+    };
+
+    let report = crate::demo(&mut logbot, config, &mut observer, &CancelToken::new());
+    let recorder = logbot.hooks_mut();
+    Trace {
+        commands: std::mem::take(&mut recorder.commands),
+        lifts: std::mem::take(&mut recorder.lifts),
+        phases: observer.phases,
+        report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use directions::VehicleDirection;
+    use logbot::hooks::LiftMotion;
+    use speed::Speed;
+    use tuning::Tuning;
+
+    use super::dry_run;
+    use crate::{config::DemoConfig, mission::Mission};
+
+    /// Test that the demo completes on the simulated course, lifting and
+    /// dropping the box and ending stopped
+    #[test]
+    fn complete_demo() {
+        // Faster than the demo, so the dry run finishes in a few seconds
+        let mut tuning = Tuning::DEMO;
+        tuning.speed = Speed::new_clamp(0.6);
+        tuning.turn_speed = Speed::new_clamp(0.3);
+        tuning.calibrate.duration_ms = 50;
+        tuning.calibrate.speed = Speed::new_clamp(0.4);
+        let config = DemoConfig {
+            settle_ms: 0,
+            leave_line_ms: 300,
+            ..DemoConfig::new(tuning)
+        };
+
+        let trace = dry_run(&config);
+        assert!(trace.report.is_success(), "{:?}", trace.report.failure);
+        assert_eq!(trace.report.laps.len(), config.boxes as usize);
+
+        let steps = Mission::demo(&config).steps().to_vec();
+        let phases: Vec<_> = trace.phases.iter().map(|phase| phase.step).collect();
+        assert_eq!(phases, steps);
+
+        let lifts: Vec<_> = trace.lifts.iter().map(|lift| lift.motion).collect();
+        assert_eq!(
+            lifts,
+            vec![
+                LiftMotion::Up(tuning.lift_speed),
+                LiftMotion::Down(tuning.lift_speed)
+            ]
+        );
+        assert_eq!(
+            trace.commands.last().map(|command| command.direction),
+            Some(VehicleDirection::STOP)
+        );
+    }
+}
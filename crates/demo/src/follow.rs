@@ -0,0 +1,183 @@
+//! Following the line until a limit is reached
+//!
+//! A [`FollowLimit`] ends following the line after a duration, after driving
+//! a distance or when crossing a number of stop lines, whichever comes first.
+//! The distance is estimated by [`DeadReckoning`] from the driven speeds and
+//! the `drive_rate` of the [`Tuning`](tuning::Tuning), since logbot has no
+//! wheel encoders.
+
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use directions::VehicleDirection;
+use odometry::{DeadReckoning, Odometry};
+use serde::{Deserialize, Serialize};
+
+use crate::Calibration;
+
+/// When to stop following the line, none of the limits means never
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FollowLimit {
+    /// Milliseconds to follow the line for
+    pub duration_ms: Option<u64>,
+    /// Meters to follow the line for
+    pub distance_m: Option<f64>,
+    /// Number of stop lines to follow the line until
+    pub stop_lines: Option<u32>,
+}
+
+impl FollowLimit {
+    /// Whether none of the limits is set
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for FollowLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let limits: Vec<String> = [
+            self.duration_ms.map(|duration| format!("for {duration}ms")),
+            self.distance_m.map(|distance| format!("for {distance}m")),
+            self.stop_lines
+                .map(|lines| format!("across {lines} stop lines")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        match limits.is_empty() {
+            true => f.write_str("until stopped"),
+            false => f.write_str(&limits.join(" or ")),
+        }
+    }
+}
+
+/// Whether both sensors are on top of the line at the same time, which
+/// marks a stop line
+pub fn is_stop_line(calibration: &Calibration, left: u8, right: u8) -> bool {
+    let (left_calibration, right_calibration) = calibration;
+    left > left_calibration.line.saturating_sub(1)
+        && right > right_calibration.line.saturating_sub(1)
+}
+
+/// Progress towards a [`FollowLimit`]
+#[derive(Debug, Clone, Copy)]
+pub struct FollowProgress {
+    /// The limit to reach
+    limit: FollowLimit,
+    /// [`Instant`] the line following started, moved by pauses
+    started: Instant,
+    /// Estimate of the distance driven
+    odometry: DeadReckoning,
+    /// Stop lines crossed
+    stop_lines: u32,
+    /// Whether the sensors were on a stop line at the last update
+    on_stop_line: bool,
+}
+
+impl FollowProgress {
+    /// Start following the line towards a [`FollowLimit`], estimating the
+    /// distance driven with [`DeadReckoning`]
+    pub fn new(limit: FollowLimit, odometry: DeadReckoning) -> Self {
+        Self {
+            limit,
+            started: Instant::now(),
+            odometry,
+            stop_lines: 0,
+            on_stop_line: false,
+        }
+    }
+
+    /// Account for driving until now and the new direction, and whether the
+    /// sensors are on a stop line
+    ///
+    /// Returns whether the [`FollowLimit`] is reached.
+    pub fn update(&mut self, direction: VehicleDirection, on_stop_line: bool) -> bool {
+        let now = Instant::now();
+        self.odometry.update(direction);
+
+        if on_stop_line && !self.on_stop_line {
+            self.stop_lines += 1;
+        };
+        self.on_stop_line = on_stop_line;
+
+        self.limit
+            .duration_ms
+            .is_some_and(|duration| now - self.started >= Duration::from_millis(duration))
+            || self
+                .limit
+                .distance_m
+                .is_some_and(|distance| self.odometry.distance() >= distance)
+            || self
+                .limit
+                .stop_lines
+                .is_some_and(|lines| self.stop_lines >= lines)
+    }
+
+    /// Continue after logbot stood still for a [`Duration`], which does not
+    /// count towards the duration followed
+    pub fn resume(&mut self, paused: Duration) {
+        self.started += paused;
+        // Standing still, so the distance does not change
+        self.odometry.update(VehicleDirection::STOP);
+    }
+
+    /// Estimated meters driven
+    pub fn distance(&self) -> f64 {
+        self.odometry.distance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use directions::VehicleDirection;
+    use odometry::DeadReckoning;
+
+    use super::{FollowLimit, FollowProgress};
+
+    /// Test that stop lines are counted once each, when they are entered
+    #[test]
+    fn count_stop_lines() {
+        let limit = FollowLimit {
+            stop_lines: Some(2),
+            ..FollowLimit::default()
+        };
+        let mut progress = FollowProgress::new(limit, DeadReckoning::new(1.0, 360.0));
+
+        assert!(!progress.update(VehicleDirection::STOP, true));
+        assert!(!progress.update(VehicleDirection::STOP, true));
+        assert!(!progress.update(VehicleDirection::STOP, false));
+        assert!(progress.update(VehicleDirection::STOP, true));
+    }
+
+    /// Test that the distance is estimated from the driven speed
+    #[test]
+    fn estimate_distance() {
+        let limit = FollowLimit {
+            distance_m: Some(0.01),
+            ..FollowLimit::default()
+        };
+        let mut progress = FollowProgress::new(limit, DeadReckoning::new(1.0, 360.0));
+        let forward: VehicleDirection = "forward:1.0".parse().unwrap();
+
+        assert!(!progress.update(forward, false));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(progress.update(forward, false));
+        assert!(progress.distance() >= 0.02);
+    }
+
+    /// Test that the limits are described for the mission progress
+    #[test]
+    fn describe_limit() {
+        let limit = FollowLimit {
+            duration_ms: Some(1500),
+            stop_lines: Some(1),
+            ..FollowLimit::default()
+        };
+        assert_eq!(limit.to_string(), "for 1500ms or across 1 stop lines");
+        assert_eq!(FollowLimit::default().to_string(), "until stopped");
+    }
+}
@@ -1,78 +1,121 @@
 //! logbot demo of following a line and lifting boxes
+//!
+//! The steps of the demo are exposed, so other programs can build on them.
 
 // https://github.com/rust-lang/rust/issues/95513
 #![allow(unused_crate_dependencies)]
 
-use std::{num::NonZero, time::Duration};
+use std::time::{Duration, Instant};
 
 use acceleration::{Accelerate, LinearAcceleration};
-use calibration::{SensorCalibration, SingleSensorCalibration};
+use calibration::{DualSensorCalibration, SensorCalibration};
+use cancel::CancelToken;
+use config::DemoConfig;
 use consts::Sensors;
 use directions::{SpinDirection, VehicleDirection};
+use follow::{is_stop_line, FollowLimit, FollowProgress};
 use interfaces::{Drive, Lift, SensorRead, Spin};
 use line::{FollowLineConfig, FollowLineState};
 use logbot::error::LogbotError;
-use oscillate::Oscillate;
-use speed::Speed;
-
-// Result of a calibration
-type Calibration = (SensorCalibration, SensorCalibration);
-
-/// Calibrate logbot
-fn calibrate<L, LiftError>(
+use odometry::DeadReckoning;
+use tuning::Tuning;
+
+pub mod avoid;
+pub mod behavior;
+pub mod calibrated;
+pub mod cancel;
+pub mod config;
+#[cfg(feature = "sim")]
+pub mod dry_run;
+pub mod follow;
+pub mod localization;
+pub mod mission;
+pub mod observer;
+pub mod report;
+pub mod tree;
+
+#[cfg(feature = "sim")]
+pub use dry_run::dry_run;
+
+use behavior::{Behavior, Calibrate, Context, FindEdge, LiftDown};
+use mission::{Mission, Step};
+use observer::DemoObserver;
+use report::{DemoReport, Failure, Recovery};
+
+/// Calibration of the left and right sensor
+pub type Calibration = (SensorCalibration, SensorCalibration);
+
+/// [`LogbotError`] of a type implementing [`Drive`], [`SensorRead`] and [`Lift`]
+pub type DemoError<L> =
+    LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, <L as Lift>::Error>;
+
+/// Calibrate logbot, until done or cancelled
+///
+/// Both sensors are sampled while oscillating over the line. Fails with
+/// [`LogbotError::Calibration`] if a sensor can't tell the line from the
+/// floor, e.g. when logbot was not placed on the line.
+pub fn calibrate<L, LiftError>(
     logbot: &mut L,
+    tuning: &Tuning,
+    cancel: &CancelToken,
 ) -> Result<Calibration, LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, LiftError>>
 where
     L: Spin<SpinDirection = SpinDirection>,
     L: SensorRead<Output = u8>,
 {
-    let mut left_calibration = SingleSensorCalibration::default();
-    let mut right_calibration = SingleSensorCalibration::default();
+    let mut samples = DualSensorCalibration::default();
 
     // Configure and start oscillation
-    let mut oscillate = Oscillate::new(
-        Duration::from_millis(500),
-        SpinDirection::Left(Speed::new_clamp(0.08)),
-        NonZero::<u32>::new(2).unwrap(),
-    )
-    .start(logbot)
-    .map_err(LogbotError::Vehicle)?;
+    let mut oscillate = tuning
+        .calibrate
+        .oscillate()
+        .start(logbot)
+        .map_err(LogbotError::Vehicle)?;
 
     // Wait for the first change in direction until we start logging values
-    oscillate.wait_until_next();
+    cancel.sleep(logbot, oscillate.next_oscillation())?;
     oscillate.step(logbot).map_err(LogbotError::Vehicle)?;
 
     // Read sensor values continuously until we're supposed to oscillate again
     while !oscillate.should_step() {
+        cancel.check(logbot)?;
         let left_value = logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?;
-        let right_value = logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?;
-
-        left_calibration.log(left_value as f64);
-        right_calibration.log(right_value as f64);
+        let right_value = logbot.read(Sensors::Right).map_err(LogbotError::Sensor)?;
+        samples.log(left_value as f64, right_value as f64);
     }
 
     logbot.stop().map_err(LogbotError::Vehicle)?;
 
-    // Evaluate sensor readings
-    Ok((left_calibration.calibrate(), right_calibration.calibrate()))
+    // Evaluate sensor readings, refusing to go on if a sensor never saw the line
+    samples.calibrate().map_err(LogbotError::Calibration)
 }
 
-/// Find the edge of the line
-fn find_edge<L, LiftError>(
+/// Find the edge of the line, until found or cancelled
+///
+/// Fails with [`LogbotError::TimedOut`] when the edge is not found within
+/// the `timeout`, if one is given.
+pub fn find_edge<L, LiftError>(
     logbot: &mut L,
     calibration: &SensorCalibration,
     direction: SpinDirection,
+    timeout: Option<Duration>,
+    cancel: &CancelToken,
 ) -> Result<(), LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, LiftError>>
 where
     L: Spin<SpinDirection = SpinDirection>,
     L: SensorRead<Output = u8>,
 {
     logbot.spin(direction).map_err(LogbotError::Vehicle)?;
+    let start = Instant::now();
 
     while logbot.read(Sensors::Right).map_err(LogbotError::Sensor)?
         < calibration.line.saturating_sub(1)
     {
-        std::thread::sleep(Duration::from_micros(300));
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            logbot.stop().map_err(LogbotError::Vehicle)?;
+            return Err(LogbotError::TimedOut);
+        };
+        cancel.sleep(logbot, Duration::from_micros(300))?;
     }
 
     // Stop logbot after edge is found
@@ -82,11 +125,15 @@ where
 
 /// Spin logbot in-place from the line, until it finds the line again
 ///
-/// Basically means making a 180 degree turn in most cases
-fn turn_on_line<L, LiftError>(
+/// Basically means making a 180 degree turn in most cases. The line is only
+/// looked for after spinning off it for `leave_line`. Cancelling stops the
+/// turn wherever logbot is.
+pub fn turn_on_line<L, LiftError>(
     logbot: &mut L,
     left_calibration: &SensorCalibration,
     direction: SpinDirection,
+    leave_line: Duration,
+    cancel: &CancelToken,
 ) -> Result<(), LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, LiftError>>
 where
     L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
@@ -96,13 +143,13 @@ where
     logbot.spin(direction).map_err(LogbotError::Vehicle)?;
 
     // Give a little time of get off the line first
-    std::thread::sleep(Duration::from_secs(1));
+    cancel.sleep(logbot, leave_line)?;
 
     // Wait until we find the line again
     while logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?
         < left_calibration.line.saturating_sub(3)
     {
-        std::thread::sleep(Duration::from_micros(300));
+        cancel.sleep(logbot, Duration::from_micros(300))?;
     }
 
     // Stop the vehicle once we are back on the line
@@ -114,29 +161,31 @@ where
 /// Follow line until a stop line is detected
 ///
 /// A stop line means that both sensors consider themselves ontop of the line at the same time
-fn follow_until_line<L, LiftError>(
+pub fn follow_until_line<L, LiftError>(
     logbot: &mut L,
     left_calibration: &SensorCalibration,
     right_calibration: &SensorCalibration,
     config: FollowLineConfig,
+    acceleration: Duration,
+    cancel: &CancelToken,
 ) -> Result<(), LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, LiftError>>
 where
     L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
     L: SensorRead<Output = u8>,
 {
     // Create a new state from the config
-    let mut state = FollowLineState::new(config.clone());
+    let mut state = FollowLineState::new(config);
 
-    let mut acceleration = LinearAcceleration::new(Duration::from_secs(2));
+    let mut acceleration = LinearAcceleration::new(acceleration);
 
-    let stop_left = left_calibration.line.saturating_sub(1);
-    let stop_right = right_calibration.line.saturating_sub(1);
+    let calibration = (*left_calibration, *right_calibration);
 
     loop {
+        cancel.check(logbot)?;
         let left_sensor_value = logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?;
         let right_sensor_value = logbot.read(Sensors::Right).map_err(LogbotError::Sensor)?;
 
-        if left_sensor_value > stop_left && right_sensor_value > stop_right {
+        if is_stop_line(&calibration, left_sensor_value, right_sensor_value) {
             break;
         };
 
@@ -149,60 +198,249 @@ where
     Ok(())
 }
 
+/// Follow the line until a [`FollowLimit`] is reached
+///
+/// Without any limit the line is followed until cancelled.
+pub fn follow_for<L, LiftError>(
+    logbot: &mut L,
+    calibration: &Calibration,
+    tuning: &Tuning,
+    limit: FollowLimit,
+    cancel: &CancelToken,
+) -> Result<(), LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, LiftError>>
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+{
+    let mut state = FollowLineState::new(tuning.follow_line_config(calibration.0));
+    let mut acceleration = LinearAcceleration::new(tuning.acceleration());
+    let mut progress = FollowProgress::new(
+        limit,
+        DeadReckoning::new(tuning.drive_rate, tuning.spin_rate),
+    );
+
+    loop {
+        cancel.check(logbot)?;
+        let left_sensor_value = logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?;
+        let right_sensor_value = logbot.read(Sensors::Right).map_err(LogbotError::Sensor)?;
+
+        let direction = state.step(left_sensor_value);
+        let direction = direction.accelerate(&mut acceleration);
+        let on_stop_line = is_stop_line(calibration, left_sensor_value, right_sensor_value);
+        if progress.update(direction, on_stop_line) {
+            break;
+        };
+        logbot.drive(direction).map_err(LogbotError::Vehicle)?;
+    }
+
+    logbot.stop().map_err(LogbotError::Vehicle)?;
+    Ok(())
+}
+
 /// Demo logbot, by following the line and lifting boxes in an pre-arranged setup
+///
+/// This runs the steps of the [`Mission::demo`] with a [`DemoConfig`],
+/// telling the [`DemoObserver`] about every phase and lap. Finding the edge
+/// that times out calibrates again, and a failing lift is lowered and retried
+/// once. Any other failure, or cancelling the [`CancelToken`], ends the demo
+/// within the running step with logbot stopped. The [`DemoReport`] tells how
+/// long each lap took, what was recovered from and how the demo ended.
 pub fn demo<L>(
     logbot: &mut L,
-) -> Result<(), LogbotError<<L as Drive>::Error, <L as SensorRead>::Error, <L as Lift>::Error>>
+    config: &DemoConfig,
+    observer: &mut impl DemoObserver,
+    cancel: &CancelToken,
+) -> DemoReport<DemoError<L>>
 where
     L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
     L: SensorRead<Output = u8>,
     L: Lift,
 {
-    let (left_calibration, right_calibration) = calibrate(logbot)?;
-
-    find_edge(
-        logbot,
-        &right_calibration,
-        SpinDirection::Left(Speed::new_clamp(0.1)),
-    )?;
-
-    std::thread::sleep(Duration::from_millis(200));
-
-    // Create the config for following the line
-    let config = FollowLineConfig {
-        default_speed: Speed::new_clamp(0.1),
-        proportional: 0.001,
-        derivative: 0.0005,
-        integral: None,
-        calibration: left_calibration,
-        reset_integral_on_target: true,
+    let mission = Mission::demo(config);
+    let mut context = Context::new(config.tuning, cancel.clone());
+    let mut report = DemoReport::new();
+    let started = Instant::now();
+    let mut lap_start: Option<Instant> = None;
+
+    for (number, step) in (1..).zip(mission.steps()) {
+        if Mission::demo_lap(number).is_some() {
+            if let Some(start) = lap_start.replace(Instant::now()) {
+                let duration = start.elapsed();
+                report.laps.push(duration);
+                observer.on_lap(report.laps.len(), duration);
+            };
+        };
+
+        observer.on_phase_start(number, step, started.elapsed());
+        let start = Instant::now();
+        let result = recover_step(logbot, step, number, config, &mut context, &mut report);
+        observer.on_phase_end(number, step, start.elapsed());
+
+        if let Err(error) = result {
+            // Leave logbot standing still, the step may have failed while driving
+            let _ = logbot.stop();
+            report.failure = Some(Failure {
+                step: number,
+                error,
+            });
+            return report;
+        };
+    }
+
+    if let Some(start) = lap_start {
+        let duration = start.elapsed();
+        report.laps.push(duration);
+        observer.on_lap(report.laps.len(), duration);
     };
+    report
+}
 
-    // Follow line until the first stopline
-    follow_until_line(logbot, &left_calibration, &right_calibration, config)?;
+/// Execute a step of the [demo], recovering from the failures it can
+fn recover_step<L>(
+    logbot: &mut L,
+    step: &Step,
+    number: usize,
+    config: &DemoConfig,
+    context: &mut Context,
+    report: &mut DemoReport<DemoError<L>>,
+) -> Result<(), DemoError<L>>
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    match *step {
+        Step::FindEdge => {
+            let find_edge = FindEdge {
+                timeout: Some(Duration::from_millis(config.find_edge_timeout_ms)),
+                ..FindEdge::left(&config.tuning)
+            };
+            match find_edge.run(logbot, context) {
+                Err(LogbotError::TimedOut) => {
+                    report
+                        .recoveries
+                        .push(Recovery::Recalibrated { step: number });
+                    Calibrate.run(logbot, context)?;
+                    find_edge.run(logbot, context)
+                }
+                result => result,
+            }
+        }
+        Step::LiftUp { speed } | Step::LiftDown { speed } => {
+            match mission::run_step(logbot, step, context) {
+                Err(LogbotError::Lift(_)) => {
+                    report
+                        .recoveries
+                        .push(Recovery::LiftRetried { step: number });
+                    // Lowering the lift is already the retry of moving it down
+                    LiftDown(speed).run(logbot, context)?;
+                    match step {
+                        Step::LiftUp { .. } => mission::run_step(logbot, step, context),
+                        _ => Ok(()),
+                    }
+                }
+                result => result,
+            }
+        }
+        _ => mission::run_step(logbot, step, context),
+    }
+}
 
-    logbot.up(Speed::HALF).map_err(LogbotError::Lift)?;
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
 
-    // Turn the logbot 180 degrees in relation to the line
-    turn_on_line(
-        logbot,
-        &left_calibration,
-        SpinDirection::Right(Speed::new_clamp(0.08)),
-    )?;
+    use calibration::{CalibrationError, SensorCalibration};
+    use components::{
+        mock::{MockLift, MockMotor, MockSensorController},
+        Left, Right,
+    };
+    use interfaces::{SensorRead, ToSensorChannel};
+    use logbot::{error::LogbotError, Logbot};
+    use tuning::Tuning;
+    use vehicle::Vehicle;
+
+    use super::{calibrate, recover_step};
+    use crate::{
+        behavior::Context,
+        cancel::CancelToken,
+        config::DemoConfig,
+        mission::Step,
+        report::{DemoReport, Recovery},
+    };
 
-    std::thread::sleep(Duration::from_millis(200));
+    /// Sensors passing over stripes, both reading the line every other pair
+    /// of reads and the floor in between
+    #[derive(Debug, Default)]
+    struct Stripes {
+        reads: usize,
+    }
 
-    find_edge(
-        logbot,
-        &right_calibration,
-        SpinDirection::Left(Speed::new_clamp(0.1)),
-    )?;
+    impl SensorRead for Stripes {
+        type Output = u8;
+        type Error = Infallible;
 
-    std::thread::sleep(Duration::from_millis(200));
+        fn read(&mut self, _sensor: impl ToSensorChannel) -> Result<u8, Infallible> {
+            self.reads += 1;
+            Ok(if self.reads % 4 < 2 { 0 } else { 100 })
+        }
+    }
 
-    follow_until_line(logbot, &left_calibration, &right_calibration, config)?;
+    /// Test that calibrating fails if the sensors never see the line
+    #[test]
+    fn calibrate_without_line() {
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(MockSensorController::new(50))
+            .lift(MockLift::new())
+            .build();
+        let mut tuning = Tuning::DEMO;
+        tuning.calibrate.duration_ms = 1;
+
+        let calibration = SensorCalibration::new(50, 50);
+        let result = calibrate::<_, Infallible>(&mut logbot, &tuning, &CancelToken::new());
+        assert!(matches!(
+            result,
+            Err(LogbotError::Calibration(CalibrationError::Left(c))) if c == calibration
+        ));
+    }
 
-    logbot.down(Speed::HALF).map_err(LogbotError::Lift)?;
+    /// Test that finding the edge calibrates again once it times out
+    #[test]
+    fn recalibrate_on_timeout() {
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(Stripes::default())
+            .lift(MockLift::new())
+            .build();
+        let mut tuning = Tuning::DEMO;
+        tuning.calibrate.duration_ms = 1;
+        let config = DemoConfig {
+            find_edge_timeout_ms: 10,
+            ..DemoConfig::new(tuning)
+        };
 
-    Ok(())
+        // The line is never seen with this calibration, but is after calibrating
+        let mut context = Context::new(tuning, CancelToken::new());
+        let calibration = SensorCalibration::new(200, 40);
+        context.calibration = Some((calibration, calibration));
+
+        let mut report = DemoReport::new();
+        let result = recover_step(
+            &mut logbot,
+            &Step::FindEdge,
+            2,
+            &config,
+            &mut context,
+            &mut report,
+        );
+        assert!(result.is_ok());
+        assert_eq!(report.recoveries, vec![Recovery::Recalibrated { step: 2 }]);
+    }
 }
@@ -0,0 +1,292 @@
+//! Finding where logbot is on a known course
+//!
+//! A [`TrackMap`] describes a course as the segments of line between events
+//! logbot notices while following it: stop lines, intersections and turns.
+//! A segment may end at a station, e.g. where boxes are picked up. The
+//! [`Localizer`] matches the events seen while following the line against
+//! the map, narrowing down which segment logbot is on until only one is
+//! left. From there, the [`Route`] to a station tells how to get there, e.g.
+//! to follow the line across two more stop lines. Maps are deserialized from
+//! e.g. TOML:
+//!
+//! ```toml
+//! looped = true
+//!
+//! [[segments]]
+//! end = "stop_line"
+//! station = "pick up"
+//!
+//! [[segments]]
+//! end = "turn_left"
+//!
+//! [[segments]]
+//! end = "stop_line"
+//! station = "drop off"
+//!
+//! [[segments]]
+//! end = "turn_left"
+//! ```
+
+use std::fmt::Display;
+
+use serde::Deserialize;
+
+use crate::follow::FollowLimit;
+
+/// Something logbot notices while following the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEvent {
+    /// Both sensors cross a stop line
+    StopLine,
+    /// Another line crosses the line
+    Intersection,
+    /// The line turns to the left
+    TurnLeft,
+    /// The line turns to the right
+    TurnRight,
+}
+
+impl Display for LineEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::StopLine => "stop line",
+            Self::Intersection => "intersection",
+            Self::TurnLeft => "left turn",
+            Self::TurnRight => "right turn",
+        })
+    }
+}
+
+/// Line between two events of a [`TrackMap`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackSegment {
+    /// Event ending the segment
+    pub end: LineEvent,
+    /// Name of the station at the end of the segment, if any
+    #[serde(default)]
+    pub station: Option<String>,
+}
+
+/// Course described by the segments of line between events, in the order
+/// logbot follows them
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackMap {
+    /// Segments in the order they are followed
+    pub segments: Vec<TrackSegment>,
+    /// Whether the last segment leads back to the first
+    #[serde(default)]
+    pub looped: bool,
+}
+
+impl TrackMap {
+    /// The segment following another, none at the end of an open track
+    fn next(&self, segment: usize) -> Option<usize> {
+        match segment + 1 {
+            next if next < self.segments.len() => Some(next),
+            _ if self.looped => Some(0),
+            _ => None,
+        }
+    }
+
+    /// The segment ending at a station
+    pub fn station(&self, name: &str) -> Option<usize> {
+        self.segments
+            .iter()
+            .position(|segment| segment.station.as_deref() == Some(name))
+    }
+}
+
+/// Events to pass to reach a station from the current segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// Events in the order they are passed, the last one at the station
+    pub events: Vec<LineEvent>,
+}
+
+impl Route {
+    /// Number of stop lines on the route
+    pub fn stop_lines(&self) -> u32 {
+        self.events
+            .iter()
+            .filter(|event| **event == LineEvent::StopLine)
+            .count() as u32
+    }
+
+    /// [`FollowLimit`] following the line to the station, if it is at a stop
+    /// line
+    ///
+    /// Only stop lines are seen while following the line, so the station is
+    /// reached at the last stop line of the route.
+    pub fn follow_limit(&self) -> Option<FollowLimit> {
+        (self.events.last() == Some(&LineEvent::StopLine)).then(|| FollowLimit {
+            stop_lines: Some(self.stop_lines()),
+            ..FollowLimit::default()
+        })
+    }
+}
+
+/// Estimates the segment of a [`TrackMap`] logbot is on from the events it
+/// sees
+#[derive(Debug, Clone)]
+pub struct Localizer {
+    /// The course
+    map: TrackMap,
+    /// Segments logbot may be on, given the events seen so far
+    candidates: Vec<usize>,
+}
+
+impl Localizer {
+    /// Start without knowing where logbot is, any segment is possible
+    pub fn new(map: TrackMap) -> Self {
+        let candidates = (0..map.segments.len()).collect();
+        Self { map, candidates }
+    }
+
+    /// Start on a known segment
+    pub fn on_segment(map: TrackMap, segment: usize) -> Self {
+        let candidates = (segment < map.segments.len())
+            .then_some(segment)
+            .into_iter()
+            .collect();
+        Self { map, candidates }
+    }
+
+    /// The course
+    pub fn map(&self) -> &TrackMap {
+        &self.map
+    }
+
+    /// Segments logbot may be on
+    pub fn candidates(&self) -> &[usize] {
+        &self.candidates
+    }
+
+    /// The segment logbot is on, once the events seen match only one
+    pub fn segment(&self) -> Option<usize> {
+        match self.candidates[..] {
+            [segment] => Some(segment),
+            _ => None,
+        }
+    }
+
+    /// Whether the events seen match no place on the map, e.g. after missing
+    /// an event
+    pub fn is_lost(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Account for an event seen at the end of the current segment, moving
+    /// on to the next one
+    ///
+    /// Returns the segment logbot is on, if known.
+    pub fn observe(&mut self, event: LineEvent) -> Option<usize> {
+        let map = &self.map;
+        self.candidates = self
+            .candidates
+            .iter()
+            .filter(|&&segment| map.segments[segment].end == event)
+            .filter_map(|&segment| map.next(segment))
+            .collect();
+        self.segment()
+    }
+
+    /// The [`Route`] to a station, once the segment logbot is on is known
+    ///
+    /// Logbot already past the station on an open track, or an unknown
+    /// station, has no route.
+    pub fn route(&self, station: &str) -> Option<Route> {
+        let target = self.map.station(station)?;
+        let mut segment = self.segment()?;
+        let mut events = Vec::new();
+        loop {
+            events.push(self.map.segments[segment].end);
+            if segment == target {
+                return Some(Route { events });
+            };
+            segment = self.map.next(segment)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineEvent, Localizer, TrackMap, TrackSegment};
+    use crate::follow::FollowLimit;
+
+    /// Loop of stations at stop lines, told apart by the turns between them
+    fn map() -> TrackMap {
+        let segment = |end, station: Option<&str>| TrackSegment {
+            end,
+            station: station.map(str::to_owned),
+        };
+        TrackMap {
+            segments: vec![
+                segment(LineEvent::StopLine, Some("1")),
+                segment(LineEvent::TurnLeft, None),
+                segment(LineEvent::StopLine, Some("2")),
+                segment(LineEvent::StopLine, Some("3")),
+                segment(LineEvent::TurnLeft, None),
+            ],
+            looped: true,
+        }
+    }
+
+    /// Test that the events seen narrow down the segment logbot is on
+    #[test]
+    fn localize() {
+        let mut localizer = Localizer::new(map());
+        assert_eq!(localizer.observe(LineEvent::StopLine), None);
+        assert_eq!(localizer.candidates(), &[1, 3, 4]);
+
+        // Only station 3 is followed by another stop line
+        assert_eq!(localizer.observe(LineEvent::StopLine), Some(4));
+        assert_eq!(localizer.observe(LineEvent::TurnLeft), Some(0));
+
+        // Missing an event loses logbot
+        localizer.observe(LineEvent::Intersection);
+        assert!(localizer.is_lost());
+    }
+
+    /// Test that the route to a station wraps around the loop
+    #[test]
+    fn route_to_station() {
+        let localizer = Localizer::on_segment(map(), 3);
+
+        let route = localizer.route("2").unwrap();
+        assert_eq!(
+            route.events,
+            vec![
+                LineEvent::StopLine,
+                LineEvent::TurnLeft,
+                LineEvent::StopLine,
+                LineEvent::TurnLeft,
+                LineEvent::StopLine,
+            ]
+        );
+        assert_eq!(
+            route.follow_limit(),
+            Some(FollowLimit {
+                stop_lines: Some(3),
+                ..FollowLimit::default()
+            })
+        );
+        assert_eq!(localizer.route("4"), None);
+    }
+
+    /// Test that maps are read from the documented format
+    #[test]
+    fn deserialize_map() {
+        let map: TrackMap = serde_json::from_str(
+            r#"{"looped": true, "segments": [
+                {"end": "stop_line", "station": "pick up"},
+                {"end": "turn_left"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(map.station("pick up"), Some(0));
+        assert_eq!(map.segments[1].end, LineEvent::TurnLeft);
+    }
+}
@@ -0,0 +1,606 @@
+//! Missions of steps executed in order
+//!
+//! A [`Mission`] generalizes the [demo](crate::demo), which is itself the
+//! mission returned by [`Mission::demo`]. Steps are deserialized from their
+//! `action` and parameters, e.g. in JSON:
+//!
+//! ```json
+//! [
+//!     {"action": "calibrate"},
+//!     {"action": "find_edge"},
+//!     {"action": "follow"},
+//!     {"action": "lift_up", "speed": 0.8},
+//!     {"action": "follow_for", "distance_m": 2.0, "duration_ms": 10000},
+//!     {"action": "turn", "direction": "spin_left:0.3", "leave_line_ms": 800},
+//!     {"action": "drive", "direction": "backward:0.2", "duration_ms": 1500},
+//!     {"action": "spin", "direction": "spin_right:0.2", "angle": 180.0}
+//! ]
+//! ```
+//!
+//! Spinning by an angle is timed using the `spin_rate` of the [`Tuning`], and
+//! the distance followed by `follow_for` is estimated using its `drive_rate`.
+//! Lifting and turning use the speeds of the [`Tuning`] unless a `speed` or
+//! `direction` is given, and turning spins off the line for
+//! [`DEFAULT_LEAVE_LINE_MS`] unless `leave_line_ms` is given.
+
+use std::{fmt::Display, ops::ControlFlow, str::FromStr, time::Duration};
+
+use directions::{SpeedControl, SpinDirection, VehicleDirection};
+use interfaces::{Lift, SensorRead, Spin};
+use logbot::error::LogbotError;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use speed::Speed;
+use tuning::Tuning;
+
+use crate::{
+    behavior::{
+        Behavior, Calibrate, Context, DriveFor, FindEdge, FollowFor, FollowUntilLine, LiftDown,
+        LiftUp, SpinBy, TurnOnLine, Wait,
+    },
+    cancel::CancelToken,
+    config::{DemoConfig, DEFAULT_LEAVE_LINE_MS},
+    follow::FollowLimit,
+    DemoError,
+};
+
+/// A single step of a [`Mission`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    /// Drive in a [`VehicleDirection`] for a duration
+    Drive {
+        /// Direction to drive in, e.g. `forward:0.5`
+        #[serde(serialize_with = "to_str", deserialize_with = "from_str")]
+        direction: VehicleDirection,
+        /// Duration of the drive in milliseconds
+        duration_ms: u64,
+    },
+    /// Spin in-place by an angle
+    Spin {
+        /// Direction to spin in, e.g. `spin_left:0.2`
+        #[serde(serialize_with = "to_str", deserialize_with = "from_str")]
+        direction: SpinDirection,
+        /// Angle to spin by in degrees
+        angle: f64,
+    },
+    /// Stand still for a duration
+    Wait {
+        /// Duration of the wait in milliseconds
+        duration_ms: u64,
+    },
+    /// Move the lift up
+    LiftUp {
+        /// Speed of the lift, the lift speed of the [`Tuning`] if not given
+        #[serde(default)]
+        speed: Option<Speed>,
+    },
+    /// Move the lift down
+    LiftDown {
+        /// Speed of the lift, the lift speed of the [`Tuning`] if not given
+        #[serde(default)]
+        speed: Option<Speed>,
+    },
+    /// Calibrate the sensors by oscillating over the line
+    Calibrate,
+    /// Spin left until the right sensor finds the edge of the line
+    FindEdge,
+    /// Follow the line until a stop line is detected
+    Follow,
+    /// Follow the line until a [`FollowLimit`] is reached
+    FollowFor(FollowLimit),
+    /// Spin off the line until the left sensor finds it again
+    Turn {
+        /// Direction to spin in, e.g. `spin_left:0.3`, right at the turn
+        /// speed of the [`Tuning`] if not given
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "option_to_str",
+            deserialize_with = "option_from_str"
+        )]
+        direction: Option<SpinDirection>,
+        /// Milliseconds to spin off the line before looking for it again,
+        /// [`DEFAULT_LEAVE_LINE_MS`] if not given
+        #[serde(default)]
+        leave_line_ms: Option<u64>,
+    },
+}
+
+impl Step {
+    /// Whether the step requires a calibration
+    fn requires_calibration(&self) -> bool {
+        matches!(
+            self,
+            Self::FindEdge | Self::Follow | Self::FollowFor(_) | Self::Turn { .. }
+        )
+    }
+
+    /// Name of the phase logbot is in while executing the step, e.g.
+    /// `finding_edge`
+    pub fn phase(&self) -> &'static str {
+        match self {
+            Self::Drive { .. } => "driving",
+            Self::Spin { .. } => "spinning",
+            Self::Wait { .. } => "waiting",
+            Self::LiftUp { .. } => "lifting",
+            Self::LiftDown { .. } => "lowering",
+            Self::Calibrate => "calibrating",
+            Self::FindEdge => "finding_edge",
+            Self::Follow | Self::FollowFor(_) => "following",
+            Self::Turn { .. } => "turning",
+        }
+    }
+
+    /// The step with the direction it is given, if any, scaled to a share of
+    /// its speed
+    ///
+    /// Spinning by an angle still spins by the angle, taking longer, while
+    /// the speeds of the lift are kept.
+    pub fn scaled(self, limit: Speed) -> Self {
+        match self {
+            Self::Drive {
+                direction,
+                duration_ms,
+            } => Self::Drive {
+                direction: direction * limit,
+                duration_ms,
+            },
+            Self::Spin { direction, angle } => Self::Spin {
+                direction: direction * limit,
+                angle,
+            },
+            Self::Turn {
+                direction,
+                leave_line_ms,
+            } => Self::Turn {
+                direction: direction.map(|direction| direction * limit),
+                leave_line_ms,
+            },
+            step => step,
+        }
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Drive {
+                direction,
+                duration_ms,
+            } => write!(f, "drive {direction} for {duration_ms}ms"),
+            Self::Spin { direction, angle } => write!(f, "spin {direction} by {angle} degrees"),
+            Self::Wait { duration_ms } => write!(f, "wait for {duration_ms}ms"),
+            Self::LiftUp { .. } => write!(f, "lift up"),
+            Self::LiftDown { .. } => write!(f, "lift down"),
+            Self::Calibrate => write!(f, "calibrate"),
+            Self::FindEdge => write!(f, "find the edge"),
+            Self::Follow => write!(f, "follow until stop line"),
+            Self::FollowFor(limit) => write!(f, "follow {limit}"),
+            Self::Turn { .. } => write!(f, "turn on the line"),
+        }
+    }
+}
+
+/// A validated sequence of [`Step`]s and the [`Tuning`] they are executed with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mission {
+    /// The steps in order of execution
+    steps: Vec<Step>,
+    /// The tuning the steps are executed with
+    tuning: Tuning,
+}
+
+impl Mission {
+    /// Create a [`Mission`], checking that every step can be executed with
+    /// the [`Tuning`] before any hardware is moved
+    pub fn new(steps: Vec<Step>, tuning: Tuning) -> Result<Self, MissionError> {
+        let mut calibrated = false;
+        for (number, step) in (1..).zip(&steps) {
+            match *step {
+                Step::Spin { direction, angle }
+                    if tuning.spin_duration(angle, direction.speed()).is_none() =>
+                {
+                    return Err(MissionError::Spin {
+                        step: number,
+                        direction,
+                        angle,
+                    });
+                }
+                Step::FollowFor(limit) if limit.is_unlimited() => {
+                    return Err(MissionError::Unlimited { step: number });
+                }
+                Step::Calibrate => calibrated = true,
+                step if step.requires_calibration() && !calibrated => {
+                    return Err(MissionError::Uncalibrated { step: number });
+                }
+                _ => {}
+            };
+        }
+        Ok(Self { steps, tuning })
+    }
+
+    /// The box-moving [demo](crate::demo) as a [`Mission`]
+    ///
+    /// After calibrating and finding the edge, every box is moved in a lap:
+    /// logbot picks the box up, turns and drops it off, then turns back for
+    /// the next box unless it was the last one.
+    pub fn demo(config: &DemoConfig) -> Self {
+        let settle = Step::Wait {
+            duration_ms: config.settle_ms,
+        };
+        let turn = Step::Turn {
+            direction: None,
+            leave_line_ms: Some(config.leave_line_ms),
+        };
+        let lift_up = Step::LiftUp { speed: None };
+        let mut steps = vec![Step::Calibrate, Step::FindEdge, settle];
+        for lap in 1..=config.boxes {
+            steps.extend([
+                Step::Follow,
+                lift_up,
+                turn,
+                settle,
+                Step::FindEdge,
+                settle,
+                Step::Follow,
+                Step::LiftDown { speed: None },
+            ]);
+            if lap < config.boxes {
+                steps.extend([turn, settle, Step::FindEdge, settle]);
+            };
+        }
+        Self {
+            steps,
+            tuning: config.tuning,
+        }
+    }
+
+    /// Number of the lap of the [demo](Self::demo) starting at a step, both
+    /// counting from 1, none if no lap starts at the step
+    pub fn demo_lap(step: usize) -> Option<usize> {
+        let step = step.checked_sub(DEMO_SETUP_STEPS + 1)?;
+        (step % DEMO_LAP_STEPS == 0).then_some(step / DEMO_LAP_STEPS + 1)
+    }
+
+    /// The steps in order of execution
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// The [`Tuning`] the steps are executed with
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+}
+
+/// Steps of the [demo](Mission::demo) before its first lap
+const DEMO_SETUP_STEPS: usize = 3;
+
+/// Steps of a lap of the [demo](Mission::demo) that returns for the next box
+const DEMO_LAP_STEPS: usize = 12;
+
+/// Execute a [`Mission`]
+///
+/// `on_step` is called with the number of each step before it starts,
+/// counting from 1, and ends the mission early when it breaks. Cancelling the
+/// [`CancelToken`] ends the mission within the running step, failing with
+/// [`LogbotError::Cancelled`]. The mission ends with logbot stopped after the
+/// last step.
+pub fn run<L>(
+    logbot: &mut L,
+    mission: &Mission,
+    cancel: &CancelToken,
+    mut on_step: impl FnMut(usize, &Step) -> ControlFlow<()>,
+) -> Result<(), DemoError<L>>
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    let mut context = Context::new(mission.tuning, cancel.clone());
+
+    for (number, step) in (1..).zip(&mission.steps) {
+        if on_step(number, step).is_break() {
+            break;
+        };
+        run_step(logbot, step, &mut context)?;
+    }
+
+    logbot.stop().map_err(LogbotError::Vehicle)?;
+    Ok(())
+}
+
+/// Execute a single [`Step`] as its [`Behavior`]
+pub fn run_step<L>(logbot: &mut L, step: &Step, context: &mut Context) -> Result<(), DemoError<L>>
+where
+    L: Spin<SpinDirection = SpinDirection, Direction = VehicleDirection>,
+    L: SensorRead<Output = u8>,
+    L: Lift,
+{
+    let tuning = context.tuning;
+    match *step {
+        Step::Drive {
+            direction,
+            duration_ms,
+        } => DriveFor {
+            direction,
+            duration: Duration::from_millis(duration_ms),
+        }
+        .run(logbot, context),
+        Step::Spin { direction, angle } => SpinBy { direction, angle }.run(logbot, context),
+        Step::Wait { duration_ms } => Wait(Duration::from_millis(duration_ms)).run(logbot, context),
+        Step::LiftUp { speed } => LiftUp(speed).run(logbot, context),
+        Step::LiftDown { speed } => LiftDown(speed).run(logbot, context),
+        Step::Calibrate => Calibrate.run(logbot, context),
+        Step::FindEdge => FindEdge::left(&tuning).run(logbot, context),
+        Step::Follow => FollowUntilLine.run(logbot, context),
+        Step::FollowFor(limit) => FollowFor(limit).run(logbot, context),
+        Step::Turn {
+            direction,
+            leave_line_ms,
+        } => TurnOnLine {
+            direction: direction.unwrap_or(SpinDirection::Right(tuning.turn_speed)),
+            leave_line: Duration::from_millis(leave_line_ms.unwrap_or(DEFAULT_LEAVE_LINE_MS)),
+        }
+        .run(logbot, context),
+    }
+}
+
+/// Serialize a value as its [`Display`] representation, read by [`from_str`]
+fn to_str<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Display,
+{
+    serializer.collect_str(value)
+}
+
+/// Serialize an optional value as its [`Display`] representation, read by
+/// [`option_from_str`]
+fn option_to_str<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Display,
+{
+    match value {
+        Some(value) => to_str(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserialize a value from its [`FromStr`] representation
+fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(de::Error::custom)
+}
+
+/// Deserialize an optional value from its [`FromStr`] representation
+fn option_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    from_str(deserializer).map(Some)
+}
+
+/// Reasons a [`Mission`] cannot be executed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissionError {
+    /// A spin step never completes at the speed of its direction
+    Spin {
+        /// Number of the step, counting from 1
+        step: usize,
+        /// Direction of the spin
+        direction: SpinDirection,
+        /// Angle of the spin in degrees
+        angle: f64,
+    },
+    /// A step requires a calibration, but no calibrate step comes before it
+    Uncalibrated {
+        /// Number of the step, counting from 1
+        step: usize,
+    },
+    /// A step follows the line without any limit, so it never completes
+    Unlimited {
+        /// Number of the step, counting from 1
+        step: usize,
+    },
+}
+
+impl Display for MissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Spin {
+                step,
+                direction,
+                angle,
+            } => write!(
+                f,
+                "step {step}: cannot spin by {angle} degrees using `{direction}`"
+            ),
+            Self::Uncalibrated { step } => {
+                write!(f, "step {step}: requires a calibrate step first")
+            }
+            Self::Unlimited { step } => {
+                write!(f, "step {step}: follows the line without any limit")
+            }
+        }
+    }
+}
+
+impl core::error::Error for MissionError {}
+
+#[cfg(test)]
+mod tests {
+    use speed::Speed;
+    use tuning::Tuning;
+
+    use super::{Mission, MissionError, Step};
+    use crate::{config::DemoConfig, follow::FollowLimit};
+
+    /// Test that steps requiring a calibration are only accepted after one
+    #[test]
+    fn validate_mission() {
+        let demo = Mission::demo(&DemoConfig::default());
+        assert_eq!(Mission::new(demo.steps().to_vec(), Tuning::DEMO), Ok(demo));
+
+        let limit = FollowLimit {
+            distance_m: Some(2.0),
+            ..FollowLimit::default()
+        };
+        for step in [
+            Step::FindEdge,
+            Step::Follow,
+            Step::FollowFor(limit),
+            Step::Turn {
+                direction: None,
+                leave_line_ms: None,
+            },
+        ] {
+            assert_eq!(
+                Mission::new(vec![Step::LiftUp { speed: None }, step], Tuning::DEMO),
+                Err(MissionError::Uncalibrated { step: 2 })
+            );
+        }
+
+        assert_eq!(
+            Mission::new(
+                vec![Step::Calibrate, Step::FollowFor(FollowLimit::default())],
+                Tuning::DEMO
+            ),
+            Err(MissionError::Unlimited { step: 2 })
+        );
+    }
+
+    /// Test that the demo repeats its lap for every box
+    #[test]
+    fn demo_laps() {
+        let demo = Mission::demo(&DemoConfig {
+            boxes: 2,
+            ..DemoConfig::default()
+        });
+        assert_eq!(demo.steps().len(), 3 + 12 + 8);
+        assert_eq!(demo.steps().last(), Some(&Step::LiftDown { speed: None }));
+
+        let laps: Vec<_> = (1..=demo.steps().len())
+            .filter_map(Mission::demo_lap)
+            .collect();
+        assert_eq!(laps, vec![1, 2]);
+        // Laps start by following the line to the box
+        assert_eq!(demo.steps()[3], Step::Follow);
+        assert_eq!(demo.steps()[15], Step::Follow);
+    }
+
+    /// Test that limits of following the line are deserialized with the action
+    #[test]
+    fn deserialize_follow_for() {
+        let step: Step =
+            serde_json::from_str(r#"{"action": "follow_for", "stop_lines": 2}"#).unwrap();
+        assert_eq!(
+            step,
+            Step::FollowFor(FollowLimit {
+                stop_lines: Some(2),
+                ..FollowLimit::default()
+            })
+        );
+    }
+
+    /// Test that turning and lifting take optional parameters
+    #[test]
+    fn deserialize_parameters() {
+        let step: Step = serde_json::from_str(r#"{"action": "turn"}"#).unwrap();
+        assert_eq!(
+            step,
+            Step::Turn {
+                direction: None,
+                leave_line_ms: None
+            }
+        );
+
+        let step: Step = serde_json::from_str(
+            r#"{"action": "turn", "direction": "spin_left:0.3", "leave_line_ms": 800}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            step,
+            Step::Turn {
+                direction: Some("spin_left:0.3".parse().unwrap()),
+                leave_line_ms: Some(800)
+            }
+        );
+
+        let step: Step = serde_json::from_str(r#"{"action": "lift_up", "speed": 0.5}"#).unwrap();
+        assert_eq!(
+            step,
+            Step::LiftUp {
+                speed: Some(Speed::HALF)
+            }
+        );
+        assert!(serde_json::from_str::<Step>(r#"{"action": "lift_down", "speed": 2.0}"#).is_err());
+    }
+
+    /// Test that serialized steps, e.g. of a persisted schedule, are read
+    /// back unchanged
+    #[test]
+    fn serialize_round_trip() {
+        let demo = Mission::demo(&DemoConfig::default());
+        let steps = [
+            demo.steps(),
+            &[
+                Step::Drive {
+                    direction: "backward:0.2".parse().unwrap(),
+                    duration_ms: 1500,
+                },
+                Step::Spin {
+                    direction: "spin_right:0.2".parse().unwrap(),
+                    angle: 180.0,
+                },
+                Step::Turn {
+                    direction: Some("spin_left:0.3".parse().unwrap()),
+                    leave_line_ms: None,
+                },
+                Step::FollowFor(FollowLimit {
+                    stop_lines: Some(2),
+                    ..FollowLimit::default()
+                }),
+            ],
+        ]
+        .concat();
+
+        let json = serde_json::to_string(&steps).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Step>>(&json).unwrap(), steps);
+    }
+
+    /// Test that scaling a step scales the direction it is given, keeping
+    /// the angle and the speed of the lift
+    #[test]
+    fn scale_steps() {
+        let spin = Step::Spin {
+            direction: "spin_right:0.4".parse().unwrap(),
+            angle: 90.0,
+        };
+        assert_eq!(
+            spin.scaled(Speed::HALF),
+            Step::Spin {
+                direction: "spin_right:0.2".parse().unwrap(),
+                angle: 90.0,
+            }
+        );
+
+        let turn = Step::Turn {
+            direction: None,
+            leave_line_ms: Some(800),
+        };
+        let lift = Step::LiftUp {
+            speed: Some(Speed::MAX),
+        };
+        assert_eq!(turn.scaled(Speed::HALF), turn);
+        assert_eq!(lift.scaled(Speed::HALF), lift);
+    }
+}
@@ -0,0 +1,50 @@
+//! Observing the progress of the [demo](crate::demo)
+//!
+//! Every [`Step`] of the demo is a phase, named by [`Step::phase`], e.g.
+//! `calibrating` or `finding_edge`. A [`DemoObserver`] is told when each phase
+//! starts and ends, and when each lap of moving a box ends, so hosts can show
+//! how far the demo has come and record how long each phase took.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use demo::{mission::Step, observer::DemoObserver};
+//!
+//! /// Prints every phase of the demo with its duration
+//! #[derive(Debug)]
+//! struct Printer;
+//!
+//! impl DemoObserver for Printer {
+//!     fn on_phase_end(&mut self, _number: usize, step: &Step, duration: Duration) {
+//!         println!("{}: {:.1}s", step.phase(), duration.as_secs_f64());
+//!     }
+//! }
+//! ```
+
+use std::time::Duration;
+
+use crate::mission::Step;
+
+/// Observer of the phases of the [demo](crate::demo)
+///
+/// Every method defaults to doing nothing, so an observer only implements
+/// what it needs.
+pub trait DemoObserver {
+    /// Called when a phase starts, with the number of its step counting from
+    /// 1 and the time since the demo started
+    fn on_phase_start(&mut self, _number: usize, _step: &Step, _elapsed: Duration) {}
+
+    /// Called when a phase ends, with the number of its step counting from 1
+    /// and how long the phase took
+    fn on_phase_end(&mut self, _number: usize, _step: &Step, _duration: Duration) {}
+
+    /// Called when a lap of moving a box ends, with the number of the lap
+    /// counting from 1 and how long the lap took
+    fn on_lap(&mut self, _lap: usize, _duration: Duration) {}
+}
+
+/// [`DemoObserver`] that observes nothing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoObserver;
+
+impl DemoObserver for NoObserver {}
@@ -0,0 +1,88 @@
+//! Report of what happened during the [demo](crate::demo)
+//!
+//! The demo recovers from some failures instead of aborting: when finding the
+//! edge times out the sensors are calibrated again, and when the lift fails
+//! it is lowered and the step is retried once. Every [`Recovery`] is recorded
+//! in the [`DemoReport`], together with the time of each lap and the
+//! [`Failure`] that ended the demo, if any.
+
+use std::{fmt::Display, time::Duration};
+
+/// A failure the [demo](crate::demo) recovered from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Finding the edge timed out, so the sensors were calibrated again
+    /// before finding the edge once more
+    Recalibrated {
+        /// Number of the step, counting from 1
+        step: usize,
+    },
+    /// The lift failed, so it was lowered and the step retried
+    LiftRetried {
+        /// Number of the step, counting from 1
+        step: usize,
+    },
+}
+
+impl Display for Recovery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Recalibrated { step } => {
+                write!(f, "step {step}: finding the edge timed out, recalibrated")
+            }
+            Self::LiftRetried { step } => {
+                write!(f, "step {step}: the lift failed, lowered it and retried")
+            }
+        }
+    }
+}
+
+/// The failure that ended the [demo](crate::demo), with logbot stopped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Failure<E> {
+    /// Number of the failed step, counting from 1
+    pub step: usize,
+    /// Why the step failed
+    pub error: E,
+}
+
+impl<E: Display> Display for Failure<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "step {}: {}", self.step, self.error)
+    }
+}
+
+impl<E: core::error::Error> core::error::Error for Failure<E> {}
+
+/// What happened during the [demo](crate::demo), failing with errors `E`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemoReport<E> {
+    /// Time each finished lap of moving a box took
+    pub laps: Vec<Duration>,
+    /// Failures recovered from, in order
+    pub recoveries: Vec<Recovery>,
+    /// The failure ending the demo early, none if it completed
+    pub failure: Option<Failure<E>>,
+}
+
+impl<E> DemoReport<E> {
+    /// Create an empty [`DemoReport`]
+    pub fn new() -> Self {
+        Self {
+            laps: Vec::new(),
+            recoveries: Vec::new(),
+            failure: None,
+        }
+    }
+
+    /// Whether the demo completed
+    pub fn is_success(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl<E> Default for DemoReport<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,267 @@
+//! Behavior trees deciding which [`Behavior`]s logbot runs
+//!
+//! A tree is made of [`Node`]s, where an [`Action`] runs a [`Behavior`], a
+//! [`Condition`] checks logbot or its surroundings, a [`Sequence`] ticks its
+//! children until one fails and a [`Selector`] ticks its children until one
+//! succeeds. Following the line, recovering when it is lost and aborting on a
+//! low battery is then written as:
+//!
+//! ```
+//! use calibration::SensorCalibration;
+//! use components::{mock::{MockLift, MockMotor, MockSensorController}, Left, Right};
+//! use demo::{
+//!     behavior::{Context, FindEdge, FollowFor},
+//!     cancel::CancelToken,
+//!     follow::FollowLimit,
+//!     tree::{Action, Condition, Node, OnLine, Selector, Sequence, Status},
+//! };
+//! use logbot::Logbot;
+//! use tuning::Tuning;
+//! use vehicle::Vehicle;
+//!
+//! let mut logbot = Logbot::builder()
+//!     .vehicle(Vehicle::new(MockMotor::<Left>::new(), MockMotor::<Right>::new()))
+//!     .sensors(MockSensorController::new(200))
+//!     .lift(MockLift::new())
+//!     .build();
+//! let mut context = Context::new(Tuning::DEMO, CancelToken::new());
+//! let calibration = SensorCalibration::new(200, 40);
+//! context.calibration = Some((calibration, calibration));
+//!
+//! let battery_ok = || true;
+//! let limit = FollowLimit { duration_ms: Some(10), ..FollowLimit::default() };
+//! let mut tree = Sequence::new(vec![
+//!     // Abort on a low battery
+//!     Box::new(Condition::new(move |_, _| Ok(battery_ok()))),
+//!     Box::new(Selector::new(vec![
+//!         // Follow the line while it is seen
+//!         Box::new(Sequence::new(vec![
+//!             Box::new(OnLine),
+//!             Box::new(Action(FollowFor(limit))),
+//!         ])),
+//!         // Otherwise find it again
+//!         Box::new(Action(FindEdge::left(&Tuning::DEMO))),
+//!     ])),
+//! ]);
+//!
+//! assert_eq!(tree.tick(&mut logbot, &mut context).unwrap(), Status::Success);
+//! ```
+
+use std::fmt::Debug;
+
+use consts::Sensors;
+use interfaces::{Drive, Lift, SensorRead};
+use logbot::error::LogbotError;
+
+use crate::{
+    behavior::{Behavior, Context},
+    DemoError,
+};
+
+/// Outcome of ticking a [`Node`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The node did what it was meant to
+    Success,
+    /// The node did not apply, so another branch of the tree is tried
+    Failure,
+}
+
+impl From<bool> for Status {
+    fn from(success: bool) -> Self {
+        match success {
+            true => Self::Success,
+            false => Self::Failure,
+        }
+    }
+}
+
+/// Node of a behavior tree
+///
+/// Errors of the components and cancellation abort the whole tree, while a
+/// [`Status::Failure`] lets the parent node decide what happens next.
+pub trait Node<L>
+where
+    L: Drive + SensorRead + Lift,
+{
+    /// Tick the node and the nodes below it
+    fn tick(&mut self, logbot: &mut L, context: &mut Context) -> Result<Status, DemoError<L>>;
+}
+
+/// Node running a [`Behavior`], succeeding once it is done
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Action<B>(pub B);
+
+impl<L, B> Node<L> for Action<B>
+where
+    L: Drive + SensorRead + Lift,
+    B: Behavior<L>,
+{
+    fn tick(&mut self, logbot: &mut L, context: &mut Context) -> Result<Status, DemoError<L>> {
+        self.0.run(logbot, context)?;
+        Ok(Status::Success)
+    }
+}
+
+/// Node succeeding when a check of logbot and the [`Context`] holds
+#[derive(Debug, Clone, Copy)]
+pub struct Condition<F>(F);
+
+impl<F> Condition<F> {
+    /// Create a [`Condition`] from a check
+    pub fn new<L>(check: F) -> Self
+    where
+        L: Drive + SensorRead + Lift,
+        F: FnMut(&mut L, &Context) -> Result<bool, DemoError<L>>,
+    {
+        Self(check)
+    }
+}
+
+impl<L, F> Node<L> for Condition<F>
+where
+    L: Drive + SensorRead + Lift,
+    F: FnMut(&mut L, &Context) -> Result<bool, DemoError<L>>,
+{
+    fn tick(&mut self, logbot: &mut L, context: &mut Context) -> Result<Status, DemoError<L>> {
+        (self.0)(logbot, context).map(Status::from)
+    }
+}
+
+/// Condition succeeding while the left sensor sees the line, failing when
+/// the [`Context`] has no calibration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OnLine;
+
+impl<L> Node<L> for OnLine
+where
+    L: Drive + SensorRead<Output = u8> + Lift,
+{
+    fn tick(&mut self, logbot: &mut L, context: &mut Context) -> Result<Status, DemoError<L>> {
+        let Some((left, _)) = context.calibration else {
+            return Ok(Status::Failure);
+        };
+        let value = logbot.read(Sensors::Left).map_err(LogbotError::Sensor)?;
+        Ok(Status::from(value as f64 > left.average()))
+    }
+}
+
+/// Node ticking its children in order until one fails
+pub struct Sequence<L>(Vec<Box<dyn Node<L>>>);
+
+impl<L> Debug for Sequence<L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sequence")
+            .field("children", &self.0.len())
+            .finish()
+    }
+}
+
+impl<L> Sequence<L> {
+    /// Create a [`Sequence`] of nodes
+    pub fn new(children: Vec<Box<dyn Node<L>>>) -> Self {
+        Self(children)
+    }
+}
+
+impl<L> Node<L> for Sequence<L>
+where
+    L: Drive + SensorRead + Lift,
+{
+    fn tick(&mut self, logbot: &mut L, context: &mut Context) -> Result<Status, DemoError<L>> {
+        for child in &mut self.0 {
+            if child.tick(logbot, context)? == Status::Failure {
+                return Ok(Status::Failure);
+            };
+        }
+        Ok(Status::Success)
+    }
+}
+
+/// Node ticking its children in order until one succeeds
+pub struct Selector<L>(Vec<Box<dyn Node<L>>>);
+
+impl<L> Debug for Selector<L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Selector")
+            .field("children", &self.0.len())
+            .finish()
+    }
+}
+
+impl<L> Selector<L> {
+    /// Create a [`Selector`] of nodes
+    pub fn new(children: Vec<Box<dyn Node<L>>>) -> Self {
+        Self(children)
+    }
+}
+
+impl<L> Node<L> for Selector<L>
+where
+    L: Drive + SensorRead + Lift,
+{
+    fn tick(&mut self, logbot: &mut L, context: &mut Context) -> Result<Status, DemoError<L>> {
+        for child in &mut self.0 {
+            if child.tick(logbot, context)? == Status::Success {
+                return Ok(Status::Success);
+            };
+        }
+        Ok(Status::Failure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components::{
+        mock::{MockLift, MockMotor, MockSensorController},
+        Left, Right,
+    };
+    use interfaces::Lift;
+    use logbot::Logbot;
+    use tuning::Tuning;
+    use vehicle::Vehicle;
+
+    use super::{Action, Condition, Node, OnLine, Selector, Sequence, Status};
+    use crate::{
+        behavior::{Context, LiftDown, LiftUp},
+        cancel::CancelToken,
+    };
+
+    /// Test that a selector falls back once a sequence fails
+    #[test]
+    fn fall_back() {
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(MockSensorController::new(0))
+            .lift(MockLift::new())
+            .build();
+        let mut context = Context::new(Tuning::DEMO, CancelToken::new());
+
+        // Uncalibrated, so logbot is not known to be on the line
+        let mut tree = Selector::new(vec![
+            Box::new(Sequence::new(vec![
+                Box::new(OnLine),
+                Box::new(Action(LiftUp(None))),
+            ])),
+            Box::new(Action(LiftDown(None))),
+        ]);
+        assert_eq!(
+            tree.tick(&mut logbot, &mut context).unwrap(),
+            Status::Success
+        );
+        assert!(logbot.is_down());
+
+        let mut tree = Sequence::new(vec![
+            Box::new(Condition::new(|_, _| Ok(false))),
+            Box::new(Action(LiftUp(None))),
+        ]);
+        assert_eq!(
+            tree.tick(&mut logbot, &mut context).unwrap(),
+            Status::Failure
+        );
+        assert!(logbot.is_down());
+    }
+}
@@ -1,16 +1,25 @@
 //! Abstractions for different directions our hardware can move
 //!
-//! Primarily we implement [`MotorDirection`], [`SpinDirection`] and [`VehicleDirection`]
+//! Primarily we implement [`MotorDirection`], [`SpinDirection`], [`TurnDirection`]
+//! and [`VehicleDirection`]
 
 mod motor;
+mod parse;
 mod spin;
+mod turn;
 mod vehicle;
 
 pub use motor::MotorDirection;
+pub use parse::ParseDirectionError;
 use speed::Speed;
 pub use spin::SpinDirection;
+pub use turn::{TurnDirection, TurnSide};
 pub use vehicle::VehicleDirection;
 
+// Only used by the tests of the `serde` feature
+#[cfg(all(test, not(feature = "serde")))]
+use serde_json as _;
+
 /// Trait for allowing types to change their [`Speed`]
 pub trait SpeedControl {
     /// Get the ascossiated [`Speed`] of the type
@@ -1,16 +1,27 @@
-use std::ops::{Mul, Not};
+use std::{
+    fmt::Display,
+    ops::{Mul, Not},
+    str::FromStr,
+};
 
 use speed::Speed;
 
-use crate::{SpeedControl, Stop};
+use crate::{
+    parse::{require_speed, split_speed},
+    ParseDirectionError, SpeedControl, Stop,
+};
 
 /// Directions in which a Motor can move
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MotorDirection {
     /// Forward direction
     Forward(Speed),
     /// Backward direction
     Backward(Speed),
+    /// Motor is stopped
+    #[default]
+    Stop,
 }
 
 impl SpeedControl for MotorDirection {
@@ -18,13 +29,18 @@ impl SpeedControl for MotorDirection {
         match self {
             Self::Forward(speed) => *speed,
             Self::Backward(speed) => *speed,
+            Self::Stop => Speed::MIN,
         }
     }
 
+    /// Change the [`Speed`] of the [`MotorDirection`]
+    ///
+    /// [`MotorDirection::Stop`] has no direction, so it stays stopped
     fn with_speed(self, speed: Speed) -> Self {
         match self {
             Self::Forward(_) => Self::Forward(speed),
             Self::Backward(_) => Self::Backward(speed),
+            Self::Stop => Self::Stop,
         }
     }
 }
@@ -58,6 +74,42 @@ impl MotorDirection {
     pub fn wrapping_sub(self, speed: Speed) -> Self {
         self.wrapping_sub_f64(speed.value())
     }
+
+    /// Create a [`MotorDirection`] from a signed speed between -1.0 and 1.0
+    ///
+    /// Positive values are [forward](Self::Forward), negative values are
+    /// [backward](Self::Backward) and zero is [stop](Self::Stop).
+    /// Values outside of the bounds are clamped.
+    pub fn from_signed(value: f64) -> Self {
+        if value > 0.0 {
+            Self::Forward(Speed::new_clamp(value))
+        } else if value < 0.0 {
+            Self::Backward(Speed::new_clamp(-value))
+        } else {
+            Self::Stop
+        }
+    }
+
+    /// The speed of the [`MotorDirection`] as a signed value between -1.0 and 1.0
+    ///
+    /// [Backward](Self::Backward) speeds are negative
+    pub fn signed(&self) -> f64 {
+        match self {
+            Self::Forward(speed) => speed.value(),
+            Self::Backward(speed) => -speed.value(),
+            Self::Stop => 0.0,
+        }
+    }
+
+    /// Linearly interpolate between two [`MotorDirection`]s
+    ///
+    /// `t` is clamped between 0.0 (`self`) and 1.0 (`other`). Interpolating
+    /// between opposite directions passes through [stop](Self::Stop).
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let from = self.signed();
+        Self::from_signed(from + (other.signed() - from) * t)
+    }
 }
 
 impl Stop for MotorDirection {
@@ -81,6 +133,32 @@ impl Not for MotorDirection {
         match self {
             Self::Forward(speed) => Self::Backward(speed),
             Self::Backward(speed) => Self::Forward(speed),
+            Self::Stop => Self::Stop,
+        }
+    }
+}
+
+/// Format as `forward:<speed>`, `backward:<speed>` or `stop`
+impl Display for MotorDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Forward(speed) => write!(f, "forward:{}", speed.value()),
+            Self::Backward(speed) => write!(f, "backward:{}", speed.value()),
+            Self::Stop => f.write_str("stop"),
+        }
+    }
+}
+
+impl FromStr for MotorDirection {
+    type Err = ParseDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, speed) = split_speed(s)?;
+        match name {
+            "forward" => Ok(Self::Forward(require_speed(name, speed)?)),
+            "backward" => Ok(Self::Backward(require_speed(name, speed)?)),
+            "stop" => Ok(Self::Stop),
+            _ => Err(ParseDirectionError::UnknownDirection(name.to_string())),
         }
     }
 }
@@ -89,9 +167,10 @@ impl Not for MotorDirection {
 mod tests {
     use std::ops::Not;
 
+    use proptest::prelude::*;
     use speed::Speed;
 
-    use crate::{MotorDirection, SpeedControl};
+    use crate::{MotorDirection, SpeedControl, Stop};
 
     /// Verify that the .speed() function returns the correct speed
     #[test]
@@ -117,4 +196,104 @@ mod tests {
             MotorDirection::Backward(speed)
         );
     }
+
+    /// Verify that [`MotorDirection::Stop`] has no speed and is a stop
+    #[test]
+    fn stop_is_stop() {
+        assert_eq!(MotorDirection::Stop.speed(), Speed::MIN);
+        assert!(MotorDirection::Stop.is_stop());
+    }
+
+    /// Verify that interpolating between opposite directions crosses zero
+    #[test]
+    fn lerp_crosses_stop() {
+        let forward = MotorDirection::Forward(Speed::MAX);
+        let backward = MotorDirection::Backward(Speed::MAX);
+        assert_eq!(forward.lerp(backward, 0.0), forward);
+        assert_eq!(forward.lerp(backward, 0.5), MotorDirection::Stop);
+        assert_eq!(
+            forward.lerp(backward, 0.75),
+            MotorDirection::Backward(Speed::HALF)
+        );
+        assert_eq!(forward.lerp(backward, 2.0), backward);
+    }
+
+    /// Verify that [`MotorDirection`] can be parsed back from its [`Display`](std::fmt::Display) output
+    #[test]
+    fn display_round_trip() {
+        for direction in [
+            MotorDirection::Forward(Speed::HALF),
+            MotorDirection::Backward(Speed::MAX),
+            MotorDirection::Stop,
+        ] {
+            assert_eq!(direction.to_string().parse(), Ok(direction));
+        }
+    }
+
+    /// Verify that parsing rejects missing and out of bounds speeds
+    #[test]
+    fn parse_rejects_invalid() {
+        assert!("forward".parse::<MotorDirection>().is_err());
+        assert!("forward:1.5".parse::<MotorDirection>().is_err());
+        assert!("sideways:0.1".parse::<MotorDirection>().is_err());
+    }
+
+    /// Verify that changing the speed of [`MotorDirection::Stop`] keeps it stopped
+    #[test]
+    fn stop_with_speed_stays_stop() {
+        assert_eq!(
+            MotorDirection::Stop.with_speed(Speed::MAX),
+            MotorDirection::Stop
+        );
+    }
+
+    /// Any [`MotorDirection`]
+    fn motor_direction() -> impl Strategy<Value = MotorDirection> {
+        let speed = (0.0..=1.0f64).prop_map(Speed::new_clamp);
+        prop_oneof![
+            speed.clone().prop_map(MotorDirection::Forward),
+            speed.prop_map(MotorDirection::Backward),
+            Just(MotorDirection::Stop),
+        ]
+    }
+
+    proptest! {
+        /// Verify that wrapping subtraction keeps the speed in bounds for
+        /// any value, including NaN and the infinities
+        #[test]
+        fn wrapping_sub_stays_in_bounds(direction in motor_direction(), value in prop::num::f64::ANY) {
+            let speed = direction.wrapping_sub_f64(value).speed().value();
+            prop_assert!((0.0..=1.0).contains(&speed), "{speed}");
+        }
+
+        /// Verify that wrapping subtraction turns the motor around once the
+        /// speed drops below zero, and never starts a stopped motor
+        #[test]
+        fn wrapping_sub_turns_around(direction in motor_direction(), value in -2.0..2.0f64) {
+            let result = direction.wrapping_sub_f64(value);
+            let sign = match direction {
+                MotorDirection::Forward(_) => 1.0,
+                MotorDirection::Backward(_) => -1.0,
+                MotorDirection::Stop => 0.0,
+            };
+            let expected = sign * (direction.speed().value() - value).clamp(-1.0, 1.0);
+            prop_assert_eq!(result.signed(), expected);
+            prop_assert_eq!(result == MotorDirection::Stop, direction == MotorDirection::Stop);
+            prop_assert_eq!(direction.wrapping_sub(Speed::new_clamp(value)), direction.wrapping_sub_f64(value.clamp(0.0, 1.0)));
+        }
+
+        /// Verify that saturating addition never changes the direction
+        #[test]
+        fn saturating_add_keeps_direction(direction in motor_direction(), value in prop::num::f64::ANY) {
+            let result = direction.saturating_add_f64(value);
+            prop_assert_eq!(result.with_speed(Speed::MIN), direction.with_speed(Speed::MIN));
+            prop_assert!((0.0..=1.0).contains(&result.speed().value()));
+        }
+
+        /// Verify that a signed speed within bounds is kept
+        #[test]
+        fn signed_round_trip(value in -1.0..=1.0f64) {
+            prop_assert_eq!(MotorDirection::from_signed(value).signed(), value);
+        }
+    }
 }
@@ -0,0 +1,52 @@
+//! Shared helpers for parsing directions from strings
+
+use std::fmt::Display;
+
+use speed::Speed;
+
+/// Errors that can occur when parsing a direction from a string
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseDirectionError {
+    /// The name of the direction is not known
+    UnknownDirection(String),
+    /// The direction requires a [`Speed`], but none was given
+    MissingSpeed(String),
+    /// The [`Speed`] could not be parsed or is out of bounds
+    InvalidSpeed(String),
+}
+
+impl Display for ParseDirectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownDirection(name) => write!(f, "unknown direction `{name}`"),
+            Self::MissingSpeed(name) => write!(f, "direction `{name}` requires a speed"),
+            Self::InvalidSpeed(value) => write!(f, "invalid speed `{value}`"),
+        }
+    }
+}
+
+impl core::error::Error for ParseDirectionError {}
+
+/// Split a `name:speed` pair, parsing the optional [`Speed`]
+pub(crate) fn split_speed(value: &str) -> Result<(&str, Option<Speed>), ParseDirectionError> {
+    match value.trim().split_once(':') {
+        Some((name, speed)) => {
+            let speed = speed
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(|speed| Speed::new(speed).ok())
+                .ok_or_else(|| ParseDirectionError::InvalidSpeed(speed.trim().to_string()))?;
+            Ok((name.trim(), Some(speed)))
+        }
+        None => Ok((value.trim(), None)),
+    }
+}
+
+/// Require that a [`Speed`] was parsed for a given direction name
+pub(crate) fn require_speed(
+    name: &str,
+    speed: Option<Speed>,
+) -> Result<Speed, ParseDirectionError> {
+    speed.ok_or_else(|| ParseDirectionError::MissingSpeed(name.to_string()))
+}
@@ -1,11 +1,19 @@
-use std::ops::{Mul, Not};
+use std::{
+    fmt::Display,
+    ops::{Mul, Not},
+    str::FromStr,
+};
 
 use speed::Speed;
 
-use crate::{SpeedControl, Stop};
+use crate::{
+    parse::{require_speed, split_speed},
+    ParseDirectionError, SpeedControl, Stop,
+};
 
 /// Directions in which a Vehicle can spin in-place
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SpinDirection {
     /// Left spin
     Left(Speed),
@@ -54,6 +62,29 @@ impl Not for SpinDirection {
     }
 }
 
+/// Format as `spin_left:<speed>` or `spin_right:<speed>`
+impl Display for SpinDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Left(speed) => write!(f, "spin_left:{}", speed.value()),
+            Self::Right(speed) => write!(f, "spin_right:{}", speed.value()),
+        }
+    }
+}
+
+impl FromStr for SpinDirection {
+    type Err = ParseDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, speed) = split_speed(s)?;
+        match name {
+            "spin_left" => Ok(Self::Left(require_speed(name, speed)?)),
+            "spin_right" => Ok(Self::Right(require_speed(name, speed)?)),
+            _ => Err(ParseDirectionError::UnknownDirection(name.to_string())),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::Not;
@@ -70,4 +101,17 @@ mod tests {
         let direction = SpinDirection::Right(speed);
         assert_eq!(direction.not(), SpinDirection::Left(speed));
     }
+
+    /// Test that [`SpinDirection`] parses the textual representation
+    #[test]
+    fn parse_spin() {
+        assert_eq!(
+            "spin_left:0.1".parse(),
+            Ok(SpinDirection::Left(Speed::new_clamp(0.1)))
+        );
+        assert_eq!(
+            SpinDirection::Right(Speed::HALF).to_string(),
+            "spin_right:0.5"
+        );
+    }
 }
@@ -0,0 +1,109 @@
+use speed::Speed;
+
+use crate::{MotorDirection, SpeedControl, Stop, VehicleDirection};
+
+/// Side towards which a [`TurnDirection`] curves
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TurnSide {
+    /// Curve to the left
+    Left,
+    /// Curve to the right
+    Right,
+}
+
+/// Drive forward along an arc with a given radius
+///
+/// The radius is measured from the center of the turn to the center of the
+/// vehicle, using the same unit as the wheelbase given when converting into
+/// a [`VehicleDirection`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TurnDirection {
+    /// The side towards which the vehicle turns
+    pub side: TurnSide,
+    /// Radius of the turn, a radius of 0.0 spins the vehicle in-place
+    pub radius: f64,
+    /// The [`Speed`] of the outer wheel
+    pub speed: Speed,
+}
+
+impl TurnDirection {
+    /// Create a new [`TurnDirection`]
+    pub fn new(side: TurnSide, radius: f64, speed: Speed) -> Self {
+        Self {
+            side,
+            radius,
+            speed,
+        }
+    }
+
+    /// Convert the [`TurnDirection`] into a [`VehicleDirection`] for a vehicle
+    /// with a given wheelbase (distance between the two wheels)
+    ///
+    /// The outer wheel drives at [speed](Self::speed), while the inner wheel is
+    /// slowed down proportionally to the radius of its own arc. When the radius
+    /// is smaller than half of the wheelbase the inner wheel drives backward.
+    pub fn into_vehicle_direction(self, wheelbase: f64) -> VehicleDirection {
+        let half = wheelbase / 2.0;
+        let radius = self.radius.abs();
+
+        // Ratio between the inner and outer wheel arcs, ranges from -1.0 to 1.0
+        let ratio = if radius + half > 0.0 {
+            (radius - half) / (radius + half)
+        } else {
+            1.0
+        };
+
+        let outer = MotorDirection::Forward(self.speed);
+        let inner = outer.wrapping_sub_f64(self.speed.value() * (1.0 - ratio));
+
+        match self.side {
+            TurnSide::Left => VehicleDirection::new(inner, outer),
+            TurnSide::Right => VehicleDirection::new(outer, inner),
+        }
+    }
+}
+
+impl SpeedControl for TurnDirection {
+    fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    fn with_speed(self, speed: Speed) -> Self {
+        Self { speed, ..self }
+    }
+}
+
+impl Stop for TurnDirection {
+    fn is_stop(&self) -> bool {
+        self.speed.value() == 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use speed::Speed;
+
+    use super::{TurnDirection, TurnSide};
+    use crate::{MotorDirection, SpeedControl, VehicleDirection};
+
+    /// Test that a radius of zero spins the vehicle in-place
+    #[test]
+    fn zero_radius_spins() {
+        let turn = TurnDirection::new(TurnSide::Left, 0.0, Speed::HALF);
+        assert_eq!(
+            turn.into_vehicle_direction(0.2),
+            VehicleDirection::spin_left(Speed::HALF)
+        );
+    }
+
+    /// Test that the inner wheel stands still when turning around it
+    #[test]
+    fn half_wheelbase_radius_pivots() {
+        let turn = TurnDirection::new(TurnSide::Right, 0.1, Speed::HALF);
+        let direction = turn.into_vehicle_direction(0.2);
+        assert_eq!(direction.left, MotorDirection::Forward(Speed::HALF));
+        assert_eq!(direction.right.speed().value(), 0.0);
+    }
+}
@@ -1,10 +1,17 @@
-use std::ops::Mul;
+use std::{
+    fmt::Display,
+    ops::{Mul, Not},
+    str::FromStr,
+};
 
-use crate::{MotorDirection, SpeedControl, SpinDirection, Stop};
+use crate::{
+    MotorDirection, ParseDirectionError, SpeedControl, SpinDirection, Stop, TurnDirection,
+};
 use speed::Speed;
 
 /// Represents directions a vehicle can take
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VehicleDirection {
     /// The [`MotorDirection`] for the left motor
     pub left: MotorDirection,
@@ -13,10 +20,85 @@ pub struct VehicleDirection {
 }
 
 impl VehicleDirection {
+    /// Both motors are [stopped](MotorDirection::Stop)
+    pub const STOP: Self = Self::new(MotorDirection::Stop, MotorDirection::Stop);
+
     /// Create a new [`VehicleDirection`] from the individual [`MotorDirection`]s
-    pub fn new(left: MotorDirection, right: MotorDirection) -> Self {
+    pub const fn new(left: MotorDirection, right: MotorDirection) -> Self {
         Self { left, right }
     }
+
+    /// Create a [`VehicleDirection`] from joystick axes using arcade (differential) mixing
+    ///
+    /// `x` steers (positive is right) and `y` throttles (positive is forward),
+    /// both are clamped between -1.0 and 1.0. The motor speeds are scaled down
+    /// together so neither exceeds `max`, preserving the ratio between them.
+    pub fn from_axes(x: f64, y: f64, max: Speed) -> Self {
+        let x = x.clamp(-1.0, 1.0);
+        let y = y.clamp(-1.0, 1.0);
+
+        let left = y + x;
+        let right = y - x;
+
+        // Normalize so that the faster motor runs at most at full speed
+        let scale = left.abs().max(right.abs()).max(1.0);
+
+        Self::new(
+            MotorDirection::from_signed(left / scale * max.value()),
+            MotorDirection::from_signed(right / scale * max.value()),
+        )
+    }
+
+    /// Flip the [`MotorDirection`] of both motors
+    ///
+    /// Driving forward becomes driving backward and a left spin becomes a right spin
+    pub fn reversed(self) -> Self {
+        Self::new(self.left.not(), self.right.not())
+    }
+
+    /// Swap the left and right [`MotorDirection`]s
+    ///
+    /// Useful when the chassis is mounted mirrored, a left spin becomes a right spin
+    pub fn mirrored(self) -> Self {
+        Self::new(self.right, self.left)
+    }
+
+    /// Linearly interpolate between two [`VehicleDirection`]s
+    ///
+    /// Each motor is interpolated separately, see [`MotorDirection::lerp`]
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        Self::new(
+            self.left.lerp(other.left, t),
+            self.right.lerp(other.right, t),
+        )
+    }
+
+    /// Blend multiple weighted [`VehicleDirection`]s into one
+    ///
+    /// Each motor is the weighted average of the signed speeds of all votes.
+    /// Non-positive weights are ignored, when no vote has a positive weight
+    /// the result is [`VehicleDirection::STOP`].
+    pub fn blend(votes: &[(VehicleDirection, f64)]) -> Self {
+        let (left, right, total) = votes.iter().filter(|(_, weight)| *weight > 0.0).fold(
+            (0.0, 0.0, 0.0),
+            |(left, right, total), (direction, weight)| {
+                (
+                    left + direction.left.signed() * weight,
+                    right + direction.right.signed() * weight,
+                    total + weight,
+                )
+            },
+        );
+
+        if total == 0.0 {
+            return Self::STOP;
+        };
+
+        Self::new(
+            MotorDirection::from_signed(left / total),
+            MotorDirection::from_signed(right / total),
+        )
+    }
 }
 
 // Implement basic directions with a given [`Speed`]
@@ -37,21 +119,11 @@ impl VehicleDirection {
         )
     }
 
-    /// Turn into a [`SpinDirection`] with a given [`Speed`]
+    /// Turn along the arc of a [`TurnDirection`] for a vehicle with a given wheelbase
     ///
-    /// The [`Speed`] component of the [`SpinDirection`] is subtracted from the
-    /// [`MotorDirection`] on the side on which the [`SpinDirection`] instructs
-    pub fn turn(speed: Speed, direction: SpinDirection) -> Self {
-        match direction {
-            SpinDirection::Left(ratio) => Self::new(
-                MotorDirection::Forward(speed).wrapping_sub_f64(2.0 * ratio.value()),
-                MotorDirection::Forward(speed),
-            ),
-            SpinDirection::Right(ratio) => Self::new(
-                MotorDirection::Forward(speed),
-                MotorDirection::Forward(speed).wrapping_sub_f64(2.0 * ratio.value()),
-            ),
-        }
+    /// See [`TurnDirection::into_vehicle_direction`]
+    pub fn turn(direction: TurnDirection, wheelbase: f64) -> Self {
+        direction.into_vehicle_direction(wheelbase)
     }
 
     /// Spin the vehicle to the left in-place with a given [`Speed`]
@@ -99,3 +171,156 @@ impl From<SpinDirection> for VehicleDirection {
         }
     }
 }
+
+/// Format using the shortest representation
+///
+/// Equal motors are formatted as a [`MotorDirection`] (e.g. `forward:0.3`),
+/// in-place spins as a [`SpinDirection`] (e.g. `spin_left:0.1`) and anything
+/// else as the left and right [`MotorDirection`] separated by a comma.
+impl Display for VehicleDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.left, self.right) {
+            (left, right) if left == right => left.fmt(f),
+            (MotorDirection::Backward(left), MotorDirection::Forward(right)) if left == right => {
+                SpinDirection::Left(left).fmt(f)
+            }
+            (MotorDirection::Forward(left), MotorDirection::Backward(right)) if left == right => {
+                SpinDirection::Right(left).fmt(f)
+            }
+            (left, right) => write!(f, "{left},{right}"),
+        }
+    }
+}
+
+impl FromStr for VehicleDirection {
+    type Err = ParseDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((left, right)) = s.split_once(',') {
+            return Ok(Self::new(left.parse()?, right.parse()?));
+        };
+
+        if s.trim().starts_with("spin_") {
+            return Ok(s.parse::<SpinDirection>()?.into());
+        };
+
+        let direction = s.parse::<MotorDirection>()?;
+        Ok(Self::new(direction, direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use speed::Speed;
+
+    use super::VehicleDirection;
+    use crate::MotorDirection;
+
+    /// Test that interpolating from a stop towards a direction scales both motors
+    #[test]
+    fn lerp_from_stop() {
+        let target = VehicleDirection::forward(Speed::MAX);
+        assert_eq!(
+            VehicleDirection::STOP.lerp(target, 0.5),
+            VehicleDirection::forward(Speed::HALF)
+        );
+    }
+
+    /// Test that the pure axes map onto the basic directions
+    #[test]
+    fn from_axes_basic_directions() {
+        let speed = Speed::HALF;
+        assert_eq!(
+            VehicleDirection::from_axes(0.0, 1.0, speed),
+            VehicleDirection::forward(speed)
+        );
+        assert_eq!(
+            VehicleDirection::from_axes(0.0, -1.0, speed),
+            VehicleDirection::backward(speed)
+        );
+        assert_eq!(
+            VehicleDirection::from_axes(1.0, 0.0, speed),
+            VehicleDirection::spin_right(speed)
+        );
+        assert_eq!(
+            VehicleDirection::from_axes(0.0, 0.0, speed),
+            VehicleDirection::STOP
+        );
+    }
+
+    /// Test that mixed axes never exceed the maximum speed
+    #[test]
+    fn from_axes_normalizes() {
+        let direction = VehicleDirection::from_axes(-0.5, 1.0, Speed::MAX);
+        assert_eq!(direction.right, MotorDirection::Forward(Speed::MAX));
+        assert!((direction.left.signed() - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    /// Test that reversing flips the direction of both motors
+    #[test]
+    fn reversed_flips_motors() {
+        let speed = Speed::HALF;
+        assert_eq!(
+            VehicleDirection::forward(speed).reversed(),
+            VehicleDirection::backward(speed)
+        );
+        assert_eq!(VehicleDirection::STOP.reversed(), VehicleDirection::STOP);
+    }
+
+    /// Test that mirroring swaps left and right
+    #[test]
+    fn mirrored_swaps_sides() {
+        let speed = Speed::HALF;
+        assert_eq!(
+            VehicleDirection::spin_left(speed).mirrored(),
+            VehicleDirection::spin_right(speed)
+        );
+    }
+
+    /// Test that [`VehicleDirection`] can be parsed back from its textual representation
+    #[test]
+    fn display_round_trip() {
+        let speed = Speed::HALF;
+        for (direction, text) in [
+            (VehicleDirection::forward(speed), "forward:0.5"),
+            (VehicleDirection::spin_left(speed), "spin_left:0.5"),
+            (VehicleDirection::STOP, "stop"),
+            (
+                VehicleDirection::new(MotorDirection::Forward(speed), MotorDirection::Stop),
+                "forward:0.5,stop",
+            ),
+        ] {
+            assert_eq!(direction.to_string(), text);
+            assert_eq!(text.parse(), Ok(direction));
+        }
+    }
+
+    /// Test that blending averages the votes by their weights
+    #[test]
+    fn blend_weighted_average() {
+        let forward = VehicleDirection::forward(Speed::MAX);
+        let spin = VehicleDirection::spin_right(Speed::MAX);
+        let blended = VehicleDirection::blend(&[(forward, 3.0), (spin, 1.0), (spin, -1.0)]);
+        assert_eq!(blended.left, MotorDirection::Forward(Speed::MAX));
+        assert_eq!(blended.right, MotorDirection::Forward(Speed::HALF));
+    }
+
+    /// Test that blending without any positive weight stops
+    #[test]
+    fn blend_empty_stops() {
+        assert_eq!(VehicleDirection::blend(&[]), VehicleDirection::STOP);
+    }
+
+    /// Test that a [`VehicleDirection`] survives a round-trip through JSON
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let direction = VehicleDirection::spin_left(Speed::HALF);
+        let json = serde_json::to_string(&direction).unwrap();
+        assert_eq!(json, r#"{"left":{"Backward":0.5},"right":{"Forward":0.5}}"#);
+        assert_eq!(
+            serde_json::from_str::<VehicleDirection>(&json).unwrap(),
+            direction
+        );
+    }
+}
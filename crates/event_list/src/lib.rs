@@ -86,6 +86,11 @@ impl<T> TimedSequence<T> {
         self.end.map(|v| v.duration_since(self.start))
     }
 
+    /// The [`CompletedEvent`]'s of the sequence in order
+    pub fn events(&self) -> &[CompletedEvent<T>] {
+        &self.values
+    }
+
     /// Complete the sequence by replacing [end](Self::end) with an [`Instant`]
     pub fn complete(&mut self, end: Instant) -> Option<Instant> {
         self.end.replace(end)
@@ -140,6 +145,13 @@ impl<T> EventList<T> {
         }
     }
 
+    /// Iterate over all [`CompletedEvent`]'s of all sequences in order
+    pub fn events(&self) -> impl Iterator<Item = &CompletedEvent<T>> {
+        self.completed_events
+            .iter()
+            .flat_map(|sequence| sequence.values.iter())
+    }
+
     /// The current [`ActiveEvent`]
     pub fn active_event(&self) -> &Option<ActiveEvent<T>> {
         &self.active_event
@@ -168,6 +180,12 @@ impl<T> EventList<T> {
         }
     }
 
+    /// Remove the oldest sequences, keeping at most `len` of them
+    pub fn truncate_front(&mut self, len: usize) {
+        let excess = self.completed_events.len().saturating_sub(len);
+        self.completed_events.drain(..excess);
+    }
+
     /// The total number of completed sequences
     pub fn total_completed_sequences(&self) -> usize {
         let mut len = self.completed_events.len();
@@ -0,0 +1,77 @@
+//! Hardware abstraction layer of the board logbot runs on
+//!
+//! The hardware components of the `components` crate are written against the
+//! traits of this crate instead of a specific board: an [`OutputPin`] drives
+//! the motors using software PWM, an [`InputPin`] reads the end switches of
+//! the lift, a [`PwmChannel`] generates hardware PWM and an [`I2cBus`] talks
+//! to the sensor controller.
+//!
+//! With the `rppal` feature, which is enabled by default, the traits are
+//! implemented for the GPIO pins, PWM channels and I2C bus of a Raspberry Pi
+//! using the [`rppal`](https://docs.rs/rppal) crate. Other boards, such as a
+//! BeagleBone or an x86 single-board computer, are supported by implementing
+//! the traits on top of their drivers, e.g. the Linux GPIO character device
+//! and `i2c-dev`, and creating the components from those types.
+
+use std::time::Duration;
+
+#[cfg(feature = "rppal")]
+mod pi;
+
+/// A GPIO pin set to output
+///
+/// Besides setting the pin high or low, the pin generates a PWM signal in
+/// software until the PWM is cleared.
+pub trait OutputPin {
+    /// Error type
+    type Error;
+
+    /// Set the pin high
+    fn set_high(&mut self) -> Result<(), Self::Error>;
+
+    /// Set the pin low
+    fn set_low(&mut self) -> Result<(), Self::Error>;
+
+    /// Generate a software PWM signal with a period and a pulse width
+    fn set_pwm(&mut self, period: Duration, pulse_width: Duration) -> Result<(), Self::Error>;
+
+    /// Generate a software PWM signal with a frequency in Hz and a duty cycle
+    /// from 0.0 to 1.0
+    fn set_pwm_frequency(&mut self, frequency: f64, duty_cycle: f64) -> Result<(), Self::Error>;
+
+    /// Stop generating the software PWM signal
+    fn clear_pwm(&mut self) -> Result<(), Self::Error>;
+}
+
+/// A GPIO pin set to input
+pub trait InputPin {
+    /// Whether the pin reads low
+    fn is_low(&self) -> bool;
+}
+
+/// A channel generating a PWM signal in hardware
+pub trait PwmChannel {
+    /// Error type
+    type Error;
+
+    /// Set the period of the signal
+    fn set_period(&mut self, period: Duration) -> Result<(), Self::Error>;
+
+    /// Set the pulse width of the signal
+    fn set_pulse_width(&mut self, pulse_width: Duration) -> Result<(), Self::Error>;
+
+    /// Start generating the signal
+    fn enable(&mut self) -> Result<(), Self::Error>;
+}
+
+/// An I2C bus addressing a single device
+pub trait I2cBus {
+    /// Error type
+    type Error;
+
+    /// Write all bytes to the device
+    fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error>;
+
+    /// Fill the buffer with bytes read from the device
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
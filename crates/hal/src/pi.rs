@@ -0,0 +1,67 @@
+//! Backend for the Raspberry Pi using [`rppal`]
+
+use std::time::Duration;
+
+use rppal::{gpio, i2c, pwm};
+
+use crate::{I2cBus, InputPin, OutputPin, PwmChannel};
+
+impl OutputPin for gpio::OutputPin {
+    type Error = gpio::Error;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        gpio::OutputPin::set_high(self);
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        gpio::OutputPin::set_low(self);
+        Ok(())
+    }
+
+    fn set_pwm(&mut self, period: Duration, pulse_width: Duration) -> Result<(), Self::Error> {
+        gpio::OutputPin::set_pwm(self, period, pulse_width)
+    }
+
+    fn set_pwm_frequency(&mut self, frequency: f64, duty_cycle: f64) -> Result<(), Self::Error> {
+        gpio::OutputPin::set_pwm_frequency(self, frequency, duty_cycle)
+    }
+
+    fn clear_pwm(&mut self) -> Result<(), Self::Error> {
+        gpio::OutputPin::clear_pwm(self)
+    }
+}
+
+impl InputPin for gpio::InputPin {
+    fn is_low(&self) -> bool {
+        gpio::InputPin::is_low(self)
+    }
+}
+
+impl PwmChannel for pwm::Pwm {
+    type Error = pwm::Error;
+
+    fn set_period(&mut self, period: Duration) -> Result<(), Self::Error> {
+        pwm::Pwm::set_period(self, period)
+    }
+
+    fn set_pulse_width(&mut self, pulse_width: Duration) -> Result<(), Self::Error> {
+        pwm::Pwm::set_pulse_width(self, pulse_width)
+    }
+
+    fn enable(&mut self) -> Result<(), Self::Error> {
+        pwm::Pwm::enable(self)
+    }
+}
+
+impl I2cBus for i2c::I2c {
+    type Error = i2c::Error;
+
+    fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error> {
+        i2c::I2c::write(self, buffer).map(|_| ())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        i2c::I2c::read(self, buffer).map(|_| ())
+    }
+}
@@ -1,6 +1,8 @@
 //! Define core abstractions which are completely generic
 
-use std::ops::Not;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+use std::{ops::Not, time::Duration};
 
 use speed::Speed;
 
@@ -20,6 +22,12 @@ pub trait Drive {
     fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error>;
 }
 
+/// Trait that defines a driveable as knowing the direction it is driven in
+pub trait DriveState: Drive {
+    /// The direction the component is currently driven in
+    fn state(&self) -> Self::Direction;
+}
+
 /// Trait that defines a driveable as being able to spin
 pub trait Spin: Drive {
     /// The enum/struct used for indicating the spin direction
@@ -51,6 +59,34 @@ pub trait Lift {
     fn is_down(&self) -> bool;
 }
 
+/// Trait for a [`Lift`] that can be jogged, moving without blocking
+///
+/// A jog keeps the Lift moving until [`JogLift::halt`] is called. The position
+/// is only checked when jogging, so a jog needs to be repeated while the Lift
+/// is moving to stop it at the end of its range.
+pub trait JogLift: Lift {
+    /// Start or keep moving the Lift up, stopping it once it is up
+    fn jog_up(&mut self, speed: Speed) -> Result<(), Self::Error>;
+
+    /// Start or keep moving the Lift down, stopping it once it is down
+    fn jog_down(&mut self, speed: Speed) -> Result<(), Self::Error>;
+
+    /// Stop moving the Lift
+    fn halt(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Trait for stopping all movement of a component as fast as possible
+///
+/// Implementations should attempt to stop every moving part, even when stopping
+/// one of them fails, before returning the first error.
+pub trait EmergencyStop {
+    /// Error type
+    type Error;
+
+    /// Immediately stop all movement
+    fn emergency_stop(&mut self) -> Result<(), Self::Error>;
+}
+
 /// Get the Sensor channel for a given sensor
 ///
 /// This trait returns a channel that is associated with a sensor
@@ -70,3 +106,69 @@ pub trait SensorRead {
     /// Read a value from a sensor given a sensor channel
     fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error>;
 }
+
+/// Trait for a sensor locating the line across its field of view
+///
+/// Unlike a [`SensorRead`] of a reflectance sensor, which only tells how much
+/// of the line is below it, the position tells where the line is.
+pub trait LinePosition {
+    /// Error type
+    type Error;
+
+    /// Position of the line from -1.0 (left) over 0.0 (centre) to 1.0
+    /// (right), or [None] if no line is seen
+    fn line_position(&mut self) -> Result<Option<f64>, Self::Error>;
+}
+
+/// Trait for a sensor measuring the distance to the nearest obstacle ahead,
+/// e.g. an ultrasonic or time-of-flight sensor
+pub trait RangeFinder {
+    /// Error type
+    type Error;
+
+    /// Distance to the obstacle in meters, or [None] if nothing is in range
+    fn distance(&mut self) -> Result<Option<f64>, Self::Error>;
+}
+
+/// Trait for a monotonic source of time
+///
+/// Time is measured since an arbitrary start of the clock. Targets without
+/// [`Instant`](std::time::Instant), such as `wasm32-unknown-unknown` in a
+/// browser, implement it on the time they have, e.g. `performance.now()`.
+pub trait Clock {
+    /// Time passed since the start of the clock
+    fn now(&self) -> Duration;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+/// [`Clock`] following the [`Instant`] of the system, started when created
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemClock(Instant);
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl SystemClock {
+    /// Start a new [`SystemClock`]
+    pub fn new() -> Self {
+        Self(Instant::now())
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
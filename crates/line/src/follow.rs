@@ -26,6 +26,22 @@ pub struct FollowLineConfig {
     pub reset_integral_on_target: bool,
 }
 
+/// Diagnostics of a single [step](FollowLineState::step_diagnostics) of
+/// following a line, used for tuning the controller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FollowLineStep {
+    /// Difference of the sensor value from the calibrated target
+    pub error: f64,
+    /// Change of the error since the previous step
+    pub derivative: f64,
+    /// Sum of the errors since the integral was last reset
+    pub integral: f64,
+    /// Weighted sum of the terms, steering logbot back towards the target
+    pub control: f64,
+    /// The resulting [`VehicleDirection`]
+    pub direction: VehicleDirection,
+}
+
 /// Follow a line in steps, saves state between calls to [step](Self::step) here.
 #[derive(Debug, Copy, Clone)]
 pub struct FollowLineState {
@@ -59,6 +75,12 @@ impl FollowLineState {
     ///
     /// Takes a new sensor value and calculates a new [`VehicleDirection`]
     pub fn step(&mut self, sensor_value: u8) -> VehicleDirection {
+        self.step_diagnostics(sensor_value).direction
+    }
+
+    /// Move the line following state forward like [step](Self::step), but
+    /// return all terms of the controller as a [`FollowLineStep`]
+    pub fn step_diagnostics(&mut self, sensor_value: u8) -> FollowLineStep {
         let error = sensor_value as f64 - self.config.calibration.average();
 
         self.derivative = error - self.last_error;
@@ -94,6 +116,103 @@ impl FollowLineState {
         let left = MotorDirection::Forward(speed).wrapping_sub_f64(control);
         let right = MotorDirection::Forward(speed).saturating_add_f64(control);
 
-        VehicleDirection::new(left, right)
+        FollowLineStep {
+            error,
+            derivative: self.derivative,
+            integral: self.integral,
+            control,
+            direction: VehicleDirection::new(left, right),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use calibration::SensorCalibration;
+    use directions::MotorDirection;
+    use proptest::{collection::vec, option, prelude::*};
+    use speed::Speed;
+
+    use super::{FollowLineConfig, FollowLineState};
+
+    /// Test that the diagnostics report the terms of the controller
+    #[test]
+    fn step_diagnostics() {
+        let mut state = FollowLineState::new(FollowLineConfig {
+            default_speed: Speed::HALF,
+            proportional: 0.01,
+            derivative: 0.005,
+            integral: None,
+            calibration: SensorCalibration::new(180, 40),
+            reset_integral_on_target: true,
+        });
+        let mut plain = state;
+
+        let first = state.step_diagnostics(130);
+        assert_eq!(first.error, 20.0);
+        assert_eq!(first.derivative, 20.0);
+        assert!((first.control - 0.3).abs() < 1e-9);
+        assert_eq!(first.direction, plain.step(130));
+
+        let second = state.step_diagnostics(120);
+        assert_eq!(second.derivative, -10.0);
+        assert_eq!(second.integral, 30.0);
+        assert_eq!(second.direction, plain.step(120));
+    }
+
+    /// Any [`FollowLineConfig`] with gains from a strategy
+    fn config(gain: impl Strategy<Value = f64> + Clone) -> impl Strategy<Value = FollowLineConfig> {
+        (
+            (0.0..=1.0f64).prop_map(Speed::new_clamp),
+            gain.clone(),
+            gain.clone(),
+            option::of(gain),
+            any::<(u8, u8)>(),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(default_speed, proportional, derivative, integral, (line, floor), reset)| {
+                    FollowLineConfig {
+                        default_speed,
+                        proportional,
+                        derivative,
+                        integral,
+                        calibration: SensorCalibration::new(line, floor),
+                        reset_integral_on_target: reset,
+                    }
+                },
+            )
+    }
+
+    proptest! {
+        /// Test that following the line keeps both motors within their speed
+        /// bounds and only ever turns the left motor around, whatever the
+        /// gains and the sensor values
+        #[test]
+        fn step_stays_in_bounds(config in config(prop::num::f64::ANY), values in vec(any::<u8>(), 1..100)) {
+            let mut state = FollowLineState::new(config);
+            for value in values {
+                let direction = state.step(value);
+                for motor in [direction.left, direction.right] {
+                    let speed = motor.signed().abs();
+                    prop_assert!((0.0..=1.0).contains(&speed), "{direction}");
+                }
+                prop_assert!(matches!(direction.right, MotorDirection::Forward(_)), "{direction}");
+            }
+        }
+
+        /// Test that the first step steers towards the side of the edge the
+        /// sensor is off to
+        #[test]
+        fn step_steers_towards_edge(config in config(0.0..1.0f64), value in any::<u8>()) {
+            let direction = FollowLineState::new(config).step(value);
+            let (left, right) = (direction.left.signed(), direction.right.signed());
+            let error = value as f64 - config.calibration.average();
+            if error > 0.0 {
+                prop_assert!(right >= left, "{direction}");
+            } else if error < 0.0 {
+                prop_assert!(left >= right, "{direction}");
+            };
+        }
     }
 }
@@ -5,4 +5,4 @@
 
 mod follow;
 
-pub use follow::{FollowLineConfig, FollowLineState};
+pub use follow::{FollowLineConfig, FollowLineState, FollowLineStep};
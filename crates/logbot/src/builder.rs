@@ -0,0 +1,138 @@
+//! Builder for assembling a [`Logbot`] step by step
+
+use std::time::Duration;
+
+use crate::{
+    calibrated::Uncalibrated, hooks::NoHooks, no_lift::NoLift, watchdog::Watchdog, Logbot,
+};
+
+/// Marker for a component that has not been given to a [`LogbotBuilder`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Missing;
+
+/// A component given to a [`LogbotBuilder`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Given<T>(T);
+
+/// Builder for a [`Logbot`], taking each component by name
+///
+/// The builder only [builds](Self::build) once the vehicle, the sensors and
+/// the lift are given, which is checked at compile time.
+///
+/// ```
+/// use components::{mock::{MockLift, MockMotor, MockSensorController}, Left};
+/// use logbot::Logbot;
+///
+/// let logbot = Logbot::builder()
+///     .vehicle(MockMotor::<Left>::new())
+///     .sensors(MockSensorController::new(0))
+///     .lift(MockLift::new())
+///     .build();
+/// ```
+///
+/// Leaving out a component fails to compile:
+///
+/// ```compile_fail
+/// use components::{mock::{MockMotor, MockSensorController}, Left};
+/// use logbot::Logbot;
+///
+/// let logbot = Logbot::builder()
+///     .vehicle(MockMotor::<Left>::new())
+///     .sensors(MockSensorController::new(0))
+///     .build();
+/// ```
+#[derive(Debug, Default)]
+pub struct LogbotBuilder<V, S, L, H = NoHooks> {
+    /// The vehicle, or [`Missing`]
+    vehicle: V,
+    /// The sensors, or [`Missing`]
+    sensors: S,
+    /// The lift, or [`Missing`]
+    lift: L,
+    /// Timeout of the [`Watchdog`], none to disable it
+    watchdog: Option<Duration>,
+    /// The [`hooks`](crate::hooks) of logbot
+    hooks: H,
+}
+
+impl LogbotBuilder<Missing, Missing, Missing> {
+    /// Create a [`LogbotBuilder`] without any components
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<V, S, L, H> LogbotBuilder<V, S, L, H> {
+    /// Use a vehicle that drives logbot
+    pub fn vehicle<T>(self, vehicle: T) -> LogbotBuilder<Given<T>, S, L, H> {
+        LogbotBuilder {
+            vehicle: Given(vehicle),
+            sensors: self.sensors,
+            lift: self.lift,
+            watchdog: self.watchdog,
+            hooks: self.hooks,
+        }
+    }
+
+    /// Use the sensors that read the line
+    pub fn sensors<T>(self, sensors: T) -> LogbotBuilder<V, Given<T>, L, H> {
+        LogbotBuilder {
+            vehicle: self.vehicle,
+            sensors: Given(sensors),
+            lift: self.lift,
+            watchdog: self.watchdog,
+            hooks: self.hooks,
+        }
+    }
+
+    /// Use a lift that moves boxes
+    pub fn lift<T>(self, lift: T) -> LogbotBuilder<V, S, Given<T>, H> {
+        LogbotBuilder {
+            vehicle: self.vehicle,
+            sensors: self.sensors,
+            lift: Given(lift),
+            watchdog: self.watchdog,
+            hooks: self.hooks,
+        }
+    }
+
+    /// Build logbot without a lift, see [`NoLift`]
+    pub fn without_lift(self) -> LogbotBuilder<V, S, Given<NoLift>, H> {
+        self.lift(NoLift)
+    }
+
+    /// Stop the vehicle once it goes a timeout without being driven, see
+    /// [`Watchdog`]
+    pub fn watchdog(mut self, timeout: Duration) -> Self {
+        self.watchdog = Some(timeout);
+        self
+    }
+
+    /// Call [`hooks`](crate::hooks) on every interaction with logbot,
+    /// replacing any hooks given before
+    pub fn hooks<T>(self, hooks: T) -> LogbotBuilder<V, S, L, T> {
+        LogbotBuilder {
+            vehicle: self.vehicle,
+            sensors: self.sensors,
+            lift: self.lift,
+            watchdog: self.watchdog,
+            hooks,
+        }
+    }
+}
+
+impl<V, S, L, H> LogbotBuilder<Given<V>, Given<S>, Given<L>, H> {
+    /// Build the [`Logbot`] from the given components
+    pub fn build(self) -> Logbot<V, S, L, Uncalibrated, H> {
+        Logbot {
+            vehicle: self.vehicle.0,
+            sensors: self.sensors.0,
+            lift: self.lift.0,
+            calibration: Uncalibrated,
+            watchdog: self.watchdog.map(Watchdog::new),
+            driven: None,
+            stopped: false,
+            hooks: self.hooks,
+        }
+    }
+}
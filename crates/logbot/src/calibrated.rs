@@ -0,0 +1,69 @@
+//! Tracking the calibration of the sensors at the type level
+//!
+//! A [`Logbot`] starts out [`Uncalibrated`]. Once both sensors are
+//! calibrated, e.g. by spinning over the line, the calibration is handed to
+//! [`Logbot::calibrated`], returning a [`Calibrated`] logbot. Operations that
+//! need the calibration, such as finding the edge of the line or following
+//! it, are only implemented for a [`Calibrated`] logbot, so they can't be
+//! called too early.
+
+use calibration::SensorCalibration;
+
+use crate::Logbot;
+
+/// Marker of a [`Logbot`] whose sensors are not calibrated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Uncalibrated;
+
+/// Calibration of both sensors of a [`Logbot`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibrated {
+    /// Calibration of the left sensor
+    pub left: SensorCalibration,
+    /// Calibration of the right sensor
+    pub right: SensorCalibration,
+}
+
+impl<V, S, L, H> Logbot<V, S, L, Uncalibrated, H> {
+    /// Calibrate logbot with the calibration of the left and right sensor
+    pub fn calibrated(
+        self,
+        (left, right): (SensorCalibration, SensorCalibration),
+    ) -> Logbot<V, S, L, Calibrated, H> {
+        self.with_calibration(Calibrated { left, right })
+    }
+}
+
+impl<V, S, L, H> Logbot<V, S, L, Calibrated, H> {
+    /// The [`Calibrated`] sensors of logbot
+    pub fn calibration(&self) -> Calibrated {
+        self.calibration
+    }
+
+    /// Replace the calibration of the sensors, e.g. after calibrating again
+    pub fn recalibrate(&mut self, (left, right): (SensorCalibration, SensorCalibration)) {
+        self.calibration = Calibrated { left, right };
+    }
+
+    /// Forget the calibration of the sensors, e.g. after moving logbot to
+    /// another course
+    pub fn uncalibrated(self) -> Logbot<V, S, L, Uncalibrated, H> {
+        self.with_calibration(Uncalibrated)
+    }
+}
+
+impl<V, S, L, C, H> Logbot<V, S, L, C, H> {
+    /// Move the components into a [`Logbot`] with another calibration
+    fn with_calibration<T>(self, calibration: T) -> Logbot<V, S, L, T, H> {
+        Logbot {
+            vehicle: self.vehicle,
+            sensors: self.sensors,
+            lift: self.lift,
+            calibration,
+            watchdog: self.watchdog,
+            driven: self.driven,
+            stopped: self.stopped,
+            hooks: self.hooks,
+        }
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::fmt::Display;
 
+use calibration::CalibrationError;
+
 /// Generic Logbot Error
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LogbotError<VE, SE, LE> {
@@ -11,6 +13,12 @@ pub enum LogbotError<VE, SE, LE> {
     Sensor(SE),
     /// Lift error variant
     Lift(LE),
+    /// The operation was cancelled before it finished
+    Cancelled,
+    /// The operation did not finish in time
+    TimedOut,
+    /// Calibrating the sensors gave an implausible result
+    Calibration(CalibrationError),
 }
 
 impl<VE, SE, LE> Display for LogbotError<VE, SE, LE>
@@ -24,6 +32,9 @@ where
             Self::Vehicle(e) => e.fmt(f),
             Self::Sensor(e) => e.fmt(f),
             Self::Lift(e) => e.fmt(f),
+            Self::Cancelled => f.write_str("cancelled"),
+            Self::TimedOut => f.write_str("timed out"),
+            Self::Calibration(e) => e.fmt(f),
         }
     }
 }
@@ -39,6 +50,41 @@ where
             Self::Vehicle(e) => e.source(),
             Self::Sensor(e) => e.source(),
             Self::Lift(e) => e.source(),
+            Self::Cancelled | Self::TimedOut | Self::Calibration(_) => None,
+        }
+    }
+}
+
+/// Error moving a component of a [`Logbot`](crate::Logbot)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MotionError<E> {
+    /// Logbot is latched by an emergency stop until it is
+    /// [reset](crate::Logbot::reset)
+    Stopped,
+    /// The component failed to move
+    Component(E),
+}
+
+impl<E> Display for MotionError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stopped => f.write_str("logbot is emergency stopped until it is reset"),
+            Self::Component(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E> std::error::Error for MotionError<E>
+where
+    E: std::error::Error,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Stopped => None,
+            Self::Component(e) => e.source(),
         }
     }
 }
@@ -0,0 +1,261 @@
+//! Hooks layering behavior on top of a [`Logbot`](crate::Logbot)
+//!
+//! Cross-cutting concerns such as recording events, tracing or limiting the
+//! speed are implemented once as hooks, instead of wrapping every component
+//! trait. The hooks are given to the [builder](crate::builder::LogbotBuilder)
+//! and called by logbot before every motion reaches the components and after
+//! every sensor read. Each hook trait has a default for every method, so a
+//! hook only implements what it needs, and a tuple of hooks calls each of
+//! them in order. An optional hook is only called when it is there.
+//!
+//! ```
+//! use components::{mock::{MockLift, MockMotor, MockSensorController}, Left};
+//! use directions::MotorDirection;
+//! use interfaces::Drive;
+//! use logbot::{hooks::{DriveHook, LiftHook, SensorHook}, Logbot};
+//!
+//! /// Counts the drives of logbot
+//! #[derive(Debug, Default)]
+//! struct Counter(usize);
+//!
+//! impl DriveHook<MotorDirection> for Counter {
+//!     fn on_drive(&mut self, direction: MotorDirection) -> MotorDirection {
+//!         self.0 += 1;
+//!         direction
+//!     }
+//! }
+//! impl LiftHook for Counter {}
+//! impl SensorHook<u8> for Counter {}
+//!
+//! let mut logbot = Logbot::builder()
+//!     .vehicle(MockMotor::<Left>::new())
+//!     .sensors(MockSensorController::new(0))
+//!     .lift(MockLift::new())
+//!     .hooks(Counter::default())
+//!     .build();
+//!
+//! logbot.drive(MotorDirection::Stop).unwrap();
+//! assert_eq!(logbot.hooks().0, 1);
+//! ```
+
+use speed::Speed;
+
+/// Motion of the lift
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LiftMotion {
+    /// Move the lift all the way up
+    Up(Speed),
+    /// Move the lift all the way down
+    Down(Speed),
+    /// Start or keep moving the lift up
+    JogUp(Speed),
+    /// Start or keep moving the lift down
+    JogDown(Speed),
+    /// Stop moving the lift
+    Halt,
+}
+
+/// Hook called when the vehicle is driven in directions `D`
+pub trait DriveHook<D> {
+    /// Called before the vehicle is driven or spun, returning the direction
+    /// to drive in instead
+    fn on_drive(&mut self, direction: D) -> D {
+        direction
+    }
+
+    /// Called before the vehicle is stopped
+    fn on_stop(&mut self) {}
+}
+
+/// Hook called when the lift moves
+pub trait LiftHook {
+    /// Called before the lift moves or halts
+    fn on_lift(&mut self, _motion: LiftMotion) {}
+}
+
+/// Hook called when a sensor reads values `O`
+pub trait SensorHook<O> {
+    /// Called after a sensor on a channel was read
+    fn on_sensor_read(&mut self, _channel: u8, _value: &O) {}
+}
+
+/// Hooks of a [`Logbot`](crate::Logbot) that do nothing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoHooks;
+
+impl<D> DriveHook<D> for NoHooks {}
+
+impl LiftHook for NoHooks {}
+
+impl<O> SensorHook<O> for NoHooks {}
+
+impl<D, A, B> DriveHook<D> for (A, B)
+where
+    A: DriveHook<D>,
+    B: DriveHook<D>,
+{
+    fn on_drive(&mut self, direction: D) -> D {
+        let direction = self.0.on_drive(direction);
+        self.1.on_drive(direction)
+    }
+
+    fn on_stop(&mut self) {
+        self.0.on_stop();
+        self.1.on_stop();
+    }
+}
+
+impl<A, B> LiftHook for (A, B)
+where
+    A: LiftHook,
+    B: LiftHook,
+{
+    fn on_lift(&mut self, motion: LiftMotion) {
+        self.0.on_lift(motion);
+        self.1.on_lift(motion);
+    }
+}
+
+impl<O, A, B> SensorHook<O> for (A, B)
+where
+    A: SensorHook<O>,
+    B: SensorHook<O>,
+{
+    fn on_sensor_read(&mut self, channel: u8, value: &O) {
+        self.0.on_sensor_read(channel, value);
+        self.1.on_sensor_read(channel, value);
+    }
+}
+
+/// Hooks that may be left out, e.g. a recording enabled by a flag
+impl<D, H> DriveHook<D> for Option<H>
+where
+    H: DriveHook<D>,
+{
+    fn on_drive(&mut self, direction: D) -> D {
+        match self {
+            Some(hook) => hook.on_drive(direction),
+            None => direction,
+        }
+    }
+
+    fn on_stop(&mut self) {
+        if let Some(hook) = self {
+            hook.on_stop();
+        };
+    }
+}
+
+impl<H> LiftHook for Option<H>
+where
+    H: LiftHook,
+{
+    fn on_lift(&mut self, motion: LiftMotion) {
+        if let Some(hook) = self {
+            hook.on_lift(motion);
+        };
+    }
+}
+
+impl<O, H> SensorHook<O> for Option<H>
+where
+    H: SensorHook<O>,
+{
+    fn on_sensor_read(&mut self, channel: u8, value: &O) {
+        if let Some(hook) = self {
+            hook.on_sensor_read(channel, value);
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components::{
+        mock::{MockLift, MockMotor, MockSensorController},
+        Left, Right,
+    };
+    use consts::Sensors;
+    use directions::{SpinDirection, VehicleDirection};
+    use interfaces::{Drive, Lift, SensorRead, Spin};
+    use speed::Speed;
+    use vehicle::Vehicle;
+
+    use super::{DriveHook, LiftHook, LiftMotion, SensorHook};
+    use crate::Logbot;
+
+    /// Hook scaling the speed of both wheels
+    #[derive(Debug)]
+    struct Governor(Speed);
+
+    impl DriveHook<VehicleDirection> for Governor {
+        fn on_drive(&mut self, direction: VehicleDirection) -> VehicleDirection {
+            direction * self.0
+        }
+    }
+    impl LiftHook for Governor {}
+    impl SensorHook<u8> for Governor {}
+
+    /// Hook recording every call
+    #[derive(Debug, Default)]
+    struct Recorder(Vec<String>);
+
+    impl DriveHook<VehicleDirection> for Recorder {
+        fn on_drive(&mut self, direction: VehicleDirection) -> VehicleDirection {
+            self.0.push(format!("drive {direction}"));
+            direction
+        }
+
+        fn on_stop(&mut self) {
+            self.0.push("stop".to_owned());
+        }
+    }
+
+    impl LiftHook for Recorder {
+        fn on_lift(&mut self, motion: LiftMotion) {
+            self.0.push(format!("{motion:?}"));
+        }
+    }
+
+    impl SensorHook<u8> for Recorder {
+        fn on_sensor_read(&mut self, channel: u8, value: &u8) {
+            self.0.push(format!("read {channel}: {value}"));
+        }
+    }
+
+    /// Test that layered hooks see and change every interaction in order
+    #[test]
+    fn layer_hooks() {
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(MockSensorController::new(38))
+            .lift(MockLift::new())
+            .hooks((Governor(Speed::HALF), Recorder::default()))
+            .build();
+
+        logbot.drive("forward:1.0".parse().unwrap()).unwrap();
+        logbot.spin(SpinDirection::Left(Speed::MAX)).unwrap();
+        logbot.stop().unwrap();
+        logbot.up(Speed::MAX).unwrap();
+        logbot.read(Sensors::Left).unwrap();
+
+        assert_eq!(
+            logbot.hooks().1 .0,
+            vec![
+                format!(
+                    "drive {}",
+                    "forward:0.5".parse::<VehicleDirection>().unwrap()
+                ),
+                format!(
+                    "drive {}",
+                    VehicleDirection::from(SpinDirection::Left(Speed::HALF))
+                ),
+                "stop".to_owned(),
+                format!("{:?}", LiftMotion::Up(Speed::MAX)),
+                "read 0: 38".to_owned(),
+            ]
+        );
+    }
+}
@@ -2,91 +2,264 @@
 //! which then exports interfaces as a single struct. This allows for easy
 //! trait bounds checking.
 
-use interfaces::{Drive, Lift, SensorRead, Spin, ToSensorChannel};
+// https://github.com/rust-lang/rust/issues/95513
+#![allow(unused_crate_dependencies)]
+
+use std::{
+    convert::Infallible,
+    time::{Duration, Instant},
+};
+
+use builder::{LogbotBuilder, Missing};
+use calibrated::Uncalibrated;
+use consts::Sensors;
+use error::{LogbotError, MotionError};
+use hooks::{DriveHook, LiftHook, LiftMotion, NoHooks, SensorHook};
+use interfaces::{
+    Drive, DriveState, EmergencyStop, JogLift, Lift, SensorRead, Spin, ToSensorChannel,
+};
+use snapshot::{LiftPosition, Snapshot};
 use speed::Speed;
+use watchdog::Watchdog;
 
+pub mod builder;
+pub mod calibrated;
 pub mod error;
+pub mod hooks;
+pub mod no_lift;
+pub mod self_test;
+pub mod snapshot;
+pub mod watchdog;
 
 /// Logbot struct that wraps all hardware components
+///
+/// Whether the sensors are calibrated is tracked by `C`, either
+/// [`Uncalibrated`] or [`Calibrated`](calibrated::Calibrated), see
+/// [`calibrated`]. The [`hooks`] `H` are called on every interaction.
 #[derive(Debug)]
-pub struct Logbot<V, S, L> {
+pub struct Logbot<V, S, L, C = Uncalibrated, H = NoHooks> {
     vehicle: V,
     sensors: S,
     lift: L,
+    /// Calibration of the sensors, if calibrated
+    calibration: C,
+    /// Watchdog stopping the vehicle once driving stalls, if enabled
+    watchdog: Option<Watchdog>,
+    /// [`Instant`] the vehicle was last driven or stopped
+    driven: Option<Instant>,
+    /// Whether an emergency stop latched, rejecting motion until reset
+    stopped: bool,
+    /// Hooks called on every interaction
+    hooks: H,
+}
+
+impl Logbot<Missing, Missing, Missing> {
+    /// Create a [`LogbotBuilder`] to assemble a Logbot from its components
+    pub fn builder() -> LogbotBuilder<Missing, Missing, Missing> {
+        LogbotBuilder::new()
+    }
 }
 
-impl<V, S, L> Logbot<V, S, L> {
-    /// Create a new Logbot struct
-    pub fn new(vehicle: V, sensors: S, lift: L) -> Self {
-        Self {
-            vehicle,
-            sensors,
-            lift,
+impl<V, S, L, C, H> Logbot<V, S, L, C, H> {
+    /// The [`hooks`] of logbot
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    /// The [`hooks`] of logbot, e.g. to change their settings
+    pub fn hooks_mut(&mut self) -> &mut H {
+        &mut self.hooks
+    }
+
+    /// The [`Watchdog`] of logbot, if enabled
+    pub fn watchdog(&self) -> Option<&Watchdog> {
+        self.watchdog.as_ref()
+    }
+
+    /// Enable a [`Watchdog`] stopping the vehicle after a timeout without
+    /// driving, or disable it with `None`
+    pub fn set_watchdog(&mut self, timeout: Option<Duration>) {
+        self.watchdog = timeout.map(Watchdog::new);
+    }
+
+    /// Whether an [emergency stop](EmergencyStop::emergency_stop) latched,
+    /// so every motion except stopping is rejected with
+    /// [`MotionError::Stopped`]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Release the latch of an [emergency stop](EmergencyStop::emergency_stop),
+    /// allowing logbot to move again
+    pub fn reset(&mut self) {
+        self.stopped = false;
+    }
+
+    /// Reject motion while an emergency stop is latched
+    fn ensure_running<E>(&self) -> Result<(), MotionError<E>> {
+        match self.stopped {
+            true => Err(MotionError::Stopped),
+            false => Ok(()),
         }
     }
 }
 
+impl<V, S, L, C, H> Logbot<V, S, L, C, H>
+where
+    V: Drive,
+    H: DriveHook<V::Direction>,
+{
+    /// Stop the vehicle if the [`Watchdog`] expired
+    ///
+    /// Returns whether the vehicle was stopped. Control loops that may go
+    /// without interacting with logbot call this periodically.
+    pub fn check_watchdog(&mut self) -> Result<bool, V::Error> {
+        if !self.watchdog.as_ref().is_some_and(Watchdog::is_expired) {
+            return Ok(false);
+        };
+        self.stop_vehicle()?;
+        Ok(true)
+    }
+
+    /// Stop the vehicle, disarming the [`Watchdog`]
+    fn stop_vehicle(&mut self) -> Result<Option<V::Direction>, V::Error> {
+        self.hooks.on_stop();
+        let previous = self.vehicle.stop()?;
+        self.driven = Some(Instant::now());
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.disarm();
+        };
+        Ok(previous)
+    }
+
+    /// Stop the vehicle if the [`Watchdog`] expired while interacting with
+    /// another component
+    ///
+    /// Errors stopping the vehicle can't be returned from other components,
+    /// so the watchdog stays armed and retries on the next interaction.
+    fn enforce_watchdog(&mut self) {
+        let _ = self.check_watchdog();
+    }
+}
+
+impl<V, S, L, C, H> Logbot<V, S, L, C, H>
+where
+    V: DriveState,
+    S: SensorRead,
+    L: Lift,
+    H: DriveHook<V::Direction> + SensorHook<S::Output>,
+{
+    /// Take a [`Snapshot`] of the current direction, both sensors and the
+    /// lift
+    pub fn snapshot(&mut self) -> Result<Snapshot<V::Direction, S::Output>, S::Error> {
+        let left = self.read(Sensors::Left)?;
+        let right = self.read(Sensors::Right)?;
+        Ok(Snapshot {
+            timestamp_ms: snapshot::timestamp_ms(),
+            direction: self.vehicle.state(),
+            direction_ms: self
+                .driven
+                .map(|driven| driven.elapsed().as_millis() as u64),
+            left,
+            right,
+            lift: LiftPosition::of(&self.lift),
+            stopped: self.stopped,
+        })
+    }
+}
+
 // Export Drive Trait for Logbot
-impl<V, S, L> Drive for Logbot<V, S, L>
+impl<V, S, L, C, H> Drive for Logbot<V, S, L, C, H>
 where
     V: Drive,
+    H: DriveHook<V::Direction>,
 {
     type Direction = V::Direction;
-    type Error = V::Error;
+    type Error = MotionError<V::Error>;
 
     fn drive(
         &mut self,
         direction: Self::Direction,
     ) -> Result<Option<Self::Direction>, Self::Error> {
-        self.vehicle.drive(direction)
+        self.ensure_running()?;
+        let direction = self.hooks.on_drive(direction);
+        let previous = self
+            .vehicle
+            .drive(direction)
+            .map_err(MotionError::Component)?;
+        self.driven = Some(Instant::now());
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.arm();
+        };
+        Ok(previous)
     }
 
+    /// Stop the vehicle, which is allowed even while an emergency stop is
+    /// latched
     fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
-        self.vehicle.stop()
+        self.stop_vehicle().map_err(MotionError::Component)
     }
 }
 
 // Export Spin Trait for Logbot
-impl<V, S, L> Spin for Logbot<V, S, L>
+impl<V, S, L, C, H> Spin for Logbot<V, S, L, C, H>
 where
     V: Spin,
+    V::Direction: From<V::SpinDirection>,
+    H: DriveHook<V::Direction>,
 {
     type SpinDirection = V::SpinDirection;
 
+    /// Spin by driving in the direction of the spin, so the [`DriveHook`]
+    /// sees spins as well
     fn spin(
         &mut self,
         direction: Self::SpinDirection,
     ) -> Result<Option<Self::Direction>, Self::Error> {
-        self.vehicle.spin(direction)
+        self.drive(direction.into())
     }
 }
 
 // Export SensorRead Trait for Logbot
-impl<V, S, L> SensorRead for Logbot<V, S, L>
+impl<V, S, L, C, H> SensorRead for Logbot<V, S, L, C, H>
 where
+    V: Drive,
     S: SensorRead,
+    H: DriveHook<V::Direction> + SensorHook<S::Output>,
 {
     type Output = S::Output;
     type Error = S::Error;
 
     fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
-        self.sensors.read(sensor)
+        self.enforce_watchdog();
+        let channel = sensor.to_channel();
+        let value = self.sensors.read(sensor)?;
+        self.hooks.on_sensor_read(channel, &value);
+        Ok(value)
     }
 }
 
 // Export Lift Trait for Logbot
-impl<V, S, L> Lift for Logbot<V, S, L>
+impl<V, S, L, C, H> Lift for Logbot<V, S, L, C, H>
 where
+    V: Drive,
     L: Lift,
+    H: DriveHook<V::Direction> + LiftHook,
 {
-    type Error = L::Error;
+    type Error = MotionError<L::Error>;
 
     fn up(&mut self, speed: Speed) -> Result<(), Self::Error> {
-        self.lift.up(speed)
+        self.enforce_watchdog();
+        self.ensure_running()?;
+        self.hooks.on_lift(LiftMotion::Up(speed));
+        self.lift.up(speed).map_err(MotionError::Component)
     }
 
     fn down(&mut self, speed: Speed) -> Result<(), Self::Error> {
-        self.lift.down(speed)
+        self.enforce_watchdog();
+        self.ensure_running()?;
+        self.hooks.on_lift(LiftMotion::Down(speed));
+        self.lift.down(speed).map_err(MotionError::Component)
     }
 
     fn is_up(&self) -> bool {
@@ -97,3 +270,96 @@ where
         self.lift.is_down()
     }
 }
+
+// Export JogLift Trait for Logbot
+impl<V, S, L, C, H> JogLift for Logbot<V, S, L, C, H>
+where
+    V: Drive,
+    L: JogLift,
+    H: DriveHook<V::Direction> + LiftHook,
+{
+    fn jog_up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.enforce_watchdog();
+        self.ensure_running()?;
+        self.hooks.on_lift(LiftMotion::JogUp(speed));
+        self.lift.jog_up(speed).map_err(MotionError::Component)
+    }
+
+    fn jog_down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.enforce_watchdog();
+        self.ensure_running()?;
+        self.hooks.on_lift(LiftMotion::JogDown(speed));
+        self.lift.jog_down(speed).map_err(MotionError::Component)
+    }
+
+    /// Stop moving the lift, which is allowed even while an emergency stop
+    /// is latched
+    fn halt(&mut self) -> Result<(), Self::Error> {
+        self.hooks.on_lift(LiftMotion::Halt);
+        self.lift.halt().map_err(MotionError::Component)
+    }
+}
+
+impl<V, S, L, C, H> EmergencyStop for Logbot<V, S, L, C, H>
+where
+    V: Drive,
+    L: JogLift,
+    H: DriveHook<V::Direction> + LiftHook,
+{
+    type Error = LogbotError<V::Error, Infallible, L::Error>;
+
+    /// Stop the vehicle and halt the lift, the lift is halted even if stopping
+    /// the vehicle failed
+    ///
+    /// Logbot is latched [stopped](Logbot::is_stopped), rejecting any motion
+    /// until it is [reset](Logbot::reset), even if stopping failed.
+    fn emergency_stop(&mut self) -> Result<(), Self::Error> {
+        self.stopped = true;
+        let vehicle = self.stop_vehicle();
+        self.hooks.on_lift(LiftMotion::Halt);
+        let lift = self.lift.halt();
+
+        vehicle.map_err(LogbotError::Vehicle)?;
+        lift.map_err(LogbotError::Lift)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components::{
+        mock::{MockLift, MockMotor, MockSensorController},
+        Left, Right,
+    };
+    use directions::VehicleDirection;
+    use interfaces::{Drive, EmergencyStop, JogLift, Lift};
+    use speed::Speed;
+    use vehicle::Vehicle;
+
+    use crate::{error::MotionError, Logbot};
+
+    /// Test that an emergency stop rejects motion until logbot is reset
+    #[test]
+    fn latch_emergency_stop() {
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(MockSensorController::new(0))
+            .lift(MockLift::new())
+            .build();
+        let forward: VehicleDirection = "forward:0.5".parse().unwrap();
+
+        logbot.drive(forward).unwrap();
+        logbot.emergency_stop().unwrap();
+        assert!(logbot.is_stopped());
+        assert!(matches!(logbot.stop(), Ok(None)));
+        assert!(matches!(logbot.drive(forward), Err(MotionError::Stopped)));
+        assert_eq!(logbot.up(Speed::MAX), Err(MotionError::Stopped));
+        assert_eq!(logbot.halt(), Ok(()));
+
+        logbot.reset();
+        assert!(matches!(logbot.drive(forward), Ok(None)));
+        assert_eq!(logbot.up(Speed::MAX), Ok(()));
+    }
+}
@@ -0,0 +1,90 @@
+//! Lift of a [`Logbot`](crate::Logbot) built without one
+//!
+//! Drivetrain-only builds use [`NoLift`] in place of a lift, so they run the
+//! same demo and server stack. The lift is always down, moving it fails with
+//! [`NoLiftError`] and halting it succeeds, so emergency stops still work.
+
+use std::fmt::Display;
+
+use interfaces::{JogLift, Lift};
+use speed::Speed;
+
+/// Marker for a [`Logbot`](crate::Logbot) without a lift
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoLift;
+
+/// Error moving the lift of a [`Logbot`](crate::Logbot) without one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoLiftError;
+
+impl Display for NoLiftError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("logbot has no lift")
+    }
+}
+
+impl core::error::Error for NoLiftError {}
+
+impl Lift for NoLift {
+    type Error = NoLiftError;
+
+    fn up(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        Err(NoLiftError)
+    }
+
+    fn down(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        Err(NoLiftError)
+    }
+
+    fn is_up(&self) -> bool {
+        false
+    }
+
+    fn is_down(&self) -> bool {
+        true
+    }
+}
+
+impl JogLift for NoLift {
+    fn jog_up(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        Err(NoLiftError)
+    }
+
+    fn jog_down(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        Err(NoLiftError)
+    }
+
+    fn halt(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components::{
+        mock::{MockMotor, MockSensorController},
+        Left,
+    };
+    use interfaces::{EmergencyStop, Lift};
+    use speed::Speed;
+
+    use super::NoLiftError;
+    use crate::{error::MotionError, snapshot::LiftPosition, Logbot};
+
+    /// Test that a logbot without a lift fails to move it, but still stops
+    #[test]
+    fn drive_without_lift() {
+        let mut logbot = Logbot::builder()
+            .vehicle(MockMotor::<Left>::new())
+            .sensors(MockSensorController::new(0))
+            .without_lift()
+            .build();
+
+        assert_eq!(
+            logbot.up(Speed::MAX),
+            Err(MotionError::Component(NoLiftError))
+        );
+        assert_eq!(LiftPosition::of(&logbot), LiftPosition::Down);
+        assert!(logbot.emergency_stop().is_ok());
+    }
+}
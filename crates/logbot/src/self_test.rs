@@ -0,0 +1,165 @@
+//! Self-test of the hardware components
+//!
+//! The [self-test](Logbot::self_test) of an idle logbot checks that the
+//! motors accept a stop, that both sensors can be read and return a plausible
+//! value and that the limit switches of the lift agree with each other. The
+//! battery voltage is read when a [`BatteryConfig`] is given. With the
+//! `serde` feature the [`SelfTest`] serializes to e.g.
+//!
+//! ```json
+//! {"healthy":true,"motors":{"passed":true,"value":null,"error":null},"left_sensor":{"passed":true,"value":38,"error":null},"right_sensor":{"passed":true,"value":201,"error":null},"lift":{"passed":true,"value":"Down","error":null},"battery":{"passed":true,"value":7.9,"error":null}}
+//! ```
+
+use std::fmt::{Debug, Display};
+
+use consts::{config::BatteryConfig, Sensors};
+use interfaces::{Drive, Lift, SensorRead};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{snapshot::LiftPosition, Logbot};
+
+/// Outcome of checking a single component
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Check<T> {
+    /// Whether the component works as expected
+    pub passed: bool,
+    /// Value read from the component, none if it could not be read
+    pub value: Option<T>,
+    /// Why the check failed
+    pub error: Option<String>,
+}
+
+impl<T> Check<T> {
+    /// The [`Check`] of a component read with a [`Result`]
+    fn of<E: Debug>(result: Result<T, E>) -> Self {
+        match result {
+            Ok(value) => Self {
+                passed: true,
+                value: Some(value),
+                error: None,
+            },
+            Err(e) => Self::failed(None, format!("{e:?}")),
+        }
+    }
+
+    /// A failed [`Check`] of a component
+    fn failed(value: Option<T>, error: impl Display) -> Self {
+        Self {
+            passed: false,
+            value,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Health report of all components
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SelfTest {
+    /// Whether every check passed
+    pub healthy: bool,
+    /// Both drive motors accept a stop
+    pub motors: Check<()>,
+    /// Value of the left sensor
+    pub left_sensor: Check<u8>,
+    /// Value of the right sensor
+    pub right_sensor: Check<u8>,
+    /// Position of the lift, failing when both limit switches are active
+    pub lift: Check<LiftPosition>,
+    /// Battery voltage, none when no battery monitor is configured
+    pub battery: Option<Check<f64>>,
+}
+
+impl<V, S, L, C, H> Logbot<V, S, L, C, H>
+where
+    Self: Drive + SensorRead<Output = u8> + Lift,
+    <Self as Drive>::Error: Debug,
+    <Self as SensorRead>::Error: Debug,
+{
+    /// Run the [`SelfTest`] of the components, reading the battery voltage
+    /// when a [`BatteryConfig`] is given
+    ///
+    /// Logbot has to be idle, since the motors are stopped.
+    pub fn self_test(&mut self, battery: Option<BatteryConfig>) -> SelfTest {
+        run(self, battery)
+    }
+}
+
+/// Run the [`SelfTest`] of the components of an idle logbot
+///
+/// This is the [`Logbot::self_test`] of anything implementing the component
+/// traits, e.g. a logbot owned by another abstraction.
+pub fn run<L>(logbot: &mut L, battery: Option<BatteryConfig>) -> SelfTest
+where
+    L: Drive + SensorRead<Output = u8> + Lift,
+    <L as Drive>::Error: Debug,
+    <L as SensorRead>::Error: Debug,
+{
+    let motors = Check::of(logbot.stop().map(|_| ()));
+    let left_sensor = plausible(Check::of(logbot.read(Sensors::Left)));
+    let right_sensor = plausible(Check::of(logbot.read(Sensors::Right)));
+
+    let lift = match (logbot.is_up(), logbot.is_down()) {
+        (true, true) => Check::failed(None, "both limit switches are active"),
+        _ => Check::of(Ok::<_, ()>(LiftPosition::of(logbot))),
+    };
+
+    let battery = battery
+        .map(|battery| Check::of(logbot.read(battery).map(|reading| battery.volts(reading))));
+
+    SelfTest {
+        healthy: motors.passed
+            && left_sensor.passed
+            && right_sensor.passed
+            && lift.passed
+            && battery.as_ref().is_none_or(|battery| battery.passed),
+        motors,
+        left_sensor,
+        right_sensor,
+        lift,
+        battery,
+    }
+}
+
+/// Fail the [`Check`] of a sensor read at either end of the range of the
+/// ADC, which means the sensor is disconnected or shorted
+fn plausible(check: Check<u8>) -> Check<u8> {
+    match check.value {
+        Some(value @ (u8::MIN | u8::MAX)) => Check::failed(
+            Some(value),
+            format!("reading {value} is at the end of the range, check the wiring"),
+        ),
+        _ => check,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components::{
+        mock::{MockLift, MockMotor, MockSensorController},
+        Left,
+    };
+    use consts::Sensors;
+
+    use crate::Logbot;
+
+    /// Test that sensors reading the end of their range fail the self-test
+    #[test]
+    fn implausible_sensor() {
+        let mut sensors = MockSensorController::new(120);
+        sensors.set(Sensors::Right, u8::MAX);
+        let mut logbot = Logbot::builder()
+            .vehicle(MockMotor::<Left>::new())
+            .sensors(sensors)
+            .lift(MockLift::new())
+            .build();
+
+        let report = logbot.self_test(None);
+        assert!(!report.healthy);
+        assert!(report.left_sensor.passed);
+        assert!(!report.right_sensor.passed);
+        assert_eq!(report.right_sensor.value, Some(u8::MAX));
+    }
+}
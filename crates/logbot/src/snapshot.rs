@@ -0,0 +1,126 @@
+//! Snapshot of the state of a [`Logbot`](crate::Logbot)
+//!
+//! A [`Snapshot`] is the single representation of what logbot is doing,
+//! consumed by the server, the CLI dashboard and recordings alike. With the
+//! `serde` feature it serializes to e.g.
+//!
+//! ```json
+//! {"timestamp_ms":1760000000000,"direction":{"left":{"Forward":0.2},"right":{"Forward":0.2}},"direction_ms":1530,"left":38,"right":201,"lift":"Down","stopped":false}
+//! ```
+
+use std::{
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use interfaces::Lift;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Position of the lift
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum LiftPosition {
+    /// The lift is up
+    Up,
+    /// The lift is down
+    Down,
+    /// The lift is neither up nor down
+    Between,
+}
+
+impl LiftPosition {
+    /// The current [`LiftPosition`] of a [`Lift`]
+    pub fn of<L: Lift>(lift: &L) -> Self {
+        match (lift.is_up(), lift.is_down()) {
+            (true, _) => Self::Up,
+            (_, true) => Self::Down,
+            _ => Self::Between,
+        }
+    }
+}
+
+impl Display for LiftPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Up => "up",
+            Self::Down => "down",
+            Self::Between => "between",
+        })
+    }
+}
+
+/// State of a [`Logbot`](crate::Logbot) at a point in time, driven in
+/// directions `D` and reading sensor values `O`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Snapshot<D, O> {
+    /// Milliseconds since the Unix epoch the snapshot was taken at
+    pub timestamp_ms: u64,
+    /// The direction the vehicle is driven in
+    pub direction: D,
+    /// Milliseconds since the vehicle was last driven or stopped, none if it
+    /// never was
+    pub direction_ms: Option<u64>,
+    /// Value of the left sensor
+    pub left: O,
+    /// Value of the right sensor
+    pub right: O,
+    /// Position of the lift
+    pub lift: LiftPosition,
+    /// Whether an emergency stop is latched
+    pub stopped: bool,
+}
+
+/// Milliseconds since the Unix epoch
+pub(crate) fn timestamp_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use components::{
+        mock::{MockLift, MockMotor, MockSensorController},
+        Left, Right,
+    };
+    use consts::Sensors;
+    use directions::VehicleDirection;
+    use interfaces::{Drive, Lift};
+    use speed::Speed;
+    use vehicle::Vehicle;
+
+    use super::LiftPosition;
+    use crate::Logbot;
+
+    /// Test that a snapshot holds the direction, sensors and lift
+    #[test]
+    fn snapshot_state() {
+        let mut sensors = MockSensorController::new(0);
+        sensors.set(Sensors::Left, 38);
+        sensors.set(Sensors::Right, 201);
+        let mut logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                MockMotor::<Left>::new(),
+                MockMotor::<Right>::new(),
+            ))
+            .sensors(sensors)
+            .lift(MockLift::new())
+            .build();
+
+        let snapshot = logbot.snapshot().unwrap();
+        assert_eq!(snapshot.direction, VehicleDirection::STOP);
+        assert_eq!(snapshot.direction_ms, None);
+        assert_eq!((snapshot.left, snapshot.right), (38, 201));
+
+        let forward: VehicleDirection = "forward:0.5".parse().unwrap();
+        logbot.drive(forward).unwrap();
+        logbot.up(Speed::MAX).unwrap();
+
+        let snapshot = logbot.snapshot().unwrap();
+        assert_eq!(snapshot.direction, forward);
+        assert!(snapshot.direction_ms.is_some());
+        assert_eq!(snapshot.lift, LiftPosition::Up);
+    }
+}
@@ -0,0 +1,100 @@
+//! Watchdog stopping a [`Logbot`](crate::Logbot) whose control code stalls
+//!
+//! Every [`drive`](interfaces::Drive::drive) and
+//! [`spin`](interfaces::Spin::spin) arms the [`Watchdog`] and stopping the
+//! vehicle disarms it. Once an armed watchdog runs past its timeout, the
+//! vehicle is stopped on the next interaction with logbot, such as reading a
+//! sensor or moving the lift, or when the watchdog is
+//! [checked](crate::Logbot::check_watchdog) explicitly by a control loop.
+
+use std::time::{Duration, Instant};
+
+/// Deadline for the next drive of a [`Logbot`](crate::Logbot)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchdog {
+    /// Time without a drive after which the vehicle is stopped
+    timeout: Duration,
+    /// [`Instant`] of the last drive, none while the vehicle is stopped
+    last_drive: Option<Instant>,
+}
+
+impl Watchdog {
+    /// Create a disarmed [`Watchdog`] stopping after a timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_drive: None,
+        }
+    }
+
+    /// Time without a drive after which the vehicle is stopped
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Whether the vehicle was driven and not stopped since
+    pub fn is_armed(&self) -> bool {
+        self.last_drive.is_some()
+    }
+
+    /// Whether the watchdog is armed and the timeout passed since the last
+    /// drive
+    pub fn is_expired(&self) -> bool {
+        self.last_drive
+            .is_some_and(|last_drive| last_drive.elapsed() >= self.timeout)
+    }
+
+    /// Record a drive, restarting the timeout
+    pub(crate) fn arm(&mut self) {
+        self.last_drive = Some(Instant::now());
+    }
+
+    /// Record that the vehicle stopped
+    pub(crate) fn disarm(&mut self) {
+        self.last_drive = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use components::{mock::MockMotor, Left};
+    use directions::MotorDirection;
+    use interfaces::Drive;
+    use speed::Speed;
+
+    use super::Watchdog;
+    use crate::Logbot;
+
+    /// Test that the watchdog only expires while the vehicle is driven
+    #[test]
+    fn expire_while_driving() {
+        let mut watchdog = Watchdog::new(Duration::ZERO);
+        assert!(!watchdog.is_expired());
+
+        watchdog.arm();
+        assert!(watchdog.is_expired());
+
+        watchdog.disarm();
+        assert!(!watchdog.is_armed());
+    }
+
+    /// Test that a stalled vehicle is stopped when the watchdog is checked
+    #[test]
+    fn stop_stalled_vehicle() {
+        let mut logbot = Logbot::builder()
+            .vehicle(MockMotor::<Left>::new())
+            .sensors(())
+            .lift(())
+            .watchdog(Duration::from_millis(10))
+            .build();
+
+        logbot.drive(MotorDirection::Forward(Speed::HALF)).unwrap();
+        assert_eq!(logbot.check_watchdog(), Ok(false));
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(logbot.check_watchdog(), Ok(true));
+        assert_eq!(logbot.stop(), Ok(None));
+    }
+}
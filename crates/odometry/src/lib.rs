@@ -0,0 +1,221 @@
+//! Estimating where logbot is by dead reckoning
+//!
+//! Logbot has no sensors telling where it is, so its [`Pose`] is estimated
+//! by integrating the speeds of its wheels over time. [`DeadReckoning`] does
+//! so either from the directions logbot is commanded in, converted to wheel
+//! speeds using how fast logbot drives and spins at full speed, or from wheel
+//! speeds measured by encoders. Every estimate is exposed through the
+//! [`Odometry`] trait, so missions and telemetry don't depend on where it
+//! comes from.
+//!
+//! Used as a [hook](logbot::hooks) of a [`Logbot`](logbot::Logbot),
+//! [`DeadReckoning`] follows every drive, spin and stop:
+//!
+//! ```
+//! use components::{mock::{MockLift, MockMotor, MockSensorController}, Left, Right};
+//! use directions::VehicleDirection;
+//! use interfaces::Drive;
+//! use logbot::Logbot;
+//! use odometry::{DeadReckoning, Odometry};
+//! use vehicle::Vehicle;
+//!
+//! let mut logbot = Logbot::builder()
+//!     .vehicle(Vehicle::new(MockMotor::<Left>::new(), MockMotor::<Right>::new()))
+//!     .sensors(MockSensorController::new(0))
+//!     .lift(MockLift::new())
+//!     .hooks(DeadReckoning::new(1.0, 360.0))
+//!     .build();
+//!
+//! logbot.drive("forward:0.5".parse::<VehicleDirection>().unwrap()).unwrap();
+//! std::thread::sleep(std::time::Duration::from_millis(10));
+//! logbot.stop().unwrap();
+//! assert!(logbot.hooks().pose().x > 0.0);
+//! ```
+
+// https://github.com/rust-lang/rust/issues/95513
+#![allow(unused_crate_dependencies)]
+
+use std::time::{Duration, Instant};
+
+use directions::VehicleDirection;
+use logbot::hooks::{DriveHook, LiftHook, SensorHook};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Estimated position of logbot, relative to where the estimate started
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Pose {
+    /// Meters driven along the initial heading
+    pub x: f64,
+    /// Meters driven to the left of the initial heading
+    pub y: f64,
+    /// Heading in radians, counterclockwise from the initial heading
+    pub heading: f64,
+}
+
+/// Source of [`Pose`] estimates
+pub trait Odometry {
+    /// The estimated [`Pose`] at this moment
+    fn pose(&self) -> Pose;
+
+    /// Meters driven since the estimate started, negative when driven
+    /// backwards
+    fn distance(&self) -> f64;
+
+    /// Start estimating again from a [`Pose`], with no distance driven
+    fn reset(&mut self, pose: Pose);
+}
+
+/// [`Odometry`] integrating wheel speeds over time
+#[derive(Debug, Clone, Copy)]
+pub struct DeadReckoning {
+    /// Meters per second driven straight at full speed
+    drive_rate: f64,
+    /// Radians per second turned when spinning in-place at full speed
+    spin_rate: f64,
+    /// Pose at the last update
+    pose: Pose,
+    /// Meters driven up to the last update
+    distance: f64,
+    /// Direction commanded since the last update
+    direction: VehicleDirection,
+    /// [`Instant`] of the last update
+    updated: Instant,
+}
+
+impl DeadReckoning {
+    /// Start estimating at the origin, driving `drive_rate` meters per
+    /// second straight and spinning `spin_rate` degrees per second in-place
+    /// at full speed, like the `Tuning`
+    pub fn new(drive_rate: f64, spin_rate: f64) -> Self {
+        Self {
+            drive_rate,
+            spin_rate: spin_rate.to_radians(),
+            pose: Pose::default(),
+            distance: 0.0,
+            direction: VehicleDirection::STOP,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Account for driving until now, then continue in a commanded direction
+    pub fn update(&mut self, direction: VehicleDirection) {
+        let now = Instant::now();
+        (self.pose, self.distance) = self.advance(self.direction, now - self.updated);
+        (self.updated, self.direction) = (now, direction);
+    }
+
+    /// Account for the wheels turning at speeds measured by encoders, in
+    /// meters per second, for a duration
+    ///
+    /// The commanded direction is forgotten, so only the measured speeds
+    /// count until the next [update](Self::update).
+    pub fn integrate(&mut self, left: f64, right: f64, duration: Duration) {
+        let now = Instant::now();
+        (self.pose, self.distance) = self.integrated(left, right, duration);
+        (self.updated, self.direction) = (now, VehicleDirection::STOP);
+    }
+
+    /// The pose and distance after driving in a direction for a duration
+    fn advance(&self, direction: VehicleDirection, duration: Duration) -> (Pose, f64) {
+        let left = direction.left.signed() * self.drive_rate;
+        let right = direction.right.signed() * self.drive_rate;
+        self.integrated(left, right, duration)
+    }
+
+    /// The pose and distance after the wheels turn at speeds in meters per
+    /// second for a duration
+    fn integrated(&self, left: f64, right: f64, duration: Duration) -> (Pose, f64) {
+        let dt = duration.as_secs_f64();
+        let velocity = (left + right) / 2.0;
+        // Spinning in-place at full speed turns at the spin rate
+        let rotation = (right - left) / 2.0 / self.drive_rate * self.spin_rate;
+        let mut pose = self.pose;
+
+        if rotation.abs() < f64::EPSILON || !rotation.is_finite() {
+            pose.x += velocity * dt * pose.heading.cos();
+            pose.y += velocity * dt * pose.heading.sin();
+        } else {
+            let radius = velocity / rotation;
+            let heading = pose.heading + rotation * dt;
+            pose.x += radius * (heading.sin() - pose.heading.sin());
+            pose.y -= radius * (heading.cos() - pose.heading.cos());
+            pose.heading = heading;
+        };
+        (pose, self.distance + velocity * dt)
+    }
+}
+
+impl Odometry for DeadReckoning {
+    fn pose(&self) -> Pose {
+        self.advance(self.direction, self.updated.elapsed()).0
+    }
+
+    fn distance(&self) -> f64 {
+        self.advance(self.direction, self.updated.elapsed()).1
+    }
+
+    fn reset(&mut self, pose: Pose) {
+        (self.pose, self.distance, self.updated) = (pose, 0.0, Instant::now());
+    }
+}
+
+impl DriveHook<VehicleDirection> for DeadReckoning {
+    fn on_drive(&mut self, direction: VehicleDirection) -> VehicleDirection {
+        self.update(direction);
+        direction
+    }
+
+    fn on_stop(&mut self) {
+        self.update(VehicleDirection::STOP);
+    }
+}
+
+impl LiftHook for DeadReckoning {}
+
+impl<O> SensorHook<O> for DeadReckoning {}
+
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::PI, time::Duration};
+
+    use super::{DeadReckoning, Odometry, Pose};
+
+    /// Test that measured wheel speeds move the pose along straights and arcs
+    #[test]
+    fn integrate_speeds() {
+        let mut odometry = DeadReckoning::new(1.0, 360.0);
+
+        odometry.integrate(0.5, 0.5, Duration::from_secs(2));
+        let pose = odometry.pose();
+        assert!((pose.x - 1.0).abs() < 1e-9 && pose.y.abs() < 1e-9);
+
+        // Spinning in-place at half speed for a second is half a turn
+        odometry.integrate(-0.5, 0.5, Duration::from_secs(1));
+        let pose = odometry.pose();
+        assert!((pose.x - 1.0).abs() < 1e-9 && pose.y.abs() < 1e-9);
+        assert!((pose.heading - PI).abs() < 1e-9);
+        assert!((odometry.distance() - 1.0).abs() < 1e-9);
+
+        // Driving back ends where it started
+        odometry.integrate(0.5, 0.5, Duration::from_secs(2));
+        assert!(odometry.pose().x.abs() < 1e-9);
+        assert!((odometry.distance() - 2.0).abs() < 1e-9);
+
+        odometry.reset(Pose::default());
+        assert_eq!(odometry.pose(), Pose::default());
+        assert_eq!(odometry.distance(), 0.0);
+    }
+
+    /// Test that the commanded direction counts up to the current moment
+    #[test]
+    fn commanded_direction() {
+        let mut odometry = DeadReckoning::new(1.0, 360.0);
+        odometry.update("backward:1.0".parse().unwrap());
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert!(odometry.pose().x <= -0.02);
+        assert!(odometry.distance() <= -0.02);
+    }
+}
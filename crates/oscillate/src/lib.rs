@@ -69,6 +69,19 @@ impl ActiveOscillation {
         }
     }
 
+    /// Continue the oscillation after it was paused for a [`Duration`]
+    ///
+    /// Spins in the direction it was spinning before the pause, which is not
+    /// counted towards the time until the next oscillation
+    pub fn resume<D>(&mut self, driveable: &mut D, paused: Duration) -> Result<(), D::Error>
+    where
+        D: Spin<SpinDirection = SpinDirection>,
+    {
+        self.since_last += paused;
+        driveable.spin(self.config.direction)?;
+        Ok(())
+    }
+
     /// Boolean indicating whether [step](Self::step) is ready to be called
     pub fn should_step(&self) -> bool {
         self.next_oscillation().is_zero()
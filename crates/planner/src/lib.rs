@@ -0,0 +1,407 @@
+//! Planning routes over a grid of lines
+//!
+//! A warehouse floor is laid out as a [`Grid`] of lines: every node is an
+//! intersection at whole coordinates, and every edge is a straight line
+//! between two nodes on the same row or column. Logbot notices a node like a
+//! stop line, when both sensors see the crossing line.
+//!
+//! [`Grid::plan`] finds the cheapest [`Route`] from where logbot stands,
+//! facing along a line, to another node. Driving along a line costs its
+//! length, and turning at an intersection costs extra, so routes prefer going
+//! straight. The [`Maneuver`]s of a route become [`Step`]s of a
+//! [`Mission`](demo::mission::Mission), executed like any other mission:
+//!
+//! ```
+//! use demo::mission::{Mission, Step};
+//! use planner::{Grid, Heading};
+//! use tuning::Tuning;
+//!
+//! let grid = Grid::full(3, 3);
+//! let route = grid.plan((0, 0), Heading::North, (2, 2)).unwrap();
+//!
+//! let mut steps = vec![Step::Calibrate, Step::FindEdge];
+//! steps.extend(route.steps(&Tuning::DEMO));
+//! assert!(Mission::new(steps, Tuning::DEMO).is_ok());
+//! ```
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap, HashMap},
+    fmt::Display,
+};
+
+use demo::mission::Step;
+use directions::{SpinDirection, VehicleDirection};
+use tuning::Tuning;
+
+/// An intersection of the grid at its x and y coordinate
+pub type Node = (i32, i32);
+
+/// Cost of driving along a line of length 1
+const STRAIGHT_COST: u32 = 10;
+/// Extra cost of turning left or right at an intersection
+const TURN_COST: u32 = 5;
+/// Extra cost of turning around at an intersection
+const TURN_AROUND_COST: u32 = 15;
+/// Meters driven straight to cross the line of an intersection
+const CROSS_DISTANCE: f64 = 0.05;
+
+/// Direction logbot faces along a line of the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Heading {
+    /// Towards increasing y
+    North,
+    /// Towards increasing x
+    East,
+    /// Towards decreasing y
+    South,
+    /// Towards decreasing x
+    West,
+}
+
+impl Heading {
+    /// Every heading, turning clockwise
+    const ALL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    /// Step in x and y taken when driving one unit along the heading
+    fn delta(self) -> Node {
+        match self {
+            Self::North => (0, 1),
+            Self::East => (1, 0),
+            Self::South => (0, -1),
+            Self::West => (-1, 0),
+        }
+    }
+
+    /// The heading after turning clockwise by a number of quarter turns
+    fn clockwise(self, quarters: usize) -> Self {
+        Self::ALL[(self as usize + quarters) % 4]
+    }
+
+    /// The [`Maneuver`] changing from this heading to another
+    fn maneuver(self, to: Self) -> Maneuver {
+        match (to as usize + 4 - self as usize) % 4 {
+            0 => Maneuver::Straight,
+            1 => Maneuver::Right,
+            2 => Maneuver::TurnAround,
+            _ => Maneuver::Left,
+        }
+    }
+}
+
+/// What logbot does at an intersection before following the next line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Maneuver {
+    /// Cross the intersection
+    Straight,
+    /// Turn onto the line to the left
+    Left,
+    /// Turn onto the line to the right
+    Right,
+    /// Turn around to follow the line back
+    TurnAround,
+}
+
+impl Maneuver {
+    /// Cost of the maneuver on top of driving the line after it
+    fn cost(self) -> u32 {
+        match self {
+            Self::Straight => 0,
+            Self::Left | Self::Right => TURN_COST,
+            Self::TurnAround => TURN_AROUND_COST,
+        }
+    }
+}
+
+impl Display for Maneuver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Straight => "go straight",
+            Self::Left => "turn left",
+            Self::Right => "turn right",
+            Self::TurnAround => "turn around",
+        })
+    }
+}
+
+/// Part of a [`Route`], a maneuver at an intersection followed by the line
+/// to the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leg {
+    /// Maneuver at the intersection the leg starts at
+    pub maneuver: Maneuver,
+    /// Intersection the leg ends at
+    pub to: Node,
+}
+
+/// Legs leading to a node of a [`Grid`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// Legs in the order they are driven
+    pub legs: Vec<Leg>,
+    /// Heading of logbot at the end of the route
+    pub heading: Heading,
+}
+
+impl Route {
+    /// [`Step`]s driving the route with a [`Tuning`]
+    ///
+    /// Every leg follows the line until the next intersection, after
+    /// crossing the line of the current one, spinning onto the line to the
+    /// side or turning around. The steps follow the line, so a calibrate
+    /// step has to come before them in a mission.
+    pub fn steps(&self, tuning: &Tuning) -> Vec<Step> {
+        let mut steps = Vec::new();
+        for leg in &self.legs {
+            match leg.maneuver {
+                Maneuver::Straight => steps.push(Step::Drive {
+                    direction: VehicleDirection::forward(tuning.speed),
+                    duration_ms: (CROSS_DISTANCE / (tuning.drive_rate * tuning.speed.value())
+                        * 1000.0) as u64,
+                }),
+                Maneuver::Left => steps.push(Step::Turn {
+                    direction: Some(SpinDirection::Left(tuning.turn_speed)),
+                    leave_line_ms: None,
+                }),
+                Maneuver::Right => steps.push(Step::Turn {
+                    direction: Some(SpinDirection::Right(tuning.turn_speed)),
+                    leave_line_ms: None,
+                }),
+                Maneuver::TurnAround => steps.extend([
+                    Step::Spin {
+                        direction: SpinDirection::Left(tuning.turn_speed),
+                        angle: 180.0,
+                    },
+                    Step::FindEdge,
+                ]),
+            };
+            steps.push(Step::Follow);
+        }
+        steps
+    }
+}
+
+/// Reasons a line cannot be added to a [`Grid`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridError {
+    /// The nodes are neither on the same row nor on the same column
+    Diagonal(Node, Node),
+    /// Both ends of the line are the same node
+    Loop(Node),
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Diagonal(a, b) => write!(f, "line from {a:?} to {b:?} is not straight"),
+            Self::Loop(node) => write!(f, "line from {node:?} leads back to itself"),
+        }
+    }
+}
+
+impl core::error::Error for GridError {}
+
+/// Intersections joined by straight lines
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Grid {
+    /// Lines leaving each node, by the heading they leave in
+    lines: HashMap<Node, BTreeSet<(Heading, Node)>>,
+}
+
+impl Grid {
+    /// Create an empty [`Grid`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grid with lines between all neighbouring nodes from `(0, 0)` up to
+    /// `(width - 1, height - 1)`
+    pub fn full(width: i32, height: i32) -> Self {
+        let mut grid = Self::new();
+        for x in 0..width {
+            for y in 0..height {
+                for to in [(x + 1, y), (x, y + 1)] {
+                    if to.0 < width && to.1 < height {
+                        grid.insert((x, y), to);
+                    };
+                }
+            }
+        }
+        grid
+    }
+
+    /// Add a straight line between two nodes
+    pub fn add_line(&mut self, a: Node, b: Node) -> Result<(), GridError> {
+        if a == b {
+            return Err(GridError::Loop(a));
+        };
+        if a.0 != b.0 && a.1 != b.1 {
+            return Err(GridError::Diagonal(a, b));
+        };
+        self.insert(a, b);
+        Ok(())
+    }
+
+    /// Remove the line between two nodes, e.g. where it is blocked
+    pub fn remove_line(&mut self, a: Node, b: Node) {
+        for (from, to) in [(a, b), (b, a)] {
+            if let Some(lines) = self.lines.get_mut(&from) {
+                lines.remove(&(heading(from, to), to));
+            };
+        }
+    }
+
+    /// Add a straight line, known to be valid, in both directions
+    fn insert(&mut self, a: Node, b: Node) {
+        for (from, to) in [(a, b), (b, a)] {
+            self.lines
+                .entry(from)
+                .or_default()
+                .insert((heading(from, to), to));
+        }
+    }
+
+    /// The cheapest [`Route`] from a node, facing a heading, to another node
+    ///
+    /// There is no route if the node cannot be reached along the lines.
+    pub fn plan(&self, from: Node, heading: Heading, to: Node) -> Option<Route> {
+        let mut costs = HashMap::from([((from, heading), 0)]);
+        let mut previous: HashMap<(Node, Heading), (Node, Heading, Maneuver)> = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0, from, heading))]);
+
+        while let Some(Reverse((cost, node, facing))) = queue.pop() {
+            if node == to {
+                return Some(route(&previous, (from, heading), (node, facing)));
+            };
+            if costs.get(&(node, facing)).is_some_and(|best| cost > *best) {
+                continue;
+            };
+            for &(leaving, next) in self.lines.get(&node).into_iter().flatten() {
+                let maneuver = facing.maneuver(leaving);
+                let cost = cost + maneuver.cost() + length(node, next) * STRAIGHT_COST;
+                if costs.get(&(next, leaving)).is_none_or(|best| cost < *best) {
+                    costs.insert((next, leaving), cost);
+                    previous.insert((next, leaving), (node, facing, maneuver));
+                    queue.push(Reverse((cost, next, leaving)));
+                };
+            }
+        }
+        None
+    }
+}
+
+/// Heading from a node to another on the same row or column
+fn heading(from: Node, to: Node) -> Heading {
+    let delta = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    (0..4)
+        .map(|quarters| Heading::North.clockwise(quarters))
+        .find(|heading| heading.delta() == delta)
+        .unwrap_or(Heading::North)
+}
+
+/// Length of a line between two nodes on the same row or column
+fn length(a: Node, b: Node) -> u32 {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+/// Walk back from the end of a route to its start
+fn route(
+    previous: &HashMap<(Node, Heading), (Node, Heading, Maneuver)>,
+    start: (Node, Heading),
+    end: (Node, Heading),
+) -> Route {
+    let mut legs = Vec::new();
+    let mut state = end;
+    while state != start {
+        let Some(&(node, facing, maneuver)) = previous.get(&state) else {
+            break;
+        };
+        legs.push(Leg {
+            maneuver,
+            to: state.0,
+        });
+        state = (node, facing);
+    }
+    legs.reverse();
+    Route {
+        legs,
+        heading: end.1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use demo::mission::Step;
+    use directions::SpinDirection;
+    use tuning::Tuning;
+
+    use super::{Grid, GridError, Heading, Leg, Maneuver};
+
+    /// Test that routes prefer going straight over turning
+    #[test]
+    fn plan_straight() {
+        let grid = Grid::full(3, 3);
+        let route = grid.plan((0, 0), Heading::North, (1, 2)).unwrap();
+        assert_eq!(
+            route.legs,
+            vec![
+                Leg {
+                    maneuver: Maneuver::Straight,
+                    to: (0, 1)
+                },
+                Leg {
+                    maneuver: Maneuver::Straight,
+                    to: (0, 2)
+                },
+                Leg {
+                    maneuver: Maneuver::Right,
+                    to: (1, 2)
+                },
+            ]
+        );
+        assert_eq!(route.heading, Heading::East);
+    }
+
+    /// Test that blocked lines are driven around, turning around if needed
+    #[test]
+    fn plan_around() {
+        let mut grid = Grid::new();
+        grid.add_line((0, 0), (0, 1)).unwrap();
+        grid.add_line((0, 0), (2, 0)).unwrap();
+        assert_eq!(
+            grid.add_line((0, 0), (1, 1)),
+            Err(GridError::Diagonal((0, 0), (1, 1)))
+        );
+
+        let route = grid.plan((0, 1), Heading::North, (2, 0)).unwrap();
+        assert_eq!(
+            route
+                .legs
+                .iter()
+                .map(|leg| leg.maneuver)
+                .collect::<Vec<_>>(),
+            vec![Maneuver::TurnAround, Maneuver::Left]
+        );
+
+        grid.remove_line((0, 0), (2, 0));
+        assert_eq!(grid.plan((0, 1), Heading::North, (2, 0)), None);
+    }
+
+    /// Test that each leg follows the line after its maneuver
+    #[test]
+    fn mission_steps() {
+        let tuning = Tuning::DEMO;
+        let route = Grid::full(2, 2).plan((0, 0), Heading::East, (0, 1));
+        let steps = route.unwrap().steps(&tuning);
+        assert_eq!(
+            steps,
+            vec![
+                Step::Turn {
+                    direction: Some(SpinDirection::Left(tuning.turn_speed)),
+                    leave_line_ms: None,
+                },
+                Step::Follow,
+            ]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Writing the calibrated stop pulse width into the hardware configuration
+//!
+//! The stop pulse width is written into the `hardware_pwm` or `software_pwm`
+//! table of the calibrated motor, inside a robot profile when one is selected.
+//! The rest of the file, including its comments, is kept as is. The range at
+//! which the motor stands still is noted next to the value, e.g.
+//!
+//! ```toml
+//! [left_motor.hardware_pwm]
+//! stop_pulse_width_us = 1485 # stationary from 1470µs to 1500µs
+//! ```
+
+use std::{path::Path, time::Duration};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use consts::config::{HardwareConfig, PROFILES_KEY};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::PWMVariant;
+
+/// Motor whose stop pulse width is calibrated
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motor {
+    /// The left drive motor
+    Left,
+    /// The right drive motor
+    Right,
+}
+
+/// Results of a calibration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Findings {
+    /// The calibrated motor
+    pub motor: Motor,
+    /// The PWM variant the motor was calibrated with
+    pub pwm: PWMVariant,
+    /// The pulse width at which the motor stops
+    pub stop: Duration,
+    /// The lowest and highest pulse width at which the motor stood still
+    pub stationary: Option<(Duration, Duration)>,
+}
+
+impl Findings {
+    /// Keys of the table holding the pulse widths, inside a robot profile
+    fn table<'a>(&self, profile: Option<&'a str>) -> Vec<&'a str> {
+        let motor = match self.motor {
+            Motor::Left => "left_motor",
+            Motor::Right => "right_motor",
+        };
+        let pwm = match self.pwm {
+            PWMVariant::Hardware => "hardware_pwm",
+            PWMVariant::Software => "software_pwm",
+        };
+        match profile {
+            Some(profile) => vec![PROFILES_KEY, profile, motor, pwm],
+            None => vec![motor, pwm],
+        }
+    }
+
+    /// Write the findings into the TOML of a hardware configuration
+    ///
+    /// Fails if the result is not a valid [`HardwareConfig`].
+    pub fn update(&self, value: &str, profile: Option<&str>) -> Result<String> {
+        let mut document: DocumentMut = value.parse().context("invalid TOML")?;
+
+        let mut table = document.as_table_mut();
+        for key in self.table(profile) {
+            let mut implicit = Table::new();
+            implicit.set_implicit(true);
+            table = table
+                .entry(key)
+                .or_insert(Item::Table(implicit))
+                .as_table_mut()
+                .with_context(|| format!("`{key}` is not a table"))?;
+        }
+
+        let mut stop = toml_edit::value(self.stop.as_micros() as i64);
+        if let (Some((low, high)), Some(value)) = (self.stationary, stop.as_value_mut()) {
+            value.decor_mut().set_suffix(format!(
+                " # stationary from {}µs to {}µs",
+                low.as_micros(),
+                high.as_micros()
+            ));
+        };
+        table["stop_pulse_width_us"] = stop;
+
+        let value = document.to_string();
+        HardwareConfig::from_toml_profile(&value, profile)
+            .context("the updated hardware configuration is invalid")?;
+        Ok(value)
+    }
+
+    /// Write the findings into a hardware configuration file, creating it if
+    /// it does not exist
+    pub fn write(&self, path: &Path, profile: Option<&str>) -> Result<()> {
+        let value = match std::fs::read_to_string(path) {
+            Ok(value) => value,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read `{}`", path.display()))
+            }
+        };
+        let value = self
+            .update(&value, profile)
+            .with_context(|| format!("failed to update `{}`", path.display()))?;
+        std::fs::write(path, value).with_context(|| format!("failed to write `{}`", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use consts::config::HardwareConfig;
+
+    use super::{Findings, Motor};
+    use crate::PWMVariant;
+
+    /// Test that the findings are written into the matching table, keeping
+    /// the rest of the file
+    #[test]
+    fn update_config() {
+        let findings = Findings {
+            motor: Motor::Right,
+            pwm: PWMVariant::Software,
+            stop: Duration::from_micros(1_485),
+            stationary: Some((Duration::from_micros(1_470), Duration::from_micros(1_500))),
+        };
+        let value = "# logbot\ni2c_sensor_address = 0x49\n\n[right_motor]\npower_pin = 13\n";
+
+        let updated = findings.update(value, None).unwrap();
+        assert!(updated.starts_with("# logbot\ni2c_sensor_address = 0x49\n"));
+        assert!(updated.contains(
+            "[right_motor.software_pwm]\nstop_pulse_width_us = 1485 # stationary from 1470µs to 1500µs\n"
+        ));
+        let config = HardwareConfig::from_toml(&updated).unwrap();
+        assert_eq!(config.right_motor.software_pwm.stop_pulse_width_us, 1_485);
+        assert_eq!(config.right_motor.power_pin, 13);
+
+        // Updating again replaces the value
+        let findings = Findings {
+            stop: Duration::from_micros(1_490),
+            stationary: None,
+            ..findings
+        };
+        let config = HardwareConfig::from_toml(&findings.update(&updated, None).unwrap()).unwrap();
+        assert_eq!(config.right_motor.software_pwm.stop_pulse_width_us, 1_490);
+
+        // Profiles are written into the `robot` table
+        let updated = findings.update("", Some("alpha")).unwrap();
+        assert_eq!(
+            updated,
+            "[robot.alpha.right_motor.software_pwm]\nstop_pulse_width_us = 1490\n"
+        );
+
+        assert!(findings.update("right_motor = 1", None).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Detecting the stop pulse width using feedback from the wheel
+//!
+//! Each pulse width is held for [`INTERVAL`](crate::INTERVAL). After letting the
+//! motor settle, the wheel is watched for motion using either a wheel encoder
+//! on a GPIO pin or a reflective sensor on the ADC pointed at a marked wheel.
+//! The longest run of consecutive widths without motion is reported as the
+//! range where the motor stays stationary.
+//!
+//! The dead band is instead searched outwards from the nominal stop width,
+//! alternating below and above it, until the wheel starts moving in each
+//! direction.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{ensure, Result};
+use components::SensorController;
+use consts::ADC_CHANNELS;
+use defaults::TryDefault;
+use interfaces::{SensorRead, ToSensorChannel};
+use rppal::gpio::{Gpio, InputPin, Trigger};
+
+use crate::{Output, INTERVAL, START_DURATION, STEP, STOP_DURATION};
+
+/// Time the motor is given to settle after changing the pulse width
+pub const SETTLE: Duration = Duration::from_millis(300);
+
+/// Encoder edges needed to consider the wheel moving, ignoring single
+/// spurious edges
+const MIN_EDGES: usize = 2;
+
+/// Change of a sensor value needed to consider the wheel moving
+const MIN_CHANGE: u8 = 16;
+
+/// Time between reads of a sensor watching the wheel
+const SENSOR_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Distance from the nominal stop width the dead band is searched within
+const DEAD_BAND_RANGE: Duration = Duration::from_micros(100);
+
+/// An input of the ADC the sensors are read from
+#[derive(Debug, Clone, Copy)]
+pub struct AdcInput(u8);
+
+impl ToSensorChannel for AdcInput {
+    fn to_channel(&self) -> u8 {
+        self.0
+    }
+}
+
+/// Feedback telling whether the wheel moves
+#[derive(Debug)]
+pub enum Encoder {
+    /// Wheel encoder counting edges on a GPIO pin
+    Pin {
+        /// The pin, kept to keep its interrupt registered
+        _pin: InputPin,
+        /// Number of edges seen
+        edges: Arc<AtomicUsize>,
+    },
+    /// Reflective sensor on an ADC input watching a marked wheel
+    Sensor {
+        /// The sensors
+        sensors: SensorController,
+        /// The watching sensor
+        input: AdcInput,
+    },
+}
+
+impl Encoder {
+    /// Count the edges of a wheel encoder on a GPIO pin
+    pub fn pin(pin: u8) -> Result<Self> {
+        let mut pin = Gpio::new()?.get(pin)?.into_input();
+        let edges = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&edges);
+        pin.set_async_interrupt(Trigger::Both, None, move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        })?;
+        Ok(Self::Pin { _pin: pin, edges })
+    }
+
+    /// Watch the wheel using the sensor on an ADC input
+    pub fn sensor(input: u8) -> Result<Self> {
+        ensure!(
+            input < ADC_CHANNELS,
+            "ADC input {input} does not exist, expected 0 to {}",
+            ADC_CHANNELS - 1
+        );
+        Ok(Self::Sensor {
+            sensors: SensorController::try_default()?,
+            input: AdcInput(input),
+        })
+    }
+
+    /// Watch the wheel for a [`Duration`], returning whether it moved
+    pub fn moved(&mut self, duration: Duration) -> Result<bool> {
+        match self {
+            Self::Pin { edges, .. } => {
+                let before = edges.load(Ordering::Relaxed);
+                std::thread::sleep(duration);
+                Ok(edges.load(Ordering::Relaxed) - before >= MIN_EDGES)
+            }
+            Self::Sensor { sensors, input } => {
+                let start = Instant::now();
+                let first = sensors.read(*input)?;
+                let (mut min, mut max) = (first, first);
+                while start.elapsed() < duration {
+                    let value = sensors.read(*input)?;
+                    (min, max) = (min.min(value), max.max(value));
+                    std::thread::sleep(SENSOR_INTERVAL);
+                }
+                Ok(max - min >= MIN_CHANGE)
+            }
+        }
+    }
+}
+
+/// Output a pulse width and watch the wheel once it settled, returning
+/// whether it moved
+fn moves_at(output: &mut Output, encoder: &mut Encoder, width: Duration) -> Result<bool> {
+    output.set_pulse_width(width)?;
+    std::thread::sleep(SETTLE);
+    let moved = encoder.moved(INTERVAL - SETTLE)?;
+    println!(
+        "Pulse width: {} {}",
+        width.as_micros(),
+        if moved { "moving" } else { "stationary" }
+    );
+    Ok(moved)
+}
+
+/// Sweep the pulse width from [`START_DURATION`] to [`STOP_DURATION`],
+/// returning whether the wheel moved at each width
+pub fn sweep(output: &mut Output, encoder: &mut Encoder) -> Result<Vec<(Duration, bool)>> {
+    let mut results = Vec::new();
+    let mut width = START_DURATION;
+
+    while width <= STOP_DURATION {
+        results.push((width, moves_at(output, encoder, width)?));
+        width += STEP;
+    }
+
+    Ok(results)
+}
+
+/// First pulse widths at which the wheel moves on either side of the
+/// nominal stop width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadBand {
+    /// The nominal stop width the search started from
+    pub stop: Duration,
+    /// First moving width below the stop width, if any within [`DEAD_BAND_RANGE`]
+    pub below: Option<Duration>,
+    /// First moving width above the stop width, if any within [`DEAD_BAND_RANGE`]
+    pub above: Option<Duration>,
+}
+
+impl DeadBand {
+    /// Search the dead band around a nominal stop width, stepping outwards in
+    /// both directions until `moves` reports motion on each side
+    fn search(stop: Duration, mut moves: impl FnMut(Duration) -> Result<bool>) -> Result<Self> {
+        ensure!(
+            !moves(stop)?,
+            "the motor moves at the nominal stop width of {}µs",
+            stop.as_micros()
+        );
+
+        let mut dead_band = Self {
+            stop,
+            below: None,
+            above: None,
+        };
+        let mut offset = STEP;
+        while offset <= DEAD_BAND_RANGE && (dead_band.below.is_none() || dead_band.above.is_none())
+        {
+            if dead_band.below.is_none() && moves(stop.saturating_sub(offset))? {
+                dead_band.below = Some(stop.saturating_sub(offset));
+            };
+            if dead_band.above.is_none() && moves(stop + offset)? {
+                dead_band.above = Some(stop + offset);
+            };
+            offset += STEP;
+        }
+        Ok(dead_band)
+    }
+
+    /// The lowest and highest width at which the wheel stood still, if motion
+    /// started on both sides
+    pub fn stationary(&self) -> Option<(Duration, Duration)> {
+        Some((self.below? + STEP, self.above? - STEP))
+    }
+}
+
+/// Search the dead band around a nominal stop width
+pub fn dead_band(output: &mut Output, encoder: &mut Encoder, stop: Duration) -> Result<DeadBand> {
+    DeadBand::search(stop, |width| moves_at(output, encoder, width))
+}
+
+/// The first and last width of the longest run of consecutive widths at
+/// which the wheel did not move
+pub fn stationary_range(results: &[(Duration, bool)]) -> Option<(Duration, Duration)> {
+    results
+        .chunk_by(|(_, a), (_, b)| a == b)
+        .filter(|run| !run[0].1)
+        .max_by_key(|run| run.len())
+        .map(|run| (run[0].0, run[run.len() - 1].0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{stationary_range, DeadBand};
+
+    /// Test that the longest stationary run is reported
+    #[test]
+    fn longest_stationary_run() {
+        let results: Vec<(Duration, bool)> = [
+            (1_400, true),
+            (1_410, false),
+            (1_420, true),
+            (1_430, false),
+            (1_440, false),
+            (1_450, false),
+            (1_460, true),
+        ]
+        .map(|(micros, moved)| (Duration::from_micros(micros), moved))
+        .to_vec();
+
+        assert_eq!(
+            stationary_range(&results),
+            Some((Duration::from_micros(1_430), Duration::from_micros(1_450)))
+        );
+        assert_eq!(stationary_range(&results[..1]), None);
+    }
+
+    /// Test that the dead band search stops at the first motion on each side
+    #[test]
+    fn dead_band_search() {
+        let micros = Duration::from_micros;
+        let mut tested = Vec::new();
+        let dead_band = DeadBand::search(micros(1_480), |width| {
+            tested.push(width.as_micros());
+            Ok(width <= micros(1_450) || width >= micros(1_500))
+        })
+        .unwrap();
+
+        assert_eq!(tested, [1_480, 1_470, 1_490, 1_460, 1_500, 1_450]);
+        assert_eq!(dead_band.below, Some(micros(1_450)));
+        assert_eq!(dead_band.above, Some(micros(1_500)));
+        assert_eq!(dead_band.stationary(), Some((micros(1_460), micros(1_490))));
+
+        // Searching stops at the end of the range
+        let dead_band =
+            DeadBand::search(micros(1_480), |width| Ok(width >= micros(1_500))).unwrap();
+        assert_eq!(dead_band.below, None);
+        assert_eq!(dead_band.stationary(), None);
+
+        assert!(DeadBand::search(micros(1_480), |_| Ok(true)).is_err());
+    }
+}
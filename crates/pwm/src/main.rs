@@ -9,18 +9,50 @@
 //! activates. Allowing you to use it in the future as a hardcoded value. The
 //! current implementation is targeted towards our specific hardware component,
 //! however it can be adapted to any motor by tweaking the given const values.
+//!
+//! By default the pulse width is swept upwards in fixed steps. With
+//! `--interactive` the arrow keys nudge the pulse width up and down instead,
+//! and `[`/`]` mark the lowest and highest width at which the motor stands
+//! still, so the stop point can be bracketed precisely. With `--encoder-pin` or
+//! `--encoder-channel` the sweep watches the wheel itself and reports the range
+//! where the motor stays stationary. With `--dead-band` as well, the sweep
+//! starts at the nominal stop width and steps outwards in both directions,
+//! reporting the first width at which the motor moves each way.
+//!
+//! With `--period-sweep` a software PWM pin holds a driving pulse width while
+//! the PWM frequency is swept instead, reporting the frequencies at which the
+//! ESC stops responding when there is wheel feedback.
+//!
+//! With `--motor` the found stop pulse width is printed as a snippet of the
+//! hardware configuration for that motor, and `--config` writes it into a
+//! configuration file, optionally inside the robot profile given by `--profile`.
 
-use std::time::Duration;
+use std::{
+    io::{stdout, Write},
+    path::PathBuf,
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use clap::{Parser, ValueEnum};
 
+use config::{Findings, Motor};
+use consts::config::{set_profile, HardwareConfig};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    terminal,
+};
+use detect::Encoder;
 use rppal::{
-    gpio::Gpio,
+    gpio::{Gpio, OutputPin},
     pwm::{Channel, Pwm},
 };
 
+mod config;
+mod detect;
+mod period;
+
 /// Command Line Arguments for PWM Calibration
 #[derive(Parser)]
 struct Args {
@@ -29,10 +61,46 @@ struct Args {
     /// Pick software or hardware PWM
     #[clap(value_enum)]
     pwm: PWMVariant,
+    /// Adjust the pulse width using the arrow keys instead of sweeping it
+    #[clap(long)]
+    interactive: bool,
+    /// Microseconds the arrow keys change the pulse width by
+    #[clap(long, default_value_t = STEP.as_micros() as u64, requires = "interactive")]
+    step: u64,
+    /// Detect motion using a wheel encoder on this GPIO pin
+    #[clap(long, group = "encoder", conflicts_with = "interactive")]
+    encoder_pin: Option<u8>,
+    /// Detect motion using the sensor on this ADC input watching a marked wheel
+    #[clap(long, group = "encoder", conflicts_with = "interactive")]
+    encoder_channel: Option<u8>,
+    /// Search the dead band outwards from the nominal stop width in both
+    /// directions
+    #[clap(long, requires = "encoder")]
+    dead_band: bool,
+    /// Nominal stop width in microseconds to search the dead band around,
+    /// defaults to the configured width of `--motor`
+    #[clap(long, requires = "dead_band")]
+    stop: Option<u64>,
+    /// Sweep the PWM frequency of a software PWM pin instead of the pulse width
+    #[clap(long, conflicts_with_all = ["interactive", "dead_band"])]
+    period_sweep: bool,
+    /// Pulse width in microseconds held while sweeping the frequency
+    #[clap(long, default_value_t = STOP_DURATION.as_micros() as u64, requires = "period_sweep")]
+    width: u64,
+    /// The motor being calibrated, prints its configuration with the found
+    /// stop pulse width
+    #[clap(long, value_enum)]
+    motor: Option<Motor>,
+    /// Write the found stop pulse width into this hardware configuration file
+    #[clap(long, requires = "motor")]
+    config: Option<PathBuf>,
+    /// Write into this robot profile of the hardware configuration
+    #[clap(long, requires = "motor")]
+    profile: Option<String>,
 }
 
 /// Allow specifying the PWM variant (hardware or software)
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
 enum PWMVariant {
     /// Hardware PWM
     Hardware,
@@ -67,33 +135,84 @@ const STEP: Duration = Duration::from_micros(10);
 /// [`Duration`] what to keep the current pulse width for
 const INTERVAL: Duration = Duration::from_secs(1);
 
-/// Calibrate Software pwm stop pulse width
-fn software_pwm(pin: u8) -> Result<()> {
-    let mut pin = Gpio::new()?.get(pin)?.into_output_low();
+/// Smallest step of the interactive mode
+const MIN_STEP: Duration = Duration::from_micros(1);
 
-    let mut width = START_DURATION;
+/// Largest step of the interactive mode
+const MAX_STEP: Duration = Duration::from_micros(100);
 
-    while width <= STOP_DURATION {
-        pin.set_pwm(PERIOD, width)?;
-        println!("Pulse width: {}", width.as_micros());
-        // Wait for INTERVAL and update pulse width
-        std::thread::sleep(INTERVAL);
-        width += STEP;
+/// A software or hardware PWM output
+#[derive(Debug)]
+enum Output {
+    /// Software PWM on a GPIO pin
+    Software(OutputPin),
+    /// Hardware [`Pwm`] channel
+    Hardware(Pwm),
+}
+
+impl Output {
+    /// Set up software PWM on a pin
+    fn software(pin: u8) -> Result<Self> {
+        Ok(Self::Software(Gpio::new()?.get(pin)?.into_output_low()))
     }
 
-    Ok(())
+    /// Set up and enable a hardware [`Pwm`] channel
+    fn hardware(channel: Channel) -> Result<Self> {
+        let pwm = Pwm::new(channel)?;
+        pwm.set_period(PERIOD)?;
+        pwm.enable()?;
+        Ok(Self::Hardware(pwm))
+    }
+
+    /// Output a pulse width
+    fn set_pulse_width(&mut self, width: Duration) -> Result<()> {
+        match self {
+            Self::Software(pin) => pin.set_pwm(PERIOD, width)?,
+            Self::Hardware(pwm) => pwm.set_pulse_width(width)?,
+        };
+        Ok(())
+    }
 }
 
-/// Calibrate Hardware [`Pwm`] stop pulse width
-fn hardware_pwm(channel: Channel) -> Result<()> {
-    let pwm = Pwm::new(channel)?;
-    pwm.set_period(PERIOD)?;
-    pwm.enable()?;
+/// The nominal stop width the dead band is searched around
+///
+/// Either given on the command line, configured for the calibrated motor in
+/// the hardware configuration, or the middle of the swept range.
+fn nominal_stop(args: &Args) -> Result<Duration> {
+    if let Some(stop) = args.stop {
+        return Ok(Duration::from_micros(stop));
+    };
+    let Some(motor) = args.motor else {
+        return Ok((START_DURATION + STOP_DURATION) / 2);
+    };
+
+    let profile = args.profile.as_deref();
+    let config = match &args.config {
+        Some(path) if path.exists() => HardwareConfig::from_file_profile(path, profile)?,
+        _ => {
+            if let Some(profile) = profile {
+                set_profile(profile);
+            };
+            HardwareConfig::load()?
+        }
+    };
+    let motor = match motor {
+        Motor::Left => config.left_motor,
+        Motor::Right => config.right_motor,
+    };
+    Ok(match args.pwm {
+        PWMVariant::Hardware => motor.hardware_pwm.stop_pulse_width(),
+        PWMVariant::Software => motor.software_pwm.stop_pulse_width(),
+    })
+}
 
+/// Calibrate the stop pulse width by sweeping from [`START_DURATION`] to
+/// [`STOP_DURATION`]
+fn sweep(output: &mut Output) -> Result<()> {
     let mut width = START_DURATION;
 
     while width <= STOP_DURATION {
-        pwm.set_pulse_width(width)?;
+        output.set_pulse_width(width)?;
         println!("Pulse width: {}", width.as_micros());
         // Wait for INTERVAL and update pulse width
         std::thread::sleep(INTERVAL);
@@ -103,18 +222,246 @@ fn hardware_pwm(channel: Channel) -> Result<()> {
     Ok(())
 }
 
+/// State of the interactive calibration
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bracket {
+    /// Current pulse width
+    width: Duration,
+    /// Amount the pulse width is nudged by
+    step: Duration,
+    /// Lowest pulse width marked as stopped
+    low: Option<Duration>,
+    /// Highest pulse width marked as stopped
+    high: Option<Duration>,
+}
+
+impl Bracket {
+    /// Start in the middle of the swept range, nudging by `step`
+    fn new(step: Duration) -> Self {
+        Self {
+            width: (START_DURATION + STOP_DURATION) / 2,
+            step: step.clamp(MIN_STEP, MAX_STEP),
+            low: None,
+            high: None,
+        }
+    }
+
+    /// Increase the pulse width by the step, staying within the [`PERIOD`]
+    fn up(&mut self) {
+        self.width = (self.width + self.step).min(PERIOD);
+    }
+
+    /// Decrease the pulse width by the step
+    fn down(&mut self) {
+        self.width = self.width.saturating_sub(self.step);
+    }
+
+    /// Mark the current pulse width as the lowest or the highest stopped
+    /// width, keeping the marks in order
+    fn mark(&mut self, low: bool) {
+        match low {
+            true => self.low = Some(self.width),
+            false => self.high = Some(self.width),
+        };
+        if let (Some(low), Some(high)) = (self.low, self.high) {
+            if low > high {
+                (self.low, self.high) = (Some(high), Some(low));
+            };
+        };
+    }
+
+    /// The middle of the marked stop range
+    fn middle(&self) -> Option<Duration> {
+        Some((self.low? + self.high?) / 2)
+    }
+}
+
+/// Print the state of a [`Bracket`] over the previous line
+fn print_bracket(bracket: &Bracket) -> Result<()> {
+    let micros = |width: Option<Duration>| match width {
+        Some(width) => width.as_micros().to_string(),
+        None => "-".to_string(),
+    };
+    print!(
+        "\r\x1b[KPulse width: {}µs (step {}µs) | stopped from {} to {}, middle {}",
+        bracket.width.as_micros(),
+        bracket.step.as_micros(),
+        micros(bracket.low),
+        micros(bracket.high),
+        micros(bracket.middle()),
+    );
+    Ok(stdout().flush()?)
+}
+
+/// Calibrate the stop pulse width interactively until Esc is pressed
+///
+/// Returns the final [`Bracket`].
+fn interactive(output: &mut Output, step: Duration) -> Result<Bracket> {
+    println!("up/down nudge | +/- step size | [ lowest stop | ] highest stop | esc exit");
+    let mut bracket = Bracket::new(step);
+
+    loop {
+        output.set_pulse_width(bracket.width)?;
+        print_bracket(&bracket)?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Right => bracket.up(),
+            KeyCode::Down | KeyCode::Left => bracket.down(),
+            KeyCode::Char('+') => bracket.step = (bracket.step * 10).min(MAX_STEP),
+            KeyCode::Char('-') => bracket.step = (bracket.step / 10).max(MIN_STEP),
+            KeyCode::Char('[') => bracket.mark(true),
+            KeyCode::Char(']') => bracket.mark(false),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(bracket),
+            _ => {}
+        };
+    }
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    match args.pwm {
+    let mut output = match args.pwm {
         PWMVariant::Hardware => {
             // Convert pin to a hardware pwm channel
             let channel = pin_to_channel(args.pin)?;
-            hardware_pwm(channel)?;
+            Output::hardware(channel)?
         }
-        PWMVariant::Software => {
-            software_pwm(args.pin)?;
+        PWMVariant::Software => Output::software(args.pin)?,
+    };
+
+    let mut encoder = match (args.encoder_pin, args.encoder_channel) {
+        (Some(pin), _) => Some(Encoder::pin(pin)?),
+        (_, Some(input)) => Some(Encoder::sensor(input)?),
+        (None, None) => None,
+    };
+    if args.period_sweep {
+        let Output::Software(pin) = &mut output else {
+            bail!("sweeping the frequency needs software PWM");
+        };
+        let width = Duration::from_micros(args.width);
+        let results = period::sweep(pin, width, encoder.as_mut())?;
+        if encoder.is_some() {
+            let ignored: Vec<_> = results
+                .iter()
+                .filter(|(_, moved)| *moved == Some(false))
+                .map(|(frequency, _)| format!("{frequency}Hz"))
+                .collect();
+            match ignored.is_empty() {
+                true => println!("The ESC responded at every frequency"),
+                false => println!("The ESC did not respond at {}", ignored.join(", ")),
+            };
+        };
+        return Ok(());
+    };
+
+    let stationary = if let Some(encoder) = encoder.as_mut().filter(|_| args.dead_band) {
+        let stop = nominal_stop(&args)?;
+        let dead_band = detect::dead_band(&mut output, encoder, stop)?;
+        let micros = |width: Option<Duration>| match width {
+            Some(width) => format!("{}µs", width.as_micros()),
+            None => "no motion".to_string(),
+        };
+        println!(
+            "Around {}µs: moving below at {}, moving above at {}",
+            stop.as_micros(),
+            micros(dead_band.below),
+            micros(dead_band.above)
+        );
+        dead_band.stationary()
+    } else if let Some(encoder) = encoder.as_mut() {
+        let results = detect::sweep(&mut output, encoder)?;
+        let range = detect::stationary_range(&results);
+        match range {
+            Some((low, high)) => println!(
+                "Stationary from {}µs to {}µs, stop pulse width: {}µs",
+                low.as_micros(),
+                high.as_micros(),
+                ((low + high) / 2).as_micros()
+            ),
+            None => println!("The motor moved at every pulse width"),
+        };
+        range
+    } else if args.interactive {
+        terminal::enable_raw_mode()?;
+        let result = interactive(&mut output, Duration::from_micros(args.step));
+        terminal::disable_raw_mode()?;
+
+        let bracket = result?;
+        println!();
+        match bracket.middle() {
+            Some(middle) => println!("Stop pulse width: {}µs", middle.as_micros()),
+            None => println!("Last pulse width: {}µs", bracket.width.as_micros()),
+        };
+        bracket.low.zip(bracket.high)
+    } else {
+        sweep(&mut output)?;
+        None
+    };
+
+    let Some(motor) = args.motor else {
+        return Ok(());
+    };
+    let Some((low, high)) = stationary else {
+        println!("No stop pulse width found, the configuration is left unchanged");
+        return Ok(());
+    };
+    let findings = Findings {
+        motor,
+        pwm: args.pwm,
+        stop: (low + high) / 2,
+        stationary,
+    };
+    let profile = args.profile.as_deref();
+    match args.config {
+        Some(path) => {
+            findings.write(&path, profile)?;
+            println!("Wrote the stop pulse width to `{}`", path.display());
         }
+        None => print!("\n{}", findings.update("", profile)?),
     };
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Bracket, MAX_STEP};
+
+    /// Test nudging the pulse width and bracketing the stop point
+    #[test]
+    fn bracket_stop() {
+        let mut bracket = Bracket::new(Duration::from_micros(5));
+        assert_eq!(bracket.width, Duration::from_micros(1_500));
+
+        bracket.up();
+        bracket.mark(false);
+        bracket.down();
+        bracket.down();
+        bracket.down();
+        assert_eq!(bracket.middle(), None);
+        bracket.mark(true);
+        assert_eq!(bracket.low, Some(Duration::from_micros(1_490)));
+        assert_eq!(
+            bracket.middle(),
+            Some(Duration::from_micros(1_497) + Duration::from_nanos(500))
+        );
+
+        // Marks are kept in order
+        bracket.up();
+        bracket.up();
+        bracket.up();
+        bracket.up();
+        bracket.mark(true);
+        assert_eq!(bracket.low, Some(Duration::from_micros(1_505)));
+        assert_eq!(bracket.high, Some(Duration::from_micros(1_510)));
+
+        assert_eq!(Bracket::new(Duration::from_secs(1)).step, MAX_STEP);
+    }
+}
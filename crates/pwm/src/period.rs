@@ -0,0 +1,99 @@
+//! Sweeping the PWM period of a software PWM pin
+//!
+//! ESCs built for servo signals expect a period of 20ms (50Hz), while others
+//! accept much higher frequencies. A pulse width that drives the motor is held
+//! at frequencies from [`MIN_FREQUENCY`] to [`MAX_FREQUENCY`], skipping those
+//! whose period is shorter than the pulse. Before each frequency the signal is
+//! cut, so the motor comes to a stop and only moves again if the ESC responds.
+//! With wheel feedback the frequencies the ESC stops responding at are
+//! reported, otherwise the motor has to be watched.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use rppal::gpio::OutputPin;
+
+use crate::{
+    detect::{Encoder, SETTLE},
+    INTERVAL,
+};
+
+/// Lowest swept frequency in Hz, the servo frequency
+const MIN_FREQUENCY: u32 = 50;
+
+/// Highest swept frequency in Hz
+const MAX_FREQUENCY: u32 = 600;
+
+/// Amount to increase the frequency by on each step in Hz
+const FREQUENCY_STEP: u32 = 50;
+
+/// The swept frequencies at which a pulse width fits into the period
+fn frequencies(width: Duration) -> impl Iterator<Item = u32> {
+    (MIN_FREQUENCY..=MAX_FREQUENCY)
+        .step_by(FREQUENCY_STEP as usize)
+        .filter(move |&frequency| period(frequency) > width)
+}
+
+/// The period of a frequency in Hz
+fn period(frequency: u32) -> Duration {
+    Duration::from_secs(1) / frequency
+}
+
+/// Hold a pulse width at every swept frequency, returning whether the wheel
+/// moved at each one if there is an [`Encoder`]
+pub fn sweep(
+    pin: &mut OutputPin,
+    width: Duration,
+    mut encoder: Option<&mut Encoder>,
+) -> Result<Vec<(u32, Option<bool>)>> {
+    let mut results = Vec::new();
+
+    for frequency in frequencies(width) {
+        // Let the motor stop without a signal
+        pin.clear_pwm()?;
+        pin.set_low();
+        std::thread::sleep(SETTLE);
+
+        pin.set_pwm(period(frequency), width)?;
+        let moved = match encoder.as_deref_mut() {
+            Some(encoder) => {
+                std::thread::sleep(SETTLE);
+                Some(encoder.moved(INTERVAL - SETTLE)?)
+            }
+            None => {
+                std::thread::sleep(INTERVAL);
+                None
+            }
+        };
+        match moved {
+            Some(true) => println!("Frequency: {frequency}Hz responding"),
+            Some(false) => println!("Frequency: {frequency}Hz not responding"),
+            None => println!("Frequency: {frequency}Hz"),
+        };
+        results.push((frequency, moved));
+    }
+
+    pin.clear_pwm()?;
+    pin.set_low();
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::frequencies;
+
+    /// Test that only frequencies with room for the pulse width are swept
+    #[test]
+    fn swept_frequencies() {
+        assert_eq!(
+            frequencies(Duration::from_micros(1_600)).collect::<Vec<_>>(),
+            [50, 100, 150, 200, 250, 300, 350, 400, 450, 500, 550, 600]
+        );
+        assert_eq!(
+            frequencies(Duration::from_micros(2_500)).collect::<Vec<_>>(),
+            [50, 100, 150, 200, 250, 300, 350]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Python client of the REST API of the `server`
+//!
+//! Every command returns the JSON response of the server as a `dict`, also
+//! when the command is denied, e.g. `{"status": 409, "reason": "Calibrate"}`.
+//! Only failing to reach the server raises a `ConnectionError`.
+
+use std::io::{BufRead, BufReader};
+
+use pyo3::{
+    exceptions::{PyConnectionError, PyIOError},
+    prelude::*,
+};
+
+use crate::directions::PyVehicleDirection;
+
+/// Client of a logbot `server`
+#[pyclass(module = "logbot", frozen)]
+#[derive(Debug)]
+pub struct Client {
+    /// Agent used for all requests
+    agent: ureq::Agent,
+    /// Base URL of the server, without a trailing slash
+    url: String,
+}
+
+impl Client {
+    /// The full URL of an endpoint, e.g. `lift/up`
+    fn endpoint(&self, endpoint: &str) -> String {
+        format!("{}/v1/{endpoint}", self.url)
+    }
+
+    /// Send a request without holding the GIL, with a JSON body if given,
+    /// returning the decoded JSON response
+    fn request<'py>(
+        &self,
+        py: Python<'py>,
+        request: ureq::Request,
+        json: Option<String>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let url = request.url().to_string();
+        let body = py.allow_threads(|| {
+            let result = match json {
+                Some(json) => request
+                    .set("Content-Type", "application/json")
+                    .send_string(&json),
+                None => request.call(),
+            };
+            body(&url, result)
+        })?;
+        loads(py, &body)
+    }
+}
+
+#[pymethods]
+impl Client {
+    /// Create a [`Client`] for a server at the given base URL, e.g.
+    /// `http://logbot.local:9999`
+    #[new]
+    fn new(url: &str) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Check that the server and its hardware are running
+    fn health<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.request(py, self.agent.get(&self.endpoint("health")), None)
+    }
+
+    /// Describe the server and its hardware
+    fn info<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.request(py, self.agent.get(&self.endpoint("info")), None)
+    }
+
+    /// The commands sent to logbot, most recent last
+    fn history<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.request(py, self.agent.get(&self.endpoint("history")), None)
+    }
+
+    /// The state of a long-running command started by another command
+    fn job<'py>(&self, py: Python<'py>, id: u64) -> PyResult<Bound<'py, PyAny>> {
+        let url = self.endpoint(&format!("jobs/{id}"));
+        self.request(py, self.agent.get(&url), None)
+    }
+
+    /// Send a command without a body to an endpoint, e.g. `lift/up`
+    fn send<'py>(&self, py: Python<'py>, endpoint: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.request(py, self.agent.post(&self.endpoint(endpoint)), None)
+    }
+
+    /// Drive in a [`PyVehicleDirection`] until the next command
+    fn drive<'py>(
+        &self,
+        py: Python<'py>,
+        direction: PyVehicleDirection,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let json = serde_json::to_string(&direction.0).expect("directions are serializable");
+        self.request(py, self.agent.post(&self.endpoint("drive")), Some(json))
+    }
+
+    /// Stop whatever logbot is doing
+    fn stop<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "stop")
+    }
+
+    /// Calibrate both sensors by oscillating over the line
+    fn calibrate<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "calibrate")
+    }
+
+    /// Find the edge of the line, requires a calibration
+    fn find_edge<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "edge")
+    }
+
+    /// Follow the line until stopped, requires finding the edge first
+    fn follow<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "follow")
+    }
+
+    /// Move the lift up
+    fn lift_up<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "lift/up")
+    }
+
+    /// Move the lift down
+    fn lift_down<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "lift/down")
+    }
+
+    /// Pause the running command, keeping its progress
+    fn pause<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "pause")
+    }
+
+    /// Resume the paused command
+    fn resume<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "resume")
+    }
+
+    /// Run the full demo
+    fn demo<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.send(py, "demo")
+    }
+
+    /// Run a mission of steps, a list of the same JSON objects the server
+    /// accepts at `POST /v1/mission`
+    fn mission<'py>(
+        &self,
+        py: Python<'py>,
+        steps: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let steps: String = py
+            .import("json")?
+            .call_method1("dumps", (steps,))?
+            .extract()?;
+        self.request(py, self.agent.post(&self.endpoint("mission")), Some(steps))
+    }
+
+    /// Read a number of samples from the telemetry stream, each a `dict`
+    /// with the `elapsed_us` and the `left` and `right` sensor values
+    fn telemetry<'py>(&self, py: Python<'py>, count: usize) -> PyResult<Vec<Bound<'py, PyAny>>> {
+        let url = self.endpoint("telemetry");
+        let lines = py.allow_threads(|| {
+            let response = self
+                .agent
+                .get(&url)
+                .call()
+                .map_err(|e| PyConnectionError::new_err(e.to_string()))?;
+            BufReader::new(response.into_reader())
+                .lines()
+                .take(count)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| PyIOError::new_err(format!("failed to read `{url}`: {e}")))
+        })?;
+        lines.iter().map(|line| loads(py, line)).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Client('{}')", self.url)
+    }
+}
+
+/// The body of the response to a request of a URL
+///
+/// Denied commands are answered with an error status, explained by the body.
+fn body(url: &str, result: Result<ureq::Response, ureq::Error>) -> PyResult<String> {
+    let response = match result {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(e) => return Err(PyConnectionError::new_err(e.to_string())),
+    };
+    response
+        .into_string()
+        .map_err(|e| PyIOError::new_err(format!("failed to read `{url}`: {e}")))
+}
+
+/// Decode JSON into Python objects
+fn loads<'py>(py: Python<'py>, json: &str) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?.call_method1("loads", (json,))
+}
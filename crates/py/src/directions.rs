@@ -0,0 +1,177 @@
+//! Python classes of a [`MotorDirection`] and a [`VehicleDirection`]
+//!
+//! Both are created from the same strings the CLI and the scripts parse,
+//! e.g. `forward:0.5`, `spin_left:0.2` or `forward:0.3,forward:0.5`.
+
+use std::ops::Not;
+
+use directions::{MotorDirection, SpeedControl, VehicleDirection};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::speed::PySpeed;
+
+/// Direction a single motor turns in
+#[pyclass(name = "MotorDirection", module = "logbot", frozen, eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PyMotorDirection(pub MotorDirection);
+
+#[pymethods]
+impl PyMotorDirection {
+    /// The stopped motor
+    #[classattr]
+    const STOP: Self = Self(MotorDirection::Stop);
+
+    /// Parse a direction, e.g. `forward:0.5` or `stop`
+    #[new]
+    fn new(direction: &str) -> PyResult<Self> {
+        direction
+            .parse()
+            .map(Self)
+            .map_err(|e| PyValueError::new_err(format!("{e}")))
+    }
+
+    /// Turn forward at a [`PySpeed`]
+    #[staticmethod]
+    fn forward(speed: PySpeed) -> Self {
+        Self(MotorDirection::Forward(speed.0))
+    }
+
+    /// Turn backward at a [`PySpeed`]
+    #[staticmethod]
+    fn backward(speed: PySpeed) -> Self {
+        Self(MotorDirection::Backward(speed.0))
+    }
+
+    /// Create a direction from a signed speed, negative when turning backward
+    #[staticmethod]
+    fn from_signed(value: f64) -> Self {
+        Self(MotorDirection::from_signed(value))
+    }
+
+    /// The speed regardless of the direction
+    #[getter]
+    fn speed(&self) -> PySpeed {
+        PySpeed(self.0.speed())
+    }
+
+    /// The speed, negative when turning backward
+    #[getter]
+    fn signed(&self) -> f64 {
+        self.0.signed()
+    }
+
+    fn __mul__(&self, speed: PySpeed) -> Self {
+        Self(self.0 * speed.0)
+    }
+
+    fn __invert__(&self) -> Self {
+        Self(self.0.not())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MotorDirection('{}')", self.0)
+    }
+}
+
+/// Directions both motors of logbot turn in
+#[pyclass(name = "VehicleDirection", module = "logbot", frozen, eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PyVehicleDirection(pub VehicleDirection);
+
+#[pymethods]
+impl PyVehicleDirection {
+    /// Both motors stopped
+    #[classattr]
+    const STOP: Self = Self(VehicleDirection::STOP);
+
+    /// Parse a direction, e.g. `forward:0.5`, `spin_left:0.2` or
+    /// `forward:0.3,forward:0.5`
+    #[new]
+    fn new(direction: &str) -> PyResult<Self> {
+        direction
+            .parse()
+            .map(Self)
+            .map_err(|e| PyValueError::new_err(format!("{e}")))
+    }
+
+    /// Combine the directions of the left and the right motor
+    #[staticmethod]
+    fn from_motors(left: PyMotorDirection, right: PyMotorDirection) -> Self {
+        Self(VehicleDirection::new(left.0, right.0))
+    }
+
+    /// Drive straight forward at a [`PySpeed`]
+    #[staticmethod]
+    fn forward(speed: PySpeed) -> Self {
+        Self(VehicleDirection::forward(speed.0))
+    }
+
+    /// Drive straight backward at a [`PySpeed`]
+    #[staticmethod]
+    fn backward(speed: PySpeed) -> Self {
+        Self(VehicleDirection::backward(speed.0))
+    }
+
+    /// Spin left in place at a [`PySpeed`]
+    #[staticmethod]
+    fn spin_left(speed: PySpeed) -> Self {
+        Self(VehicleDirection::spin_left(speed.0))
+    }
+
+    /// Spin right in place at a [`PySpeed`]
+    #[staticmethod]
+    fn spin_right(speed: PySpeed) -> Self {
+        Self(VehicleDirection::spin_right(speed.0))
+    }
+
+    /// Steer with the axes of a joystick, each from -1.0 to 1.0, up to a
+    /// maximum [`PySpeed`]
+    #[staticmethod]
+    fn from_axes(x: f64, y: f64, max: PySpeed) -> Self {
+        Self(VehicleDirection::from_axes(x, y, max.0))
+    }
+
+    /// Direction of the left motor
+    #[getter]
+    fn left(&self) -> PyMotorDirection {
+        PyMotorDirection(self.0.left)
+    }
+
+    /// Direction of the right motor
+    #[getter]
+    fn right(&self) -> PyMotorDirection {
+        PyMotorDirection(self.0.right)
+    }
+
+    /// The same movement driven backward
+    fn reversed(&self) -> Self {
+        Self(self.0.reversed())
+    }
+
+    /// The same movement with left and right swapped
+    fn mirrored(&self) -> Self {
+        Self(self.0.mirrored())
+    }
+
+    /// Interpolate towards another direction, where `t` of 0.0 is this and
+    /// 1.0 the other direction
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Self(self.0.lerp(other.0, t))
+    }
+
+    fn __mul__(&self, speed: PySpeed) -> Self {
+        Self(self.0 * speed.0)
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("VehicleDirection('{}')", self.0)
+    }
+}
@@ -0,0 +1,45 @@
+//! Python bindings for controlling logbot and analyzing its telemetry
+//!
+//! The `logbot` Python module exposes the [`Speed`] and the directions
+//! logbot is driven in, a [`Client`] of the REST API of the `server` and a
+//! [`Simulator`] of logbot on a virtual line course, so missions can be
+//! scripted and telemetry analyzed from a Python notebook.
+//!
+//! ```python
+//! import logbot
+//!
+//! sim = logbot.Simulator("oval")
+//! sim.drive(logbot.VehicleDirection("forward:0.5"))
+//! sim.advance(1.0)
+//! print(sim.pose, sim.sensors)
+//!
+//! client = logbot.Client("http://logbot.local:9999")
+//! client.calibrate()
+//! samples = client.telemetry(200)
+//! ```
+//!
+//! The module is built with [maturin](https://www.maturin.rs), e.g.
+//! `maturin develop -m crates/py/Cargo.toml` inside a virtual environment.
+//!
+//! [`Speed`]: speed::PySpeed
+//! [`Client`]: client::Client
+//! [`Simulator`]: sim::Simulator
+
+use pyo3::prelude::*;
+
+pub mod client;
+pub mod directions;
+pub mod sim;
+pub mod speed;
+
+/// The `logbot` Python module
+#[pymodule]
+#[pyo3(name = "logbot")]
+fn logbot_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<speed::PySpeed>()?;
+    m.add_class::<directions::PyMotorDirection>()?;
+    m.add_class::<directions::PyVehicleDirection>()?;
+    m.add_class::<client::Client>()?;
+    m.add_class::<sim::Simulator>()?;
+    Ok(())
+}
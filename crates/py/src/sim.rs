@@ -0,0 +1,147 @@
+//! Python class of a simulated logbot
+//!
+//! Unlike the simulation of the `mock` server, the [`Simulator`] only moves
+//! on when it is [advanced](Simulator::advance), so a script gives the same
+//! result every time it runs.
+
+use std::time::Duration;
+
+use directions::VehicleDirection;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use sim::{Clock, Course, Obstacle, Wheel, World};
+
+use crate::directions::PyVehicleDirection;
+
+/// Logbot driving on a virtual line course
+#[pyclass(module = "logbot")]
+#[derive(Debug)]
+pub struct Simulator {
+    /// The simulation
+    world: World,
+    /// Direction logbot is driven in
+    direction: PyVehicleDirection,
+}
+
+#[pymethods]
+impl Simulator {
+    /// Create a [`Simulator`] with logbot standing on the edge of the line of
+    /// the `rectangle` or the `oval` course
+    #[new]
+    #[pyo3(signature = (course = "rectangle"))]
+    fn new(course: &str) -> PyResult<Self> {
+        let course = match course {
+            "rectangle" => Course::rectangle(),
+            "oval" => Course::oval(),
+            _ => return Err(PyValueError::new_err(format!("unknown course `{course}`"))),
+        };
+        Ok(Self {
+            world: World::new(course, Clock::Manual),
+            direction: PyVehicleDirection(VehicleDirection::STOP),
+        })
+    }
+
+    /// Drive in a [`PyVehicleDirection`] until driven otherwise
+    fn drive(&mut self, direction: PyVehicleDirection) {
+        self.world.set_wheel(Wheel::Left, direction.0.left);
+        self.world.set_wheel(Wheel::Right, direction.0.right);
+        self.direction = direction;
+    }
+
+    /// Stop both motors
+    fn stop(&mut self) {
+        self.drive(PyVehicleDirection(VehicleDirection::STOP));
+    }
+
+    /// The direction logbot is driven in
+    #[getter]
+    fn direction(&self) -> PyVehicleDirection {
+        self.direction
+    }
+
+    /// Move the simulation on by a number of seconds
+    fn advance(&mut self, seconds: f64) -> PyResult<()> {
+        let duration = Duration::try_from_secs_f64(seconds)
+            .map_err(|e| PyValueError::new_err(format!("invalid duration {seconds}: {e}")))?;
+        self.world.advance(duration);
+        Ok(())
+    }
+
+    /// Position of logbot as `(x, y, heading)`, in meters and radians
+    /// counterclockwise from the x axis
+    #[getter]
+    fn pose(&mut self) -> (f64, f64, f64) {
+        let pose = self.world.pose();
+        (pose.x, pose.y, pose.heading)
+    }
+
+    /// Values of the left and the right sensor
+    #[getter]
+    fn sensors(&mut self) -> (u8, u8) {
+        (self.world.sensor(0), self.world.sensor(1))
+    }
+
+    /// Distance in meters to the nearest obstacle ahead, none if none is
+    /// within reach of the range finder
+    #[getter]
+    fn range(&mut self) -> Option<f64> {
+        self.world.range()
+    }
+
+    /// Place a round obstacle on the course, centered at `x` and `y`
+    fn place_obstacle(&mut self, x: f64, y: f64, radius: f64) {
+        self.world.place_obstacle(Obstacle { x, y, radius });
+    }
+
+    /// Remove all obstacles from the course
+    fn clear_obstacles(&mut self) {
+        self.world.clear_obstacles();
+    }
+
+    /// Position of the lift, from 0.0 when down to 1.0 when up
+    #[getter]
+    fn lift(&mut self) -> f64 {
+        self.world.lift()
+    }
+
+    /// Move the lift all the way up
+    fn lift_up(&mut self) {
+        self.world.set_lift(true);
+    }
+
+    /// Move the lift all the way down
+    fn lift_down(&mut self) {
+        self.world.set_lift(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use directions::VehicleDirection;
+    use speed::Speed;
+
+    use super::Simulator;
+    use crate::directions::PyVehicleDirection;
+
+    /// Test that the simulator only moves on when advanced
+    #[test]
+    fn drive_when_advanced() {
+        let mut sim = Simulator::new("rectangle").unwrap();
+        let (x, y, _) = sim.pose();
+
+        sim.drive(PyVehicleDirection(VehicleDirection::forward(Speed::HALF)));
+        assert_eq!(sim.pose(), (x, y, 0.0));
+
+        sim.advance(1.0).unwrap();
+        let (moved, _, _) = sim.pose();
+        assert!((moved - x - 0.5).abs() < 1e-9);
+
+        assert!(sim.advance(-1.0).is_err());
+    }
+
+    /// Test that only the known courses are simulated
+    #[test]
+    fn unknown_course() {
+        assert!(Simulator::new("oval").is_ok());
+        assert!(Simulator::new("figure eight").is_err());
+    }
+}
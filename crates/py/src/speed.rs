@@ -0,0 +1,56 @@
+//! Python class of a [`Speed`]
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use speed::Speed;
+
+/// Speed of a motor, from 0.0 when stopped to 1.0 at full speed
+#[pyclass(name = "Speed", module = "logbot", frozen, eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PySpeed(pub Speed);
+
+#[pymethods]
+impl PySpeed {
+    /// Standing still
+    #[classattr]
+    const MIN: Self = Self(Speed::MIN);
+
+    /// Half speed
+    #[classattr]
+    const HALF: Self = Self(Speed::HALF);
+
+    /// Full speed
+    #[classattr]
+    const MAX: Self = Self(Speed::MAX);
+
+    /// Create a speed, raising a `ValueError` outside of 0.0 to 1.0
+    #[new]
+    fn new(value: f64) -> PyResult<Self> {
+        Speed::new(value)
+            .map(Self)
+            .map_err(|value| PyValueError::new_err(format!("{value} is not within 0.0 to 1.0")))
+    }
+
+    /// Create a speed, clamping the value to 0.0 to 1.0
+    #[staticmethod]
+    fn clamp(value: f64) -> Self {
+        Self(Speed::new_clamp(value))
+    }
+
+    /// The speed as a float
+    #[getter]
+    fn value(&self) -> f64 {
+        self.0.value()
+    }
+
+    fn __float__(&self) -> f64 {
+        self.0.value()
+    }
+
+    fn __mul__(&self, other: &Self) -> Self {
+        Self(self.0 * other.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Speed({})", self.0.value())
+    }
+}
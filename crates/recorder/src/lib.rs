@@ -0,0 +1,323 @@
+//! Black-box recording of logbot to disk
+//!
+//! A [`Recorder`] writes every drive command, sensor read, step of the line
+//! follower and motion of the lift as a compact [`Record`] line to log files
+//! in a directory. Once a file reaches [`RecorderConfig::max_file_bytes`],
+//! recording continues in a new file and the oldest files beyond
+//! [`RecorderConfig::max_files`] are deleted, so the recording never takes
+//! more disk space than configured, however long logbot runs.
+//!
+//! Every file starts with a header giving the start of the recording in
+//! milliseconds since the Unix epoch, all times of the records count from
+//! there:
+//!
+//! ```text
+//! # logbot black box 1792125921445
+//! 0 d 0.2 0.1
+//! 1040 s 0 38
+//! ```
+//!
+//! Used as a [hook](logbot::hooks) of a [`Logbot`](logbot::Logbot), the
+//! recorder follows every drive, sensor read and lift motion, whether logbot
+//! is driven by the cli, the demo or the server. Steps of the line follower
+//! are not seen by the hooks, they are [recorded](Recorder::record) from
+//! their [`FollowLineStep`](line::FollowLineStep) as an [`Event::Pid`]:
+//!
+//! ```
+//! use components::{mock::{MockLift, MockMotor, MockSensorController}, Left, Right};
+//! use consts::Sensors;
+//! use interfaces::{Drive, SensorRead};
+//! use logbot::Logbot;
+//! use recorder::{Recorder, RecorderConfig};
+//! use vehicle::Vehicle;
+//!
+//! let directory = std::env::temp_dir().join(format!("logbot-doc-{}", std::process::id()));
+//! let mut logbot = Logbot::builder()
+//!     .vehicle(Vehicle::new(MockMotor::<Left>::new(), MockMotor::<Right>::new()))
+//!     .sensors(MockSensorController::new(0))
+//!     .lift(MockLift::new())
+//!     .hooks(Recorder::open(RecorderConfig::new(&directory)).unwrap())
+//!     .build();
+//!
+//! logbot.drive("forward:0.5".parse().unwrap()).unwrap();
+//! logbot.read(Sensors::Left).unwrap();
+//! logbot.hooks_mut().flush().unwrap();
+//! assert_eq!(recorder::read(&directory).unwrap().len(), 2);
+//! # std::fs::remove_dir_all(&directory).unwrap();
+//! ```
+
+// https://github.com/rust-lang/rust/issues/95513
+#![allow(unused_crate_dependencies)]
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use directions::VehicleDirection;
+use logbot::hooks::{DriveHook, LiftHook, LiftMotion, SensorHook};
+
+mod record;
+pub mod replay;
+
+pub use record::{Event, ParseRecordError, Record};
+
+/// Size in bytes a log file grows to before recording continues in a new one
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Number of log files kept before the oldest ones are deleted
+pub const DEFAULT_MAX_FILES: usize = 16;
+
+/// Prefix of the header line starting every log file
+const HEADER: &str = "# logbot black box ";
+
+/// Where and how much a [`Recorder`] records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecorderConfig {
+    /// Directory of the log files
+    pub directory: PathBuf,
+    /// Size in bytes a log file grows to at most
+    pub max_file_bytes: u64,
+    /// Number of log files kept, the disk space used is at most this many
+    /// times `max_file_bytes`
+    pub max_files: usize,
+}
+
+impl RecorderConfig {
+    /// Record to a directory with the default limits
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+/// Writer of [`Record`]s to rotating log files
+#[derive(Debug)]
+pub struct Recorder {
+    /// Where and how much to record
+    config: RecorderConfig,
+    /// When the recording started
+    started: Instant,
+    /// Milliseconds since the Unix epoch when the recording started
+    started_ms: u128,
+    /// The log file being written
+    file: BufWriter<File>,
+    /// Bytes written to the log file
+    written: u64,
+    /// Number of the log file being written
+    sequence: u64,
+    /// First error of recording from a hook, which can't return it
+    error: Option<io::Error>,
+}
+
+impl Recorder {
+    /// Start recording to a new log file in the configured directory,
+    /// creating the directory if needed
+    pub fn open(config: RecorderConfig) -> io::Result<Self> {
+        std::fs::create_dir_all(&config.directory)?;
+        let sequence = files(&config.directory)?
+            .last()
+            .and_then(|path| sequence(path))
+            .map_or(0, |last| last + 1);
+        let started_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis());
+        let (file, written) = create(&config.directory, sequence, started_ms)?;
+        let mut recorder = Self {
+            config,
+            started: Instant::now(),
+            started_ms,
+            file,
+            written,
+            sequence,
+            error: None,
+        };
+        recorder.prune()?;
+        Ok(recorder)
+    }
+
+    /// Record an [`Event`] happening now
+    pub fn record(&mut self, event: Event) -> io::Result<()> {
+        let record = Record {
+            time_us: self.started.elapsed().as_micros() as u64,
+            event,
+        };
+        let line = format!("{record}\n");
+        if self.written + line.len() as u64 > self.config.max_file_bytes {
+            self.rotate()?;
+        };
+        self.file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    /// Write the buffered records to disk
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    /// The first error of recording from a hook, since which nothing is
+    /// recorded from hooks
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Record an [`Event`] from a hook, keeping the first error
+    fn record_hooked(&mut self, event: Event) {
+        if self.error.is_none() {
+            self.error = self.record(event).err();
+        };
+    }
+
+    /// Continue recording in a new log file, deleting the oldest ones
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.sequence += 1;
+        (self.file, self.written) = create(&self.config.directory, self.sequence, self.started_ms)?;
+        self.prune()
+    }
+
+    /// Delete the oldest log files beyond the configured number
+    fn prune(&mut self) -> io::Result<()> {
+        let files = files(&self.config.directory)?;
+        let excess = files.len().saturating_sub(self.config.max_files.max(1));
+        for path in &files[..excess] {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl DriveHook<VehicleDirection> for Recorder {
+    fn on_drive(&mut self, direction: VehicleDirection) -> VehicleDirection {
+        self.record_hooked(Event::Drive(direction));
+        direction
+    }
+
+    fn on_stop(&mut self) {
+        self.record_hooked(Event::Drive(VehicleDirection::STOP));
+    }
+}
+
+impl LiftHook for Recorder {
+    fn on_lift(&mut self, motion: LiftMotion) {
+        self.record_hooked(Event::Lift(motion));
+    }
+}
+
+impl SensorHook<u8> for Recorder {
+    fn on_sensor_read(&mut self, channel: u8, value: &u8) {
+        self.record_hooked(Event::Sensor {
+            channel,
+            value: *value,
+        });
+    }
+}
+
+/// Create the log file with a sequence number, returning it with the bytes
+/// of its header
+fn create(directory: &Path, sequence: u64, started_ms: u128) -> io::Result<(BufWriter<File>, u64)> {
+    let mut file = BufWriter::new(File::create(directory.join(format!("{sequence:08}.log")))?);
+    let header = format!("{HEADER}{started_ms}\n");
+    file.write_all(header.as_bytes())?;
+    Ok((file, header.len() as u64))
+}
+
+/// Sequence number of a log file
+fn sequence(path: &Path) -> Option<u64> {
+    match path.extension()?.to_str()? {
+        "log" => path.file_stem()?.to_str()?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// The log files in a directory, oldest first
+pub fn files(directory: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut files = std::fs::read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .filter(|path| path.as_ref().map_or(true, |path| sequence(path).is_some()))
+        .collect::<io::Result<Vec<_>>>()?;
+    files.sort_by_key(|path| sequence(path));
+    Ok(files)
+}
+
+/// Read the [`Record`]s of every log file in a directory, oldest first
+pub fn read(directory: impl AsRef<Path>) -> io::Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for path in files(directory)? {
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            };
+            let record = line
+                .parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use directions::VehicleDirection;
+
+    use super::{files, read, Event, Recorder, RecorderConfig};
+
+    /// Test that old log files are deleted so the recording stays bounded
+    #[test]
+    fn rotate_files() {
+        let directory =
+            std::env::temp_dir().join(format!("logbot-recorder-{}", std::process::id()));
+        let config = RecorderConfig {
+            max_file_bytes: 200,
+            max_files: 3,
+            ..RecorderConfig::new(&directory)
+        };
+
+        let mut recorder = Recorder::open(config.clone()).unwrap();
+        for value in 0..100 {
+            recorder
+                .record(Event::Sensor { channel: 0, value })
+                .unwrap();
+        }
+        recorder.flush().unwrap();
+
+        let kept = files(&directory).unwrap();
+        assert_eq!(kept.len(), 3);
+        for path in &kept {
+            assert!(std::fs::metadata(path).unwrap().len() <= 200);
+        }
+        // The newest records are kept in order
+        let records = read(&directory).unwrap();
+        assert_eq!(
+            records.last().map(|record| record.event),
+            Some(Event::Sensor {
+                channel: 0,
+                value: 99
+            })
+        );
+        assert!(records.is_sorted_by_key(|record| record.time_us));
+
+        // Recording again continues after the newest file
+        drop(recorder);
+        let mut recorder = Recorder::open(config).unwrap();
+        recorder
+            .record(Event::Drive(VehicleDirection::STOP))
+            .unwrap();
+        recorder.flush().unwrap();
+        let records = read(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            records.last().map(|record| record.event),
+            Some(Event::Drive(VehicleDirection::STOP))
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Events of the black box and their line format
+//!
+//! Every [`Record`] is written as a single line: the microseconds since the
+//! recording started, a one letter tag of the [`Event`] and its values,
+//! separated by spaces, e.g.
+//!
+//! ```text
+//! 0 d 0.2 0.1
+//! 1040 s 0 38
+//! 1042 p -12 0.5 3 -0.08
+//! 2500 l up 0.8
+//! ```
+
+use std::{fmt::Display, str::FromStr};
+
+use directions::{MotorDirection, VehicleDirection};
+use line::FollowLineStep;
+use logbot::hooks::LiftMotion;
+use speed::Speed;
+
+/// Something that happened to logbot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Logbot was driven in a direction, tagged `d` with the signed speeds
+    /// of the left and right wheel
+    Drive(VehicleDirection),
+    /// A sensor was read, tagged `s` with the channel and the value
+    Sensor {
+        /// Channel of the sensor
+        channel: u8,
+        /// Value read
+        value: u8,
+    },
+    /// A step of following the line, tagged `p` with the terms of the
+    /// controller, see [`FollowLineStep`]
+    Pid {
+        /// Difference of the sensor value from the calibrated target
+        error: f64,
+        /// Change of the error since the previous step
+        derivative: f64,
+        /// Sum of the errors since the integral was last reset
+        integral: f64,
+        /// Weighted sum of the terms
+        control: f64,
+    },
+    /// The lift moved, tagged `l` with the motion and its speed
+    Lift(LiftMotion),
+}
+
+impl From<&FollowLineStep> for Event {
+    fn from(step: &FollowLineStep) -> Self {
+        Self::Pid {
+            error: step.error,
+            derivative: step.derivative,
+            integral: step.integral,
+            control: step.control,
+        }
+    }
+}
+
+/// An [`Event`] at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record {
+    /// Microseconds since the recording started
+    pub time_us: u64,
+    /// What happened
+    pub event: Event,
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.time_us)?;
+        match self.event {
+            Event::Drive(direction) => write!(
+                f,
+                "d {} {}",
+                direction.left.signed(),
+                direction.right.signed()
+            ),
+            Event::Sensor { channel, value } => write!(f, "s {channel} {value}"),
+            Event::Pid {
+                error,
+                derivative,
+                integral,
+                control,
+            } => write!(f, "p {error} {derivative} {integral} {control}"),
+            Event::Lift(motion) => match motion {
+                LiftMotion::Up(speed) => write!(f, "l up {}", speed.value()),
+                LiftMotion::Down(speed) => write!(f, "l down {}", speed.value()),
+                LiftMotion::JogUp(speed) => write!(f, "l jog_up {}", speed.value()),
+                LiftMotion::JogDown(speed) => write!(f, "l jog_down {}", speed.value()),
+                LiftMotion::Halt => write!(f, "l halt"),
+            },
+        }
+    }
+}
+
+/// Error returned when a line is not a valid [`Record`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRecordError(pub String);
+
+impl Display for ParseRecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid record `{}`", self.0)
+    }
+}
+
+impl core::error::Error for ParseRecordError {}
+
+impl FromStr for Record {
+    type Err = ParseRecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseRecordError(s.to_string());
+        let mut fields = s.split_whitespace();
+        let mut next = || fields.next().ok_or_else(invalid);
+        let time_us = next()?.parse().map_err(|_| invalid())?;
+
+        let event = match next()? {
+            "d" => {
+                let left: f64 = next()?.parse().map_err(|_| invalid())?;
+                let right: f64 = next()?.parse().map_err(|_| invalid())?;
+                Event::Drive(VehicleDirection::new(
+                    MotorDirection::from_signed(left),
+                    MotorDirection::from_signed(right),
+                ))
+            }
+            "s" => Event::Sensor {
+                channel: next()?.parse().map_err(|_| invalid())?,
+                value: next()?.parse().map_err(|_| invalid())?,
+            },
+            "p" => Event::Pid {
+                error: next()?.parse().map_err(|_| invalid())?,
+                derivative: next()?.parse().map_err(|_| invalid())?,
+                integral: next()?.parse().map_err(|_| invalid())?,
+                control: next()?.parse().map_err(|_| invalid())?,
+            },
+            "l" => {
+                let motion = next()?;
+                let mut speed = || -> Result<Speed, ParseRecordError> {
+                    let value: f64 = next()?.parse().map_err(|_| invalid())?;
+                    Speed::new(value).map_err(|_| invalid())
+                };
+                Event::Lift(match motion {
+                    "up" => LiftMotion::Up(speed()?),
+                    "down" => LiftMotion::Down(speed()?),
+                    "jog_up" => LiftMotion::JogUp(speed()?),
+                    "jog_down" => LiftMotion::JogDown(speed()?),
+                    "halt" => LiftMotion::Halt,
+                    _ => return Err(invalid()),
+                })
+            }
+            _ => return Err(invalid()),
+        };
+
+        match fields.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(Self { time_us, event }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use logbot::hooks::LiftMotion;
+    use speed::Speed;
+
+    use super::{Event, Record};
+
+    /// Test that every kind of record is read back from its line unchanged
+    #[test]
+    fn round_trip() {
+        let events = [
+            Event::Drive("forward:0.2".parse().unwrap()),
+            Event::Drive("spin_left:0.5".parse().unwrap()),
+            Event::Sensor {
+                channel: 1,
+                value: 201,
+            },
+            Event::Pid {
+                error: -12.0,
+                derivative: 0.5,
+                integral: 3.0,
+                control: -0.08,
+            },
+            Event::Lift(LiftMotion::JogDown(Speed::HALF)),
+            Event::Lift(LiftMotion::Halt),
+        ];
+        for (time_us, event) in (0..).zip(events) {
+            let record = Record { time_us, event };
+            assert_eq!(record.to_string().parse(), Ok(record));
+        }
+
+        assert_eq!(
+            "1040 s 0 38".parse(),
+            Ok(Record {
+                time_us: 1040,
+                event: Event::Sensor {
+                    channel: 0,
+                    value: 38
+                }
+            })
+        );
+        assert!("1040 s 0".parse::<Record>().is_err());
+        assert!("1040 s 0 38 1".parse::<Record>().is_err());
+        assert!("1040 x".parse::<Record>().is_err());
+    }
+}
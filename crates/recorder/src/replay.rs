@@ -0,0 +1,336 @@
+//! Replaying recorded runs
+//!
+//! The [`Record`]s [read](crate::read) from a black box are replayed in two
+//! ways. [`redrive`] drives logbot again with the recorded drive commands and
+//! lift motions, at the pace they were recorded. [`follow_offline`] instead
+//! feeds the recorded values of the followed sensor into a line follower
+//! without any hardware, and [`compare`] does so for two controllers, e.g.
+//! before and after changing the gains, so a change of the PID controller is
+//! checked against real-world data:
+//!
+//! ```
+//! use calibration::SensorCalibration;
+//! use recorder::{replay::compare, Event, Record};
+//! use tuning::Tuning;
+//!
+//! let records: Vec<Record> = (0..50)
+//!     .map(|i| Record {
+//!         time_us: i * 5000,
+//!         event: Event::Sensor { channel: 0, value: 60 + (i % 10) as u8 * 10 },
+//!     })
+//!     .collect();
+//!
+//! let calibration = SensorCalibration::new(180, 40);
+//! let baseline = Tuning::SLOW.follow_line_config(calibration);
+//! let candidate = Tuning::RACE.follow_line_config(calibration);
+//! let comparison = compare(&records, 0, baseline, candidate);
+//! assert_eq!(comparison.steps(), 50);
+//! assert!(comparison.max_control_difference() > 0.0);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use directions::VehicleDirection;
+use interfaces::{Drive, JogLift, Lift};
+use line::{FollowLineConfig, FollowLineState, FollowLineStep};
+use logbot::hooks::LiftMotion;
+
+use crate::{Event, Record};
+
+/// Drive logbot with the recorded drive commands and lift motions, each at
+/// the time it was recorded relative to the first record
+///
+/// Sensor reads and steps of the line follower are skipped. Logbot is
+/// stopped once the records end, and also when driving fails.
+pub fn redrive<L>(
+    logbot: &mut L,
+    records: &[Record],
+) -> Result<(), RedriveError<<L as Drive>::Error, <L as Lift>::Error>>
+where
+    L: Drive<Direction = VehicleDirection>,
+    L: JogLift,
+{
+    let result = drive_records(logbot, records);
+    let stopped = logbot.stop().map(|_| ()).map_err(RedriveError::Drive);
+    result.and(stopped)
+}
+
+/// Drive the records without stopping afterwards
+fn drive_records<L>(
+    logbot: &mut L,
+    records: &[Record],
+) -> Result<(), RedriveError<<L as Drive>::Error, <L as Lift>::Error>>
+where
+    L: Drive<Direction = VehicleDirection>,
+    L: JogLift,
+{
+    let Some(first) = records.first() else {
+        return Ok(());
+    };
+    let start = Instant::now();
+    for record in records {
+        let at = Duration::from_micros(record.time_us.saturating_sub(first.time_us));
+        std::thread::sleep(at.saturating_sub(start.elapsed()));
+
+        match record.event {
+            Event::Drive(direction) => {
+                logbot.drive(direction).map_err(RedriveError::Drive)?;
+            }
+            Event::Lift(motion) => match motion {
+                LiftMotion::Up(speed) => logbot.up(speed),
+                LiftMotion::Down(speed) => logbot.down(speed),
+                LiftMotion::JogUp(speed) => logbot.jog_up(speed),
+                LiftMotion::JogDown(speed) => logbot.jog_down(speed),
+                LiftMotion::Halt => logbot.halt(),
+            }
+            .map_err(RedriveError::Lift)?,
+            Event::Sensor { .. } | Event::Pid { .. } => {}
+        };
+    }
+    Ok(())
+}
+
+/// Errors that occur while [redriving](redrive) a recording
+#[derive(Debug)]
+pub enum RedriveError<D, L> {
+    /// Driving or stopping failed
+    Drive(D),
+    /// Moving the lift failed
+    Lift(L),
+}
+
+impl<D, L> std::fmt::Display for RedriveError<D, L>
+where
+    D: std::fmt::Display,
+    L: std::fmt::Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Drive(e) => write!(f, "failed to drive: {e}"),
+            Self::Lift(e) => write!(f, "failed to move the lift: {e}"),
+        }
+    }
+}
+
+impl<D, L> core::error::Error for RedriveError<D, L>
+where
+    D: core::error::Error + 'static,
+    L: core::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Drive(e) => Some(e),
+            Self::Lift(e) => Some(e),
+        }
+    }
+}
+
+/// Step a line follower with the recorded values of the sensor on a channel
+pub fn follow_offline(
+    records: &[Record],
+    channel: u8,
+    config: FollowLineConfig,
+) -> Vec<FollowLineStep> {
+    let mut state = FollowLineState::new(config);
+    records
+        .iter()
+        .filter_map(|record| match record.event {
+            Event::Sensor {
+                channel: read,
+                value,
+            } if read == channel => Some(value),
+            _ => None,
+        })
+        .map(|value| state.step_diagnostics(value))
+        .collect()
+}
+
+/// Steps of two line followers fed the same recorded sensor values
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Steps of the controller compared against, e.g. the current one
+    pub baseline: Vec<FollowLineStep>,
+    /// Steps of the changed controller
+    pub candidate: Vec<FollowLineStep>,
+}
+
+impl Comparison {
+    /// Number of steps of each controller
+    pub fn steps(&self) -> usize {
+        self.baseline.len()
+    }
+
+    /// Largest difference of the control terms in any step
+    pub fn max_control_difference(&self) -> f64 {
+        self.differences()
+            .map(|(baseline, candidate)| (baseline.control - candidate.control).abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Largest difference of the wheel speeds in any step
+    pub fn max_speed_difference(&self) -> f64 {
+        self.differences()
+            .map(|(baseline, candidate)| {
+                let left = baseline.direction.left.signed() - candidate.direction.left.signed();
+                let right = baseline.direction.right.signed() - candidate.direction.right.signed();
+                left.abs().max(right.abs())
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Pairs of steps of both controllers
+    fn differences(&self) -> impl Iterator<Item = (&FollowLineStep, &FollowLineStep)> {
+        self.baseline.iter().zip(&self.candidate)
+    }
+}
+
+/// Feed the recorded values of the sensor on a channel into two line
+/// followers, to compare how a change of the controller steers
+pub fn compare(
+    records: &[Record],
+    channel: u8,
+    baseline: FollowLineConfig,
+    candidate: FollowLineConfig,
+) -> Comparison {
+    Comparison {
+        baseline: follow_offline(records, channel, baseline),
+        candidate: follow_offline(records, channel, candidate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use calibration::SensorCalibration;
+    use components::{
+        mock::{MockLift, MockMotor},
+        Left, Right,
+    };
+    use directions::VehicleDirection;
+    use interfaces::Lift;
+    use logbot::hooks::LiftMotion;
+    use speed::Speed;
+    use tuning::Tuning;
+    use vehicle::Vehicle;
+
+    use super::{compare, follow_offline, redrive};
+    use crate::{Event, Record};
+
+    /// Logbot driven by mock components
+    #[derive(Debug)]
+    struct Mock {
+        /// The vehicle
+        vehicle: Vehicle<MockMotor<Left>, MockMotor<Right>>,
+        /// The lift
+        lift: MockLift,
+    }
+
+    impl interfaces::Drive for Mock {
+        type Direction = VehicleDirection;
+        type Error = <Vehicle<MockMotor<Left>, MockMotor<Right>> as interfaces::Drive>::Error;
+
+        fn drive(
+            &mut self,
+            direction: Self::Direction,
+        ) -> Result<Option<Self::Direction>, Self::Error> {
+            self.vehicle.drive(direction)
+        }
+
+        fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+            self.vehicle.stop()
+        }
+    }
+
+    impl Lift for Mock {
+        type Error = <MockLift as Lift>::Error;
+
+        fn up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+            self.lift.up(speed)
+        }
+
+        fn down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+            self.lift.down(speed)
+        }
+
+        fn is_up(&self) -> bool {
+            self.lift.is_up()
+        }
+
+        fn is_down(&self) -> bool {
+            self.lift.is_down()
+        }
+    }
+
+    impl interfaces::JogLift for Mock {
+        fn jog_up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+            self.lift.jog_up(speed)
+        }
+
+        fn jog_down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+            self.lift.jog_down(speed)
+        }
+
+        fn halt(&mut self) -> Result<(), Self::Error> {
+            self.lift.halt()
+        }
+    }
+
+    /// Test that recorded commands drive logbot again at their pace
+    #[test]
+    fn redrive_commands() {
+        let mut logbot = Mock {
+            vehicle: Vehicle::new(MockMotor::new(), MockMotor::new()),
+            lift: MockLift::new(),
+        };
+        let records = [
+            Record {
+                time_us: 1000,
+                event: Event::Drive("forward:0.5".parse().unwrap()),
+            },
+            Record {
+                time_us: 1500,
+                event: Event::Sensor {
+                    channel: 0,
+                    value: 100,
+                },
+            },
+            Record {
+                time_us: 21_000,
+                event: Event::Lift(LiftMotion::Up(Speed::HALF)),
+            },
+        ];
+
+        let start = std::time::Instant::now();
+        redrive(&mut logbot, &records).unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(20));
+        assert!(logbot.lift.is_up());
+        assert_eq!(logbot.vehicle.state(), VehicleDirection::STOP);
+    }
+
+    /// Test that controllers are fed the values of the followed sensor only
+    #[test]
+    fn compare_controllers() {
+        let records: Vec<Record> = [(0, 110), (1, 40), (0, 150), (0, 90)]
+            .into_iter()
+            .zip(0..)
+            .map(|((channel, value), time_us)| Record {
+                time_us,
+                event: Event::Sensor { channel, value },
+            })
+            .collect();
+        let config = Tuning::SLOW.follow_line_config(SensorCalibration::new(180, 40));
+
+        let steps = follow_offline(&records, 0, config);
+        assert_eq!(
+            steps.iter().map(|step| step.error).collect::<Vec<_>>(),
+            vec![0.0, 40.0, -20.0]
+        );
+
+        // The same controller steers the same
+        let comparison = compare(&records, 0, config, config);
+        assert_eq!(comparison.max_control_difference(), 0.0);
+        assert_eq!(comparison.max_speed_difference(), 0.0);
+
+        let changed = Tuning::RACE.follow_line_config(SensorCalibration::new(180, 40));
+        assert!(compare(&records, 0, config, changed).max_control_difference() > 0.0);
+    }
+}
@@ -0,0 +1,108 @@
+// Control panel of logbot, talking to the REST API and WebSocket of the server
+
+// Interval of the heartbeat while a drive button is held, in milliseconds
+const HEARTBEAT_MS = 300;
+
+const speed = document.getElementById("speed");
+const response = document.getElementById("response");
+
+// Send a command to the server and show its response
+async function send(path, body) {
+  const options = { method: "POST" };
+  if (body !== undefined) {
+    options.headers = { "Content-Type": "application/json" };
+    options.body = JSON.stringify(body);
+  }
+  try {
+    const reply = await fetch(`/v1/${path}`, options);
+    response.textContent = `${path}: ${await reply.text()}`;
+  } catch (error) {
+    response.textContent = `${path}: ${error}`;
+  }
+}
+
+// The direction of both motors for a drive button
+function direction(name) {
+  const value = Number(speed.value);
+  const forward = { Forward: value };
+  const backward = { Backward: value };
+  switch (name) {
+    case "forward":
+      return { left: forward, right: forward };
+    case "backward":
+      return { left: backward, right: backward };
+    case "spin_left":
+      return { left: backward, right: forward };
+    case "spin_right":
+      return { left: forward, right: backward };
+  }
+}
+
+// Drive while a button is held, refreshing the heartbeat, and stop on release
+let heartbeat = null;
+
+function release() {
+  if (heartbeat !== null) {
+    clearInterval(heartbeat);
+    heartbeat = null;
+    send("stop");
+  }
+}
+
+for (const button of document.querySelectorAll("[data-drive]")) {
+  button.addEventListener("pointerdown", (event) => {
+    event.preventDefault();
+    release();
+    send("drive", direction(button.dataset.drive));
+    heartbeat = setInterval(() => send("drive/heartbeat"), HEARTBEAT_MS);
+  });
+  button.addEventListener("pointerup", release);
+  button.addEventListener("pointerleave", release);
+  button.addEventListener("pointercancel", release);
+}
+
+document.getElementById("stop").addEventListener("click", () => {
+  release();
+  send("stop");
+});
+
+for (const button of document.querySelectorAll("[data-command]")) {
+  button.addEventListener("click", () => send(button.dataset.command));
+}
+
+speed.addEventListener("input", () => {
+  document.getElementById("speed-value").textContent = speed.value;
+});
+
+// Show the telemetry frames of the WebSocket, reconnecting when it closes
+function connect() {
+  const protocol = location.protocol === "https:" ? "wss:" : "ws:";
+  const socket = new WebSocket(`${protocol}//${location.host}/v1/ws`);
+  const connection = document.getElementById("connection");
+
+  socket.addEventListener("open", () => {
+    connection.textContent = "online";
+    connection.className = "online";
+  });
+  socket.addEventListener("close", () => {
+    connection.textContent = "offline";
+    connection.className = "offline";
+    setTimeout(connect, 1000);
+  });
+  socket.addEventListener("message", (message) => {
+    const frame = JSON.parse(message.data);
+    document.getElementById("command").textContent = frame.command;
+    document.getElementById("lift").textContent = frame.lift;
+    document.getElementById("mission").textContent = frame.mission
+      ? `${frame.mission.number}/${frame.mission.steps} ${frame.mission.phase}: ${frame.mission.description}`
+      : "-";
+    if (frame.sensors) {
+      for (const side of ["left", "right"]) {
+        document.getElementById(side).value = frame.sensors[side];
+        document.getElementById(`${side}-value`).textContent = frame.sensors[side];
+      }
+    }
+  });
+}
+
+connect();
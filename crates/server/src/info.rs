@@ -0,0 +1,77 @@
+//! Description of the server and the hardware it runs
+//!
+//! Operators can check which build, robot profile, components and pins a
+//! given logbot is running with, without access to its configuration files.
+
+use consts::HardwareConfig;
+use serde::Serialize;
+use tuning::{Preset, Tuning};
+
+/// Implementations of the hardware components in use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Backends {
+    /// Implementation of both drive motors
+    pub motors: &'static str,
+    /// Implementation of the sensors
+    pub sensors: &'static str,
+    /// Implementation of the lift
+    pub lift: &'static str,
+}
+
+/// Speeds of the [`Tuning`] the server started with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Speeds {
+    /// Speed when driving and following a line
+    pub drive: f64,
+    /// Speed when spinning in-place to turn around on a line
+    pub turn: f64,
+    /// Speed of the lift
+    pub lift: f64,
+}
+
+impl From<&Tuning> for Speeds {
+    fn from(tuning: &Tuning) -> Self {
+        Self {
+            drive: tuning.speed.value(),
+            turn: tuning.turn_speed.value(),
+            lift: tuning.lift_speed.value(),
+        }
+    }
+}
+
+/// Description of the server and the hardware it runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Info {
+    /// Version of the server
+    pub version: &'static str,
+    /// Named robot profile of the hardware configuration, if any
+    pub profile: Option<String>,
+    /// Implementations of the hardware components
+    pub backends: Backends,
+    /// Pins and channels of the hardware, none when it is simulated
+    pub hardware: Option<HardwareConfig>,
+    /// Tuning preset the server started with
+    pub preset: String,
+    /// Speeds the server started with
+    pub speeds: Speeds,
+}
+
+impl Info {
+    /// Describe the server running the [`Backends`] with a [`HardwareConfig`],
+    /// starting with the [`Tuning`] of a [`Preset`]
+    pub fn new(
+        backends: Backends,
+        hardware: Option<HardwareConfig>,
+        preset: Preset,
+        tuning: &Tuning,
+    ) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            profile: consts::config::profile(),
+            backends,
+            hardware,
+            preset: preset.to_string(),
+            speeds: Speeds::from(tuning),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Exclusive control of logbot by a single client
+//!
+//! Without a lease every client may move logbot. Once a client acquires a
+//! [`Lease`], only requests carrying its token in the [`HEADER`] may move
+//! logbot, or change its calibration, tuning or schedule, until the lease is
+//! released or expires after [`DURATION`]. Acquiring the lease again with its
+//! token renews it. Stopping or pausing logbot and holding the schedule is
+//! always allowed, so anyone can stop it in an emergency.
+
+use std::{
+    fmt::Display,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+/// Header carrying the token of a [`Lease`]
+pub const HEADER: &str = "lease-token";
+
+/// Duration after which a lease expires unless it is renewed
+pub const DURATION: Duration = Duration::from_secs(30);
+
+/// A lease granted to a client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Lease {
+    /// Token identifying the holder of the lease
+    pub token: String,
+    /// Milliseconds until the lease expires
+    pub expires_in_ms: u64,
+}
+
+/// The active lease
+#[derive(Debug)]
+struct Held {
+    /// Token of the holder
+    token: String,
+    /// [`Instant`] at which the lease expires
+    expires: Instant,
+}
+
+/// The lease of logbot, if any client holds it
+#[derive(Debug, Default)]
+pub struct Leases {
+    /// The lease, which may have expired
+    held: Mutex<Option<Held>>,
+}
+
+impl Leases {
+    /// Access the lease, even if another thread panicked while accessing it
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Held>> {
+        self.held.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Acquire the lease, or renew it when `token` is the token of its holder
+    pub fn acquire(&self, token: Option<&str>) -> Result<Lease, LeaseHeld> {
+        self.acquire_at(token, Instant::now())
+    }
+
+    /// Acquire or renew the lease at an [`Instant`]
+    fn acquire_at(&self, token: Option<&str>, now: Instant) -> Result<Lease, LeaseHeld> {
+        let mut held = self.lock();
+        let token = match held.as_ref() {
+            Some(lease) if lease.expires > now && Some(lease.token.as_str()) != token => {
+                return Err(LeaseHeld {
+                    remaining: lease.expires - now,
+                });
+            }
+            Some(lease) if lease.expires > now => lease.token.clone(),
+            // The token is all that keeps other clients from moving logbot,
+            // so it comes from a cryptographically secure generator
+            _ => format!("{:032x}", rand::random::<u128>()),
+        };
+
+        *held = Some(Held {
+            token: token.clone(),
+            expires: now + DURATION,
+        });
+        Ok(Lease {
+            token,
+            expires_in_ms: DURATION.as_millis() as u64,
+        })
+    }
+
+    /// Release the lease, returning whether `token` was the token of its holder
+    pub fn release(&self, token: Option<&str>) -> bool {
+        self.release_at(token, Instant::now())
+    }
+
+    /// Release the lease at an [`Instant`]
+    fn release_at(&self, token: Option<&str>, now: Instant) -> bool {
+        let mut held = self.lock();
+        let holder = held
+            .as_ref()
+            .is_some_and(|lease| lease.expires > now && Some(lease.token.as_str()) == token);
+        if holder {
+            *held = None;
+        };
+        holder
+    }
+
+    /// Check that a client with an optional token may move logbot
+    pub fn permits(&self, token: Option<&str>) -> Result<(), LeaseHeld> {
+        self.permits_at(token, Instant::now())
+    }
+
+    /// Check that a client may move logbot at an [`Instant`]
+    fn permits_at(&self, token: Option<&str>, now: Instant) -> Result<(), LeaseHeld> {
+        match self.lock().as_ref() {
+            Some(lease) if lease.expires > now && Some(lease.token.as_str()) != token => {
+                Err(LeaseHeld {
+                    remaining: lease.expires - now,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The lease is held by another client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaseHeld {
+    /// Time until the lease expires, unless it is renewed
+    pub remaining: Duration,
+}
+
+impl Display for LeaseHeld {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "logbot is leased to another client for another {}s",
+            self.remaining.as_secs_f64().ceil()
+        )
+    }
+}
+
+impl core::error::Error for LeaseHeld {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{LeaseHeld, Leases, DURATION};
+
+    /// Test that without a lease every client may move logbot
+    #[test]
+    fn permits_without_lease() {
+        let leases = Leases::default();
+        assert_eq!(leases.permits(None), Ok(()));
+        assert_eq!(leases.permits(Some("token")), Ok(()));
+    }
+
+    /// Test that only the holder of a lease may move logbot or acquire it
+    #[test]
+    fn acquire_excludes_others() {
+        let leases = Leases::default();
+        let now = Instant::now();
+        let lease = leases.acquire_at(None, now).unwrap();
+        assert_eq!(lease.token.len(), 32);
+        assert_eq!(lease.expires_in_ms, DURATION.as_millis() as u64);
+
+        let later = now + Duration::from_secs(10);
+        let held = LeaseHeld {
+            remaining: DURATION - Duration::from_secs(10),
+        };
+        assert_eq!(leases.permits_at(Some(&lease.token), later), Ok(()));
+        assert_eq!(leases.permits_at(None, later), Err(held));
+        assert_eq!(leases.permits_at(Some("other"), later), Err(held));
+        assert_eq!(leases.acquire_at(Some("other"), later), Err(held));
+    }
+
+    /// Test that the holder renews the lease with its token, keeping it
+    #[test]
+    fn renew() {
+        let leases = Leases::default();
+        let now = Instant::now();
+        let lease = leases.acquire_at(None, now).unwrap();
+
+        let later = now + DURATION - Duration::from_secs(1);
+        let renewed = leases.acquire_at(Some(&lease.token), later).unwrap();
+        assert_eq!(renewed.token, lease.token);
+        assert!(leases.permits_at(None, now + DURATION).is_err());
+        assert_eq!(leases.permits_at(None, later + DURATION), Ok(()));
+    }
+
+    /// Test that an expired lease permits everyone and is acquired with a
+    /// new token
+    #[test]
+    fn expiry() {
+        let leases = Leases::default();
+        let now = Instant::now();
+        let lease = leases.acquire_at(None, now).unwrap();
+
+        let expired = now + DURATION;
+        assert_eq!(leases.permits_at(None, expired), Ok(()));
+        assert!(!leases.release_at(Some(&lease.token), expired));
+
+        let acquired = leases.acquire_at(Some(&lease.token), expired).unwrap();
+        assert_ne!(acquired.token, lease.token);
+    }
+
+    /// Test that only the holder releases the lease
+    #[test]
+    fn release() {
+        let leases = Leases::default();
+        let lease = leases.acquire(None).unwrap();
+
+        assert!(!leases.release(None));
+        assert!(!leases.release(Some("other")));
+        assert!(leases.permits(None).is_err());
+
+        assert!(leases.release(Some(&lease.token)));
+        assert_eq!(leases.permits(None), Ok(()));
+    }
+}
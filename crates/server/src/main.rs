@@ -1,21 +1,42 @@
 //! Axum server for controlling logbot hardware using a REST-api
 
-use std::sync::Arc;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::Result;
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
+#[cfg(feature = "tls")]
+use axum_server::tls_rustls::RustlsConfig;
 use clap::Parser;
-use routes::{calibrate, demo, find_edge, follow, health, lift_down, lift_up, stop};
+use logbot_control::scheduler::Scheduler;
+use ratelimit::RateLimit;
+use recorder::{Recorder, RecorderConfig};
+use routes::{
+    acquire_lease, calibrate, calibration, commands, config, demo, diagnostics, download_recording,
+    drive, find_edge, follow, health, heartbeat, history, hold_schedule, info, job, lift_down,
+    lift_up, mission, pause, rate_limit, release_lease, release_schedule, remove_scheduled,
+    require_lease, restore_calibration, resume, schedule, schedule_mission, sensor_stream,
+    start_recording, status_stream, stop, stop_recording, telemetry, update_config, ws,
+};
 use state::LogbotState;
 use tokio::net::TcpListener;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tuning::{Preset, Tuning};
 
-mod hardware;
+mod info;
+mod lease;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "panel")]
+mod panel;
+mod ratelimit;
+mod recording;
 mod routes;
+mod schedule;
 mod state;
 
 /// Logbot REST-api
@@ -24,6 +45,48 @@ struct Args {
     /// IP Address at which to serve at
     #[clap(default_value = "0.0.0.0:9999")]
     ip: String,
+    /// Named robot profile of the hardware configuration
+    #[clap(long)]
+    profile: Option<String>,
+    /// Tuning preset (slow, demo or race)
+    #[clap(long, default_value_t = Preset::Slow)]
+    preset: Preset,
+    /// TOML file overriding values of the tuning preset
+    #[clap(long)]
+    tuning: Option<PathBuf>,
+    /// Milliseconds after which manual driving stops without a heartbeat
+    #[clap(long, default_value_t = logbot_control::watchdog::DEFAULT_INTERVAL.as_millis() as u64)]
+    heartbeat_ms: u64,
+    /// Commands per second each client may send, 0 for no limit
+    #[clap(long, default_value_t = ratelimit::DEFAULT_RATE)]
+    rate_limit: f64,
+    /// Commands each client may send at once
+    #[clap(long, default_value_t = ratelimit::DEFAULT_BURST)]
+    rate_burst: u32,
+    /// Record the sensor samples and driven directions to rotating log files
+    /// in this directory
+    #[clap(long)]
+    black_box: Option<PathBuf>,
+    /// JSON file keeping the scheduled missions across restarts, kept in
+    /// memory only if not given
+    #[clap(long)]
+    schedule: Option<PathBuf>,
+    /// MQTT broker to bridge to, as `host` or `host:port`
+    #[cfg(feature = "mqtt")]
+    #[clap(long)]
+    mqtt: Option<mqtt::Broker>,
+    /// Client id and prefix of the MQTT topics
+    #[cfg(feature = "mqtt")]
+    #[clap(long, default_value = "logbot")]
+    mqtt_prefix: String,
+    /// PEM file with the TLS certificate chain, serving HTTPS with `--tls-key`
+    #[cfg(feature = "tls")]
+    #[clap(long, env = "LOGBOT_TLS_CERT", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+    /// PEM file with the private key of the TLS certificate
+    #[cfg(feature = "tls")]
+    #[clap(long, env = "LOGBOT_TLS_KEY", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
 }
 
 /// Entry point for the server
@@ -32,6 +95,10 @@ async fn main() -> Result<()> {
     // parse command line arguments
     let args = Args::parse();
 
+    if let Some(profile) = args.profile {
+        consts::config::set_profile(profile);
+    };
+
     // Setup tracing
     tracing_subscriber::registry()
         .with(fmt::layer())
@@ -42,23 +109,124 @@ async fn main() -> Result<()> {
     let listener = TcpListener::bind(args.ip).await?;
 
     // new state
-    let state = Arc::new(LogbotState::new()?);
+    let tuning = match args.tuning {
+        Some(path) => Tuning::from_file(args.preset, path)?,
+        None => args.preset.tuning(),
+    };
+    let heartbeat_interval = Duration::from_millis(args.heartbeat_ms);
+    let limit = RateLimit {
+        rate: args.rate_limit,
+        burst: args.rate_burst,
+    };
+    let scheduler = match args.schedule {
+        Some(path) => Scheduler::open(path)?,
+        None => Scheduler::default(),
+    };
+    let state = Arc::new(LogbotState::new(
+        args.preset,
+        tuning,
+        heartbeat_interval,
+        limit,
+        scheduler,
+    )?);
 
-    // create routes
-    let router = Router::new()
-        .route("/v1/health", get(health))
-        .route("/v1/stop", post(stop))
+    // record to the black box
+    if let Some(directory) = args.black_box {
+        let black_box = Recorder::open(RecorderConfig::new(directory))?;
+        tokio::spawn(recording::black_box(
+            black_box,
+            state.telemetry.subscribe(),
+            state.status.subscribe(),
+        ));
+    };
+
+    // execute the scheduled missions, resuming an interrupted one
+    tokio::spawn(schedule::run(Arc::clone(&state)));
+
+    // bridge to the MQTT broker
+    #[cfg(feature = "mqtt")]
+    if let Some(broker) = args.mqtt {
+        tokio::spawn(mqtt::bridge(broker, args.mqtt_prefix, Arc::clone(&state)));
+    };
+
+    // routes moving logbot or changing how it moves, only for the holder of
+    // a lease
+    let motion = Router::new()
+        .route("/v1/drive", post(drive))
+        .route("/v1/drive/heartbeat", post(heartbeat))
         .route("/v1/demo", post(demo))
+        .route("/v1/mission", post(mission))
+        .route("/v1/commands", post(commands))
         .route("/v1/calibrate", post(calibrate))
         .route("/v1/follow", post(follow))
         .route("/v1/edge", post(find_edge))
         .route("/v1/lift/up", post(lift_up))
         .route("/v1/lift/down", post(lift_down))
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
+        .route("/v1/resume", post(resume))
+        .route("/v1/schedule", post(schedule_mission))
+        .route("/v1/schedule/release", post(release_schedule))
+        .route("/v1/schedule/:id", delete(remove_scheduled))
+        .route("/v1/calibration", put(restore_calibration))
+        .route("/v1/config", put(update_config))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            require_lease,
+        ));
+
+    // routes queueing commands on the hardware thread, including restoring the
+    // calibration, limited per client
+    let commands = Router::new()
+        .route("/v1/diagnostics", get(diagnostics))
+        .merge(motion)
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            rate_limit,
+        ));
+
+    // create routes
+    let router = Router::new()
+        .route("/v1/health", get(health))
+        .route("/v1/info", get(info))
+        .route("/v1/stop", post(stop))
+        .route("/v1/pause", post(pause))
+        .route("/v1/schedule", get(schedule))
+        .route("/v1/schedule/hold", post(hold_schedule))
+        .route("/v1/lease", post(acquire_lease).delete(release_lease))
+        .route("/v1/calibration", get(calibration))
+        .route("/v1/telemetry", get(telemetry))
+        .route("/v1/jobs/:id", get(job))
+        .route("/v1/history", get(history))
+        .route("/v1/record/start", post(start_recording))
+        .route("/v1/record/stop", post(stop_recording))
+        .route("/v1/record/download", get(download_recording))
+        .route("/v1/config", get(config))
+        .route("/v1/ws", get(ws))
+        .route("/v1/sensors/stream", get(sensor_stream))
+        .route("/v1/status/stream", get(status_stream))
+        .merge(commands);
+
+    // serve the control panel
+    #[cfg(feature = "panel")]
+    let router = router.merge(panel::routes());
+
+    let router = router.layer(TraceLayer::new_for_http()).with_state(state);
+
+    // serve over TLS when a certificate is given
+    #[cfg(feature = "tls")]
+    if let (Some(cert), Some(key)) = (args.tls_cert, args.tls_key) {
+        let config = RustlsConfig::from_pem_file(cert, key).await?;
+        axum_server::from_tcp_rustls(listener.into_std()?, config)
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+            .await?;
+        return Ok(());
+    };
 
     // serve
-    axum::serve(listener, router).await?;
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
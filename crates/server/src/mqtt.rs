@@ -0,0 +1,279 @@
+//! Bridge between the server and an MQTT broker
+//!
+//! The bridge publishes what logbot is doing and executes the commands it
+//! receives, using topics below a prefix (`logbot` by default):
+//!
+//! - `<prefix>/online`: `true` while connected, `false` once the connection
+//!   is lost (retained)
+//! - `<prefix>/status`: the JSON [`Status`] whenever it changes (retained)
+//! - `<prefix>/telemetry`: JSON [`TelemetrySample`]s, at most
+//!   [`TELEMETRY_RATE`] per second
+//! - `<prefix>/command/<command>`: executes a command, where `<command>` is
+//!   the path of its REST API endpoint without `/v1/`, e.g. `stop`, `lift/up`,
+//!   `drive` taking the direction, `follow` optionally taking its limits,
+//!   `lift/up` and `lift/down` optionally taking their speed or `mission`
+//!   taking the steps as JSON
+//! - `<prefix>/response`: the JSON response to each command
+//! - `<prefix>/error`: why a received command could not be executed
+//!
+//! [`Status`]: logbot_control::telemetry::Status
+//! [`TelemetrySample`]: logbot_control::telemetry::TelemetrySample
+
+use std::{
+    fmt::Display,
+    num::ParseIntError,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use demo::{
+    follow::FollowLimit,
+    mission::{Mission, Step},
+};
+use directions::VehicleDirection;
+use logbot_control::hardware::Command;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, Publish, QoS};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    routes::{HardwareResponse, LiftOptions},
+    state::LogbotState,
+};
+
+/// Port of the broker when none is given
+pub const DEFAULT_PORT: u16 = 1883;
+
+/// Telemetry samples published per second
+pub const TELEMETRY_RATE: u32 = 10;
+
+/// Address of an MQTT broker, parsed from `host` or `host:port`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Broker {
+    /// Host name or IP address of the broker
+    pub host: String,
+    /// Port of the broker
+    pub port: u16,
+}
+
+impl FromStr for Broker {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, port) = match s.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse()?),
+            None => (s, DEFAULT_PORT),
+        };
+        Ok(Self {
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl Display for Broker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// Bridge the server to a [`Broker`], using `prefix` as the client id and the
+/// prefix of every topic
+///
+/// Reconnects whenever the connection is lost, so it only returns once the
+/// server shuts down.
+pub async fn bridge(broker: Broker, prefix: String, state: Arc<LogbotState>) {
+    let mut options = MqttOptions::new(&prefix, &broker.host, broker.port);
+    options.set_keep_alive(Duration::from_secs(5));
+    options.set_last_will(LastWill::new(
+        format!("{prefix}/online"),
+        "false",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    let (client, mut eventloop) = AsyncClient::new(options, 10);
+
+    tokio::spawn(publish_status(
+        client.clone(),
+        prefix.clone(),
+        Arc::clone(&state),
+    ));
+    tokio::spawn(publish_telemetry(
+        client.clone(),
+        prefix.clone(),
+        Arc::clone(&state),
+    ));
+
+    loop {
+        match eventloop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                tracing::info!("Connected to MQTT broker {broker}");
+                // Subscriptions are lost with the connection
+                let _ = client
+                    .subscribe(format!("{prefix}/command/#"), QoS::AtLeastOnce)
+                    .await;
+                let _ = client
+                    .publish(format!("{prefix}/online"), QoS::AtLeastOnce, true, "true")
+                    .await;
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                // Commands may take a while to be responded to
+                tokio::spawn(respond(
+                    client.clone(),
+                    prefix.clone(),
+                    Arc::clone(&state),
+                    publish,
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("MQTT connection to {broker} failed: {e}");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        };
+    }
+}
+
+/// Publish the [`Status`](logbot_control::telemetry::Status) whenever it changes
+async fn publish_status(client: AsyncClient, prefix: String, state: Arc<LogbotState>) {
+    let mut status = state.status.subscribe();
+    loop {
+        let payload = serde_json::to_string(&*status.borrow_and_update())
+            .expect("Status is always serializable");
+        if client
+            .publish(format!("{prefix}/status"), QoS::AtLeastOnce, true, payload)
+            .await
+            .is_err()
+            || status.changed().await.is_err()
+        {
+            return;
+        };
+    }
+}
+
+/// Publish the telemetry samples at [`TELEMETRY_RATE`]
+async fn publish_telemetry(client: AsyncClient, prefix: String, state: Arc<LogbotState>) {
+    let mut samples = state.telemetry.subscribe();
+    let period = Duration::from_secs(1) / TELEMETRY_RATE;
+    let mut due = Instant::now();
+    loop {
+        let sample = match samples.recv().await {
+            Ok(sample) => sample,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+        if Instant::now() < due {
+            continue;
+        };
+        due += period;
+        // Don't catch up on samples after a pause
+        due = due.max(Instant::now());
+
+        let payload = serde_json::to_string(&sample).expect("Samples are always serializable");
+        if client
+            .publish(
+                format!("{prefix}/telemetry"),
+                QoS::AtMostOnce,
+                false,
+                payload,
+            )
+            .await
+            .is_err()
+        {
+            return;
+        };
+    }
+}
+
+/// Execute a received command, publishing its response or why it could not
+/// be executed
+async fn respond(client: AsyncClient, prefix: String, state: Arc<LogbotState>, publish: Publish) {
+    let Some(name) = publish
+        .topic
+        .strip_prefix(&prefix)
+        .and_then(|topic| topic.strip_prefix("/command/"))
+    else {
+        return;
+    };
+
+    let (topic, payload) = match execute(&state, name, &publish.payload).await {
+        Ok(Some(response)) => (
+            "response",
+            serde_json::to_string(&response).expect("Responses are always serializable"),
+        ),
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!("MQTT command `{name}` failed: {e}");
+            ("error", format!("{name}: {e}"))
+        }
+    };
+    let _ = client
+        .publish(
+            format!("{prefix}/{topic}"),
+            QoS::AtLeastOnce,
+            false,
+            payload,
+        )
+        .await;
+}
+
+/// Parse the [`LiftOptions`] of a lift command from its optional JSON payload
+fn lift_options(payload: &[u8]) -> Result<LiftOptions, String> {
+    match payload.is_empty() {
+        true => Ok(LiftOptions::default()),
+        false => serde_json::from_slice(payload).map_err(|e| e.to_string()),
+    }
+}
+
+/// Execute a command by the path of its REST API endpoint, with its payload
+///
+/// Returns the [`HardwareResponse`] of the command, if it has one.
+async fn execute(
+    state: &LogbotState,
+    name: &str,
+    payload: &[u8],
+) -> Result<Option<HardwareResponse>, String> {
+    // MQTT clients can't hold a lease, but may always stop or pause logbot
+    if name != "stop" && name != "pause" {
+        state.leases.permits(None).map_err(|e| e.to_string())?;
+    };
+
+    let command = match name {
+        "stop" => Command::Stop,
+        "calibrate" => Command::Calibrate,
+        "edge" => Command::FindEdge,
+        "follow" if payload.is_empty() => Command::FollowLine,
+        "follow" => {
+            let limit: FollowLimit = serde_json::from_slice(payload).map_err(|e| e.to_string())?;
+            Command::FollowFor(limit)
+        }
+        "demo" => Command::Demo,
+        "lift/up" => Command::LiftUp(lift_options(payload)?.speed),
+        "lift/down" => Command::LiftDown(lift_options(payload)?.speed),
+        "pause" => Command::Pause,
+        "resume" => Command::Resume,
+        "drive" => {
+            state.hardware.heartbeat();
+            let direction: VehicleDirection =
+                serde_json::from_slice(payload).map_err(|e| e.to_string())?;
+            Command::Drive(direction)
+        }
+        "drive/heartbeat" => {
+            state.hardware.heartbeat();
+            return Ok(None);
+        }
+        "mission" => {
+            let steps: Vec<Step> = serde_json::from_slice(payload).map_err(|e| e.to_string())?;
+            let mission = Mission::new(steps, *state.tuning.borrow()).map_err(|e| e.to_string())?;
+            Command::Mission(mission)
+        }
+        name => return Err(format!("unknown command `{name}`")),
+    };
+
+    let response = state
+        .hardware
+        .send(command)
+        .await
+        .ok_or("the hardware thread is not running")?;
+    Ok(Some(HardwareResponse::from(response)))
+}
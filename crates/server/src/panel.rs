@@ -0,0 +1,53 @@
+//! Web control panel embedded into the server
+//!
+//! The files of the `panel` directory are bundled into the binary, so the
+//! panel is served at `/` without any files next to the server. It offers
+//! buttons for the commands and shows the telemetry of the WebSocket at
+//! `/v1/ws`.
+
+use axum::{
+    extract::Path,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use include_dir::{include_dir, Dir};
+
+/// The bundled files of the panel
+static PANEL: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/panel");
+
+/// Routes serving the panel at `/` and its files below `/panel`
+pub fn routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(index))
+        .route("/panel/*path", get(file))
+}
+
+/// The page of the panel
+async fn index() -> impl IntoResponse {
+    serve("index.html")
+}
+
+/// A file of the panel
+async fn file(Path(path): Path<String>) -> impl IntoResponse {
+    serve(&path)
+}
+
+/// Respond with a bundled file, or `404 Not Found` if there is none
+fn serve(path: &str) -> impl IntoResponse {
+    let Some(file) = PANEL.get_file(path) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let content_type = match file.path().extension().and_then(|e| e.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    };
+    Ok(([(CONTENT_TYPE, content_type)], file.contents()))
+}
@@ -0,0 +1,235 @@
+//! Limiting how fast each client sends commands
+//!
+//! Every client, identified by its IP address, has a bucket of
+//! [`RateLimit::burst`] commands, refilled at [`RateLimit::rate`] commands
+//! per second. Commands beyond the bucket are rejected before they reach the
+//! bounded channel of the [`HardwareThread`](logbot_control::hardware::HardwareThread),
+//! so a misbehaving script can't starve other clients. Stopping and pausing
+//! logbot are never limited.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    net::IpAddr,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Default number of commands per second each client may send
+pub const DEFAULT_RATE: f64 = 20.0;
+
+/// Default number of commands each client may send at once
+pub const DEFAULT_BURST: u32 = 20;
+
+/// How many clients are tracked before the idle ones are forgotten
+const CLIENTS: usize = 1024;
+
+/// Rate at which each client may send commands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Commands per second, zero for no limit
+    pub rate: f64,
+    /// Commands that may be sent at once
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            rate: DEFAULT_RATE,
+            burst: DEFAULT_BURST,
+        }
+    }
+}
+
+/// Commands a client may still send
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    /// Commands left, refilled over time
+    tokens: f64,
+    /// [`Instant`] the tokens were last refilled
+    refilled: Instant,
+}
+
+/// Buckets of the clients, limiting each to a [`RateLimit`]
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    /// The limit of every client
+    limit: RateLimit,
+    /// Bucket of each client
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Create a [`RateLimiter`] limiting each client to a [`RateLimit`]
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::default(),
+        }
+    }
+
+    /// Take a command from the bucket of a client
+    pub fn check(&self, client: IpAddr) -> Result<(), RateLimited> {
+        self.check_at(client, Instant::now())
+    }
+
+    /// Take a command from the bucket of a client at an [`Instant`]
+    fn check_at(&self, client: IpAddr, now: Instant) -> Result<(), RateLimited> {
+        if self.limit.rate <= 0.0 {
+            return Ok(());
+        };
+
+        let burst = self.limit.burst as f64;
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // Clients with a full bucket are the same as new ones
+        if buckets.len() >= CLIENTS {
+            let rate = self.limit.rate;
+            buckets.retain(|_, bucket| {
+                bucket.tokens + (now - bucket.refilled).as_secs_f64() * rate < burst
+            });
+        };
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: burst,
+            refilled: now,
+        });
+        bucket.tokens =
+            burst.min(bucket.tokens + (now - bucket.refilled).as_secs_f64() * self.limit.rate);
+        bucket.refilled = now;
+
+        if bucket.tokens < 1.0 {
+            return Err(RateLimited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / self.limit.rate),
+            });
+        };
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+/// A client sent more commands than its [`RateLimit`] allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited {
+    /// Time until the client may send the next command
+    pub retry_after: Duration,
+}
+
+impl Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "too many commands, retry in {}ms",
+            self.retry_after.as_millis().max(1)
+        )
+    }
+}
+
+impl core::error::Error for RateLimited {}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    use super::{RateLimit, RateLimited, RateLimiter, CLIENTS};
+
+    /// Limit of 10 commands per second with a burst of 3
+    const LIMIT: RateLimit = RateLimit {
+        rate: 10.0,
+        burst: 3,
+    };
+
+    /// Address of the `n`th client
+    fn client(n: u32) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::from(n))
+    }
+
+    /// Test that a client may send a burst of commands at once, and no more
+    #[test]
+    fn burst() {
+        let limiter = RateLimiter::new(LIMIT);
+        let now = Instant::now();
+        for _ in 0..LIMIT.burst {
+            assert_eq!(limiter.check_at(client(1), now), Ok(()));
+        }
+        assert!(limiter.check_at(client(1), now).is_err());
+        assert_eq!(limiter.check_at(client(2), now), Ok(()));
+    }
+
+    /// Test that a limited client is told to retry once a command refilled
+    #[test]
+    fn retry_after() {
+        let limiter = RateLimiter::new(LIMIT);
+        let now = Instant::now();
+        for _ in 0..LIMIT.burst {
+            limiter.check_at(client(1), now).unwrap();
+        }
+        let Err(RateLimited { retry_after }) =
+            limiter.check_at(client(1), now + Duration::from_millis(40))
+        else {
+            panic!("a client past its burst must be limited");
+        };
+        assert!(retry_after.abs_diff(Duration::from_millis(60)) < Duration::from_micros(1));
+    }
+
+    /// Test that the bucket refills at the rate, up to the burst
+    #[test]
+    fn refill() {
+        let limiter = RateLimiter::new(LIMIT);
+        let now = Instant::now();
+        for _ in 0..LIMIT.burst {
+            limiter.check_at(client(1), now).unwrap();
+        }
+
+        let refilled = now + Duration::from_millis(100);
+        assert_eq!(limiter.check_at(client(1), refilled), Ok(()));
+        assert!(limiter.check_at(client(1), refilled).is_err());
+
+        let idle = refilled + Duration::from_secs(10);
+        for _ in 0..LIMIT.burst {
+            assert_eq!(limiter.check_at(client(1), idle), Ok(()));
+        }
+        assert!(limiter.check_at(client(1), idle).is_err());
+    }
+
+    /// Test that a rate of zero disables the limit
+    #[test]
+    fn zero_rate_unlimited() {
+        let limiter = RateLimiter::new(RateLimit {
+            rate: 0.0,
+            burst: 0,
+        });
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert_eq!(limiter.check_at(client(1), now), Ok(()));
+        }
+    }
+
+    /// Test that once the tracked clients are full, those with a full bucket
+    /// are forgotten while limited ones stay limited
+    #[test]
+    fn evicts_idle_clients() {
+        let limiter = RateLimiter::new(LIMIT);
+        let now = Instant::now();
+        for _ in 0..LIMIT.burst {
+            limiter.check_at(client(0), now).unwrap();
+        }
+        for n in 1..CLIENTS as u32 {
+            limiter.check_at(client(n), now).unwrap();
+        }
+
+        let later = now + Duration::from_millis(50);
+        assert!(limiter.check_at(client(0), later).is_err());
+        assert_eq!(limiter.buckets.lock().unwrap().len(), CLIENTS);
+
+        let refilled = now + Duration::from_millis(100);
+        limiter.check_at(client(CLIENTS as u32), refilled).unwrap();
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+        assert_eq!(limiter.check_at(client(0), refilled), Ok(()));
+        assert!(limiter.check_at(client(0), refilled).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Black-box recording of a run
+//!
+//! While recording, every sensor sample and every change of the [`Status`],
+//! which holds the command being executed and the direction logbot is driven
+//! in, is kept in memory. The recording is downloaded as JSON lines, each
+//! timed in microseconds since the recording started, e.g.
+//!
+//! ```json
+//! {"time_us":0,"kind":"status","command":"FollowLine","direction":{"left":{"Forward":0.2},"right":{"Forward":0.1}},"lift":"Down","mission":null}
+//! {"time_us":4870,"kind":"sample","elapsed_us":1040,"left":38,"right":201}
+//! ```
+//!
+//! Only the first [`CAPACITY`] records are kept.
+
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use logbot_control::telemetry::{Status, TelemetrySample};
+use recorder::Event;
+use serde::Serialize;
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        watch,
+    },
+    task::AbortHandle,
+};
+
+/// How many records are kept, about 40 minutes of samples at the highest rate
+pub const CAPACITY: usize = 500_000;
+
+/// What was recorded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Recorded {
+    /// Values of both sensors
+    Sample(TelemetrySample),
+    /// The changed [`Status`]
+    Status(Status),
+}
+
+/// A single record of a recording
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Record {
+    /// Microseconds since the recording started
+    pub time_us: u64,
+    /// What was recorded
+    #[serde(flatten)]
+    pub recorded: Recorded,
+}
+
+/// Records shared with the recording task
+type Records = Arc<Mutex<Vec<Record>>>;
+
+/// Recorder of the sensor samples and [`Status`] changes of a run
+#[derive(Debug, Default)]
+pub struct Recorder {
+    /// Records of the running or last recording
+    records: Records,
+    /// Task of the running recording
+    task: Mutex<Option<AbortHandle>>,
+}
+
+impl Recorder {
+    /// Start a new recording of the samples of `telemetry` and the [`Status`]
+    /// of `status`, discarding the last recording
+    pub fn start(
+        &self,
+        telemetry: &broadcast::Sender<TelemetrySample>,
+        status: &watch::Sender<Status>,
+    ) -> Result<(), AlreadyRecording> {
+        let mut task = self.task.lock().unwrap_or_else(PoisonError::into_inner);
+        if task.as_ref().is_some_and(|task| !task.is_finished()) {
+            return Err(AlreadyRecording);
+        };
+
+        lock(&self.records).clear();
+        let recording = tokio::spawn(record(
+            Arc::clone(&self.records),
+            telemetry.subscribe(),
+            status.subscribe(),
+        ));
+        *task = Some(recording.abort_handle());
+        Ok(())
+    }
+
+    /// Stop the running recording, returning how many records it holds
+    ///
+    /// Returns [None](`Option::None`) when nothing is being recorded.
+    pub fn stop(&self) -> Option<usize> {
+        let task = self
+            .task
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()?;
+        task.abort();
+        Some(lock(&self.records).len())
+    }
+
+    /// The records of the running or last recording as JSON lines
+    pub fn download(&self) -> String {
+        lock(&self.records)
+            .iter()
+            .map(|record| {
+                serde_json::to_string(record).expect("Records are always serializable") + "\n"
+            })
+            .collect()
+    }
+}
+
+/// Access the records, even if another thread panicked while accessing them
+fn lock(records: &Records) -> std::sync::MutexGuard<'_, Vec<Record>> {
+    records.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Keep a record unless the recording is full
+fn push(records: &Records, start: Instant, recorded: Recorded) {
+    let mut records = lock(records);
+    if records.len() < CAPACITY {
+        records.push(Record {
+            time_us: start.elapsed().as_micros() as u64,
+            recorded,
+        });
+    };
+}
+
+/// Record samples and [`Status`] changes until aborted or the hardware thread
+/// stops
+async fn record(
+    records: Records,
+    mut samples: broadcast::Receiver<TelemetrySample>,
+    mut status: watch::Receiver<Status>,
+) {
+    let start = Instant::now();
+    let mut recorded = Recorded::Status(status.borrow_and_update().clone());
+    loop {
+        push(&records, start, recorded);
+        recorded = loop {
+            tokio::select! {
+                sample = samples.recv() => match sample {
+                    Ok(sample) => break Recorded::Sample(sample),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Recording skipped {skipped} samples");
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+                changed = status.changed() => match changed {
+                    Ok(()) => break Recorded::Status(status.borrow_and_update().clone()),
+                    Err(_) => return,
+                },
+            };
+        };
+    }
+}
+
+/// Longest time records of the black box stay buffered before written to disk
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Record samples and the directions logbot is driven in to the rotating log
+/// files of a black box, until the hardware thread stops or writing fails
+pub async fn black_box(
+    mut black_box: recorder::Recorder,
+    mut samples: broadcast::Receiver<TelemetrySample>,
+    mut status: watch::Receiver<Status>,
+) {
+    let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+    let mut direction = None;
+    status.mark_changed();
+    loop {
+        let written = tokio::select! {
+            sample = samples.recv() => match sample {
+                Ok(sample) => black_box
+                    .record(Event::Sensor { channel: 0, value: sample.left })
+                    .and_then(|()| black_box.record(Event::Sensor { channel: 1, value: sample.right })),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Black box skipped {skipped} samples");
+                    Ok(())
+                }
+                Err(RecvError::Closed) => break,
+            },
+            changed = status.changed() => match changed {
+                Ok(()) => match status.borrow_and_update().direction {
+                    Some(changed) if direction != Some(changed) => {
+                        direction = Some(changed);
+                        black_box.record(Event::Drive(changed))
+                    }
+                    _ => Ok(()),
+                },
+                Err(_) => break,
+            },
+            _ = flush.tick() => black_box.flush(),
+        };
+        if let Err(e) = written {
+            tracing::error!("Failed to write the black box: {e}");
+            return;
+        };
+    }
+    if let Err(e) = black_box.flush() {
+        tracing::error!("Failed to write the black box: {e}");
+    };
+}
+
+/// A recording is already running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlreadyRecording;
+
+impl Display for AlreadyRecording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a recording is already running")
+    }
+}
+
+impl core::error::Error for AlreadyRecording {}
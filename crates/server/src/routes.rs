@@ -1,13 +1,57 @@
-use std::sync::Arc;
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use axum::{extract::State, http::StatusCode, Json};
-use serde::Serialize;
+use axum::{
+    body::Body,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, Request, State,
+    },
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE, RETRY_AFTER},
+        HeaderMap, HeaderName, StatusCode,
+    },
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Json,
+};
+use calibration::SensorCalibration;
+use demo::{
+    follow::FollowLimit,
+    mission::{Mission, MissionError, Step},
+};
+use directions::VehicleDirection;
+use futures_util::{stream, Stream};
+use logbot::self_test::SelfTest;
+use logbot_control::{
+    batch,
+    hardware::{Command, CommandDenied, Response},
+    history::Entry,
+    jobs::{Job, JobId, JobState},
+    scheduler::{MissionId, Schedule},
+    telemetry::{Frame, MAX_RATE},
+};
+use serde::{Deserialize, Serialize};
+use speed::Speed;
+use tokio::sync::broadcast::error::RecvError;
+use tuning::Tuning;
 
 use crate::{
-    hardware::{Command, CommandDenied, CommandResult},
+    info::Info,
+    lease::{Lease, HEADER},
     state::LogbotState,
 };
 
+/// Rate in Hz of the sensor stream when none is given
+const DEFAULT_RATE: u32 = 20;
+
 /// Macro for generating a route handler for a given [`Command`]
 macro_rules! command_route {
     ($fn_name:ident, $command_variant:expr) => {
@@ -30,10 +74,467 @@ macro_rules! command_route {
 command_route!(stop, Command::Stop);
 command_route!(calibrate, Command::Calibrate);
 command_route!(find_edge, Command::FindEdge);
-command_route!(follow, Command::FollowLine);
 command_route!(demo, Command::Demo);
-command_route!(lift_up, Command::LiftUp);
-command_route!(lift_down, Command::LiftDown);
+command_route!(pause, Command::Pause);
+command_route!(resume, Command::Resume);
+
+/// Rest API endpoint for [`Command::Drive`], taking the [`VehicleDirection`] as JSON
+///
+/// Sending a direction also refreshes the heartbeat of manual driving.
+pub async fn drive(
+    State(state): State<Arc<LogbotState>>,
+    Json(direction): Json<VehicleDirection>,
+) -> Result<Json<HardwareResponse>, StatusCode> {
+    state.hardware.heartbeat();
+    let response = state
+        .hardware
+        .send(Command::Drive(direction))
+        .await
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tracing::debug!("Command response: {:?}", response);
+    Ok(Json(HardwareResponse::from(response)))
+}
+
+/// Options of moving the lift, given as query parameters, e.g.
+/// `/v1/lift/up?speed=0.8`
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LiftOptions {
+    /// Speed of the lift, the lift speed of the [`Tuning`] if none
+    pub speed: Option<Speed>,
+}
+
+/// Rest API endpoint for [`Command::LiftUp`], taking the [`LiftOptions`] as
+/// query parameters
+pub async fn lift_up(
+    State(state): State<Arc<LogbotState>>,
+    Query(options): Query<LiftOptions>,
+) -> Result<Json<HardwareResponse>, StatusCode> {
+    let response = state
+        .hardware
+        .send(Command::LiftUp(options.speed))
+        .await
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tracing::debug!("Command response: {:?}", response);
+    Ok(Json(HardwareResponse::from(response)))
+}
+
+/// Rest API endpoint for [`Command::LiftDown`], taking the [`LiftOptions`] as
+/// query parameters
+pub async fn lift_down(
+    State(state): State<Arc<LogbotState>>,
+    Query(options): Query<LiftOptions>,
+) -> Result<Json<HardwareResponse>, StatusCode> {
+    let response = state
+        .hardware
+        .send(Command::LiftDown(options.speed))
+        .await
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tracing::debug!("Command response: {:?}", response);
+    Ok(Json(HardwareResponse::from(response)))
+}
+
+/// Rest API endpoint for following the line until the limits given as query
+/// parameters are reached, e.g. `/v1/follow?distance_m=2&stop_lines=1`
+///
+/// Without any limit this is [`Command::FollowLine`], otherwise
+/// [`Command::FollowFor`].
+pub async fn follow(
+    State(state): State<Arc<LogbotState>>,
+    Query(limit): Query<FollowLimit>,
+) -> Result<Json<HardwareResponse>, StatusCode> {
+    let command = match limit.is_unlimited() {
+        true => Command::FollowLine,
+        false => Command::FollowFor(limit),
+    };
+    let response = state
+        .hardware
+        .send(command)
+        .await
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tracing::debug!("Command response: {:?}", response);
+    Ok(Json(HardwareResponse::from(response)))
+}
+
+/// Rest API endpoint refreshing the heartbeat of manual driving, without
+/// which logbot stops once the heartbeat interval lapses
+pub async fn heartbeat(State(state): State<Arc<LogbotState>>) -> StatusCode {
+    state.hardware.heartbeat();
+    StatusCode::NO_CONTENT
+}
+
+/// Rest API endpoint returning the current [`SensorCalibration`] as JSON
+pub async fn calibration(
+    State(state): State<Arc<LogbotState>>,
+) -> Result<Json<SensorCalibration>, StatusCode> {
+    let calibration = *state.calibration.borrow();
+    calibration.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Rest API endpoint for [`Command::RestoreCalibration`], taking the
+/// [`SensorCalibration`] as JSON
+pub async fn restore_calibration(
+    State(state): State<Arc<LogbotState>>,
+    Json(calibration): Json<SensorCalibration>,
+) -> Result<Json<HardwareResponse>, StatusCode> {
+    let response = state
+        .hardware
+        .send(Command::RestoreCalibration(calibration))
+        .await
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tracing::debug!("Command response: {:?}", response);
+    Ok(Json(HardwareResponse::from(response)))
+}
+
+/// Rest API endpoint for [`Command::Mission`], taking the [`Step`]s as JSON
+///
+/// Responds with `422 Unprocessable Entity` when the steps cannot be executed.
+pub async fn mission(
+    State(state): State<Arc<LogbotState>>,
+    Json(steps): Json<Vec<Step>>,
+) -> Result<Json<HardwareResponse>, (StatusCode, String)> {
+    let mission = Mission::new(steps, *state.tuning.borrow())
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let response = state
+        .hardware
+        .send(Command::Mission(mission))
+        .await
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, String::new()))?;
+
+    tracing::debug!("Command response: {:?}", response);
+    Ok(Json(HardwareResponse::from(response)))
+}
+
+/// Rest API endpoint returning the [`Schedule`] of missions as JSON
+pub async fn schedule(State(state): State<Arc<LogbotState>>) -> Json<Schedule> {
+    Json(state.scheduler.schedule())
+}
+
+/// Rest API endpoint queueing a mission of [`Step`]s after the scheduled
+/// ones, responding with its identifier
+///
+/// Responds with `422 Unprocessable Entity` when the steps cannot be executed.
+pub async fn schedule_mission(
+    State(state): State<Arc<LogbotState>>,
+    Json(steps): Json<Vec<Step>>,
+) -> Result<Json<MissionId>, (StatusCode, String)> {
+    Mission::new(steps.clone(), *state.tuning.borrow())
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let id = state
+        .scheduler
+        .push(steps)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(id))
+}
+
+/// Rest API endpoint removing a scheduled mission, responding with
+/// `404 Not Found` if it is not scheduled
+///
+/// Removing the running mission doesn't stop it, see [`stop`].
+pub async fn remove_scheduled(
+    State(state): State<Arc<LogbotState>>,
+    Path(id): Path<MissionId>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state.scheduler.remove(id) {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((StatusCode::NOT_FOUND, String::new())),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+/// Rest API endpoint holding the schedule, so no further mission starts
+pub async fn hold_schedule(
+    State(state): State<Arc<LogbotState>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .scheduler
+        .hold(true)
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Rest API endpoint releasing a held schedule, e.g. after a mission failed
+pub async fn release_schedule(
+    State(state): State<Arc<LogbotState>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .scheduler
+        .hold(false)
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// A command of a batch, tagged by its name like the steps of a [`Mission`],
+/// e.g. `{"command": "lift_up", "speed": 0.8}`
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BatchCommand {
+    /// [`Command::Drive`] in a direction
+    Drive {
+        /// Direction to drive in
+        direction: VehicleDirection,
+    },
+    /// [`Command::Stop`]
+    Stop,
+    /// [`Command::Calibrate`]
+    Calibrate,
+    /// [`Command::RestoreCalibration`]
+    RestoreCalibration {
+        /// Calibration of the followed sensor
+        calibration: SensorCalibration,
+    },
+    /// [`Command::FindEdge`]
+    FindEdge,
+    /// [`Command::FollowFor`] until a limit is reached
+    Follow {
+        /// When to stop following the line
+        #[serde(default)]
+        limit: FollowLimit,
+    },
+    /// [`Command::LiftUp`]
+    LiftUp {
+        /// Speed of the lift, the lift speed of the [`Tuning`] if none
+        speed: Option<Speed>,
+    },
+    /// [`Command::LiftDown`]
+    LiftDown {
+        /// Speed of the lift, the lift speed of the [`Tuning`] if none
+        speed: Option<Speed>,
+    },
+    /// [`Command::Demo`]
+    Demo,
+    /// [`Command::Mission`] of steps
+    Mission {
+        /// Steps of the mission
+        steps: Vec<Step>,
+    },
+}
+
+impl BatchCommand {
+    /// The [`Command`] to execute, validating missions with a [`Tuning`]
+    fn into_command(self, tuning: Tuning) -> Result<Command, MissionError> {
+        Ok(match self {
+            Self::Drive { direction } => Command::Drive(direction),
+            Self::Stop => Command::Stop,
+            Self::Calibrate => Command::Calibrate,
+            Self::RestoreCalibration { calibration } => Command::RestoreCalibration(calibration),
+            Self::FindEdge => Command::FindEdge,
+            Self::Follow { limit } if limit.is_unlimited() => Command::FollowLine,
+            Self::Follow { limit } => Command::FollowFor(limit),
+            Self::LiftUp { speed } => Command::LiftUp(speed),
+            Self::LiftDown { speed } => Command::LiftDown(speed),
+            Self::Demo => Command::Demo,
+            Self::Mission { steps } => Command::Mission(Mission::new(steps, tuning)?),
+        })
+    }
+}
+
+/// Result of a single command of a batch
+#[derive(Serialize)]
+pub struct BatchResult {
+    /// Name of the command
+    command: &'static str,
+    /// Response to the command, none if it was skipped since an earlier
+    /// command did not succeed
+    response: Option<HardwareResponse>,
+    /// State the job of the command finished in, none for commands without
+    /// a job
+    finished: Option<JobState>,
+}
+
+/// Rest API endpoint executing a batch of [`BatchCommand`]s in order, each
+/// once the job of the previous one finished
+///
+/// The whole batch is validated before any command is executed, responding
+/// with `422 Unprocessable Entity` when any command cannot be executed after
+/// the ones before it. Once a command is denied or its job does not succeed,
+/// the remaining commands are skipped.
+pub async fn commands(
+    State(state): State<Arc<LogbotState>>,
+    Json(batch): Json<Vec<BatchCommand>>,
+) -> Result<Json<Vec<BatchResult>>, (StatusCode, String)> {
+    let tuning = *state.tuning.borrow();
+    let commands = (1..)
+        .zip(batch)
+        .map(|(number, command)| {
+            command
+                .into_command(tuning)
+                .map_err(|e| format!("command {number}: {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    let calibrated = state.calibration.borrow().is_some();
+    batch::validate(&commands, calibrated)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    let mut results = Vec::with_capacity(commands.len());
+    let mut skipping = false;
+    for command in commands {
+        let name = command.as_str();
+        if skipping {
+            results.push(BatchResult {
+                command: name,
+                response: None,
+                finished: None,
+            });
+            continue;
+        };
+
+        if let Command::Drive(_) = command {
+            state.hardware.heartbeat();
+        };
+        let response = state
+            .hardware
+            .send(command)
+            .await
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, String::new()))?;
+        let finished = match response.job {
+            Some(job) => state.jobs.wait(job).await,
+            None => None,
+        };
+
+        skipping = response.result.is_err()
+            || finished
+                .as_ref()
+                .is_some_and(|finished| *finished != JobState::Succeeded);
+        results.push(BatchResult {
+            command: name,
+            response: Some(HardwareResponse::from(response)),
+            finished,
+        });
+    }
+
+    Ok(Json(results))
+}
+
+/// Rest API endpoint returning the active [`Tuning`] as JSON
+pub async fn config(State(state): State<Arc<LogbotState>>) -> Json<Tuning> {
+    Json(*state.tuning.borrow())
+}
+
+/// Rest API endpoint overriding values of the active [`Tuning`], e.g. the
+/// `speed`, the `follow` gains or `acceleration_ms`, taken as JSON
+///
+/// Values missing from the request are kept. The changes apply from the next
+/// command on, the updated [`Tuning`] is returned. Responds with
+/// `422 Unprocessable Entity` when the values are not a valid [`Tuning`].
+pub async fn update_config(
+    State(state): State<Arc<LogbotState>>,
+    Json(overlay): Json<toml::Table>,
+) -> Result<Json<Tuning>, (StatusCode, String)> {
+    let tuning = state
+        .tuning
+        .borrow()
+        .overlay(overlay)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    state.tuning.send_replace(tuning);
+
+    tracing::info!("Tuning updated: {:?}", tuning);
+    Ok(Json(tuning))
+}
+
+/// Rest API endpoint returning the [`Job`] with an identifier as JSON
+pub async fn job(
+    State(state): State<Arc<LogbotState>>,
+    Path(id): Path<JobId>,
+) -> Result<Json<Job>, StatusCode> {
+    state.jobs.get(id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Query parameters of the command history
+#[derive(Deserialize)]
+pub struct HistoryParams {
+    /// Earliest time in milliseconds since the Unix epoch
+    from: Option<u64>,
+    /// Latest time in milliseconds since the Unix epoch
+    to: Option<u64>,
+}
+
+/// Rest API endpoint returning the recent commands as a JSON list of
+/// [`Entry`]s, optionally only those between the `from` and `to` query
+/// parameters, e.g. `/v1/history?from=1700000000000`
+pub async fn history(
+    State(state): State<Arc<LogbotState>>,
+    Query(params): Query<HistoryParams>,
+) -> Json<Vec<Entry>> {
+    Json(state.hardware.history().entries(params.from, params.to))
+}
+
+/// The lease token sent with a request, if any
+fn lease_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get(HEADER).and_then(|token| token.to_str().ok())
+}
+
+/// Rest API endpoint acquiring the [`Lease`] of logbot, or renewing it when
+/// the request carries its token
+///
+/// Responds with `409 Conflict` while another client holds the lease.
+pub async fn acquire_lease(
+    State(state): State<Arc<LogbotState>>,
+    headers: HeaderMap,
+) -> Result<Json<Lease>, (StatusCode, String)> {
+    state
+        .leases
+        .acquire(lease_token(&headers))
+        .map(Json)
+        .map_err(|e| (StatusCode::CONFLICT, e.to_string()))
+}
+
+/// Rest API endpoint releasing the [`Lease`] whose token the request carries
+///
+/// Responds with `403 Forbidden` when the request does not hold the lease.
+pub async fn release_lease(
+    State(state): State<Arc<LogbotState>>,
+    headers: HeaderMap,
+) -> StatusCode {
+    match state.leases.release(lease_token(&headers)) {
+        true => StatusCode::NO_CONTENT,
+        false => StatusCode::FORBIDDEN,
+    }
+}
+
+/// Middleware only passing on requests that may move logbot or change how it
+/// moves while a client holds the [`Lease`]
+///
+/// Responds with `423 Locked` to requests without the token of the lease.
+pub async fn require_lease(
+    State(state): State<Arc<LogbotState>>,
+    request: Request,
+    next: Next,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    state
+        .leases
+        .permits(lease_token(request.headers()))
+        .map_err(|e| (StatusCode::LOCKED, e.to_string()))?;
+    Ok(next.run(request).await)
+}
+
+/// Middleware rejecting commands of clients sending more than their
+/// [`RateLimit`](crate::ratelimit::RateLimit) allows
+///
+/// Responds with `429 Too Many Requests` and the seconds until the next
+/// command is allowed in the `Retry-After` header.
+pub async fn rate_limit(
+    State(state): State<Arc<LogbotState>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Result<axum::response::Response, (StatusCode, [(HeaderName, String); 1], String)> {
+    state.limiter.check(client.ip()).map_err(|e| {
+        let retry_after = e.retry_after.as_secs_f64().ceil().max(1.0);
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, retry_after.to_string())],
+            e.to_string(),
+        )
+    })?;
+    Ok(next.run(request).await)
+}
 
 /// Rest API endpoint for [`Command::Health`]
 pub async fn health(
@@ -46,11 +547,226 @@ pub async fn health(
     Ok(Json(HardwareResponse::new(StatusCode::OK, "Health")))
 }
 
+/// Rest API endpoint running the self-test of the components and reading the
+/// battery voltage, responding with `409 Conflict` while logbot is busy
+pub async fn diagnostics(
+    State(state): State<Arc<LogbotState>>,
+) -> Result<Json<SelfTest>, (StatusCode, String)> {
+    let battery = state.info.hardware.and_then(|hardware| hardware.battery);
+    match state.hardware.diagnose(battery).await {
+        Some(Ok(diagnostics)) => Ok(Json(diagnostics)),
+        Some(Err(denied)) => Err((StatusCode::CONFLICT, denied.to_string())),
+        None => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "the hardware thread is not running".to_owned(),
+        )),
+    }
+}
+
+/// Rest API endpoint describing the server and the hardware it runs
+pub async fn info(State(state): State<Arc<LogbotState>>) -> Json<Info> {
+    Json(state.info.clone())
+}
+
+/// Rest API endpoint starting a new recording of the sensor samples and the
+/// status, responding with `409 Conflict` while a recording is running
+pub async fn start_recording(
+    State(state): State<Arc<LogbotState>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .recorder
+        .start(&state.telemetry, &state.status)
+        .map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Summary of a stopped recording
+#[derive(Serialize)]
+pub struct RecordingSummary {
+    /// Number of records in the recording
+    records: usize,
+}
+
+/// Rest API endpoint stopping the running recording, responding with
+/// `409 Conflict` when nothing is being recorded
+pub async fn stop_recording(
+    State(state): State<Arc<LogbotState>>,
+) -> Result<Json<RecordingSummary>, (StatusCode, &'static str)> {
+    let records = state
+        .recorder
+        .stop()
+        .ok_or((StatusCode::CONFLICT, "nothing is being recorded"))?;
+    Ok(Json(RecordingSummary { records }))
+}
+
+/// Rest API endpoint downloading the running or last recording as a file of
+/// JSON lines
+pub async fn download_recording(State(state): State<Arc<LogbotState>>) -> impl IntoResponse {
+    (
+        [
+            (CONTENT_TYPE, "application/x-ndjson"),
+            (
+                CONTENT_DISPOSITION,
+                "attachment; filename=\"recording.jsonl\"",
+            ),
+        ],
+        state.recorder.download(),
+    )
+}
+
+/// Rest API endpoint streaming sensor samples as JSON lines until the client
+/// disconnects
+pub async fn telemetry(State(state): State<Arc<LogbotState>>) -> impl IntoResponse {
+    let receiver = state.telemetry.subscribe();
+    let lines = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(sample) => {
+                    let line = serde_json::to_string(&sample)
+                        .expect("Telemetry samples are always serializable");
+                    return Some((Ok::<_, Infallible>(line + "\n"), receiver));
+                }
+                // A slow client skips the samples it missed
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!("Telemetry client skipped {skipped} samples");
+                }
+                Err(RecvError::Closed) => return None,
+            };
+        }
+    });
+
+    (
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+}
+
+/// Query parameters of the sensor stream
+#[derive(Deserialize)]
+pub struct StreamParams {
+    /// Samples per second, at most [`MAX_RATE`]
+    rate: Option<u32>,
+}
+
+/// Server-sent events endpoint streaming both sensor values at a rate given
+/// by the `rate` query parameter, e.g. `/v1/sensors/stream?rate=50`
+pub async fn sensor_stream(
+    State(state): State<Arc<LogbotState>>,
+    Query(params): Query<StreamParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    let rate = params.rate.unwrap_or(DEFAULT_RATE);
+    if !(1..=MAX_RATE).contains(&rate) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("rate must be between 1 and {MAX_RATE}"),
+        ));
+    };
+    let period = Duration::from_secs(1) / rate;
+
+    let receiver = state.telemetry.subscribe();
+    let start = (receiver, Instant::now());
+    let events = stream::unfold(start, move |(mut receiver, due)| async move {
+        loop {
+            match receiver.recv().await {
+                // Skip samples until the next event is due
+                Ok(_) if Instant::now() < due => {}
+                Ok(sample) => {
+                    let event = Event::default()
+                        .json_data(sample)
+                        .expect("Telemetry samples are always serializable");
+                    // Keep to the rate on average, without catching up on missed events
+                    let due = (due + period).max(Instant::now());
+                    return Some((Ok(event), (receiver, due)));
+                }
+                // A slow client skips the samples it missed
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!("Sensor stream client skipped {skipped} samples");
+                }
+                Err(RecvError::Closed) => return None,
+            };
+        }
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Server-sent events endpoint streaming the
+/// [`Status`](logbot_control::telemetry::Status) of logbot whenever it changes,
+/// including the progress of a running demo or mission
+pub async fn status_stream(
+    State(state): State<Arc<LogbotState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.status.subscribe();
+    let events = stream::unfold((receiver, true), |(mut receiver, first)| async move {
+        // The current status is sent right away
+        if !first && receiver.changed().await.is_err() {
+            return None;
+        };
+        let event = Event::default()
+            .json_data(&*receiver.borrow_and_update())
+            .expect("Status is always serializable");
+        Some((Ok(event), (receiver, false)))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// WebSocket endpoint streaming a [`Frame`] of the latest sensor values and
+/// the status of logbot whenever either changes
+pub async fn ws(
+    State(state): State<Arc<LogbotState>>,
+    upgrade: WebSocketUpgrade,
+) -> impl IntoResponse {
+    upgrade.on_upgrade(move |socket| stream_frames(socket, state))
+}
+
+/// Send [`Frame`]s to a WebSocket client until it disconnects
+async fn stream_frames(mut socket: WebSocket, state: Arc<LogbotState>) {
+    let mut samples = state.telemetry.subscribe();
+    let mut status = state.status.subscribe();
+    let mut sensors = None;
+
+    loop {
+        let frame = Frame {
+            sensors,
+            status: status.borrow_and_update().clone(),
+        };
+        let text = serde_json::to_string(&frame).expect("Telemetry frames are always serializable");
+        if socket.send(Message::Text(text)).await.is_err() {
+            return;
+        };
+
+        tokio::select! {
+            sample = samples.recv() => match sample {
+                Ok(sample) => sensors = Some(sample),
+                // A slow client skips the samples it missed
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!("WebSocket client skipped {skipped} samples");
+                }
+                Err(RecvError::Closed) => return,
+            },
+            changed = status.changed() => {
+                if changed.is_err() {
+                    return;
+                };
+            }
+            // Incoming messages are ignored, apart from closing the socket
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        };
+    }
+}
+
 /// [`Serialize`] hardware responses using serde
 #[derive(Serialize)]
 pub struct HardwareResponse {
     status: u16,
     reason: &'static str,
+    /// Job of an accepted long-running command, polled at `/v1/jobs/{id}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    job: Option<JobId>,
 }
 
 impl HardwareResponse {
@@ -59,18 +775,25 @@ impl HardwareResponse {
         Self {
             status: status.as_u16(),
             reason,
+            job: None,
         }
     }
 }
 
-impl From<CommandResult> for HardwareResponse {
-    fn from(value: CommandResult) -> Self {
-        match value {
+impl From<Response> for HardwareResponse {
+    fn from(value: Response) -> Self {
+        let response = match value.result {
             Ok(command) => Self::new(StatusCode::OK, command.as_str()),
             Err(CommandDenied::Busy(busy)) => Self::new(StatusCode::CONFLICT, busy.as_str()),
             Err(CommandDenied::Required(required)) => {
                 Self::new(StatusCode::FORBIDDEN, required.as_str())
             }
+            Err(CommandDenied::Idle) => Self::new(StatusCode::CONFLICT, "Idle"),
+            Err(CommandDenied::Battery) => Self::new(StatusCode::SERVICE_UNAVAILABLE, "Battery"),
+        };
+        Self {
+            job: value.job,
+            ..response
         }
     }
 }
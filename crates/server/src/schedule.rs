@@ -0,0 +1,94 @@
+//! Executing the scheduled missions
+//!
+//! The missions of the [`Scheduler`](logbot_control::scheduler::Scheduler)
+//! are sent to the hardware thread one after another, each once the job of
+//! the previous one finished. While a mission runs, the step it reached is
+//! recorded, so a restart resumes it there. A mission denied since logbot is
+//! busy, e.g. driven manually, is retried after [`RETRY`].
+
+use std::{sync::Arc, time::Duration};
+
+use logbot_control::{
+    hardware::Command,
+    jobs::{JobId, JobState},
+    scheduler::Resumed,
+};
+
+use crate::state::LogbotState;
+
+/// Time after which a denied mission is sent again
+pub const RETRY: Duration = Duration::from_secs(1);
+
+/// Execute the scheduled missions until the hardware thread exits
+pub async fn run(state: Arc<LogbotState>) {
+    loop {
+        // Registered before checking, so no change is missed
+        let changed = state.scheduler.changed();
+        let Some(resumed) = state.scheduler.next() else {
+            changed.await;
+            continue;
+        };
+
+        let mission = match resumed.mission(*state.tuning.borrow()) {
+            Ok(mission) => mission,
+            Err(e) => {
+                tracing::warn!("Scheduled mission {} is invalid: {e}", resumed.id);
+                finish(&state, &resumed, false);
+                continue;
+            }
+        };
+        let Some(response) = state.hardware.send(Command::Mission(mission)).await else {
+            return;
+        };
+        let job = match (response.result, response.job) {
+            (Ok(_), Some(job)) => job,
+            (result, _) => {
+                tracing::debug!("Scheduled mission {} denied: {result:?}", resumed.id);
+                tokio::time::sleep(RETRY).await;
+                continue;
+            }
+        };
+
+        tracing::info!("Scheduled mission {} started", resumed.id);
+        let finished = follow(&state, &resumed, job).await;
+        finish(&state, &resumed, finished == Some(JobState::Succeeded));
+    }
+}
+
+/// Record the steps a mission reaches until its job finishes, returning the
+/// [`JobState`] it finished in
+async fn follow(state: &LogbotState, resumed: &Resumed, job: JobId) -> Option<JobState> {
+    let mut status = state.status.subscribe();
+    // The mission may have reached its first step before subscribing
+    status.mark_changed();
+    loop {
+        tokio::select! {
+            finished = state.jobs.wait(job) => return finished,
+            changed = status.changed() => {
+                if changed.is_err() {
+                    return state.jobs.wait(job).await;
+                };
+                let step = status.borrow_and_update().mission.as_ref().map(|step| step.number);
+                if let Some(step) = step {
+                    if let Err(e) = state.scheduler.progress(resumed.id, resumed.step(step)) {
+                        tracing::error!("Failed to persist the schedule: {e}");
+                    };
+                };
+            }
+        }
+    }
+}
+
+/// Record that a mission ended, which holds the schedule unless it succeeded
+fn finish(state: &LogbotState, resumed: &Resumed, succeeded: bool) {
+    match succeeded {
+        true => tracing::info!("Scheduled mission {} finished", resumed.id),
+        false => tracing::warn!(
+            "Scheduled mission {} failed, holding the schedule",
+            resumed.id
+        ),
+    };
+    if let Err(e) = state.scheduler.finish(resumed.id, succeeded) {
+        tracing::error!("Failed to persist the schedule: {e}");
+    };
+}
@@ -1,29 +1,156 @@
+use std::{sync::Arc, time::Duration};
+
 use anyhow::Result;
+use calibration::SensorCalibration;
+use consts::HardwareConfig;
+
+#[cfg(feature = "mock")]
+use components::sim::{SimRangeFinder, World};
+#[cfg(not(feature = "mock"))]
+use components::{hardware_pwm::DCMotor, software_pwm::LiftMotor, SensorController, Ultrasonic};
+#[cfg(not(feature = "mock"))]
+use components::{Left, Right};
+#[cfg(not(feature = "mock"))]
+use defaults::{AssembledLogbot, LogbotAssembler, TryFromConfig};
+use logbot_control::{
+    hardware::{HardwareThread, Obstacles},
+    jobs::Jobs,
+    power::{Power, PowerConfig},
+    scheduler::Scheduler,
+    telemetry::{Status, TelemetrySample, CAPACITY},
+};
+use tokio::sync::{broadcast, watch};
+use tuning::{Preset, Tuning};
+
+use crate::{
+    info::{Backends, Info},
+    lease::Leases,
+    ratelimit::{RateLimit, RateLimiter},
+    recording::Recorder,
+};
+
+/// The [`AssembledLogbot`] built from the default hardware components
+#[cfg(not(feature = "mock"))]
+type DefaultLogbot = AssembledLogbot<DCMotor<Left>, DCMotor<Right>, SensorController, LiftMotor>;
+
+/// [`Backends`] of the [`DefaultLogbot`]
+#[cfg(not(feature = "mock"))]
+const BACKENDS: Backends = Backends {
+    motors: "hardware-pwm",
+    sensors: "pcf8591",
+    lift: "software-pwm",
+};
 
-use components::{hardware_pwm::DCMotor, software_pwm::LiftMotor, Left, Right, SensorController};
-use defaults::TryDefault;
-use logbot::Logbot;
-use vehicle::Vehicle;
+/// The [`SimLogbot`](defaults::SimLogbot) built from simulated components
+#[cfg(feature = "mock")]
+type DefaultLogbot = defaults::SimLogbot;
 
-use crate::hardware::HardwareThread;
+/// [`Backends`] of the [`DefaultLogbot`]
+#[cfg(feature = "mock")]
+const BACKENDS: Backends = Backends {
+    motors: "sim",
+    sensors: "sim",
+    lift: "sim",
+};
+
+/// Assemble the [`DefaultLogbot`] using the loaded [`HardwareConfig`],
+/// seeing [`Obstacles`] with an [`Ultrasonic`] range finder if one is
+/// configured
+#[cfg(not(feature = "mock"))]
+fn assemble() -> Result<(DefaultLogbot, Option<HardwareConfig>, Option<Obstacles>)> {
+    let config = HardwareConfig::load()?;
+    let logbot = LogbotAssembler::new()
+        .left_motor()
+        .right_motor()
+        .sensors()
+        .lift()
+        .config(config)
+        .assemble()?;
+    let obstacles = Option::<Ultrasonic>::try_from_config(&config)?.map(Obstacles::new);
+    Ok((logbot, Some(config), obstacles))
+}
+
+/// Assemble the [`DefaultLogbot`] driving on a simulated line course, starting
+/// on the edge of the line, without any [`HardwareConfig`]
+///
+/// The simulated range finder sees the obstacles placed on the course.
+#[cfg(feature = "mock")]
+fn assemble() -> Result<(DefaultLogbot, Option<HardwareConfig>, Option<Obstacles>)> {
+    let world = World::shared();
+    let obstacles = Obstacles::new(SimRangeFinder::new(world.clone()));
+    Ok((defaults::sim(world), None, Some(obstacles)))
+}
 
 /// Global state for the Logbot API
 #[derive(Debug)]
 pub struct LogbotState {
     /// Thread for processing hardware commands
-    pub hardware:
-        HardwareThread<Logbot<Vehicle<DCMotor<Left>, DCMotor<Right>>, SensorController, LiftMotor>>,
+    pub hardware: HardwareThread<DefaultLogbot>,
+    /// Sender of the sensor samples, subscribed to by telemetry clients
+    pub telemetry: broadcast::Sender<TelemetrySample>,
+    /// Sender of the [`Status`], subscribed to by WebSocket clients
+    pub status: watch::Sender<Status>,
+    /// Calibration of the followed sensor, none until calibrated or restored
+    pub calibration: watch::Sender<Option<SensorCalibration>>,
+    /// The [`Tuning`] applied to the next command, also used to validate and
+    /// execute missions
+    pub tuning: watch::Sender<Tuning>,
+    /// Jobs of the long-running commands
+    pub jobs: Arc<Jobs>,
+    /// Lease of exclusive control over logbot
+    pub leases: Leases,
+    /// Description of the server and its hardware
+    pub info: Info,
+    /// Recorder of black-box recordings
+    pub recorder: Recorder,
+    /// Limiter of the commands sent by each client
+    pub limiter: RateLimiter,
+    /// Queue of the missions executed one after another
+    pub scheduler: Scheduler,
 }
 
 impl LogbotState {
-    pub fn new() -> Result<Self> {
-        let logbot = Logbot::new(
-            Vehicle::try_default()?,
-            SensorController::try_default()?,
-            LiftMotor::try_default()?,
+    pub fn new(
+        preset: Preset,
+        tuning: Tuning,
+        heartbeat: Duration,
+        limit: RateLimit,
+        scheduler: Scheduler,
+    ) -> Result<Self> {
+        let (logbot, config, obstacles) = assemble()?;
+        let info = Info::new(BACKENDS, config, preset, &tuning);
+        let power = config
+            .and_then(|config| config.battery)
+            .map(|battery| Power::new(battery, PowerConfig::default()));
+        let (telemetry, _) = broadcast::channel(CAPACITY);
+        let status = watch::Sender::new(Status::idle(&logbot));
+        let calibration = watch::Sender::new(None);
+        let jobs = Arc::new(Jobs::default());
+        let tuning = watch::Sender::new(tuning);
+        let thread = HardwareThread::spawn(
+            logbot,
+            tuning.subscribe(),
+            telemetry.clone(),
+            status.clone(),
+            calibration.clone(),
+            obstacles,
+            power,
+            Arc::clone(&jobs),
+            heartbeat,
         );
-        let thread = HardwareThread::spawn(logbot);
 
-        Ok(Self { hardware: thread })
+        Ok(Self {
+            hardware: thread,
+            telemetry,
+            status,
+            calibration,
+            tuning,
+            jobs,
+            leases: Leases::default(),
+            info,
+            recorder: Recorder::default(),
+            limiter: RateLimiter::new(limit),
+            scheduler,
+        })
     }
 }
@@ -0,0 +1,264 @@
+//! Description of a line course in 2D
+//!
+//! A [`Course`] is made of [`Segment`]s of tape, either straight or curved,
+//! which form the line logbot follows, and of stop lines crossing the line.
+//! All positions are in meters.
+
+use std::f64::consts::PI;
+
+/// A point on the course, in meters
+pub type Point = (f64, f64);
+
+/// A piece of tape on the course
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    /// Straight tape between two points
+    Line {
+        /// Where the tape starts
+        start: Point,
+        /// Where the tape ends
+        end: Point,
+    },
+    /// Tape curving around a center
+    Arc {
+        /// Center of the circle the tape lies on
+        center: Point,
+        /// Radius of the circle in meters
+        radius: f64,
+        /// Angle where the tape starts in radians, counterclockwise from the
+        /// x axis
+        start: f64,
+        /// Angle covered by the tape in radians, counterclockwise when positive
+        sweep: f64,
+    },
+}
+
+impl Segment {
+    /// Distance of a point from the tape
+    pub fn distance(&self, point: Point) -> f64 {
+        match *self {
+            Self::Line { start, end } => line_distance(point, start, end),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let (dx, dy) = (point.0 - center.0, point.1 - center.1);
+                // Angle of the point measured from the start, in the
+                // direction of the sweep
+                let angle = dy.atan2(dx) - start;
+                let angle = (angle * sweep.signum()).rem_euclid(2.0 * PI);
+                if angle <= sweep.abs() {
+                    ((dx * dx + dy * dy).sqrt() - radius).abs()
+                } else {
+                    let (first, last) = self.ends();
+                    distance(point, first).min(distance(point, last))
+                }
+            }
+        }
+    }
+
+    /// Points along the tape, `step` meters apart at most, e.g. for drawing
+    /// the tape as a polyline
+    pub fn points(&self, step: f64) -> Vec<Point> {
+        match *self {
+            Self::Line { start, end } => vec![start, end],
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let count = ((sweep.abs() * radius / step).ceil() as usize).max(1);
+                (0..=count)
+                    .map(|i| {
+                        let angle = start + sweep * i as f64 / count as f64;
+                        (
+                            center.0 + radius * angle.cos(),
+                            center.1 + radius * angle.sin(),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The first and last point of the tape
+    pub fn ends(&self) -> (Point, Point) {
+        match *self {
+            Self::Line { start, end } => (start, end),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let at = |angle: f64| {
+                    (
+                        center.0 + radius * angle.cos(),
+                        center.1 + radius * angle.sin(),
+                    )
+                };
+                (at(start), at(start + sweep))
+            }
+        }
+    }
+}
+
+/// A course of tape logbot drives on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Course {
+    /// Segments forming the line
+    line: Vec<Segment>,
+    /// Stop lines crossing the line
+    stop_lines: Vec<Segment>,
+}
+
+impl Course {
+    /// Create an empty [`Course`]
+    pub fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            stop_lines: Vec::new(),
+        }
+    }
+
+    /// Add a straight piece of the line
+    pub fn line(mut self, start: Point, end: Point) -> Self {
+        self.line.push(Segment::Line { start, end });
+        self
+    }
+
+    /// Add a curved piece of the line, see [`Segment::Arc`]
+    pub fn arc(mut self, center: Point, radius: f64, start: f64, sweep: f64) -> Self {
+        self.line.push(Segment::Arc {
+            center,
+            radius,
+            start,
+            sweep,
+        });
+        self
+    }
+
+    /// Add a stop line crossing the line
+    pub fn stop_line(mut self, start: Point, end: Point) -> Self {
+        self.stop_lines.push(Segment::Line { start, end });
+        self
+    }
+
+    /// Rectangular loop with a stop line on its bottom and top side, the
+    /// course logbot starts on by default
+    pub fn rectangle() -> Self {
+        Self::new()
+            .line((0.0, 0.0), (1.2, 0.0))
+            .line((1.2, 0.0), (1.2, 0.8))
+            .line((1.2, 0.8), (0.0, 0.8))
+            .line((0.0, 0.8), (0.0, 0.0))
+            .stop_line((0.6, -0.05), (0.6, 0.05))
+            .stop_line((0.6, 0.75), (0.6, 0.85))
+    }
+
+    /// Oval loop of two straights joined by half circles, with a stop line
+    /// on each straight
+    pub fn oval() -> Self {
+        Self::new()
+            .line((0.0, 0.0), (0.8, 0.0))
+            .arc((0.8, 0.4), 0.4, -PI / 2.0, PI)
+            .line((0.8, 0.8), (0.0, 0.8))
+            .arc((0.0, 0.4), 0.4, PI / 2.0, PI)
+            .stop_line((0.4, -0.05), (0.4, 0.05))
+            .stop_line((0.4, 0.75), (0.4, 0.85))
+    }
+
+    /// Segments forming the line
+    pub fn segments(&self) -> &[Segment] {
+        &self.line
+    }
+
+    /// Stop lines crossing the line
+    pub fn stop_lines(&self) -> &[Segment] {
+        &self.stop_lines
+    }
+
+    /// Distance of a point from the nearest tape, line or stop line
+    pub fn distance(&self, point: Point) -> f64 {
+        self.line
+            .iter()
+            .chain(&self.stop_lines)
+            .map(|segment| segment.distance(point))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+impl Default for Course {
+    fn default() -> Self {
+        Self::rectangle()
+    }
+}
+
+/// Distance between two points
+fn distance(a: Point, b: Point) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Distance of a point from a line segment
+fn line_distance(point: Point, start: Point, end: Point) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx * dx + dy * dy;
+    if length == 0.0 {
+        return distance(point, start);
+    };
+    let t = (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length).clamp(0.0, 1.0);
+    distance(point, (start.0 + t * dx, start.1 + t * dy))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::{Course, Segment};
+
+    /// Test the distance from straight and curved tape
+    #[test]
+    fn distance() {
+        let line = Segment::Line {
+            start: (0.0, 0.0),
+            end: (1.0, 0.0),
+        };
+        assert!((line.distance((0.5, 0.2)) - 0.2).abs() < 1e-9);
+        assert!((line.distance((2.0, 0.0)) - 1.0).abs() < 1e-9);
+
+        // Upper half of the unit circle
+        let arc = Segment::Arc {
+            center: (0.0, 0.0),
+            radius: 1.0,
+            start: 0.0,
+            sweep: PI,
+        };
+        assert!((arc.distance((0.0, 0.5)) - 0.5).abs() < 1e-9);
+        // Below the arc, the nearest points are its ends
+        assert!((arc.distance((0.0, -1.0)) - 2f64.sqrt()).abs() < 1e-9);
+
+        // Sweeping clockwise covers the lower half instead
+        let arc = Segment::Arc {
+            center: (0.0, 0.0),
+            radius: 1.0,
+            start: 0.0,
+            sweep: -PI,
+        };
+        assert!((arc.distance((0.0, -0.5)) - 0.5).abs() < 1e-9);
+    }
+
+    /// Test that the segments of the oval join into a closed loop
+    #[test]
+    fn closed_oval() {
+        let course = Course::oval();
+        let segments = course.segments();
+        for (segment, next) in segments.iter().zip(segments.iter().cycle().skip(1)) {
+            let (_, end) = segment.ends();
+            let (start, _) = next.ends();
+            assert!((end.0 - start.0).abs() < 1e-9 && (end.1 - start.1).abs() < 1e-9);
+        }
+    }
+}
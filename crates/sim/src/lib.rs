@@ -0,0 +1,438 @@
+//! Simulation of logbot driving on a virtual line course
+//!
+//! A [`World`] tracks the position of logbot on a [`Course`] using
+//! differential drive kinematics, together with the position of its lift.
+//! [`SimRobot`] drives logbot in a [`SharedWorld`] and implements [`Drive`],
+//! [`Spin`], [`SensorRead`] and [`Lift`], so the demo, the line follower and
+//! the server run without any hardware. [`Obstacle`]s placed on the course
+//! are seen by its [`RangeFinder`].
+//!
+//! The world runs on a [`Clock`]. With [`Clock::Real`] the simulation
+//! follows the time passing, like the real robot would. With
+//! [`Clock::Manual`] time only passes when the world is
+//! [advanced](SharedWorld::advance), so the same commands always give the
+//! same result, e.g. in tests. On `wasm32-unknown-unknown`, which has no
+//! [`Instant`](std::time::Instant), only the manual clock exists and the host advances the world
+//! by the time it measured, e.g. between two animation frames of a browser.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use directions::VehicleDirection;
+//! use interfaces::Drive;
+//! use sim::{Clock, Course, SimRobot, World};
+//!
+//! let world = World::new(Course::rectangle(), Clock::Manual).into_shared();
+//! let mut robot = SimRobot::new(world.clone());
+//!
+//! let start = world.pose();
+//! robot.drive("forward:0.5".parse::<VehicleDirection>().unwrap()).unwrap();
+//! world.advance(Duration::from_secs(1));
+//! assert!((world.pose().x - start.x - 0.5).abs() < 1e-9);
+//! ```
+//!
+//! [`Drive`]: interfaces::Drive
+//! [`Spin`]: interfaces::Spin
+//! [`SensorRead`]: interfaces::SensorRead
+//! [`Lift`]: interfaces::Lift
+//! [`RangeFinder`]: interfaces::RangeFinder
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use directions::MotorDirection;
+
+pub mod course;
+mod robot;
+
+pub use course::{Course, Segment};
+pub use robot::SimRobot;
+
+/// Distance between the wheels in meters
+pub const WHEELBASE: f64 = 0.15;
+/// Speed of a wheel in meters per second when driven at full speed
+pub const MAX_WHEEL_SPEED: f64 = 1.0;
+/// Distance of the sensors in front of the wheels in meters
+const SENSOR_AHEAD: f64 = 0.08;
+/// Distance of each sensor from the center of logbot in meters
+const SENSOR_SIDE: f64 = 0.02;
+/// Radius of the area a sensor sees in meters
+const SENSOR_RADIUS: f64 = 0.01;
+/// Width of the tape in meters
+pub const LINE_WIDTH: f64 = 0.02;
+/// Sensor value when fully above the line
+pub const LINE_VALUE: u8 = 200;
+/// Sensor value when fully above the floor
+pub const FLOOR_VALUE: u8 = 40;
+/// Seconds the lift needs to move from down to up at full speed
+const LIFT_TRAVEL: f64 = 1.5;
+/// Distance of the range finder in front of the wheels in meters
+const RANGE_FINDER_AHEAD: f64 = 0.1;
+/// Farthest distance the range finder measures in meters
+pub const RANGE_FINDER_MAX: f64 = 2.0;
+/// Half the opening angle of the range finder in radians
+const RANGE_FINDER_CONE: f64 = 15.0 * std::f64::consts::PI / 180.0;
+
+/// Position of logbot on the course
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    /// Horizontal position in meters
+    pub x: f64,
+    /// Vertical position in meters
+    pub y: f64,
+    /// Heading in radians, counterclockwise from the x axis
+    pub heading: f64,
+}
+
+impl Pose {
+    /// Pose with the left sensor on the edge of the line at the bottom of
+    /// the [rectangle](Course::rectangle) and the [oval](Course::oval)
+    pub const START: Self = Self {
+        x: 0.2,
+        y: -SENSOR_SIDE + LINE_WIDTH / 2.0,
+        heading: 0.0,
+    };
+
+    /// Positions of the left and right sensor
+    pub fn sensors(&self) -> [(f64, f64); 2] {
+        [
+            self.offset(SENSOR_AHEAD, SENSOR_SIDE),
+            self.offset(SENSOR_AHEAD, -SENSOR_SIDE),
+        ]
+    }
+
+    /// Position of a point relative to logbot, `side` is positive to the left
+    fn offset(&self, ahead: f64, side: f64) -> (f64, f64) {
+        let (sin, cos) = self.heading.sin_cos();
+        (
+            self.x + ahead * cos - side * sin,
+            self.y + ahead * sin + side * cos,
+        )
+    }
+}
+
+/// Round obstacle standing on the course, e.g. a box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obstacle {
+    /// Horizontal position of the center in meters
+    pub x: f64,
+    /// Vertical position of the center in meters
+    pub y: f64,
+    /// Radius in meters
+    pub radius: f64,
+}
+
+impl Obstacle {
+    /// Distance from a point to the side of the obstacle facing it, when
+    /// seen within a cone of half an opening angle around a heading
+    fn seen_from(&self, (x, y): (f64, f64), heading: f64, cone: f64) -> Option<f64> {
+        let (sin, cos) = heading.sin_cos();
+        let (dx, dy) = (self.x - x, self.y - y);
+        let ahead = dx * cos + dy * sin;
+        let side = -dx * sin + dy * cos;
+        if ahead <= 0.0 || side.abs() > ahead * cone.tan() + self.radius {
+            return None;
+        };
+        Some((dx.hypot(dy) - self.radius).max(0.0))
+    }
+}
+
+/// Time the simulation follows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clock {
+    /// Follow the real time, simulated up to the given [`Instant`]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    Real(Instant),
+    /// Only move on when [advanced](World::advance)
+    Manual,
+}
+
+/// A wheel of logbot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wheel {
+    /// The left wheel
+    Left,
+    /// The right wheel
+    Right,
+}
+
+/// State of the simulation
+#[derive(Debug, Clone)]
+pub struct World {
+    /// The course logbot drives on
+    course: Course,
+    /// Position of logbot
+    pose: Pose,
+    /// Signed speed of the left wheel in meters per second
+    left: f64,
+    /// Signed speed of the right wheel in meters per second
+    right: f64,
+    /// Position of the lift, from 0.0 when down to 1.0 when up
+    lift: f64,
+    /// Signed speed of the lift in travels per second, positive when moving up
+    lift_speed: f64,
+    /// Time the simulation follows
+    // Only the manual clock exists on the web, which is never checked
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), allow(dead_code))]
+    clock: Clock,
+    /// Obstacles standing on the course
+    obstacles: Vec<Obstacle>,
+}
+
+impl World {
+    /// Create a new [`World`] with logbot standing at [`Pose::START`]
+    pub fn new(course: Course, clock: Clock) -> Self {
+        Self {
+            course,
+            pose: Pose::START,
+            left: 0.0,
+            right: 0.0,
+            lift: 0.0,
+            lift_speed: 0.0,
+            clock,
+            obstacles: Vec::new(),
+        }
+    }
+
+    /// Create a new [`SharedWorld`] following the real time, with logbot on
+    /// the edge of the line of the [rectangle](Course::rectangle)
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn shared() -> SharedWorld {
+        Self::new(Course::rectangle(), Clock::Real(Instant::now())).into_shared()
+    }
+
+    /// Share the world between simulated components
+    pub fn into_shared(self) -> SharedWorld {
+        SharedWorld(Arc::new(Mutex::new(self)))
+    }
+
+    /// Place logbot at a [`Pose`]
+    pub fn with_pose(mut self, pose: Pose) -> Self {
+        self.pose = pose;
+        self
+    }
+
+    /// Place an [`Obstacle`] on the course
+    pub fn with_obstacle(mut self, obstacle: Obstacle) -> Self {
+        self.obstacles.push(obstacle);
+        self
+    }
+
+    /// The course logbot drives on
+    pub fn course(&self) -> &Course {
+        &self.course
+    }
+
+    /// The obstacles standing on the course
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    /// Place an [`Obstacle`] on the course
+    pub fn place_obstacle(&mut self, obstacle: Obstacle) {
+        self.obstacles.push(obstacle);
+    }
+
+    /// Remove all obstacles from the course
+    pub fn clear_obstacles(&mut self) {
+        self.obstacles.clear();
+    }
+
+    /// The current position of logbot
+    pub fn pose(&mut self) -> Pose {
+        self.sync();
+        self.pose
+    }
+
+    /// Move the simulation on by a duration, on top of the real time passed
+    /// if it follows the [`Clock::Real`]
+    pub fn advance(&mut self, duration: Duration) {
+        self.sync();
+        self.step(duration);
+    }
+
+    /// Catch up with the real time, if followed
+    fn sync(&mut self) {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        if let Clock::Real(updated) = &mut self.clock {
+            let now = Instant::now();
+            let elapsed = now.saturating_duration_since(*updated);
+            *updated = now;
+            self.step(elapsed);
+        };
+    }
+
+    /// Move logbot along the arc driven and the lift during a duration
+    fn step(&mut self, duration: Duration) {
+        let dt = duration.as_secs_f64();
+
+        self.lift = (self.lift + self.lift_speed * dt).clamp(0.0, 1.0);
+
+        let velocity = (self.left + self.right) / 2.0;
+        let rotation = (self.right - self.left) / WHEELBASE;
+        let pose = &mut self.pose;
+
+        if rotation.abs() < f64::EPSILON {
+            pose.x += velocity * dt * pose.heading.cos();
+            pose.y += velocity * dt * pose.heading.sin();
+        } else {
+            let radius = velocity / rotation;
+            let heading = pose.heading + rotation * dt;
+            pose.x += radius * (heading.sin() - pose.heading.sin());
+            pose.y -= radius * (heading.cos() - pose.heading.cos());
+            pose.heading = heading;
+        };
+    }
+
+    /// Value read by the left (0) or right (1) sensor
+    pub fn sensor(&mut self, sensor: usize) -> u8 {
+        let point = self.pose().sensors()[sensor];
+        let distance = self.course.distance(point);
+
+        // Share of the seen area covered by the line
+        let covered =
+            ((LINE_WIDTH / 2.0 + SENSOR_RADIUS - distance) / (2.0 * SENSOR_RADIUS)).clamp(0.0, 1.0);
+        FLOOR_VALUE + ((LINE_VALUE - FLOOR_VALUE) as f64 * covered).round() as u8
+    }
+
+    /// Distance measured by the range finder to the nearest obstacle ahead,
+    /// or [None] if none is within [`RANGE_FINDER_MAX`]
+    pub fn range(&mut self) -> Option<f64> {
+        let pose = self.pose();
+        let point = pose.offset(RANGE_FINDER_AHEAD, 0.0);
+        self.obstacles
+            .iter()
+            .filter_map(|obstacle| obstacle.seen_from(point, pose.heading, RANGE_FINDER_CONE))
+            .filter(|&distance| distance <= RANGE_FINDER_MAX)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Set the speed of a wheel
+    pub fn set_wheel(&mut self, wheel: Wheel, direction: MotorDirection) {
+        self.sync();
+        let speed = direction.signed() * MAX_WHEEL_SPEED;
+        match wheel {
+            Wheel::Left => self.left = speed,
+            Wheel::Right => self.right = speed,
+        };
+    }
+
+    /// Position of the lift, from 0.0 when down to 1.0 when up
+    pub fn lift(&mut self) -> f64 {
+        self.sync();
+        self.lift
+    }
+
+    /// Move the lift all the way up or down at once
+    pub fn set_lift(&mut self, up: bool) {
+        self.sync();
+        self.lift = if up { 1.0 } else { 0.0 };
+        self.lift_speed = 0.0;
+    }
+
+    /// Move the lift with a signed speed, stopping at the ends of its range
+    pub fn jog_lift(&mut self, speed: f64) {
+        self.sync();
+        let at_end = (speed > 0.0 && self.lift >= 1.0) || (speed < 0.0 && self.lift <= 0.0);
+        self.lift_speed = if at_end { 0.0 } else { speed / LIFT_TRAVEL };
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Default for World {
+    fn default() -> Self {
+        Self::new(Course::default(), Clock::Real(Instant::now()))
+    }
+}
+
+/// [`World`] shared by the simulated components, which may live on other threads
+#[derive(Debug, Clone)]
+pub struct SharedWorld(Arc<Mutex<World>>);
+
+impl SharedWorld {
+    /// Access the world, even if another thread panicked while accessing it
+    pub fn lock(&self) -> MutexGuard<'_, World> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The current position of logbot
+    pub fn pose(&self) -> Pose {
+        self.lock().pose()
+    }
+
+    /// The course logbot drives on
+    pub fn course(&self) -> Course {
+        self.lock().course().clone()
+    }
+
+    /// Move the simulation on by a duration, see [`World::advance`]
+    pub fn advance(&self, duration: Duration) {
+        self.lock().advance(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::PI, time::Duration};
+
+    use directions::MotorDirection;
+    use speed::Speed;
+
+    use super::{Clock, Course, Obstacle, Wheel, World, FLOOR_VALUE, LINE_VALUE, WHEELBASE};
+
+    /// Test that logbot starts with the left sensor on the edge of the line
+    #[test]
+    fn start_on_edge() {
+        for course in [Course::rectangle(), Course::oval()] {
+            let mut world = World::new(course, Clock::Manual);
+            let edge = world.sensor(0);
+            assert!(FLOOR_VALUE < edge && edge < LINE_VALUE);
+            assert_eq!(world.sensor(1), FLOOR_VALUE);
+        }
+    }
+
+    /// Test that a manual clock only moves on when advanced
+    #[test]
+    fn manual_clock() {
+        let mut world = World::new(Course::rectangle(), Clock::Manual);
+        world.set_wheel(Wheel::Left, MotorDirection::Backward(Speed::HALF));
+        world.set_wheel(Wheel::Right, MotorDirection::Forward(Speed::HALF));
+
+        let start = world.pose();
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(world.pose(), start);
+
+        // Spinning in place for a turn at half speed
+        let turn = 2.0 * PI * (WHEELBASE / 2.0) / 0.5;
+        world.advance(Duration::from_secs_f64(turn));
+        let end = world.pose();
+        assert!((end.x - start.x).abs() < 1e-9 && (end.y - start.y).abs() < 1e-9);
+        assert!((end.heading - start.heading - 2.0 * PI).abs() < 1e-9);
+    }
+
+    /// Test that the range finder only sees obstacles ahead and within range
+    #[test]
+    fn range_to_obstacles() {
+        let obstacle = |x, y| Obstacle { x, y, radius: 0.05 };
+        let mut world = World::new(Course::rectangle(), Clock::Manual);
+        assert_eq!(world.range(), None);
+
+        // Logbot starts at x = 0.2 heading along the x axis
+        world.place_obstacle(obstacle(0.2, 0.0));
+        world.place_obstacle(obstacle(1.0, 0.5));
+        world.place_obstacle(obstacle(3.0, 0.0));
+        assert_eq!(world.range(), None);
+
+        world.place_obstacle(obstacle(1.0, 0.0));
+        world.place_obstacle(obstacle(0.8, 0.0));
+        let range = world.range().unwrap();
+        assert!((range - 0.45).abs() < 0.01, "{range}");
+
+        world.clear_obstacles();
+        assert_eq!(world.range(), None);
+    }
+}
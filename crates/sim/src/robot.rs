@@ -0,0 +1,171 @@
+//! Simulated logbot as a single component
+
+use std::convert::Infallible;
+
+use directions::{SpinDirection, Stop, VehicleDirection};
+use interfaces::{
+    Drive, DriveState, JogLift, Lift, RangeFinder, SensorRead, Spin, ToSensorChannel,
+};
+use speed::Speed;
+
+use crate::{SharedWorld, Wheel};
+
+/// Logbot driving, sensing and lifting in a [`SharedWorld`]
+#[derive(Debug)]
+pub struct SimRobot {
+    /// The simulation
+    world: SharedWorld,
+    /// Direction logbot is driven in
+    state: VehicleDirection,
+}
+
+impl SimRobot {
+    /// Create a new stopped [`SimRobot`]
+    pub fn new(world: SharedWorld) -> Self {
+        Self {
+            world,
+            state: VehicleDirection::STOP,
+        }
+    }
+
+    /// The simulation logbot drives in
+    pub fn world(&self) -> &SharedWorld {
+        &self.world
+    }
+}
+
+impl Drive for SimRobot {
+    type Direction = VehicleDirection;
+    type Error = Infallible;
+
+    fn drive(
+        &mut self,
+        direction: Self::Direction,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
+        let mut world = self.world.lock();
+        world.set_wheel(Wheel::Left, direction.left);
+        world.set_wheel(Wheel::Right, direction.right);
+        let previous = std::mem::replace(&mut self.state, direction);
+        Ok(Some(previous).filter(|previous| !previous.is_stop()))
+    }
+
+    fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+        self.drive(VehicleDirection::STOP)
+    }
+}
+
+impl DriveState for SimRobot {
+    fn state(&self) -> Self::Direction {
+        self.state
+    }
+}
+
+impl Spin for SimRobot {
+    type SpinDirection = SpinDirection;
+
+    fn spin(
+        &mut self,
+        direction: Self::SpinDirection,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
+        self.drive(VehicleDirection::from(direction))
+    }
+}
+
+impl SensorRead for SimRobot {
+    type Output = u8;
+    type Error = Infallible;
+
+    /// Channel 0 is the left sensor, any other channel the right sensor
+    fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
+        let sensor = match sensor.to_channel() {
+            0 => 0,
+            _ => 1,
+        };
+        Ok(self.world.lock().sensor(sensor))
+    }
+}
+
+impl RangeFinder for SimRobot {
+    type Error = Infallible;
+
+    fn distance(&mut self) -> Result<Option<f64>, Self::Error> {
+        Ok(self.world.lock().range())
+    }
+}
+
+/// Full moves of the lift are instant, while jogs take time
+impl Lift for SimRobot {
+    type Error = Infallible;
+
+    fn up(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        self.world.lock().set_lift(true);
+        Ok(())
+    }
+
+    fn down(&mut self, _speed: Speed) -> Result<(), Self::Error> {
+        self.world.lock().set_lift(false);
+        Ok(())
+    }
+
+    fn is_up(&self) -> bool {
+        self.world.lock().lift() >= 1.0
+    }
+
+    fn is_down(&self) -> bool {
+        self.world.lock().lift() <= 0.0
+    }
+}
+
+impl JogLift for SimRobot {
+    fn jog_up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.world.lock().jog_lift(speed.value());
+        Ok(())
+    }
+
+    fn jog_down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        self.world.lock().jog_lift(-speed.value());
+        Ok(())
+    }
+
+    fn halt(&mut self) -> Result<(), Self::Error> {
+        self.world.lock().jog_lift(0.0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use consts::Sensors;
+    use directions::SpinDirection;
+    use interfaces::{Drive, SensorRead, Spin};
+    use speed::Speed;
+
+    use super::SimRobot;
+    use crate::{Clock, Course, World, LINE_VALUE};
+
+    /// Test that spinning onto the line finds it at the same moment every run
+    #[test]
+    fn find_line_deterministically() {
+        let find = || {
+            let world = World::new(Course::oval(), Clock::Manual).into_shared();
+            let mut robot = SimRobot::new(world.clone());
+            robot
+                .spin(SpinDirection::Left(Speed::new_clamp(0.1)))
+                .unwrap();
+
+            let mut steps = 0;
+            while robot.read(Sensors::Right).unwrap() < LINE_VALUE - 1 {
+                world.advance(Duration::from_millis(1));
+                steps += 1;
+            }
+            robot.stop().unwrap();
+            (steps, world.pose())
+        };
+
+        let (steps, pose) = find();
+        assert!(steps > 0);
+        assert_eq!(find(), (steps, pose));
+    }
+}
@@ -6,12 +6,21 @@
 use core::num::NonZero;
 use core::ops::{Div, Mul};
 
+// Only used by the tests of the `serde` feature
+#[cfg(all(test, not(feature = "serde")))]
+use serde_json as _;
+
 /// Represent Speed
 ///
 /// [`Speed`] is a simple wrapper around the [`f64`] type.
 /// It's used to enforce that the underlying value is between
 /// 0.0 and 1.0 (inclusive)
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "f64", into = "f64")
+)]
 pub struct Speed(f64);
 
 impl Speed {
@@ -31,8 +40,13 @@ impl Speed {
     }
 
     /// Create a new [`Speed`], clamping to stay in bounds
+    ///
+    /// NaN, e.g. from dividing zero by zero, is clamped to [`Speed::MIN`].
     pub fn new_clamp(value: f64) -> Self {
-        Self(value.clamp(0.0, 1.0))
+        match value.is_nan() {
+            true => Self::MIN,
+            false => Self(value.clamp(0.0, 1.0)),
+        }
     }
 
     /// Create a new [`Speed`] with a constant value
@@ -44,7 +58,11 @@ impl Speed {
         Self(value)
     }
 
-    /// SAFETY: value must be between 0.0 and 1.0 (inclusive)
+    /// Create a new [`Speed`] without checking bounds
+    ///
+    /// # Safety
+    ///
+    /// value must be between 0.0 and 1.0 (inclusive)
     pub const unsafe fn new_unchecked(value: f64) -> Self {
         Self(value)
     }
@@ -121,6 +139,12 @@ impl TryFrom<f64> for Speed {
     }
 }
 
+impl From<Speed> for f64 {
+    fn from(value: Speed) -> Self {
+        value.0
+    }
+}
+
 /// This macro implements the [`Div`] trait for a type wrapped in [`NonZero`]
 macro_rules! impl_div_for_nonzero {
     ($($ty:ty),*) => {
@@ -143,6 +167,10 @@ impl_div_for_nonzero!(usize, u8, u16, u32, u64, u128);
 
 #[cfg(test)]
 mod tests {
+    use core::num::NonZero;
+
+    use proptest::prelude::*;
+
     use crate::Speed;
 
     /// Test that [Speed::new] preserves the passed [`f64`] as the speed
@@ -182,6 +210,13 @@ mod tests {
         assert_eq!(Speed::new_clamp(-1.0).value(), 0.0);
     }
 
+    /// Test that NaN is clamped to standing still
+    #[test]
+    fn new_clamp_nan() {
+        assert_eq!(Speed::new_clamp(f64::NAN), Speed::MIN);
+        assert_eq!(Speed::MIN.saturating_div_f64(0.0), Speed::MIN);
+    }
+
     /// Test that [`Speed::MIN`] and [`Speed::MAX`] are actually at [`Speed`] bounds
     #[test]
     fn min_max_are_at_bounds() {
@@ -222,4 +257,71 @@ mod tests {
         // Check that Speed with different values are not equal
         assert_ne!(Speed::new_clamp(value), Speed::new_clamp(value2));
     }
+
+    /// Any [`Speed`]
+    fn speed() -> impl Strategy<Value = Speed> {
+        (0.0..=1.0f64).prop_map(Speed::new_clamp)
+    }
+
+    proptest! {
+        /// Test that clamping and the saturating operations stay in bounds
+        /// for any value, including NaN and the infinities
+        #[test]
+        fn saturating_stays_in_bounds(speed in speed(), value in prop::num::f64::ANY) {
+            for result in [
+                Speed::new_clamp(value),
+                speed.saturating_add_f64(value),
+                speed.saturating_sub_f64(value),
+                speed.saturating_mul_f64(value),
+                speed.saturating_div_f64(value),
+            ] {
+                prop_assert!((0.0..=1.0).contains(&result.value()), "{result:?}");
+            }
+        }
+
+        /// Test that a [`Speed`] is created from exactly the values within
+        /// bounds, keeping the value
+        #[test]
+        fn new_respects_bounds(value in prop::num::f64::ANY) {
+            match Speed::new(value) {
+                Ok(speed) => prop_assert_eq!(speed.value(), value),
+                Err(_) => prop_assert!(!(0.0..=1.0).contains(&value)),
+            };
+        }
+
+        /// Test that adding a [`Speed`] never slows down and subtracting one
+        /// never speeds up
+        #[test]
+        fn saturating_is_monotonic(a in speed(), b in speed()) {
+            prop_assert!(a.saturating_add(b) >= a);
+            prop_assert!(a.saturating_sub(b) <= a);
+            prop_assert_eq!(a.saturating_add(b), b.saturating_add(a));
+        }
+
+        /// Test that multiplying and dividing speeds stays in bounds and
+        /// never speeds up
+        #[test]
+        fn mul_div_slow_down(a in speed(), b in speed(), divisor in 1..=u32::MAX) {
+            let product = a * b;
+            prop_assert!((0.0..=1.0).contains(&product.value()));
+            prop_assert!(product <= a && product <= b);
+
+            let quotient = a / NonZero::new(divisor).unwrap();
+            prop_assert!((0.0..=1.0).contains(&quotient.value()));
+            prop_assert!(quotient <= a);
+        }
+    }
+
+    /// Test that [`Speed`] serializes as a plain [`f64`] and refuses
+    /// to deserialize values outside of its bounds
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_respects_bounds() {
+        let speed = Speed::new_clamp(0.25);
+        let json = serde_json::to_string(&speed).unwrap();
+        assert_eq!(json, "0.25");
+        assert_eq!(serde_json::from_str::<Speed>(&json).unwrap(), speed);
+
+        assert!(serde_json::from_str::<Speed>("1.5").is_err());
+    }
 }
@@ -0,0 +1,65 @@
+//! Time of a [`Scenario`](crate::Scenario), moved on by its components
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use interfaces::Clock;
+
+/// [`Clock`] that only moves on when [advanced](Self::advance), shared by
+/// cloning it
+///
+/// The time of a [`Scenario`](crate::Scenario) is independent of how fast the
+/// machine running it is: the scripted components advance the clock by the
+/// time they take, so the code under test sees the same values in the same
+/// order on a loaded CI runner as on an idle laptop.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    /// Nanoseconds since the start of the clock
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a [`ManualClock`] at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock and every clone of it on by a duration
+    pub fn advance(&self, by: Duration) {
+        let nanos = u64::try_from(by.as_nanos()).unwrap_or(u64::MAX);
+        self.nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use interfaces::Clock;
+
+    use super::ManualClock;
+
+    /// Test that a clock only moves on when a clone of it is advanced
+    #[test]
+    fn advance_clone() {
+        let clock = ManualClock::new();
+        assert_eq!(clock.now(), Duration::ZERO);
+
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(clock.now(), Duration::ZERO);
+
+        clock.clone().advance(Duration::from_micros(200));
+        assert_eq!(clock.now(), Duration::from_micros(200));
+    }
+}
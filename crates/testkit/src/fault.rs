@@ -0,0 +1,47 @@
+//! Faults injected into the components of a [`Scenario`](crate::Scenario)
+
+use std::fmt::Display;
+
+/// Component of a [`ScriptedLogbot`](crate::ScriptedLogbot) a fault is
+/// injected into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Component {
+    /// The sensor controller, failing with [`Fault::I2c`]
+    Sensors,
+    /// The left motor, failing with [`Fault::Gpio`]
+    LeftMotor,
+    /// The right motor, failing with [`Fault::Gpio`]
+    RightMotor,
+    /// The lift, failing with [`Fault::Gpio`]
+    Lift,
+}
+
+impl Component {
+    /// The [`Fault`] of the bus the component is connected to
+    pub fn fault(&self) -> Fault {
+        match self {
+            Self::Sensors => Fault::I2c,
+            Self::LeftMotor | Self::RightMotor | Self::Lift => Fault::Gpio,
+        }
+    }
+}
+
+/// Error of a scripted component while a fault is injected into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Reading from the I2C bus failed
+    I2c,
+    /// Setting a GPIO pin failed
+    Gpio,
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::I2c => f.write_str("injected I2C fault"),
+            Self::Gpio => f.write_str("injected GPIO fault"),
+        }
+    }
+}
+
+impl core::error::Error for Fault {}
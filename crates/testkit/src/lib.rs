@@ -0,0 +1,520 @@
+//! Scripted hardware scenarios for testing logbot end-to-end
+//!
+//! A [`Scenario`] scripts what the hardware does over time: the value every
+//! sensor reads as a [`Timeline`], [`Fault`]s injected into the I2C bus of
+//! the sensors or the GPIO pins of the motors and the lift, and how long the
+//! lift takes to reach its end switches. Time is told by a [`ManualClock`],
+//! which only moves on as the scripted components take time. Built, it is a
+//! [`ScriptedLogbot`],
+//! a [`Logbot`] of scripted components, so the demo, the line follower and
+//! the hardware thread of the server run on it unchanged, and a
+//! [`Recording`] of every output commanded to the motors and the lift to
+//! assert on.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use consts::Sensors;
+//! use directions::VehicleDirection;
+//! use interfaces::{Drive, SensorRead};
+//! use testkit::{Component, Fault, Scenario, Timeline};
+//!
+//! let ms = Duration::from_millis;
+//! let scenario = Scenario::new(40)
+//!     .sensor(Sensors::Left, Timeline::new(40).set(ms(10), 200))
+//!     .inject(Component::Sensors, ms(20), None);
+//! let clock = scenario.clock();
+//! let (mut logbot, recording) = scenario.build();
+//!
+//! let forward: VehicleDirection = "forward:0.5".parse().unwrap();
+//! logbot.drive(forward).unwrap();
+//! assert_eq!(logbot.read(Sensors::Left), Ok(40));
+//!
+//! clock.advance(ms(20));
+//! assert_eq!(logbot.read(Sensors::Left), Err(Fault::I2c));
+//! assert_eq!(recording.last_direction(), Some(forward));
+//! ```
+//!
+//! Every sensor read moves the clock on by the read time and every move of
+//! the lift by its travel, so the code under test reads the same values in
+//! the same order however fast the machine running it is. Only the waits of
+//! the code under test itself still take real time, during which the clock
+//! moves on at most as fast as real time.
+
+use logbot::Logbot;
+use vehicle::Vehicle;
+
+pub mod clock;
+pub mod fault;
+pub mod recording;
+pub mod scenario;
+pub mod scripted;
+pub mod timeline;
+
+pub use clock::ManualClock;
+pub use fault::{Component, Fault};
+pub use recording::{Commanded, Output, Recording, Side};
+pub use scenario::Scenario;
+pub use timeline::Timeline;
+
+use scripted::{ScriptedLift, ScriptedMotor, ScriptedSensors};
+
+/// A [`Logbot`] built from the components of a [`Scenario`]
+pub type ScriptedLogbot =
+    Logbot<Vehicle<ScriptedMotor, ScriptedMotor>, ScriptedSensors, ScriptedLift>;
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, sync::Arc, time::Duration};
+
+    use calibration::SensorCalibration;
+    use consts::{config::BatteryConfig, Sensors};
+    use demo::{
+        cancel::CancelToken,
+        config::DemoConfig,
+        mission::{Mission, Step},
+        observer::NoObserver,
+    };
+    use directions::{SpinDirection, VehicleDirection};
+    use interfaces::Clock;
+    use logbot::error::LogbotError;
+    use logbot_control::{
+        history::Outcome,
+        jobs::{JobState, Jobs},
+        power::{Power, PowerConfig, PowerLevel},
+        telemetry::Status,
+        Command, HardwareThread,
+    };
+    use speed::Speed;
+    use tokio::sync::{broadcast, watch};
+    use tuning::{OscillationTuning, Tuning};
+
+    use crate::{
+        scenario::DEFAULT_READ_TIME, Commanded, Component, Fault, ManualClock, Recording, Scenario,
+        ScriptedLogbot, Timeline,
+    };
+
+    /// Value the sensors read on the floor
+    const FLOOR: u8 = 40;
+
+    /// Value the sensors read on the line
+    const LINE: u8 = 200;
+
+    /// [`Tuning::SLOW`] accelerating and oscillating quickly, so the
+    /// scenarios are short
+    const TUNING: Tuning = Tuning {
+        acceleration_ms: 1,
+        calibrate: OscillationTuning {
+            duration_ms: 20,
+            ..Tuning::SLOW.calibrate
+        },
+        find_edge: OscillationTuning {
+            duration_ms: 20,
+            ..Tuning::SLOW.find_edge
+        },
+        ..Tuning::SLOW
+    };
+
+    /// Milliseconds as a [`Duration`]
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Time the sensors see logbot oscillating over the line for, as while
+    /// calibrating
+    const OSCILLATION: Duration = Duration::from_millis(2);
+
+    /// Timeline of a sensor oscillated over the line for the first
+    /// [`OSCILLATION`], alternating with every other pair of reads
+    fn oscillated() -> Timeline<u8> {
+        Timeline::new(FLOOR).alternate(Duration::ZERO, OSCILLATION, 2 * DEFAULT_READ_TIME, LINE)
+    }
+
+    /// The directions driven from a time until another time
+    fn driven(recording: &Recording, from: u64, until: u64) -> Vec<VehicleDirection> {
+        recording
+            .directions()
+            .into_iter()
+            .filter(|(at, _)| *at >= ms(from) && *at < ms(until))
+            .map(|(_, direction)| direction)
+            .collect()
+    }
+
+    /// Whether logbot steers right, as when the followed left sensor drifted
+    /// onto the floor
+    fn steers_right(direction: &VehicleDirection) -> bool {
+        direction.left.signed() > direction.right.signed()
+    }
+
+    /// Test that the line follower steers back to the edge of the line from
+    /// either side and stops on the stop line
+    #[test]
+    fn follow_until_stop_line() {
+        let (mut logbot, recording) = Scenario::new(FLOOR)
+            .sensor(
+                Sensors::Left,
+                Timeline::new(FLOOR)
+                    .set(ms(50), LINE)
+                    .set(ms(100), FLOOR)
+                    .set(ms(150), LINE),
+            )
+            .sensor(Sensors::Right, Timeline::new(FLOOR).set(ms(150), LINE))
+            .build();
+        let calibration = SensorCalibration::new(LINE, FLOOR);
+
+        demo::follow_until_line::<_, Infallible>(
+            &mut logbot,
+            &calibration,
+            &calibration,
+            TUNING.follow_line_config(calibration),
+            TUNING.acceleration(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let on_floor = driven(&recording, 10, 40);
+        assert!(!on_floor.is_empty());
+        assert!(on_floor.iter().all(steers_right));
+
+        let on_line = driven(&recording, 60, 90);
+        assert!(!on_line.is_empty());
+        assert!(on_line.iter().all(|direction| !steers_right(direction)));
+
+        let (stopped, direction) = *recording.directions().last().unwrap();
+        assert_eq!(direction, VehicleDirection::STOP);
+        assert!(stopped >= ms(150));
+    }
+
+    /// Scenario of the whole [demo](demo::demo) moving one box with
+    /// [`demo_config`]
+    ///
+    /// After calibrating, both sensors see a stop line at 300ms, the left
+    /// sensor finds the line again after turning at 500ms and both sensors
+    /// see the second stop line at 700ms.
+    ///
+    /// The left sensor only sees the line after turning for three reads, so
+    /// it is back on the floor once the settled logbot follows the line.
+    fn demo_scenario() -> Scenario {
+        Scenario::new(FLOOR)
+            .sensor(
+                Sensors::Left,
+                oscillated()
+                    .set(ms(300), LINE)
+                    .set(ms(340), FLOOR)
+                    .set(ms(500), LINE)
+                    .set(ms(500) + 3 * DEFAULT_READ_TIME, FLOOR)
+                    .set(ms(700), LINE)
+                    .set(ms(750), FLOOR),
+            )
+            .sensor(
+                Sensors::Right,
+                oscillated()
+                    .set(OSCILLATION, LINE)
+                    .set(ms(340), FLOOR)
+                    .set(ms(500), LINE),
+            )
+    }
+
+    /// Settings of the demo of the [`demo_scenario`]
+    fn demo_config() -> DemoConfig {
+        DemoConfig {
+            settle_ms: 30,
+            leave_line_ms: 30,
+            find_edge_timeout_ms: 500,
+            ..DemoConfig::new(TUNING)
+        }
+    }
+
+    /// Run the demo on a [`ScriptedLogbot`]
+    fn run_demo(
+        logbot: &mut ScriptedLogbot,
+    ) -> demo::report::DemoReport<demo::DemoError<ScriptedLogbot>> {
+        demo::demo(logbot, &demo_config(), &mut NoObserver, &CancelToken::new())
+    }
+
+    /// Test that the demo calibrates, picks the box up, turns and drops it
+    /// off, ending with logbot standing still
+    #[test]
+    fn demo_moves_box() {
+        let (mut logbot, recording) = demo_scenario().build();
+
+        let report = run_demo(&mut logbot);
+        assert!(report.is_success(), "{report:?}");
+        assert!(report.recoveries.is_empty());
+
+        assert_eq!(
+            recording.lift_moves(),
+            [
+                Commanded::LiftUp(TUNING.lift_speed),
+                Commanded::LiftDown(TUNING.lift_speed)
+            ]
+        );
+
+        let directions: Vec<VehicleDirection> = recording
+            .directions()
+            .into_iter()
+            .map(|(_, direction)| direction)
+            .collect();
+        assert_eq!(
+            directions.first(),
+            Some(&SpinDirection::Left(TUNING.calibrate.speed).into())
+        );
+        assert!(directions.contains(&SpinDirection::Right(TUNING.turn_speed).into()));
+        assert!(directions.iter().any(steers_right));
+        assert_eq!(directions.last(), Some(&VehicleDirection::STOP));
+    }
+
+    /// Test that an I2C fault while following the line ends the demo with
+    /// logbot standing still and the box left behind
+    #[test]
+    fn demo_sensor_fault() {
+        let (mut logbot, recording) = demo_scenario()
+            .inject(Component::Sensors, ms(200), None)
+            .build();
+
+        let failure = run_demo(&mut logbot).failure.unwrap();
+        assert_eq!(failure.step, 4);
+        assert!(matches!(failure.error, LogbotError::Sensor(Fault::I2c)));
+
+        assert!(recording.lift_moves().is_empty());
+        assert_eq!(recording.last_direction(), Some(VehicleDirection::STOP));
+    }
+
+    /// Spawn a [`HardwareThread`] on a [`ScriptedLogbot`] with [`TUNING`]
+    fn spawn(
+        logbot: ScriptedLogbot,
+    ) -> (
+        HardwareThread<ScriptedLogbot>,
+        watch::Sender<Status>,
+        Arc<Jobs>,
+    ) {
+        spawn_powered(logbot, None)
+    }
+
+    /// Spawn a [`HardwareThread`] on a [`ScriptedLogbot`] with [`TUNING`],
+    /// monitoring the battery with `power`
+    fn spawn_powered(
+        logbot: ScriptedLogbot,
+        power: Option<Power>,
+    ) -> (
+        HardwareThread<ScriptedLogbot>,
+        watch::Sender<Status>,
+        Arc<Jobs>,
+    ) {
+        let status = watch::Sender::new(Status::idle(&logbot));
+        let jobs = Arc::new(Jobs::default());
+        let thread = HardwareThread::spawn(
+            logbot,
+            watch::Sender::new(TUNING).subscribe(),
+            broadcast::channel(1).0,
+            status.clone(),
+            watch::Sender::new(None),
+            None,
+            power,
+            Arc::clone(&jobs),
+            Duration::from_secs(1),
+        );
+        (thread, status, jobs)
+    }
+
+    /// Send a long-running command, waiting until its job finished
+    async fn run(
+        thread: &HardwareThread<ScriptedLogbot>,
+        jobs: &Jobs,
+        command: Command,
+    ) -> JobState {
+        let response = thread.send(command).await.unwrap();
+        jobs.wait(response.job.unwrap()).await.unwrap()
+    }
+
+    /// Wait until a [`ManualClock`] was moved on to a time
+    async fn reached(clock: &ManualClock, at: Duration) {
+        while clock.now() < at {
+            tokio::time::sleep(ms(1)).await;
+        }
+    }
+
+    /// Test that the hardware thread calibrates, finds the edge and follows
+    /// the line until stopped
+    #[tokio::test(flavor = "multi_thread")]
+    async fn hardware_thread_follows_line() {
+        let scenario = Scenario::new(FLOOR)
+            .sensor(Sensors::Left, oscillated())
+            .sensor(Sensors::Right, oscillated().set(OSCILLATION, LINE));
+        let clock = scenario.clock();
+        let (logbot, recording) = scenario.build();
+        let (thread, status, jobs) = spawn(logbot);
+
+        assert_eq!(
+            run(&thread, &jobs, Command::Calibrate).await,
+            JobState::Succeeded
+        );
+        assert_eq!(
+            run(&thread, &jobs, Command::FindEdge).await,
+            JobState::Succeeded
+        );
+        let found = recording.directions().len();
+
+        // Follow the line for 10ms of sensor reads
+        thread.send(Command::FollowLine).await.unwrap();
+        reached(&clock, clock.now() + ms(10)).await;
+        thread.send(Command::Stop).await.unwrap();
+
+        let followed = &recording.directions()[found..];
+        assert!(followed
+            .iter()
+            .any(|(_, direction)| steers_right(direction)));
+        assert_eq!(
+            followed.last().map(|(_, direction)| *direction),
+            Some(VehicleDirection::STOP)
+        );
+        assert_eq!(status.borrow().direction, Some(VehicleDirection::STOP));
+    }
+
+    /// Test that a GPIO fault while lifting fails the job and ends the
+    /// hardware thread
+    #[tokio::test(flavor = "multi_thread")]
+    async fn hardware_thread_lift_fault() {
+        let (logbot, recording) = Scenario::new(FLOOR)
+            .inject(Component::Lift, ms(20), None)
+            .build();
+        let (thread, _status, jobs) = spawn(logbot);
+
+        let state = run(&thread, &jobs, Command::LiftUp(Some(Speed::MAX))).await;
+        assert!(matches!(state, JobState::Failed { .. }));
+        assert_eq!(recording.lift_moves(), [Commanded::LiftUp(Speed::MAX)]);
+
+        while !thread.is_finished() {
+            tokio::time::sleep(ms(5)).await;
+        }
+        assert!(thread.send(Command::Stop).await.is_none());
+    }
+
+    /// Mission moving the lift up for [`LIFTING`], during which the hardware
+    /// thread doesn't look at requests, and then driving for a minute
+    fn lift_then_drive() -> Command {
+        let steps = vec![
+            Step::LiftUp { speed: None },
+            Step::Drive {
+                direction: "forward:0.5".parse().unwrap(),
+                duration_ms: 60_000,
+            },
+        ];
+        Command::Mission(Mission::new(steps, TUNING).unwrap())
+    }
+
+    /// Time the lift travels in the scenarios queueing requests behind a
+    /// mission
+    const LIFTING: Duration = Duration::from_millis(500);
+
+    /// Test that a stop queued behind other commands while a mission runs is
+    /// answered first, and the other commands only once logbot stopped
+    #[tokio::test(flavor = "multi_thread")]
+    async fn hardware_thread_stop_first() {
+        let (logbot, recording) = Scenario::new(FLOOR).lift_travel(LIFTING).build();
+        let (thread, _status, jobs) = spawn(logbot);
+
+        let response = thread.send(lift_then_drive()).await.unwrap();
+        while recording.lift_moves().is_empty() {
+            tokio::time::sleep(ms(1)).await;
+        }
+
+        // Queue commands that don't move logbot while it lifts, then stop
+        let standstill = || thread.send(Command::Drive(VehicleDirection::STOP));
+        let stop = async {
+            tokio::time::sleep(ms(10)).await;
+            thread.send(Command::Stop).await
+        };
+        let (first, second, third, stopped) =
+            tokio::join!(standstill(), standstill(), standstill(), stop);
+        for response in [first, second, third, stopped] {
+            assert!(response.unwrap().result.is_ok());
+        }
+        assert_eq!(
+            jobs.wait(response.job.unwrap()).await,
+            Some(JobState::Cancelled)
+        );
+
+        let accepted: Vec<_> = thread
+            .history()
+            .entries(None, None)
+            .into_iter()
+            .filter(|entry| matches!(entry.outcome, Outcome::Accepted { .. }))
+            .map(|entry| entry.command)
+            .collect();
+        assert_eq!(accepted, ["Mission", "Stop", "Drive", "Drive", "Drive"]);
+        assert_eq!(recording.last_direction(), Some(VehicleDirection::STOP));
+    }
+
+    /// Test that a stop sent while a step of a mission can't be cancelled
+    /// ends the mission before its next step, and is answered once the
+    /// mission is no longer reported
+    #[tokio::test(flavor = "multi_thread")]
+    async fn hardware_thread_stop_between_steps() {
+        let (logbot, recording) = Scenario::new(FLOOR).lift_travel(LIFTING).build();
+        let (thread, status, jobs) = spawn(logbot);
+
+        let response = thread.send(lift_then_drive()).await.unwrap();
+        while recording.lift_moves().is_empty() {
+            tokio::time::sleep(ms(1)).await;
+        }
+
+        let stopped = thread.send(Command::Stop).await.unwrap();
+        assert!(stopped.result.is_ok());
+        assert_eq!(status.borrow().mission, None);
+        assert_eq!(
+            jobs.wait(response.job.unwrap()).await,
+            Some(JobState::Cancelled)
+        );
+
+        // The lift moved up to its end, but logbot never drove
+        assert_eq!(
+            recording.lift_moves(),
+            [Commanded::LiftUp(TUNING.lift_speed)]
+        );
+        assert!(recording
+            .directions()
+            .iter()
+            .all(|(_, direction)| *direction == VehicleDirection::STOP));
+    }
+
+    /// Battery read on channel 2 of the sensors, at 0.05V per step
+    const BATTERY: BatteryConfig = BatteryConfig {
+        channel: 2,
+        volts_per_step: 0.05,
+    };
+
+    /// Test that a mission runs derated on a sagging battery and is stopped
+    /// within its step once the battery turns critical
+    #[tokio::test(flavor = "multi_thread")]
+    async fn hardware_thread_battery_critical() {
+        // 7V on the first reading derates the speed, the next one a second
+        // later reads an empty battery
+        let (logbot, recording) = Scenario::new(FLOOR)
+            .sensor(BATTERY, Timeline::new(140).set(ms(1), 0))
+            .build();
+        let power = Power::new(BATTERY, PowerConfig::default());
+        let (thread, status, jobs) = spawn_powered(logbot, Some(power));
+
+        let forward: VehicleDirection = "forward:0.5".parse().unwrap();
+        let drive = Step::Drive {
+            direction: forward,
+            duration_ms: 60_000,
+        };
+        let mission = Mission::new(vec![drive], TUNING).unwrap();
+        let response = thread.send(Command::Mission(mission)).await.unwrap();
+        let state = tokio::time::timeout(Duration::from_secs(5), jobs.wait(response.job.unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(state, Some(JobState::Cancelled));
+
+        // Driven at three quarters of its speed until stopped
+        let (_, driven) = recording.directions()[0];
+        assert!((driven.left.signed() - 0.375).abs() < 1e-9);
+        assert_eq!(recording.last_direction(), Some(VehicleDirection::STOP));
+
+        let power = status.borrow().power.unwrap();
+        assert_eq!(power.level, PowerLevel::Critical);
+        assert_eq!(power.speed_limit, Speed::MIN);
+    }
+}
@@ -0,0 +1,103 @@
+//! Outputs commanded to the components of a [`Scenario`](crate::Scenario)
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use directions::{MotorDirection, VehicleDirection};
+use speed::Speed;
+
+/// Side of logbot a motor is mounted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The left motor
+    Left,
+    /// The right motor
+    Right,
+}
+
+/// What a component was commanded to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Commanded {
+    /// A motor was driven, [`MotorDirection::Stop`] when stopped
+    Motor(Side, MotorDirection),
+    /// The lift started moving up
+    LiftUp(Speed),
+    /// The lift started moving down
+    LiftDown(Speed),
+}
+
+/// An output commanded at a time of the [`Scenario`](crate::Scenario)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Output {
+    /// Time of the [`ManualClock`](crate::ManualClock) of the scenario
+    pub at: Duration,
+    /// What was commanded
+    pub commanded: Commanded,
+}
+
+/// Every [`Output`] of a [`ScriptedLogbot`](crate::ScriptedLogbot), shared
+/// with its components
+///
+/// Only outputs that reached a component are recorded, commands failing with
+/// an injected [`Fault`](crate::Fault) are not.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    /// Outputs in the order they were commanded
+    outputs: Arc<Mutex<Vec<Output>>>,
+}
+
+impl Recording {
+    /// Record an output
+    pub(crate) fn record(&self, at: Duration, commanded: Commanded) {
+        self.lock().push(Output { at, commanded });
+    }
+
+    /// Every output, in the order they were commanded
+    pub fn outputs(&self) -> Vec<Output> {
+        self.lock().clone()
+    }
+
+    /// The [`VehicleDirection`]s logbot was driven in, with the time the
+    /// right motor was driven as the second half of each
+    ///
+    /// The vehicle drives the left motor before the right one, so every
+    /// right motor output completes a direction.
+    pub fn directions(&self) -> Vec<(Duration, VehicleDirection)> {
+        let mut left = MotorDirection::Stop;
+        let mut directions = Vec::new();
+        for output in self.lock().iter() {
+            match output.commanded {
+                Commanded::Motor(Side::Left, direction) => left = direction,
+                Commanded::Motor(Side::Right, right) => {
+                    directions.push((output.at, VehicleDirection::new(left, right)));
+                }
+                Commanded::LiftUp(_) | Commanded::LiftDown(_) => {}
+            };
+        }
+        directions
+    }
+
+    /// The last [`VehicleDirection`] logbot was driven in, none if it was
+    /// never driven
+    pub fn last_direction(&self) -> Option<VehicleDirection> {
+        self.directions().last().map(|(_, direction)| *direction)
+    }
+
+    /// The moves of the lift, in the order they were commanded
+    pub fn lift_moves(&self) -> Vec<Commanded> {
+        self.lock()
+            .iter()
+            .map(|output| output.commanded)
+            .filter(|commanded| !matches!(commanded, Commanded::Motor(..)))
+            .collect()
+    }
+
+    /// Lock the outputs, which are still consistent if a component panicked
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Output>> {
+        self.outputs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,136 @@
+//! Script of the hardware logbot runs on during a test
+
+use std::{collections::HashMap, time::Duration};
+
+use interfaces::ToSensorChannel;
+use logbot::Logbot;
+use vehicle::Vehicle;
+
+use crate::{
+    recording::{Recording, Side},
+    scripted::{ScriptedLift, ScriptedMotor, ScriptedSensors},
+    Component, ManualClock, ScriptedLogbot, Timeline,
+};
+
+/// Default time the lift travels between its end switches
+pub const DEFAULT_LIFT_TRAVEL: Duration = Duration::from_millis(50);
+
+/// Default time a sensor read takes
+pub const DEFAULT_READ_TIME: Duration = Duration::from_micros(200);
+
+/// Script of the sensor values, the injected faults and the timing of the
+/// lift, built into a [`ScriptedLogbot`]
+///
+/// All times are read from the [`ManualClock`] of the scenario, which starts
+/// at zero and is moved on by the scripted components: every sensor read
+/// takes the [read time](Self::read_time) and every move of the lift its
+/// [travel](Self::lift_travel). Tests may also [advance](ManualClock::advance)
+/// it themselves, e.g. to inject a fault while logbot stands still.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    /// Timelines of the scripted sensor channels
+    sensors: HashMap<u8, Timeline<u8>>,
+    /// Value of all other sensor channels
+    fallback: u8,
+    /// Time a sensor read takes
+    read_time: Duration,
+    /// Whether a fault is injected into a component
+    faults: HashMap<Component, Timeline<bool>>,
+    /// Time the lift travels between its end switches
+    lift_travel: Duration,
+    /// Clock all times are read from
+    clock: ManualClock,
+}
+
+impl Scenario {
+    /// Create a [`Scenario`] where every sensor reads `fallback` and
+    /// nothing fails
+    pub fn new(fallback: u8) -> Self {
+        Self {
+            sensors: HashMap::new(),
+            fallback,
+            read_time: DEFAULT_READ_TIME,
+            faults: HashMap::new(),
+            lift_travel: DEFAULT_LIFT_TRAVEL,
+            clock: ManualClock::new(),
+        }
+    }
+
+    /// The [`ManualClock`] of the scenario, shared with the built components
+    pub fn clock(&self) -> ManualClock {
+        self.clock.clone()
+    }
+
+    /// Read the values of a [`Timeline`] from a sensor
+    pub fn sensor(mut self, sensor: impl ToSensorChannel, timeline: Timeline<u8>) -> Self {
+        self.sensors.insert(sensor.to_channel(), timeline);
+        self
+    }
+
+    /// Take a time for every sensor read, like a transfer on the I2C bus
+    ///
+    /// Besides moving the [`ManualClock`] on, the read sleeps for the time, so
+    /// it should stay short.
+    pub fn read_time(mut self, read_time: Duration) -> Self {
+        self.read_time = read_time;
+        self
+    }
+
+    /// Inject the [`Fault`](crate::Fault) of a [`Component`] from a time on,
+    /// until another time if given
+    pub fn inject(mut self, component: Component, from: Duration, until: Option<Duration>) -> Self {
+        let faults = self.faults.remove(&component).unwrap_or_default();
+        let faults = faults.set(from, true);
+        let faults = match until {
+            Some(until) => faults.set(until, false),
+            None => faults,
+        };
+        self.faults.insert(component, faults);
+        self
+    }
+
+    /// Close the end switch of the lift a time after it starts moving
+    pub fn lift_travel(mut self, travel: Duration) -> Self {
+        self.lift_travel = travel;
+        self
+    }
+
+    /// Build the [`ScriptedLogbot`] of the scenario and the [`Recording`] of
+    /// its outputs
+    pub fn build(mut self) -> (ScriptedLogbot, Recording) {
+        let recording = Recording::default();
+        let clock = self.clock;
+        let mut faults = |component| self.faults.remove(&component).unwrap_or_default();
+
+        let logbot = Logbot::builder()
+            .vehicle(Vehicle::new(
+                ScriptedMotor::new(
+                    Side::Left,
+                    faults(Component::LeftMotor),
+                    clock.clone(),
+                    recording.clone(),
+                ),
+                ScriptedMotor::new(
+                    Side::Right,
+                    faults(Component::RightMotor),
+                    clock.clone(),
+                    recording.clone(),
+                ),
+            ))
+            .sensors(ScriptedSensors::new(
+                self.sensors,
+                self.fallback,
+                self.read_time,
+                faults(Component::Sensors),
+                clock.clone(),
+            ))
+            .lift(ScriptedLift::new(
+                self.lift_travel,
+                faults(Component::Lift),
+                clock.clone(),
+                recording.clone(),
+            ))
+            .build();
+        (logbot, recording)
+    }
+}
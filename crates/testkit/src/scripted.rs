@@ -0,0 +1,237 @@
+//! Components following the script of a [`Scenario`](crate::Scenario)
+//!
+//! Every component tells the time by the [`ManualClock`] of the scenario,
+//! fails with a [`Fault`] while one is injected into it and records what it
+//! is commanded to do in the shared [`Recording`].
+
+use std::{collections::HashMap, time::Duration};
+
+use directions::MotorDirection;
+use interfaces::{Clock, Drive, Lift, SensorRead, ToSensorChannel};
+use speed::Speed;
+
+use crate::{
+    recording::{Commanded, Recording, Side},
+    Fault, ManualClock, Timeline,
+};
+
+/// Step the lift moves the [`ManualClock`] on by, and sleeps for, checking for
+/// faults after each
+const LIFT_STEP: Duration = Duration::from_millis(1);
+
+/// Motor recording every [`MotorDirection`] it is driven in
+#[derive(Debug)]
+pub struct ScriptedMotor {
+    /// Side of logbot the motor is mounted on
+    side: Side,
+    /// The direction driven in, [None] when stopped
+    state: Option<MotorDirection>,
+    /// Whether a [`Fault::Gpio`] is injected
+    faults: Timeline<bool>,
+    /// Clock of the scenario
+    clock: ManualClock,
+    /// Recording of the outputs
+    recording: Recording,
+}
+
+impl ScriptedMotor {
+    /// Create a stopped [`ScriptedMotor`]
+    pub(crate) fn new(
+        side: Side,
+        faults: Timeline<bool>,
+        clock: ManualClock,
+        recording: Recording,
+    ) -> Self {
+        Self {
+            side,
+            state: None,
+            faults,
+            clock,
+            recording,
+        }
+    }
+
+    /// Drive in a direction, recording it unless a fault is injected
+    fn command(&mut self, direction: MotorDirection) -> Result<Option<MotorDirection>, Fault> {
+        let now = self.clock.now();
+        if self.faults.value_at(now) {
+            return Err(Fault::Gpio);
+        };
+        self.recording
+            .record(now, Commanded::Motor(self.side, direction));
+        Ok(match direction {
+            MotorDirection::Stop => self.state.take(),
+            direction => self.state.replace(direction),
+        })
+    }
+}
+
+impl Drive for ScriptedMotor {
+    type Direction = MotorDirection;
+    type Error = Fault;
+
+    fn drive(
+        &mut self,
+        direction: Self::Direction,
+    ) -> Result<Option<Self::Direction>, Self::Error> {
+        self.command(direction)
+    }
+
+    fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
+        self.command(MotorDirection::Stop)
+    }
+}
+
+/// Sensor controller reading the [`Timeline`] of every channel
+///
+/// Like a transfer on the I2C bus, every read takes some time: it returns
+/// the values at the start of the read and moves the [`ManualClock`] on by
+/// the read time. It also sleeps for the read time, which keeps control
+/// loops from spinning faster than they would on logbot.
+#[derive(Debug)]
+pub struct ScriptedSensors {
+    /// Timelines of the channels that were scripted
+    timelines: HashMap<u8, Timeline<u8>>,
+    /// Value of all other channels
+    fallback: u8,
+    /// Time a single read takes
+    read_time: Duration,
+    /// Whether a [`Fault::I2c`] is injected
+    faults: Timeline<bool>,
+    /// Clock of the scenario
+    clock: ManualClock,
+}
+
+impl ScriptedSensors {
+    /// Create [`ScriptedSensors`] reading `fallback` on unscripted channels
+    pub(crate) fn new(
+        timelines: HashMap<u8, Timeline<u8>>,
+        fallback: u8,
+        read_time: Duration,
+        faults: Timeline<bool>,
+        clock: ManualClock,
+    ) -> Self {
+        Self {
+            timelines,
+            fallback,
+            read_time,
+            faults,
+            clock,
+        }
+    }
+}
+
+impl SensorRead for ScriptedSensors {
+    type Output = u8;
+    type Error = Fault;
+
+    fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
+        std::thread::sleep(self.read_time);
+        let now = self.clock.now();
+        self.clock.advance(self.read_time);
+        if self.faults.value_at(now) {
+            return Err(Fault::I2c);
+        };
+        Ok(self
+            .timelines
+            .get(&sensor.to_channel())
+            .map_or(self.fallback, |timeline| timeline.value_at(now)))
+    }
+}
+
+/// Lift whose end switches close a fixed time after it starts moving
+///
+/// Moving returns once the [`ManualClock`] was moved on by the travel time.
+/// Like on logbot, it also takes the travel time, during which the code under
+/// test can't look at anything else. A fault injected while moving leaves the
+/// lift between its end switches.
+#[derive(Debug)]
+pub struct ScriptedLift {
+    /// Whether the up end switch is closed
+    up: bool,
+    /// Whether the down end switch is closed
+    down: bool,
+    /// Time from leaving an end switch until reaching the other
+    travel: Duration,
+    /// Whether a [`Fault::Gpio`] is injected
+    faults: Timeline<bool>,
+    /// Clock of the scenario
+    clock: ManualClock,
+    /// Recording of the outputs
+    recording: Recording,
+}
+
+impl ScriptedLift {
+    /// Create a [`ScriptedLift`] in the down position
+    pub(crate) fn new(
+        travel: Duration,
+        faults: Timeline<bool>,
+        clock: ManualClock,
+        recording: Recording,
+    ) -> Self {
+        Self {
+            up: false,
+            down: true,
+            travel,
+            faults,
+            clock,
+            recording,
+        }
+    }
+
+    /// Whether a fault is injected right now
+    fn is_failing(&self) -> bool {
+        self.faults.value_at(self.clock.now())
+    }
+
+    /// Move for the travel time, failing once a fault is injected before the
+    /// end switch is reached
+    fn travel(&mut self, commanded: Commanded) -> Result<(), Fault> {
+        if self.is_failing() {
+            return Err(Fault::Gpio);
+        };
+        self.recording.record(self.clock.now(), commanded);
+        self.up = false;
+        self.down = false;
+
+        let mut moved = Duration::ZERO;
+        while moved < self.travel {
+            let step = LIFT_STEP.min(self.travel - moved);
+            std::thread::sleep(step);
+            self.clock.advance(step);
+            moved += step;
+            if self.is_failing() {
+                return Err(Fault::Gpio);
+            };
+        }
+        Ok(())
+    }
+}
+
+impl Lift for ScriptedLift {
+    type Error = Fault;
+
+    fn up(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        if !self.up {
+            self.travel(Commanded::LiftUp(speed))?;
+            self.up = true;
+        };
+        Ok(())
+    }
+
+    fn down(&mut self, speed: Speed) -> Result<(), Self::Error> {
+        if !self.down {
+            self.travel(Commanded::LiftDown(speed))?;
+            self.down = true;
+        };
+        Ok(())
+    }
+
+    fn is_up(&self) -> bool {
+        self.up
+    }
+
+    fn is_down(&self) -> bool {
+        self.down
+    }
+}
@@ -0,0 +1,117 @@
+//! Values of a scripted component changing over time
+
+use std::time::Duration;
+
+/// A value changing at given times of the [`ManualClock`](crate::ManualClock)
+/// of a [`Scenario`](crate::Scenario)
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use testkit::Timeline;
+///
+/// let ms = Duration::from_millis;
+/// let timeline = Timeline::new(40).set(ms(100), 200).set(ms(150), 40);
+///
+/// assert_eq!(timeline.value_at(ms(0)), 40);
+/// assert_eq!(timeline.value_at(ms(120)), 200);
+/// assert_eq!(timeline.value_at(ms(150)), 40);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline<T> {
+    /// Value before the first change
+    initial: T,
+    /// Changes, ordered by the time they happen at
+    changes: Vec<(Duration, T)>,
+}
+
+impl<T: Copy> Timeline<T> {
+    /// Create a [`Timeline`] holding a value until it is changed
+    pub fn new(initial: T) -> Self {
+        Self {
+            initial,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Change to a value at a time, a later change at the same time wins
+    pub fn set(mut self, at: Duration, value: T) -> Self {
+        let index = self.changes.partition_point(|(time, _)| *time <= at);
+        self.changes.insert(index, (at, value));
+        self
+    }
+
+    /// Alternate between `other` and the value held at `from` every
+    /// `period`, starting with `other` at `from` and returning to the held
+    /// value at `until`
+    ///
+    /// Changes within the window are replaced. Alternating over the line
+    /// and the floor is what a sensor sees while logbot oscillates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `period` is zero.
+    pub fn alternate(
+        mut self,
+        from: Duration,
+        until: Duration,
+        period: Duration,
+        other: T,
+    ) -> Self {
+        assert!(
+            !period.is_zero(),
+            "the period of alternating must not be zero"
+        );
+        let held = self.value_at(from);
+        self.changes
+            .retain(|(time, _)| *time < from || *time >= until);
+
+        let mut at = from;
+        let mut values = [other, held].into_iter().cycle();
+        while at < until {
+            self = self.set(at, values.next().unwrap_or(held));
+            at += period;
+        }
+        self.set(until, held)
+    }
+
+    /// The value at a time of the [`Scenario`](crate::Scenario)
+    pub fn value_at(&self, elapsed: Duration) -> T {
+        let index = self.changes.partition_point(|(time, _)| *time <= elapsed);
+        match index {
+            0 => self.initial,
+            index => self.changes[index - 1].1,
+        }
+    }
+}
+
+impl<T: Copy + Default> Default for Timeline<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Timeline;
+
+    /// Test that alternating toggles within the window and returns to the
+    /// held value, replacing changes within the window
+    #[test]
+    fn alternate() {
+        let ms = Duration::from_millis;
+        let timeline = Timeline::new(false)
+            .set(ms(25), true)
+            .set(ms(40), true)
+            .alternate(ms(10), ms(30), ms(5), true);
+
+        let values: Vec<bool> = (0..8).map(|step| timeline.value_at(ms(step * 5))).collect();
+        assert_eq!(
+            values,
+            [false, false, true, false, true, false, false, false]
+        );
+        assert!(timeline.value_at(ms(40)));
+    }
+}
@@ -0,0 +1,477 @@
+//! Shared tuning values for driving behaviours
+//!
+//! Speeds, line following gains and oscillation settings are bundled into a
+//! [`Tuning`]. Named [presets](Preset) provide tested values, which can be
+//! overridden at runtime using a TOML file.
+
+use std::{fmt::Display, num::NonZero, path::Path, str::FromStr, time::Duration};
+
+use calibration::SensorCalibration;
+use directions::SpinDirection;
+use line::FollowLineConfig;
+use oscillate::Oscillate;
+use serde::{Deserialize, Serialize};
+use speed::Speed;
+
+/// Gains used when following a line
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FollowTuning {
+    /// Correction based on current error
+    pub proportional: f64,
+    /// Correction based on ratio of current and previous error
+    pub derivative: f64,
+    /// Correction based on all previous errors
+    pub integral: Option<f64>,
+}
+
+/// Settings of an [`Oscillate`], which always starts spinning left
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OscillationTuning {
+    /// Duration of the first spin in milliseconds
+    pub duration_ms: u64,
+    /// [`Speed`] of the spin
+    pub speed: Speed,
+    /// Multiplier of the duration after each change of direction
+    pub multiplier: NonZero<u32>,
+}
+
+impl OscillationTuning {
+    /// Duration of the first spin
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+
+    /// Create the [`Oscillate`] described by these settings
+    pub fn oscillate(&self) -> Oscillate {
+        Oscillate::new(
+            self.duration(),
+            SpinDirection::Left(self.speed),
+            self.multiplier,
+        )
+    }
+}
+
+/// Steering of arc turns when driving manually
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SteeringTuning {
+    /// Sharpness of a turn when starting it, from 0.0 (straight) to 1.0
+    /// (pivoting on the inner wheel)
+    pub sharpness: f64,
+    /// Increase of the sharpness per second while a turn is held
+    pub sharpening: f64,
+}
+
+impl SteeringTuning {
+    /// Sharpness of a turn that has been held for a duration
+    pub fn sharpness_after(&self, held: Duration) -> f64 {
+        (self.sharpness + self.sharpening * held.as_secs_f64()).clamp(0.0, 1.0)
+    }
+}
+
+/// Avoiding obstacles seen by a range finder while following a line
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AvoidTuning {
+    /// Distance in meters from which logbot slows down for an obstacle
+    pub slow_distance_m: f64,
+    /// Distance in meters at which logbot stops in front of an obstacle
+    pub stop_distance_m: f64,
+    /// Time in milliseconds an obstacle blocks logbot before it is driven
+    /// around, never when none
+    pub detour_after_ms: Option<u64>,
+    /// Distance in meters driven away from the line to pass the obstacle
+    pub detour_width_m: f64,
+    /// Distance in meters driven alongside the line past the obstacle
+    pub detour_length_m: f64,
+    /// Farthest distance in meters driven back while looking for the line
+    pub search_distance_m: f64,
+    /// Distance in meters driven on once the line is seen, bringing the
+    /// wheels onto it
+    pub cross_distance_m: f64,
+}
+
+impl AvoidTuning {
+    /// Values fitting the size of logbot
+    pub const DEFAULT: Self = Self {
+        slow_distance_m: 0.4,
+        stop_distance_m: 0.15,
+        detour_after_ms: Some(2000),
+        detour_width_m: 0.25,
+        detour_length_m: 0.4,
+        search_distance_m: 0.5,
+        cross_distance_m: 0.08,
+    };
+
+    /// Time an obstacle blocks logbot before it is driven around
+    pub fn detour_after(&self) -> Option<Duration> {
+        self.detour_after_ms.map(Duration::from_millis)
+    }
+}
+
+impl Default for AvoidTuning {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Tuning values shared by the `cli`, `server` and `demo`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tuning {
+    /// [`Speed`] when driving and following a line
+    pub speed: Speed,
+    /// Time in milliseconds to accelerate to full speed
+    pub acceleration_ms: u64,
+    /// Gains used when following a line
+    pub follow: FollowTuning,
+    /// Oscillation used for calibrating sensors
+    pub calibrate: OscillationTuning,
+    /// Oscillation used for finding the edge of a line
+    pub find_edge: OscillationTuning,
+    /// [`Speed`] when spinning in-place to turn around on a line
+    pub turn_speed: Speed,
+    /// [`Speed`] of the lift
+    pub lift_speed: Speed,
+    /// Degrees per second turned when spinning in-place at full speed
+    pub spin_rate: f64,
+    /// Meters per second driven straight at full speed
+    pub drive_rate: f64,
+    /// Steering of arc turns when driving manually
+    pub steering: SteeringTuning,
+    /// Avoiding obstacles while following a line
+    pub avoid: AvoidTuning,
+}
+
+impl Tuning {
+    /// Careful values, used by the `server`
+    pub const SLOW: Self = Self {
+        speed: Speed::new_const(0.1),
+        acceleration_ms: 2000,
+        follow: FollowTuning {
+            proportional: 0.001,
+            derivative: 0.0005,
+            integral: None,
+        },
+        calibrate: OscillationTuning {
+            duration_ms: 1000,
+            speed: Speed::new_const(0.05),
+            multiplier: NonZero::new(2).unwrap(),
+        },
+        find_edge: OscillationTuning {
+            duration_ms: 2000,
+            speed: Speed::new_const(0.1),
+            multiplier: NonZero::new(2).unwrap(),
+        },
+        turn_speed: Speed::new_const(0.08),
+        lift_speed: Speed::HALF,
+        spin_rate: 360.0,
+        drive_rate: 1.0,
+        steering: SteeringTuning {
+            sharpness: 0.5,
+            sharpening: 0.5,
+        },
+        avoid: AvoidTuning::DEFAULT,
+    };
+
+    /// Values tuned for the course demo
+    pub const DEMO: Self = Self {
+        calibrate: OscillationTuning {
+            duration_ms: 500,
+            speed: Speed::new_const(0.08),
+            multiplier: NonZero::new(2).unwrap(),
+        },
+        ..Self::SLOW
+    };
+
+    /// Fast values, used by the `cli`
+    pub const RACE: Self = Self {
+        speed: Speed::HALF,
+        acceleration_ms: 500,
+        follow: FollowTuning {
+            proportional: 0.6,
+            derivative: 0.3,
+            integral: None,
+        },
+        calibrate: OscillationTuning {
+            duration_ms: 1000,
+            speed: Speed::HALF,
+            multiplier: NonZero::new(2).unwrap(),
+        },
+        find_edge: OscillationTuning {
+            duration_ms: 1000,
+            speed: Speed::new_const(0.2),
+            multiplier: NonZero::new(2).unwrap(),
+        },
+        turn_speed: Speed::new_const(0.2),
+        lift_speed: Speed::HALF,
+        spin_rate: 360.0,
+        drive_rate: 1.0,
+        steering: SteeringTuning {
+            sharpness: 0.5,
+            sharpening: 0.5,
+        },
+        avoid: AvoidTuning::DEFAULT,
+    };
+
+    /// Parse a [`Tuning`] from a TOML string
+    ///
+    /// The values are layered over the given [`Preset`], so any missing
+    /// value falls back to the preset.
+    pub fn from_toml(preset: Preset, value: &str) -> Result<Self, TuningError> {
+        let overlay: toml::Table = toml::from_str(value).map_err(TuningError::Toml)?;
+        preset.tuning().overlay(overlay)
+    }
+
+    /// Override values of this [`Tuning`], keeping any value missing from
+    /// the overlay
+    pub fn overlay(&self, overlay: toml::Table) -> Result<Self, TuningError> {
+        let mut merged = toml::Value::try_from(self).expect("Tuning is always serializable");
+        merge(&mut merged, toml::Value::Table(overlay));
+        merged.try_into().map_err(TuningError::Toml)
+    }
+
+    /// Read a [`Tuning`] from a TOML file, layered over the given [`Preset`]
+    pub fn from_file(preset: Preset, path: impl AsRef<Path>) -> Result<Self, TuningError> {
+        let value = std::fs::read_to_string(path).map_err(TuningError::Io)?;
+        Self::from_toml(preset, &value)
+    }
+
+    /// Duration to accelerate to full speed
+    pub fn acceleration(&self) -> Duration {
+        Duration::from_millis(self.acceleration_ms)
+    }
+
+    /// Time needed to spin in-place by `angle` degrees at a given [`Speed`]
+    ///
+    /// Assumes the spin rate grows linearly with the [`Speed`], returns
+    /// [`None`] if logbot would never complete the turn.
+    pub fn spin_duration(&self, angle: f64, speed: Speed) -> Option<Duration> {
+        Duration::try_from_secs_f64(angle.abs() / (self.spin_rate * speed.value())).ok()
+    }
+
+    /// Time needed to drive straight by `distance` meters at a given [`Speed`]
+    ///
+    /// Assumes the driven rate grows linearly with the [`Speed`], returns
+    /// [`None`] if logbot would never arrive.
+    pub fn drive_duration(&self, distance: f64, speed: Speed) -> Option<Duration> {
+        Duration::try_from_secs_f64(distance.abs() / (self.drive_rate * speed.value())).ok()
+    }
+
+    /// Create a [`FollowLineConfig`] for following a calibrated line
+    pub fn follow_line_config(&self, calibration: SensorCalibration) -> FollowLineConfig {
+        FollowLineConfig {
+            default_speed: self.speed,
+            proportional: self.follow.proportional,
+            derivative: self.follow.derivative,
+            integral: self.follow.integral,
+            calibration,
+            reset_integral_on_target: true,
+        }
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Preset::default().tuning()
+    }
+}
+
+/// Named [`Tuning`] presets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Preset {
+    /// [`Tuning::SLOW`]
+    #[default]
+    Slow,
+    /// [`Tuning::DEMO`]
+    Demo,
+    /// [`Tuning::RACE`]
+    Race,
+}
+
+impl Preset {
+    /// The [`Tuning`] of the preset
+    pub fn tuning(self) -> Tuning {
+        match self {
+            Self::Slow => Tuning::SLOW,
+            Self::Demo => Tuning::DEMO,
+            Self::Race => Tuning::RACE,
+        }
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Slow => write!(f, "slow"),
+            Self::Demo => write!(f, "demo"),
+            Self::Race => write!(f, "race"),
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = TuningError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "slow" => Ok(Self::Slow),
+            "demo" => Ok(Self::Demo),
+            "race" => Ok(Self::Race),
+            name => Err(TuningError::UnknownPreset(name.to_string())),
+        }
+    }
+}
+
+/// Recursively merge an overlay into a base [`toml::Value`]
+///
+/// Tables are merged key by key, any other value in the overlay replaces the base
+fn merge(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Errors that occur while loading a [`Tuning`]
+#[derive(Debug)]
+pub enum TuningError {
+    /// The tuning file could not be read
+    Io(std::io::Error),
+    /// The tuning file is not valid
+    Toml(toml::de::Error),
+    /// The preset with the given name does not exist
+    UnknownPreset(String),
+}
+
+impl Display for TuningError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Toml(e) => e.fmt(f),
+            Self::UnknownPreset(name) => write!(f, "unknown tuning preset `{name}`"),
+        }
+    }
+}
+
+impl core::error::Error for TuningError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Toml(e) => Some(e),
+            Self::UnknownPreset(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use speed::Speed;
+
+    use super::{Preset, SteeringTuning, Tuning};
+
+    /// Test that an empty file results in the preset
+    #[test]
+    fn empty_is_preset() {
+        assert_eq!(Tuning::from_toml(Preset::Race, "").unwrap(), Tuning::RACE);
+    }
+
+    /// Test that values in the file override the preset
+    #[test]
+    fn file_overrides_preset() {
+        let tuning = Tuning::from_toml(
+            Preset::Demo,
+            r#"
+            speed = 0.2
+
+            [follow]
+            integral = 0.0001
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(tuning.speed, Speed::new_const(0.2));
+        assert_eq!(tuning.follow.integral, Some(0.0001));
+        assert_eq!(tuning.follow.proportional, Tuning::DEMO.follow.proportional);
+        assert_eq!(tuning.calibrate, Tuning::DEMO.calibrate);
+    }
+
+    /// Test that an overlay keeps earlier changes of a tuning
+    #[test]
+    fn overlay_keeps_changes() {
+        let tuning = Tuning::from_toml(Preset::Demo, "acceleration_ms = 100").unwrap();
+        let overlay = toml::from_str("follow = { derivative = 0.5 }").unwrap();
+        let tuning = tuning.overlay(overlay).unwrap();
+
+        assert_eq!(tuning.acceleration_ms, 100);
+        assert_eq!(tuning.follow.derivative, 0.5);
+        assert_eq!(tuning.speed, Tuning::DEMO.speed);
+
+        let overlay = toml::from_str("pid = 1.0").unwrap();
+        assert!(tuning.overlay(overlay).is_err());
+    }
+
+    /// Test that out of bounds speeds are rejected
+    #[test]
+    fn invalid_speed_rejected() {
+        assert!(Tuning::from_toml(Preset::Slow, "speed = 1.5").is_err());
+    }
+
+    /// Test that presets can be parsed from their names
+    #[test]
+    fn preset_round_trip() {
+        for preset in [Preset::Slow, Preset::Demo, Preset::Race] {
+            assert_eq!(preset.to_string().parse::<Preset>().unwrap(), preset);
+        }
+        assert!("fast".parse::<Preset>().is_err());
+    }
+
+    /// Test that turns sharpen while held, up to pivoting on the inner wheel
+    #[test]
+    fn steering_sharpens() {
+        let steering = SteeringTuning {
+            sharpness: 0.5,
+            sharpening: 0.25,
+        };
+
+        assert_eq!(steering.sharpness_after(std::time::Duration::ZERO), 0.5);
+        assert_eq!(
+            steering.sharpness_after(std::time::Duration::from_secs(1)),
+            0.75
+        );
+        assert_eq!(
+            steering.sharpness_after(std::time::Duration::from_secs(10)),
+            1.0
+        );
+    }
+
+    /// Test that spinning faster takes less time and standing still never completes
+    #[test]
+    fn spin_duration() {
+        let tuning = Tuning {
+            spin_rate: 180.0,
+            ..Tuning::SLOW
+        };
+
+        assert_eq!(
+            tuning.spin_duration(90.0, Speed::MAX),
+            Some(std::time::Duration::from_millis(500))
+        );
+        assert_eq!(
+            tuning.spin_duration(-90.0, Speed::HALF),
+            Some(std::time::Duration::from_secs(1))
+        );
+        assert_eq!(tuning.spin_duration(90.0, Speed::MIN), None);
+    }
+}
@@ -1,7 +1,7 @@
 //! Abstraction for a two wheeled [`Vehicle`]
 
-use directions::{MotorDirection, SpinDirection, VehicleDirection};
-use interfaces::{Drive, Spin};
+use directions::{MotorDirection, SpinDirection, Stop, VehicleDirection};
+use interfaces::{Drive, DriveState, Spin};
 
 mod error;
 pub use error::VehicleError;
@@ -17,8 +17,8 @@ where
     left: LD,
     /// Right hardware component that implements [`Drive`]
     right: RD,
-    /// The current [`VehicleDirection`]
-    state: Option<VehicleDirection>,
+    /// The current [`VehicleDirection`], [`VehicleDirection::STOP`] when stopped
+    state: VehicleDirection,
 }
 
 impl<LD, RD> Drive for Vehicle<LD, RD>
@@ -32,6 +32,8 @@ where
     /// [`Drive`] the [`Vehicle`] in a given [`VehicleDirection`].
     /// This instructs the left and right driveables to move into their
     /// corresponding [`MotorDirection`]'s
+    ///
+    /// Returns the previous [`VehicleDirection`], or [None] if the [`Vehicle`] was stopped
     fn drive(
         &mut self,
         direction: Self::Direction,
@@ -42,14 +44,16 @@ where
         self.right
             .drive(direction.right)
             .map_err(VehicleError::Right)?;
-        Ok(self.state.replace(direction))
+        let previous = std::mem::replace(&mut self.state, direction);
+        Ok(Some(previous).filter(|previous| !previous.is_stop()))
     }
 
     /// Stop the [`Vehicle`] by stopping the underlying driveables
     fn stop(&mut self) -> Result<Option<Self::Direction>, Self::Error> {
         self.left.stop().map_err(VehicleError::Left)?;
         self.right.stop().map_err(VehicleError::Right)?;
-        Ok(self.state.take())
+        let previous = std::mem::take(&mut self.state);
+        Ok(Some(previous).filter(|previous| !previous.is_stop()))
     }
 }
 
@@ -68,7 +72,17 @@ where
     }
 
     /// Get the current state of the [`Vehicle`]
-    pub fn state(&self) -> Option<VehicleDirection> {
+    pub fn state(&self) -> VehicleDirection {
+        self.state
+    }
+}
+
+impl<LD, RD> DriveState for Vehicle<LD, RD>
+where
+    LD: Drive<Direction = MotorDirection>,
+    RD: Drive<Direction = MotorDirection>,
+{
+    fn state(&self) -> VehicleDirection {
         self.state
     }
 }
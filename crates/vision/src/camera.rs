@@ -0,0 +1,98 @@
+//! Capturing frames from the camera of a Raspberry Pi
+//!
+//! The camera is read through `rpicam-vid` of the Raspberry Pi OS, which
+//! streams raw YUV420 frames to its standard output. Only the brightness
+//! plane of every frame is kept.
+
+use std::{
+    io::{self, Read},
+    process::{Child, ChildStdout, Command, Stdio},
+};
+
+use crate::{Frame, FrameSource};
+
+/// Size and rate of the captured frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CameraConfig {
+    /// Width in pixels, a multiple of 2
+    pub width: usize,
+    /// Height in pixels, a multiple of 2
+    pub height: usize,
+    /// Frames captured per second
+    pub framerate: u32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            width: 160,
+            height: 120,
+            framerate: 30,
+        }
+    }
+}
+
+/// Camera of a Raspberry Pi streaming frames through `rpicam-vid`
+///
+/// The stream stops when the camera is dropped.
+#[derive(Debug)]
+pub struct PiCamera {
+    /// The running `rpicam-vid`
+    child: Child,
+    /// Its standard output streaming the frames
+    stdout: ChildStdout,
+    /// Size and rate of the frames
+    config: CameraConfig,
+    /// The last frame read, including its color planes
+    buffer: Vec<u8>,
+}
+
+impl PiCamera {
+    /// Start streaming frames from the camera
+    pub fn open(config: CameraConfig) -> io::Result<Self> {
+        let mut child = Command::new("rpicam-vid")
+            .args(["--timeout", "0", "--nopreview", "--codec", "yuv420"])
+            .args(["--width", &config.width.to_string()])
+            .args(["--height", &config.height.to_string()])
+            .args(["--framerate", &config.framerate.to_string()])
+            .args(["--output", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("rpicam-vid has no output"))?;
+        Ok(Self {
+            child,
+            stdout,
+            config,
+            // The color planes are a quarter of the size each
+            buffer: vec![0; config.width * config.height * 3 / 2],
+        })
+    }
+
+    /// Size and rate of the frames
+    pub fn config(&self) -> CameraConfig {
+        self.config
+    }
+}
+
+impl FrameSource for PiCamera {
+    type Error = io::Error;
+
+    fn capture(&mut self) -> Result<Frame, Self::Error> {
+        self.stdout.read_exact(&mut self.buffer)?;
+        let (width, height) = (self.config.width, self.config.height);
+        Frame::new(width, height, self.buffer[..width * height].to_vec())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty frame size"))
+    }
+}
+
+impl Drop for PiCamera {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,217 @@
+//! Finding the line in a [`Frame`]
+
+use crate::Frame;
+
+/// How the line is found in a [`Frame`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VisionConfig {
+    /// Brightness separating the line from the floor, or [None] to use the
+    /// middle between the darkest and the brightest pixel of each frame
+    pub threshold: Option<u8>,
+    /// Whether the line is darker than the floor
+    pub dark_line: bool,
+    /// Number of evenly spaced rows the line is looked for in
+    pub rows: usize,
+    /// Least difference between the darkest and the brightest pixel of a
+    /// frame for it to show a line, when no threshold is given
+    pub min_contrast: u8,
+}
+
+impl Default for VisionConfig {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            dark_line: true,
+            rows: 8,
+            min_contrast: 30,
+        }
+    }
+}
+
+/// The line found in a [`Frame`]
+///
+/// Positions across the frame go from -1.0 at the left over 0.0 in the
+/// centre to 1.0 at the right edge, distances ahead from 0.0 at the bottom to
+/// 1.0 at the top edge. The line is fitted as a parabola of the position
+/// over the distance ahead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineDetection {
+    /// Position of the line at the bottom edge, right in front of logbot
+    pub position: f64,
+    /// Change of the position with the distance ahead, positive when the line
+    /// leads to the right
+    pub heading: f64,
+    /// Change of the heading with the distance ahead, positive when the line
+    /// bends to the right
+    pub curvature: f64,
+    /// Number of rows the line was found in
+    pub rows: usize,
+}
+
+/// Find the line in a [`Frame`], or [None] if no row shows it
+pub fn detect(frame: &Frame, config: &VisionConfig) -> Option<LineDetection> {
+    let threshold = threshold(frame, config)?;
+    let rows = config.rows.clamp(1, frame.height());
+    let bottom = (frame.height() - 1) as f64;
+
+    let points: Vec<(f64, f64)> = (0..rows)
+        .filter_map(|row| {
+            // From the bottom row up to the top row
+            let y = match rows {
+                1 => frame.height() - 1,
+                _ => (frame.height() - 1) - row * (frame.height() - 1) / (rows - 1),
+            };
+            let x = centre(frame.row(y), threshold, config.dark_line)?;
+            let ahead = match frame.height() {
+                1 => 0.0,
+                _ => (bottom - y as f64) / bottom,
+            };
+            Some((ahead, x))
+        })
+        .collect();
+
+    let (position, heading, curvature) = fit(&points)?;
+    Some(LineDetection {
+        position,
+        heading,
+        curvature,
+        rows: points.len(),
+    })
+}
+
+/// Brightness separating the line from the floor in a frame, or [None] if the
+/// frame has too little contrast to show a line
+fn threshold(frame: &Frame, config: &VisionConfig) -> Option<u8> {
+    if let Some(threshold) = config.threshold {
+        return Some(threshold);
+    };
+    let (min, max) = frame.range();
+    (max - min >= config.min_contrast.max(1)).then_some(min + (max - min) / 2)
+}
+
+/// Position of the centre of the widest run of line pixels in a row
+fn centre(row: &[u8], threshold: u8, dark_line: bool) -> Option<f64> {
+    let is_line = |pixel: u8| match dark_line {
+        true => pixel < threshold,
+        false => pixel > threshold,
+    };
+
+    let mut widest: Option<(usize, usize)> = None;
+    let mut start = None;
+    for (x, &pixel) in row.iter().enumerate() {
+        match (is_line(pixel), start) {
+            (true, None) => start = Some(x),
+            (false, Some(from)) => {
+                widest = wider(widest, (from, x));
+                start = None;
+            }
+            _ => {}
+        };
+    }
+    if let Some(from) = start {
+        widest = wider(widest, (from, row.len()));
+    };
+
+    let (from, to) = widest?;
+    let centre = (from + to - 1) as f64 / 2.0;
+    Some(match row.len() {
+        1 => 0.0,
+        width => centre / (width - 1) as f64 * 2.0 - 1.0,
+    })
+}
+
+/// The wider of two runs from a start to an end column
+fn wider(widest: Option<(usize, usize)>, run: (usize, usize)) -> Option<(usize, usize)> {
+    match widest {
+        Some(widest) if widest.1 - widest.0 >= run.1 - run.0 => Some(widest),
+        _ => Some(run),
+    }
+}
+
+/// Fit `x = a + b * d + c * d²` through points `(d, x)` by least squares,
+/// returning `(a, b, 2c)`, a line through two points and a constant through
+/// one
+fn fit(points: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+    let n = points.len() as f64;
+    let sum = |f: &dyn Fn(f64, f64) -> f64| points.iter().map(|&(d, x)| f(d, x)).sum::<f64>();
+    let (d1, d2, d3, d4) = (
+        sum(&|d, _| d),
+        sum(&|d, _| d * d),
+        sum(&|d, _| d.powi(3)),
+        sum(&|d, _| d.powi(4)),
+    );
+    let (x0, x1, x2) = (sum(&|_, x| x), sum(&|d, x| d * x), sum(&|d, x| d * d * x));
+
+    match points.len() {
+        0 => None,
+        1 => Some((x0, 0.0, 0.0)),
+        2 => {
+            let det = n * d2 - d1 * d1;
+            let b = (n * x1 - d1 * x0) / det;
+            Some(((x0 - b * d1) / n, b, 0.0))
+        }
+        _ => {
+            let det = det3([[n, d1, d2], [d1, d2, d3], [d2, d3, d4]]);
+            let a = det3([[x0, d1, d2], [x1, d2, d3], [x2, d3, d4]]) / det;
+            let b = det3([[n, x0, d2], [d1, x1, d3], [d2, x2, d4]]) / det;
+            let c = det3([[n, d1, x0], [d1, d2, x1], [d2, d3, x2]]) / det;
+            Some((a, b, 2.0 * c))
+        }
+    }
+}
+
+/// Determinant of a 3x3 matrix
+fn det3(m: [[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, VisionConfig};
+    use crate::Frame;
+
+    /// Frame of a dark line 4 pixels wide, centred at a column for each row
+    fn line(centre: impl Fn(f64) -> f64) -> Frame {
+        Frame::from_fn(81, 41, |x, y| {
+            let ahead = (40 - y) as f64 / 40.0;
+            let column = (centre(ahead) + 1.0) * 40.0;
+            match (x as f64 - column).abs() < 2.0 {
+                true => 30,
+                false => 210,
+            }
+        })
+    }
+
+    /// Test that straight, slanted and bent lines are found where they are
+    #[test]
+    fn detect_lines() {
+        let config = VisionConfig::default();
+
+        let straight = detect(&line(|_| 0.5), &config).unwrap();
+        assert_eq!(straight.rows, 8);
+        assert!((straight.position - 0.5).abs() < 0.03);
+        assert!(straight.heading.abs() < 0.05);
+
+        let slanted = detect(&line(|ahead| -0.4 + 0.6 * ahead), &config).unwrap();
+        assert!((slanted.position + 0.4).abs() < 0.03);
+        assert!((slanted.heading - 0.6).abs() < 0.05);
+        assert!(slanted.curvature.abs() < 0.1);
+
+        let bent = detect(&line(|ahead| -0.5 * ahead * ahead), &config).unwrap();
+        assert!(bent.position.abs() < 0.03);
+        assert!((bent.curvature + 1.0).abs() < 0.2);
+
+        // A bright line on a dark floor
+        let bright = Frame::from_fn(81, 41, |x, _| if x < 4 { 250 } else { 10 });
+        let config = VisionConfig {
+            dark_line: false,
+            ..config
+        };
+        assert!((detect(&bright, &config).unwrap().position + 0.96).abs() < 0.03);
+
+        // An even floor shows no line
+        assert_eq!(detect(&Frame::from_fn(81, 41, |_, _| 120), &config), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Detecting the line in frames of a camera
+//!
+//! A [`Frame`] is a grayscale image of the floor ahead of logbot. [`detect`]
+//! thresholds it, finds the line in evenly spaced rows and fits a curve
+//! through them, returning a [`LineDetection`] with the position of the line
+//! in front of logbot, its heading and its curvature.
+//!
+//! A [`CameraSensor`] captures frames from any [`FrameSource`] and exposes
+//! them as an alternative to the reflectance sensors: it implements
+//! [`LinePosition`](interfaces::LinePosition), and also
+//! [`SensorRead`](interfaces::SensorRead) by sampling the brightness at
+//! virtual sensors placed across the frame. The brightness is inverted for a
+//! dark line, so the line reads high as on the reflectance sensors and
+//! calibrating and following the line work unchanged.
+//!
+//! With the `camera` feature, [`camera::PiCamera`] captures frames from the
+//! camera of a Raspberry Pi using `rpicam-vid`.
+//!
+//! ```
+//! use vision::{detect, Frame, VisionConfig};
+//!
+//! // A dark line a third from the left of a bright floor
+//! let frame = Frame::from_fn(60, 40, |x, _| if (18..22).contains(&x) { 20 } else { 200 });
+//! let line = detect(&frame, &VisionConfig::default()).unwrap();
+//! assert!((line.position + 1.0 / 3.0).abs() < 0.05);
+//! assert!(line.curvature.abs() < 1e-9);
+//! ```
+
+#[cfg(feature = "camera")]
+pub mod camera;
+mod detect;
+mod sensor;
+
+pub use detect::{detect, LineDetection, VisionConfig};
+pub use sensor::{CameraSensor, VisionError};
+
+/// A grayscale image, row by row from the top left
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Width in pixels
+    width: usize,
+    /// Height in pixels
+    height: usize,
+    /// Brightness of every pixel
+    pixels: Vec<u8>,
+}
+
+impl Frame {
+    /// Create a frame from the brightness of its pixels, row by row, or [None]
+    /// if their number doesn't match the size
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Option<Self> {
+        (pixels.len() == width * height && width > 0 && height > 0).then_some(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Create a frame from the brightness at every column and row
+    ///
+    /// Panics if the frame is empty.
+    pub fn from_fn(width: usize, height: usize, pixel: impl Fn(usize, usize) -> u8) -> Self {
+        assert!(width > 0 && height > 0, "a frame has at least one pixel");
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| pixel(x, y))
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Width in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The pixels of a row
+    pub fn row(&self, y: usize) -> &[u8] {
+        &self.pixels[y * self.width..(y + 1) * self.width]
+    }
+
+    /// Brightness of the darkest and the brightest pixel
+    pub fn range(&self) -> (u8, u8) {
+        self.pixels
+            .iter()
+            .fold((u8::MAX, u8::MIN), |(min, max), &pixel| {
+                (min.min(pixel), max.max(pixel))
+            })
+    }
+}
+
+/// Source of [`Frame`]s, e.g. a camera
+pub trait FrameSource {
+    /// Error type
+    type Error;
+
+    /// Capture the next frame
+    fn capture(&mut self) -> Result<Frame, Self::Error>;
+}
@@ -0,0 +1,208 @@
+//! A camera used as the line sensor of logbot
+
+use std::fmt::Display;
+
+use interfaces::{LinePosition, SensorRead, ToSensorChannel};
+
+use crate::{detect, Frame, FrameSource, LineDetection, VisionConfig};
+
+/// Line sensor capturing [`Frame`]s from a [`FrameSource`]
+///
+/// Reading a channel captures a frame and returns the mean brightness of a
+/// square at the bottom of the frame around the virtual sensor of that
+/// channel, the way a reflectance sensor there would see the floor. Like the
+/// reflectance sensors, the line always reads higher than the floor: with a
+/// [dark line](VisionConfig::dark_line) the brightness is inverted.
+#[derive(Debug)]
+pub struct CameraSensor<S> {
+    /// Where the frames are captured from
+    source: S,
+    /// How the line is found
+    config: VisionConfig,
+    /// Position across the frame of the virtual sensor of every channel
+    sensors: Vec<f64>,
+}
+
+impl<S: FrameSource> CameraSensor<S> {
+    /// Create a sensor with virtual sensors at positions across the frame,
+    /// from -1.0 at the left to 1.0 at the right edge, read as channels 0, 1
+    /// and so on
+    pub fn new(source: S, config: VisionConfig, sensors: impl Into<Vec<f64>>) -> Self {
+        Self {
+            source,
+            config,
+            sensors: sensors.into(),
+        }
+    }
+
+    /// Capture a frame and find the line in it
+    pub fn detect(&mut self) -> Result<Option<LineDetection>, S::Error> {
+        let frame = self.source.capture()?;
+        Ok(detect(&frame, &self.config))
+    }
+
+    /// The source of the frames
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+}
+
+impl<S: FrameSource> SensorRead for CameraSensor<S> {
+    type Output = u8;
+    type Error = VisionError<S::Error>;
+
+    fn read(&mut self, sensor: impl ToSensorChannel) -> Result<Self::Output, Self::Error> {
+        let channel = sensor.to_channel();
+        let position = *self
+            .sensors
+            .get(channel as usize)
+            .ok_or(VisionError::Channel(channel))?;
+        let frame = self.source.capture().map_err(VisionError::Capture)?;
+        let brightness = sample(&frame, position);
+        Ok(match self.config.dark_line {
+            true => u8::MAX - brightness,
+            false => brightness,
+        })
+    }
+}
+
+impl<S: FrameSource> LinePosition for CameraSensor<S> {
+    type Error = S::Error;
+
+    fn line_position(&mut self) -> Result<Option<f64>, Self::Error> {
+        Ok(self.detect()?.map(|line| line.position))
+    }
+}
+
+/// Mean brightness of a square at the bottom of a frame, centred at a
+/// position across it
+fn sample(frame: &Frame, position: f64) -> u8 {
+    let size = (frame.width() / 16).clamp(1, frame.height());
+    let centre = (position.clamp(-1.0, 1.0) + 1.0) / 2.0 * (frame.width() - 1) as f64;
+    let left = (centre.round() as usize).saturating_sub(size / 2);
+    let right = (left + size).min(frame.width());
+
+    let (sum, count) = (frame.height() - size..frame.height())
+        .flat_map(|y| &frame.row(y)[left..right])
+        .fold((0u32, 0u32), |(sum, count), &pixel| {
+            (sum + pixel as u32, count + 1)
+        });
+    (sum / count.max(1)) as u8
+}
+
+/// Error of reading a [`CameraSensor`]
+#[derive(Debug)]
+pub enum VisionError<E> {
+    /// Capturing the frame failed
+    Capture(E),
+    /// No virtual sensor is placed for the channel
+    Channel(u8),
+}
+
+impl<E: Display> Display for VisionError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Capture(e) => write!(f, "failed to capture a frame: {e}"),
+            Self::Channel(channel) => write!(f, "no camera sensor on channel {channel}"),
+        }
+    }
+}
+
+impl<E> core::error::Error for VisionError<E>
+where
+    E: core::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Capture(e) => Some(e),
+            Self::Channel(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use interfaces::{LinePosition, SensorRead, ToSensorChannel};
+
+    use calibration::SingleSensorCalibration;
+
+    use super::{CameraSensor, VisionError};
+    use crate::{Frame, FrameSource, VisionConfig};
+
+    /// Source capturing a dark line at a column on a bright floor
+    #[derive(Debug)]
+    struct Line(usize);
+
+    impl FrameSource for Line {
+        type Error = Infallible;
+
+        fn capture(&mut self) -> Result<Frame, Self::Error> {
+            let column = self.0;
+            Ok(Frame::from_fn(64, 48, move |x, _| {
+                match x.abs_diff(column) < 4 {
+                    true => 20,
+                    false => 220,
+                }
+            }))
+        }
+    }
+
+    /// Source capturing a dark line moving back and forth under the left
+    /// virtual sensor, as logbot oscillating over it while calibrating
+    #[derive(Debug, Default)]
+    struct Oscillating(usize);
+
+    impl FrameSource for Oscillating {
+        type Error = Infallible;
+
+        fn capture(&mut self) -> Result<Frame, Self::Error> {
+            self.0 += 1;
+            Line([20, 44][self.0 % 2]).capture()
+        }
+    }
+
+    /// Channel of a virtual sensor
+    #[derive(Debug, Clone, Copy)]
+    struct Channel(u8);
+
+    impl ToSensorChannel for Channel {
+        fn to_channel(&self) -> u8 {
+            self.0
+        }
+    }
+
+    /// Test that the virtual sensors see the line like reflectance sensors
+    #[test]
+    fn read_virtual_sensors() {
+        let mut sensor = CameraSensor::new(Line(20), VisionConfig::default(), [-0.35, 0.35]);
+
+        // The left sensor is on the line, the right one on the floor
+        assert_eq!(sensor.read(Channel(0)).unwrap(), 235);
+        assert_eq!(sensor.read(Channel(1)).unwrap(), 35);
+        assert!(matches!(
+            sensor.read(Channel(2)),
+            Err(VisionError::Channel(2))
+        ));
+
+        let position = sensor.line_position().unwrap().unwrap();
+        assert!((position - (20.0 / 63.0 * 2.0 - 1.0)).abs() < 0.02);
+    }
+
+    /// Test that calibrating over the line with the virtual sensors finds a
+    /// line reading higher than the floor, as the reflectance sensors do
+    #[test]
+    fn calibrate_over_line() {
+        let mut sensor =
+            CameraSensor::new(Oscillating::default(), VisionConfig::default(), [-0.35]);
+
+        let mut calibration = SingleSensorCalibration::default();
+        for _ in 0..20 {
+            calibration.log(sensor.read(Channel(0)).unwrap() as f64);
+        }
+        let calibration = calibration.calibrate();
+        assert_eq!(calibration.line, 235);
+        assert_eq!(calibration.floor, 35);
+    }
+}